@@ -2,7 +2,7 @@
 
 use crate::{
     authority::{Issuer, UcanPrf},
-    ipld::{DagCbor, DagCborRef, DagJson},
+    ipld::{schema, DagCbor, DagCborRef, DagJson},
     task, Error, Pointer, Unit,
 };
 use libipld::{cbor::DagCborCodec, prelude::Codec, serde::from_ipld, Ipld};
@@ -23,6 +23,7 @@ const OUT_KEY: &str = "out";
 const ISSUER_KEY: &str = "iss";
 const METADATA_KEY: &str = "meta";
 const PROOF_KEY: &str = "prf";
+const SIGNATURE_KEY: &str = "s";
 
 /// A Receipt is a cryptographically signed description of the [Invocation]
 /// and its [resulting output] and requested effects.
@@ -38,6 +39,7 @@ pub struct Receipt<T> {
     meta: Ipld,
     issuer: Option<Issuer>,
     prf: UcanPrf,
+    signature: Option<Vec<u8>>,
 }
 
 impl<T> Receipt<T> {
@@ -55,8 +57,18 @@ impl<T> Receipt<T> {
             meta: metadata,
             issuer,
             prf: proof,
+            signature: None,
         }
     }
+
+    /// Attach an [Issuer] signature to the [Receipt], made over its
+    /// [signing payload].
+    ///
+    /// [signing payload]: Receipt::signing_payload
+    pub fn with_signature(mut self, signature: Vec<u8>) -> Self {
+        self.signature = Some(signature);
+        self
+    }
 }
 
 impl<T> Receipt<T> {
@@ -86,6 +98,24 @@ impl<T> Receipt<T> {
     pub fn prf(&self) -> &UcanPrf {
         &self.prf
     }
+
+    /// Optional [Issuer] signature over the [Receipt].
+    pub fn signature(&self) -> Option<&[u8]> {
+        self.signature.as_deref()
+    }
+}
+
+impl Receipt<Ipld> {
+    /// DagCbor-encoded [Receipt], without its signature, which is the
+    /// payload signed by the [Issuer].
+    pub fn signing_payload(&self) -> Result<Vec<u8>, Error<Unit>> {
+        let mut ipld = Ipld::from(self);
+        if let Ipld::Map(ref mut map) = ipld {
+            map.remove(SIGNATURE_KEY);
+        }
+
+        Ok(DagCborCodec.encode(&ipld)?)
+    }
 }
 
 impl DagJson for Receipt<Ipld> {}
@@ -114,7 +144,7 @@ impl DagCborRef for Receipt<Ipld> {}
 
 impl From<&Receipt<Ipld>> for Ipld {
     fn from(receipt: &Receipt<Ipld>) -> Self {
-        Ipld::Map(BTreeMap::from([
+        let mut map = BTreeMap::from([
             (RAN_KEY.into(), receipt.ran.to_owned().into()),
             (OUT_KEY.into(), receipt.out.to_owned().into()),
             (METADATA_KEY.into(), receipt.meta.to_owned()),
@@ -127,7 +157,14 @@ impl From<&Receipt<Ipld>> for Ipld {
                     .unwrap_or(Ipld::Null),
             ),
             (PROOF_KEY.into(), receipt.prf.to_owned().into()),
-        ]))
+        ]);
+
+        // Unsigned receipts omit the signature, keeping their Cids stable.
+        if let Some(signature) = &receipt.signature {
+            map.insert(SIGNATURE_KEY.into(), Ipld::Bytes(signature.to_owned()));
+        }
+
+        Ipld::Map(map)
     }
 }
 
@@ -162,19 +199,28 @@ impl TryFrom<Ipld> for Receipt<Ipld> {
                 Ipld::Null => None,
                 ipld => Some(ipld),
             })
-            .and_then(|ipld| from_ipld(ipld.to_owned()).ok())
-            .map(Issuer::new);
+            .map(|ipld| Issuer::try_from(ipld.to_owned()))
+            .transpose()?;
 
         let prf = map
             .get(PROOF_KEY)
             .ok_or_else(|| Error::<Unit>::MissingField(PROOF_KEY.to_string()))?;
 
+        let signature = match map.get(SIGNATURE_KEY) {
+            Some(Ipld::Bytes(bytes)) => Some(bytes.to_owned()),
+            Some(Ipld::Null) | None => None,
+            Some(other_ipld) => {
+                return Err(Error::unexpected_ipld(other_ipld.to_owned()));
+            }
+        };
+
         Ok(Receipt {
             ran,
             out: task::Result::try_from(out)?,
             meta: meta.to_owned(),
             issuer,
             prf: UcanPrf::try_from(prf)?,
+            signature,
         })
     }
 }
@@ -229,6 +275,7 @@ impl<T> JsonSchema for Receipt<T> {
                     ("meta".to_owned(), Schema::Object(meta_schema)),
                     ("iss".to_owned(), gen.subschema_for::<Option<Issuer>>()),
                     ("prf".to_owned(), gen.subschema_for::<UcanPrf>()),
+                    ("s".to_owned(), gen.subschema_for::<schema::IpldBytesStub>()),
                 ]),
                 required: BTreeSet::from([
                     "ran".to_string(),
@@ -6,11 +6,12 @@ use crate::{consts, Error, Unit};
 use libipld::{serde::from_ipld, Ipld};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, time::Duration};
+use std::{collections::BTreeMap, fmt, str::FromStr, time::Duration};
 
 const FUEL_KEY: &str = "fuel";
 const MEMORY_KEY: &str = "memory";
 const TIMEOUT_KEY: &str = "time";
+const ON_ERROR_KEY: &str = "on_error";
 
 /// Resource configuration for defining fuel quota, timeout, etc.
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
@@ -46,6 +47,19 @@ impl Resources {
         }
     }
 
+    /// Read [Resources] from a task's metadata, leaving any limit that's
+    /// not set unspecified.
+    pub fn from_meta(meta: &Ipld) -> Result<Self, Error<Unit>> {
+        match meta {
+            Ipld::Map(_) => Resources::try_from(meta),
+            _ => Ok(Resources {
+                fuel: None,
+                memory: None,
+                time: None,
+            }),
+        }
+    }
+
     /// Get fuel limit.
     pub fn fuel(&self) -> Option<u64> {
         self.fuel
@@ -135,6 +149,68 @@ impl TryFrom<Ipld> for Resources {
     }
 }
 
+/// Error handling policy of a task, set under the `on_error` key of its
+/// metadata.
+///
+/// Failed tasks always produce an `error` receipt, which tasks awaiting
+/// `await/error` on them can consume. The policy decides whether or not the
+/// rest of the workflow keeps on running afterward.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+#[schemars(
+    rename = "on_error",
+    description = "Error handling policy of a task: fail (fast) or continue"
+)]
+pub enum OnError {
+    /// Fail the workflow as soon as the task fails.
+    #[default]
+    Fail,
+    /// Continue running the workflow after the task fails.
+    Continue,
+}
+
+impl OnError {
+    /// Read the [OnError] policy from a task's metadata, defaulting to
+    /// [OnError::Fail] if it's not set.
+    pub fn from_meta(meta: &Ipld) -> Result<Self, Error<Unit>> {
+        match meta {
+            Ipld::Map(map) => match map.get(ON_ERROR_KEY) {
+                None | Some(Ipld::Null) => Ok(OnError::default()),
+                Some(Ipld::String(policy)) => policy.parse(),
+                Some(ipld) => Err(Error::unexpected_ipld(ipld.to_owned())),
+            },
+            _ => Ok(OnError::default()),
+        }
+    }
+}
+
+impl fmt::Display for OnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OnError::Fail => write!(f, "fail"),
+            OnError::Continue => write!(f, "continue"),
+        }
+    }
+}
+
+impl FromStr for OnError {
+    type Err = Error<Unit>;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "fail" => Ok(OnError::Fail),
+            "continue" => Ok(OnError::Continue),
+            other => Err(Error::unexpected_ipld(Ipld::String(other.to_string()))),
+        }
+    }
+}
+
+impl From<OnError> for Ipld {
+    fn from(policy: OnError) -> Ipld {
+        Ipld::String(policy.to_string())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -158,6 +234,21 @@ mod test {
         assert_eq!(config, ipld.try_into().unwrap())
     }
 
+    #[test]
+    fn resources_from_meta() {
+        let meta = Ipld::Map(BTreeMap::from([
+            (FUEL_KEY.into(), Ipld::Integer(1000)),
+            (ON_ERROR_KEY.into(), Ipld::String("continue".into())),
+        ]));
+        let resources = Resources::from_meta(&meta).unwrap();
+        assert_eq!(resources.fuel(), Some(1000));
+        assert_eq!(resources.memory(), None);
+        assert_eq!(resources.time(), None);
+
+        let resources = Resources::from_meta(&Ipld::Null).unwrap();
+        assert_eq!(resources.fuel(), None);
+    }
+
     #[test]
     fn ser_de() {
         let config = Resources::default();
@@ -166,4 +257,25 @@ mod test {
 
         assert_eq!(config, de);
     }
+
+    #[test]
+    fn on_error_from_meta() {
+        let mut meta = match Ipld::from(Resources::default()) {
+            Ipld::Map(map) => map,
+            _ => panic!("resources should be an Ipld map"),
+        };
+        assert_eq!(
+            OnError::from_meta(&Ipld::Map(meta.clone())).unwrap(),
+            OnError::Fail
+        );
+
+        meta.insert(ON_ERROR_KEY.into(), OnError::Continue.into());
+        assert_eq!(
+            OnError::from_meta(&Ipld::Map(meta.clone())).unwrap(),
+            OnError::Continue
+        );
+
+        meta.insert(ON_ERROR_KEY.into(), Ipld::String("retry".into()));
+        assert!(OnError::from_meta(&Ipld::Map(meta)).is_err());
+    }
 }
@@ -0,0 +1,2 @@
+DROP TABLE dht_providers;
+DROP TABLE dht_records;
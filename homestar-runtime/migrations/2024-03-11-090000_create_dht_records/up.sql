@@ -0,0 +1,14 @@
+CREATE TABLE dht_records (
+  key          BLOB NOT NULL PRIMARY KEY,
+  value        BLOB NOT NULL,
+  publisher    BLOB,
+  expires_at   BIGINT
+);
+
+CREATE TABLE dht_providers (
+  key          BLOB NOT NULL,
+  provider     BLOB NOT NULL,
+  addresses    TEXT NOT NULL,
+  expires_at   BIGINT,
+  PRIMARY KEY(key, provider)
+);
@@ -0,0 +1,9 @@
+ALTER TABLE workflows RENAME COLUMN status TO status_prev;
+ALTER TABLE workflows RENAME COLUMN retries TO retries_prev;
+ALTER TABLE workflows ADD COLUMN status TEXT CHECK(
+    status IN ('pending', 'completed', 'running', 'stuck', 'cancelled', 'failed')) NOT NULL DEFAULT
+            'pending';
+ALTER TABLE workflows ADD COLUMN retries INTEGER NOT NULL DEFAULT 0;
+UPDATE workflows SET status = status_prev, retries = retries_prev;
+ALTER TABLE workflows DROP COLUMN status_prev;
+ALTER TABLE workflows DROP COLUMN retries_prev;
@@ -0,0 +1,2 @@
+DROP TABLE receipts_blocks;
+DROP TABLE blocks;
@@ -0,0 +1,10 @@
+CREATE TABLE blocks (
+  cid  TEXT NOT NULL PRIMARY KEY,
+  data BLOB NOT NULL
+);
+
+CREATE TABLE receipts_blocks (
+  receipt_cid TEXT NOT NULL REFERENCES receipts(cid),
+  block_cid   TEXT NOT NULL REFERENCES blocks(cid),
+  PRIMARY KEY(receipt_cid, block_cid)
+);
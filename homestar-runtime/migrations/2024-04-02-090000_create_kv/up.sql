@@ -0,0 +1,7 @@
+CREATE TABLE kv (
+  namespace TEXT    NOT NULL,
+  key       TEXT    NOT NULL,
+  value     BLOB    NOT NULL,
+  size      BIGINT  NOT NULL,
+  PRIMARY KEY(namespace, key)
+);
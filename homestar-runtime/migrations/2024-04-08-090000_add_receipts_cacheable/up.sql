@@ -0,0 +1 @@
+ALTER TABLE receipts ADD COLUMN cacheable BOOLEAN NOT NULL DEFAULT TRUE;
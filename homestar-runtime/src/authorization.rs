@@ -0,0 +1,476 @@
+//! Verification of the [UCAN] proofs a task is invoked with, checking that
+//! they delegate the capability for the task's resource and ability to this
+//! node.
+//!
+//! A proof is valid if it, and every UCAN it links to, is signed by its
+//! issuer, within its time bounds, and delegated to by its proofs. It grants
+//! a task's capability if it's delegated to the node and the capability is
+//! held along the delegation chain, back to an issuer trusted by the node.
+//!
+//! [UCAN]: <https://github.com/ucan-wg/spec>
+
+#[cfg(feature = "ipfs")]
+use crate::network::IpfsCli;
+use crate::{
+    db::Database,
+    scheduler::ExecutionGraph,
+    settings::{self, AuthorizationMode},
+};
+use anyhow::Result;
+use homestar_invocation::{authority::UcanPrf, Pointer};
+use libipld::Cid;
+use std::collections::HashMap;
+use tracing::warn;
+use ucan::{
+    capability::Capability,
+    chain::ProofChain,
+    crypto::did::{
+        DidParser, KeyConstructorSlice, ED25519_MAGIC_BYTES, P256_MAGIC_BYTES, RSA_MAGIC_BYTES,
+    },
+};
+use ucan_key_support::{
+    ed25519::bytes_to_ed25519_key, p256::bytes_to_p256_key, rsa::bytes_to_rsa_key,
+};
+use url::Url;
+
+mod key;
+mod receipt;
+mod store;
+
+pub(crate) use key::NodeKey;
+pub(crate) use receipt::{sign_receipt, verify_receipt};
+pub(crate) use store::ProofStore;
+
+/// Key types UCAN issuers are resolved to.
+pub(crate) const SUPPORTED_KEYS: &KeyConstructorSlice = &[
+    (ED25519_MAGIC_BYTES, bytes_to_ed25519_key),
+    (P256_MAGIC_BYTES, bytes_to_p256_key),
+    (RSA_MAGIC_BYTES, bytes_to_rsa_key),
+];
+
+/// Resource or ability granting every resource or ability.
+const ANY: &str = "*";
+
+/// Ability redelegating the capabilities of a proof.
+const DELEGATE: &str = "ucan/DELEGATE";
+
+/// Resource prefix of a proof, by index or [ANY], being redelegated.
+const PROOF_PREFIX: &str = "prf:";
+
+/// DIDs of the issuers of the proofs authorizing tasks, by the [Cid] of
+/// their instruction.
+pub(crate) type Issuers = HashMap<Cid, String>;
+
+/// Error authorizing a task via its UCAN proofs.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub(crate) enum AuthorizationError {
+    /// Task without any proofs.
+    #[error("no UCAN proofs given for instruction {instruction}")]
+    MissingProof {
+        /// Cid of the task's instruction.
+        instruction: Cid,
+    },
+    /// Proof that couldn't be resolved or validated.
+    #[error("invalid UCAN proof {proof} for instruction {instruction}: {reason}")]
+    InvalidProof {
+        /// Cid of the task's instruction.
+        instruction: Cid,
+        /// Cid of the proof.
+        proof: Cid,
+        /// Why the proof is invalid.
+        reason: String,
+    },
+    /// Valid proofs, none of which grant the task's capability.
+    #[error("UCAN proofs do not grant `{ability}` on {resource} for instruction {instruction}")]
+    Unauthorized {
+        /// Cid of the task's instruction.
+        instruction: Cid,
+        /// Resource of the task's instruction.
+        resource: Url,
+        /// Ability of the task's instruction.
+        ability: String,
+    },
+}
+
+/// Verifier of the UCAN proofs of a [Workflow]'s tasks, per the node's
+/// [settings::Authorization].
+///
+/// [Workflow]: homestar_workflow::Workflow
+#[derive(Clone, Default)]
+#[allow(missing_debug_implementations)]
+pub(crate) struct ProofVerifier {
+    mode: AuthorizationMode,
+    audience: String,
+    trusted_issuers: Vec<String>,
+    #[cfg(feature = "ipfs")]
+    ipfs: Option<IpfsCli>,
+}
+
+impl ProofVerifier {
+    /// Create a new [ProofVerifier] for proofs delegated to the node with
+    /// the given DID.
+    pub(crate) fn new(settings: &settings::Authorization, audience: String) -> Self {
+        Self {
+            mode: settings.mode,
+            audience,
+            trusted_issuers: settings.trusted_issuers.clone(),
+            #[cfg(feature = "ipfs")]
+            ipfs: None,
+        }
+    }
+
+    /// Resolve proofs missing from the local database over IPFS.
+    #[cfg(feature = "ipfs")]
+    pub(crate) fn with_ipfs(mut self, ipfs: IpfsCli) -> Self {
+        self.ipfs = Some(ipfs);
+        self
+    }
+
+    /// Verify the proofs of every task in an [ExecutionGraph], returning
+    /// the [Issuers] of the tasks authorized.
+    ///
+    /// When enforcing, the first [AuthorizationError] rejects the graph;
+    /// when auditing, errors are only logged.
+    pub(crate) async fn verify_graph<DB>(
+        &self,
+        graph: &ExecutionGraph<'_>,
+        db: &DB,
+    ) -> Result<Issuers>
+    where
+        DB: Database,
+    {
+        let mut issuers = Issuers::new();
+        if self.mode == AuthorizationMode::Off {
+            return Ok(issuers);
+        }
+
+        for node in graph.schedule.iter().flatten() {
+            let vertex = node.inner();
+            let instruction = Pointer::try_from(vertex.instruction.clone())?.cid();
+
+            match self
+                .verify(
+                    instruction,
+                    vertex.instruction.resource(),
+                    &vertex.instruction.op().to_string(),
+                    &vertex.prf,
+                    db,
+                )
+                .await
+            {
+                Ok(issuer) => {
+                    issuers.insert(instruction, issuer);
+                }
+                Err(err) if self.mode == AuthorizationMode::Enforce => return Err(err.into()),
+                Err(err) => warn!(
+                    subject = "authorization.unauthorized",
+                    category = "authorization",
+                    instruction_cid = instruction.to_string(),
+                    err = err.to_string(),
+                    "task is not authorized by its proofs, running it regardless"
+                ),
+            }
+        }
+
+        Ok(issuers)
+    }
+
+    /// Verify that a task's proofs grant an ability on a resource,
+    /// returning the DID of the issuer of the proof granting it, delegated
+    /// to the node.
+    pub(crate) async fn verify<DB>(
+        &self,
+        instruction: Cid,
+        resource: &Url,
+        ability: &str,
+        prf: &UcanPrf,
+        db: &DB,
+    ) -> Result<String, AuthorizationError>
+    where
+        DB: Database,
+    {
+        if prf.inner().is_empty() {
+            return Err(AuthorizationError::MissingProof { instruction });
+        }
+
+        let store = ProofStore::new(db.clone());
+        #[cfg(feature = "ipfs")]
+        let store = store.with_ipfs(self.ipfs.clone());
+        let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+
+        let mut invalid = None;
+        for link in prf.inner() {
+            let proof = *link.cid();
+            match ProofChain::from_cid(&proof, None, &mut did_parser, &store).await {
+                Ok(chain) if chain.ucan().audience() != self.audience => {
+                    invalid = Some(AuthorizationError::InvalidProof {
+                        instruction,
+                        proof,
+                        reason: format!(
+                            "delegated to {}, not this node ({})",
+                            chain.ucan().audience(),
+                            self.audience
+                        ),
+                    })
+                }
+                Ok(chain) if self.grants(&chain, resource, ability) => {
+                    return Ok(chain.ucan().issuer().to_string())
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    invalid = Some(AuthorizationError::InvalidProof {
+                        instruction,
+                        proof,
+                        reason: format!("{err:#}"),
+                    })
+                }
+            }
+        }
+
+        Err(invalid.unwrap_or_else(|| AuthorizationError::Unauthorized {
+            instruction,
+            resource: resource.to_owned(),
+            ability: ability.to_string(),
+        }))
+    }
+
+    /// Whether a (validated) [ProofChain] holds an ability on a resource,
+    /// along its delegation chain back to a trusted issuer.
+    ///
+    /// Each UCAN in the chain either claims the capability itself, or
+    /// redelegates it wholesale from the proof holding it.
+    fn grants(&self, chain: &ProofChain, resource: &Url, ability: &str) -> bool {
+        let ucan = chain.ucan();
+        let capabilities = ucan.capabilities().iter().collect::<Vec<_>>();
+        let claimed = capabilities
+            .iter()
+            .any(|capability| enables(capability, resource, ability));
+
+        (claimed && self.is_trusted(ucan.issuer()))
+            || chain.proofs().iter().enumerate().any(|(index, proof)| {
+                (claimed
+                    || capabilities
+                        .iter()
+                        .any(|capability| redelegates(capability, index)))
+                    && self.grants(proof, resource, ability)
+            })
+    }
+
+    fn is_trusted(&self, issuer: &str) -> bool {
+        issuer == self.audience || self.trusted_issuers.iter().any(|did| did == issuer)
+    }
+}
+
+/// Whether a [Capability] enables an ability on a resource.
+///
+/// A capability's resource enables itself and any resource beneath it,
+/// path-wise. Capabilities with caveats aren't interpreted, and so never
+/// enable anything.
+fn enables(capability: &Capability, resource: &Url, ability: &str) -> bool {
+    let ability_enabled = capability.ability == ANY || capability.ability == ability;
+    let unrestricted = capability
+        .caveat
+        .as_object()
+        .is_some_and(|caveat| caveat.is_empty());
+
+    ability_enabled && unrestricted && contains(&capability.resource, resource)
+}
+
+/// Whether a [Capability] redelegates all capabilities of the proof at a
+/// given index.
+fn redelegates(capability: &Capability, index: usize) -> bool {
+    capability.ability == DELEGATE
+        && (capability.resource == format!("{PROOF_PREFIX}{ANY}")
+            || capability.resource == format!("{PROOF_PREFIX}{index}"))
+}
+
+fn contains(scope: &str, resource: &Url) -> bool {
+    if scope == ANY {
+        return true;
+    }
+
+    let Ok(scope) = Url::parse(scope) else {
+        return false;
+    };
+
+    scope == *resource
+        || (scope.scheme() == resource.scheme()
+            && scope.host_str() == resource.host_str()
+            && scope.port() == resource.port()
+            && resource
+                .path()
+                .starts_with(&format!("{}/", scope.path().trim_end_matches('/'))))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{settings::Authorization, test_utils::db::MemoryDb};
+    use homestar_invocation::ipld::Link;
+    use libipld::multihash::Code;
+    use libp2p::identity::Keypair;
+    use ucan::{builder::UcanBuilder, store::UcanJwtStore, Ucan};
+
+    const RESOURCE: &str = "ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu";
+    const ABILITY: &str = "wasm/run";
+
+    async fn delegate(
+        issuer: &NodeKey,
+        audience: &NodeKey,
+        capability: Option<(&str, &str)>,
+        proof: Option<&Ucan>,
+    ) -> Ucan {
+        let builder = UcanBuilder::default()
+            .issued_by(issuer)
+            .for_audience(&audience.did().unwrap())
+            .with_lifetime(60);
+        let builder = match capability {
+            Some((resource, ability)) => builder.claiming_capability(Capability::new(
+                resource.to_string(),
+                ability.to_string(),
+                serde_json::json!({}),
+            )),
+            None => builder,
+        };
+        let builder = match proof {
+            Some(proof) if capability.is_some() => builder.witnessed_by(proof, None),
+            Some(proof) => builder.delegating_from(proof, None),
+            None => builder,
+        };
+
+        builder.build().unwrap().sign().await.unwrap()
+    }
+
+    async fn store(ucans: &[&Ucan], db: &MemoryDb) -> UcanPrf {
+        let mut store = ProofStore::new(db.clone());
+        let mut links = vec![];
+        for ucan in ucans {
+            let cid = store.write_token(&ucan.encode().unwrap()).await.unwrap();
+            links.push(Link::new(cid));
+        }
+
+        UcanPrf::new(links)
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn verify_proofs() {
+        let settings = TestSettings::load();
+        let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+
+        let node = NodeKey::new(Keypair::generate_ed25519());
+        let root = NodeKey::new(Keypair::generate_ed25519());
+        let alice = NodeKey::new(Keypair::generate_ed25519());
+        let verifier = ProofVerifier::new(
+            &Authorization {
+                mode: AuthorizationMode::Enforce,
+                trusted_issuers: vec![root.did().unwrap()],
+            },
+            node.did().unwrap(),
+        );
+
+        let instruction = Cid::default();
+        let resource = Url::parse(RESOURCE).unwrap();
+        let verify = |prf: UcanPrf, ability: &'static str| {
+            let verifier = verifier.clone();
+            let resource = resource.clone();
+            let db = db.clone();
+            async move {
+                verifier
+                    .verify(instruction, &resource, ability, &prf, &db)
+                    .await
+            }
+        };
+
+        assert_eq!(
+            verify(UcanPrf::default(), ABILITY).await,
+            Err(AuthorizationError::MissingProof { instruction })
+        );
+
+        // Delegated directly from a trusted issuer.
+        let direct = delegate(&root, &node, Some((RESOURCE, ABILITY)), None).await;
+        let prf = store(&[&direct], &db).await;
+        assert_eq!(verify(prf.clone(), ABILITY).await, Ok(root.did().unwrap()));
+        assert_eq!(
+            verify(prf, "wasm/other").await,
+            Err(AuthorizationError::Unauthorized {
+                instruction,
+                resource: resource.clone(),
+                ability: "wasm/other".to_string(),
+            })
+        );
+
+        // Delegated via an untrusted issuer holding the capability, both
+        // with the capability claimed again and redelegated wholesale.
+        let to_alice = delegate(&root, &alice, Some((ANY, ABILITY)), None).await;
+        store(&[&to_alice], &db).await;
+        let claimed = delegate(&alice, &node, Some((RESOURCE, ABILITY)), Some(&to_alice)).await;
+        let redelegated = delegate(&alice, &node, None, Some(&to_alice)).await;
+        assert_eq!(
+            verify(store(&[&claimed], &db).await, ABILITY).await,
+            Ok(alice.did().unwrap())
+        );
+        assert!(verify(store(&[&redelegated], &db).await, ABILITY)
+            .await
+            .is_ok());
+
+        // Self-issued by an untrusted issuer.
+        let untrusted = delegate(&alice, &node, Some((RESOURCE, ABILITY)), None).await;
+        assert!(matches!(
+            verify(store(&[&untrusted], &db).await, ABILITY).await,
+            Err(AuthorizationError::Unauthorized { .. })
+        ));
+
+        // Delegated to another node.
+        let elsewhere = delegate(&root, &alice, Some((RESOURCE, ABILITY)), None).await;
+        assert!(matches!(
+            verify(store(&[&elsewhere], &db).await, ABILITY).await,
+            Err(AuthorizationError::InvalidProof { .. })
+        ));
+
+        // Unresolvable proof.
+        let unknown = UcanPrf::new(vec![Link::new(direct.to_cid(Code::Sha2_256).unwrap())]);
+        assert!(matches!(
+            verify(unknown, ABILITY).await,
+            Err(AuthorizationError::InvalidProof { .. })
+        ));
+    }
+
+    #[test]
+    fn capability_scope() {
+        let resource = Url::parse("https://example.com/modules/add.wasm").unwrap();
+        let capability = |resource: &str, ability: &str, caveat| {
+            Capability::new(resource.to_string(), ability.to_string(), caveat)
+        };
+        let no_caveat = serde_json::json!({});
+
+        assert!(enables(
+            &capability("https://example.com/modules", ABILITY, no_caveat.clone()),
+            &resource,
+            ABILITY
+        ));
+        assert!(enables(
+            &capability(ANY, ANY, no_caveat.clone()),
+            &resource,
+            ABILITY
+        ));
+        assert!(!enables(
+            &capability("https://example.com/mod", ABILITY, no_caveat.clone()),
+            &resource,
+            ABILITY
+        ));
+        assert!(!enables(
+            &capability("https://example.org/modules", ABILITY, no_caveat),
+            &resource,
+            ABILITY
+        ));
+        assert!(!enables(
+            &capability(
+                "https://example.com/modules",
+                ABILITY,
+                serde_json::json!({"max": 1})
+            ),
+            &resource,
+            ABILITY
+        ));
+    }
+}
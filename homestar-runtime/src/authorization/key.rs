@@ -0,0 +1,99 @@
+//! The node's libp2p [Keypair] as UCAN [KeyMaterial], identifying the node
+//! by a `did:key` DID.
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use libp2p::identity::{ed25519, secp256k1, KeyType, Keypair, PublicKey};
+use ucan::crypto::{
+    did::{ED25519_MAGIC_BYTES, SECP256K1_MAGIC_BYTES},
+    JwtSignatureAlgorithm, KeyMaterial,
+};
+
+const DID_KEY_PREFIX: &str = "did:key:z";
+
+/// Node [Keypair] used to sign and verify UCANs.
+#[derive(Clone, Debug)]
+pub(crate) struct NodeKey(Keypair);
+
+impl NodeKey {
+    /// Create a new [NodeKey] from a [Keypair].
+    pub(crate) fn new(keypair: Keypair) -> Self {
+        Self(keypair)
+    }
+
+    /// `did:key` DID of the node, which must be an Ed25519 or Secp256k1
+    /// key.
+    pub(crate) fn did(&self) -> Result<String> {
+        did_key(&self.0.public())
+    }
+
+    /// Sign a payload with the node's [Keypair].
+    pub(crate) fn sign_payload(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        self.0.sign(payload).map_err(Into::into)
+    }
+}
+
+/// Encode a [PublicKey] as a `did:key` DID.
+pub(crate) fn did_key(public: &PublicKey) -> Result<String> {
+    let bytes = match public.key_type() {
+        KeyType::Ed25519 => [
+            ED25519_MAGIC_BYTES,
+            &public.clone().try_into_ed25519()?.to_bytes(),
+        ]
+        .concat(),
+        KeyType::Secp256k1 => [
+            SECP256K1_MAGIC_BYTES,
+            &public.clone().try_into_secp256k1()?.to_bytes(),
+        ]
+        .concat(),
+        key_type => return Err(anyhow!("unsupported key type for a DID: {key_type}")),
+    };
+
+    Ok(format!(
+        "{DID_KEY_PREFIX}{}",
+        bs58::encode(bytes).into_string()
+    ))
+}
+
+/// Decode a `did:key` DID into a [PublicKey], for Ed25519 and Secp256k1
+/// keys.
+pub(crate) fn public_key(did: &str) -> Result<PublicKey> {
+    let encoded = did
+        .strip_prefix(DID_KEY_PREFIX)
+        .ok_or_else(|| anyhow!("not a base58btc did:key DID: {did}"))?;
+    let bytes = bs58::decode(encoded).into_vec()?;
+
+    if let Some(key) = bytes.strip_prefix(ED25519_MAGIC_BYTES) {
+        Ok(ed25519::PublicKey::try_from_bytes(key)?.into())
+    } else if let Some(key) = bytes.strip_prefix(SECP256K1_MAGIC_BYTES) {
+        Ok(secp256k1::PublicKey::try_from_bytes(key)?.into())
+    } else {
+        Err(anyhow!("unsupported key type for DID: {did}"))
+    }
+}
+
+#[async_trait]
+impl KeyMaterial for NodeKey {
+    fn get_jwt_algorithm_name(&self) -> String {
+        match self.0.key_type() {
+            KeyType::Secp256k1 => "ES256K".to_string(),
+            _ => JwtSignatureAlgorithm::EdDSA.to_string(),
+        }
+    }
+
+    async fn get_did(&self) -> Result<String> {
+        self.did()
+    }
+
+    async fn sign(&self, payload: &[u8]) -> Result<Vec<u8>> {
+        self.sign_payload(payload)
+    }
+
+    async fn verify(&self, payload: &[u8], signature: &[u8]) -> Result<()> {
+        self.0
+            .public()
+            .verify(payload, signature)
+            .then_some(())
+            .ok_or_else(|| anyhow!("invalid signature"))
+    }
+}
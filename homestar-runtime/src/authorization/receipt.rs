@@ -0,0 +1,157 @@
+//! Signing of [Receipt]s by the node, and verification of [Receipt]s
+//! received from other peers before they're stored.
+//!
+//! [Receipt]: crate::Receipt
+
+use super::key::{self, NodeKey};
+use crate::Receipt;
+use anyhow::Result;
+use homestar_invocation::{authority::Issuer, ipld::DagCborRef, Receipt as InvocationReceipt};
+use libipld::{Cid, Ipld};
+
+/// Error verifying a received [Receipt].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub(crate) enum ReceiptError {
+    /// Receipt without an issuer or signature.
+    #[error("receipt {0} is not signed")]
+    Unsigned(Cid),
+    /// Receipt whose Cid doesn't match its content.
+    #[error("receipt Cid {0} does not match its content")]
+    CidMismatch(Cid),
+    /// Receipt with an issuer that can't be resolved to a public key.
+    #[error("unsupported receipt issuer {issuer}: {reason}")]
+    UnsupportedIssuer {
+        /// Issuer of the receipt.
+        issuer: String,
+        /// Why the issuer is unsupported.
+        reason: String,
+    },
+    /// Receipt with a signature not made by its issuer.
+    #[error("invalid signature on receipt {0}")]
+    InvalidSignature(Cid),
+    /// Receipt that couldn't be encoded for verification.
+    #[error("failed to encode receipt: {0}")]
+    Encoding(String),
+}
+
+/// Sign an [InvocationReceipt] with the node's key, setting the node as its
+/// [Issuer].
+pub(crate) fn sign_receipt(
+    node_key: &NodeKey,
+    receipt: InvocationReceipt<Ipld>,
+) -> Result<InvocationReceipt<Ipld>> {
+    let issuer = Issuer::try_from(Ipld::String(node_key.did()?))?;
+    let receipt = InvocationReceipt::new(
+        receipt.ran().to_owned(),
+        receipt.out().to_owned(),
+        receipt.meta().to_owned(),
+        Some(issuer),
+        receipt.prf().to_owned(),
+    );
+    let signature = node_key.sign_payload(&receipt.signing_payload()?)?;
+
+    Ok(receipt.with_signature(signature))
+}
+
+/// Verify a received [Receipt], checking that its Cid matches its content
+/// and that it's signed by its issuer.
+pub(crate) fn verify_receipt(receipt: &Receipt) -> Result<(), ReceiptError> {
+    let cid = receipt.cid();
+    let invocation_receipt = InvocationReceipt::from(receipt);
+
+    let computed = invocation_receipt
+        .to_cid()
+        .map_err(|err| ReceiptError::Encoding(err.to_string()))?;
+    if computed != cid {
+        return Err(ReceiptError::CidMismatch(cid));
+    }
+
+    let (Some(issuer), Some(signature)) = (receipt.issuer(), receipt.signature()) else {
+        return Err(ReceiptError::Unsigned(cid));
+    };
+
+    let public_key =
+        key::public_key(&issuer.to_string()).map_err(|err| ReceiptError::UnsupportedIssuer {
+            issuer: issuer.to_string(),
+            reason: err.to_string(),
+        })?;
+
+    let payload = invocation_receipt
+        .signing_payload()
+        .map_err(|err| ReceiptError::Encoding(err.to_string()))?;
+
+    public_key
+        .verify(&payload, signature)
+        .then_some(())
+        .ok_or(ReceiptError::InvalidSignature(cid))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils;
+    use homestar_invocation::{task, Pointer};
+    use libp2p::identity::Keypair;
+
+    #[test]
+    fn sign_and_verify_receipts() {
+        let node_key = NodeKey::new(Keypair::generate_ed25519());
+        let (invocation_receipt, receipt) = test_utils::receipt::receipts();
+
+        assert_eq!(
+            verify_receipt(&receipt),
+            Err(ReceiptError::Unsigned(receipt.cid()))
+        );
+
+        let signed = sign_receipt(&node_key, invocation_receipt.clone()).unwrap();
+        assert_eq!(
+            signed.issuer().as_ref().unwrap().to_string(),
+            node_key.did().unwrap()
+        );
+        let signed_receipt = Receipt::try_with(receipt.instruction().to_owned(), &signed).unwrap();
+        assert_ne!(signed_receipt.cid(), receipt.cid());
+        assert!(verify_receipt(&signed_receipt).is_ok());
+
+        let secp256k1_key = NodeKey::new(Keypair::generate_secp256k1());
+        let signed = sign_receipt(&secp256k1_key, invocation_receipt.clone()).unwrap();
+        let secp256k1_receipt =
+            Receipt::try_with(receipt.instruction().to_owned(), &signed).unwrap();
+        assert!(verify_receipt(&secp256k1_receipt).is_ok());
+
+        // Tampering with the output changes the Cid.
+        let mut ipld = Ipld::from(signed_receipt.clone());
+        if let Ipld::Map(ref mut map) = ipld {
+            map.insert("out".into(), task::Result::Ok(Ipld::Bool(false)).into());
+        }
+        let tampered = Receipt::try_from(ipld).unwrap();
+        assert_eq!(
+            verify_receipt(&tampered),
+            Err(ReceiptError::CidMismatch(tampered.cid()))
+        );
+
+        // Re-computing the Cid of tampered content breaks the signature.
+        let tampered = Receipt::try_with(
+            Pointer::new(receipt.instruction().cid()),
+            &InvocationReceipt::from(tampered),
+        )
+        .unwrap();
+        assert_eq!(
+            verify_receipt(&tampered),
+            Err(ReceiptError::InvalidSignature(tampered.cid()))
+        );
+
+        // A signature by another key doesn't verify for the issuer.
+        let other_key = NodeKey::new(Keypair::generate_ed25519());
+        let invocation_receipt = InvocationReceipt::from(signed_receipt);
+        let forged = invocation_receipt.clone().with_signature(
+            other_key
+                .sign_payload(&invocation_receipt.signing_payload().unwrap())
+                .unwrap(),
+        );
+        let forged = Receipt::try_with(receipt.instruction().to_owned(), &forged).unwrap();
+        assert_eq!(
+            verify_receipt(&forged),
+            Err(ReceiptError::InvalidSignature(forged.cid()))
+        );
+    }
+}
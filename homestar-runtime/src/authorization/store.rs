@@ -0,0 +1,109 @@
+//! [UcanStore] resolving proof UCANs from the local database, falling back
+//! to the network (IPFS).
+
+#[cfg(feature = "ipfs")]
+use crate::network::IpfsCli;
+use crate::{db::Database, Db};
+#[cfg(feature = "ipfs")]
+use anyhow::anyhow;
+use anyhow::Result;
+use async_trait::async_trait;
+use libipld::{
+    codec::{Decode, Encode},
+    multihash::{Code, MultihashDigest},
+    prelude::Codec,
+    raw::RawCodec,
+    Cid,
+};
+use std::io::Cursor;
+#[cfg(feature = "ipfs")]
+use tracing::debug;
+use ucan::store::{UcanStore, UcanStoreConditionalSend};
+
+/// [UcanStore] over the node's [Database], with UCANs resolved over the
+/// network cached locally.
+#[derive(Clone)]
+#[allow(missing_debug_implementations)]
+pub(crate) struct ProofStore<DB: Database> {
+    db: DB,
+    #[cfg(feature = "ipfs")]
+    ipfs: Option<IpfsCli>,
+}
+
+impl<DB> ProofStore<DB>
+where
+    DB: Database,
+{
+    /// Create a new [ProofStore] resolving UCANs from the local database
+    /// only.
+    pub(crate) fn new(db: DB) -> Self {
+        Self {
+            db,
+            #[cfg(feature = "ipfs")]
+            ipfs: None,
+        }
+    }
+
+    /// Resolve UCANs missing locally from IPFS.
+    #[cfg(feature = "ipfs")]
+    pub(crate) fn with_ipfs(mut self, ipfs: Option<IpfsCli>) -> Self {
+        self.ipfs = ipfs;
+        self
+    }
+
+    #[cfg(feature = "ipfs")]
+    async fn fetch(&self, cid: &Cid) -> Result<Option<Vec<u8>>> {
+        let Some(ipfs) = &self.ipfs else {
+            return Ok(None);
+        };
+
+        debug!(
+            subject = "authorization.fetch_ucan",
+            category = "authorization",
+            cid = cid.to_string(),
+            "attempting to fetch UCAN from IPFS"
+        );
+
+        let block = ipfs.get_cid(*cid).await?;
+        let code = Code::try_from(cid.hash().code())?;
+        if code.digest(&block) != *cid.hash() {
+            return Err(anyhow!("UCAN fetched for {cid} does not match its hash"));
+        }
+
+        let token = std::str::from_utf8(&block)?;
+        Db::store_ucan(*cid, token, &mut self.db.conn()?)?;
+        Ok(Some(block))
+    }
+
+    #[cfg(not(feature = "ipfs"))]
+    async fn fetch(&self, _cid: &Cid) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl<DB> UcanStore<RawCodec> for ProofStore<DB>
+where
+    DB: Database,
+{
+    async fn read<T: Decode<RawCodec>>(&self, cid: &Cid) -> Result<Option<T>> {
+        let block = match Db::find_ucan(*cid, &mut self.db.conn()?)? {
+            Some(token) => Some(token.into_bytes()),
+            None => self.fetch(cid).await?,
+        };
+
+        block
+            .map(|block| T::decode(RawCodec, &mut Cursor::new(block)))
+            .transpose()
+    }
+
+    async fn write<T: Encode<RawCodec> + UcanStoreConditionalSend + core::fmt::Debug>(
+        &mut self,
+        token: T,
+    ) -> Result<Cid> {
+        let block = RawCodec.encode(&token)?;
+        let cid = Cid::new_v1(RawCodec.into(), Code::Blake3_256.digest(&block));
+        Db::store_ucan(cid, std::str::from_utf8(&block)?, &mut self.db.conn()?)?;
+        Ok(cid)
+    }
+}
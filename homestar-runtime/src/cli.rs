@@ -2,11 +2,14 @@
 
 use crate::{
     network::rpc::Client,
+    receipt,
     runner::{file, response},
-    KeyType,
+    workflow, KeyType, ReceiptEncoding, ReceiptFilter,
 };
 use anyhow::anyhow;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
 use clap::{ArgGroup, Args, Parser, Subcommand};
+use libipld::Cid;
 use serde::{Deserialize, Serialize};
 use std::{
     net::{IpAddr, Ipv6Addr, SocketAddr},
@@ -145,6 +148,179 @@ impl Default for RpcArgs {
     }
 }
 
+/// Filter and paging arguments for the `workflows list` command.
+#[derive(Debug, Clone, PartialEq, Args)]
+pub struct WorkflowFilterArgs {
+    /// Only list workflows with the given status.
+    #[arg(
+        long = "status",
+        value_name = "STATUS",
+        value_parser = clap::value_parser!(workflow::Status),
+        help = "Only list workflows with the given status: pending, running, completed, stuck, cancelled or failed [optional]"
+    )]
+    status: Option<workflow::Status>,
+    /// Only list workflows whose local name contains this value.
+    #[arg(
+        short = 'n',
+        long = "name",
+        value_name = "NAME",
+        help = "Only list workflows whose local name contains this value [optional]"
+    )]
+    name: Option<String>,
+    /// Only list workflows created at or after this time (UTC).
+    #[arg(
+        long = "since",
+        value_name = "DATETIME",
+        value_parser = parse_datetime,
+        help = "Only list workflows created at or after this time (UTC), e.g. 2024-01-31 or 2024-01-31 12:00:00 [optional]"
+    )]
+    since: Option<NaiveDateTime>,
+    /// Only list workflows created at or before this time (UTC).
+    #[arg(
+        long = "until",
+        value_name = "DATETIME",
+        value_parser = parse_datetime,
+        help = "Only list workflows created at or before this time (UTC), e.g. 2024-01-31 or 2024-01-31 12:00:00 [optional]"
+    )]
+    until: Option<NaiveDateTime>,
+    /// Maximum number of workflows to list.
+    #[arg(long = "limit", default_value_t = workflow::filter::DEFAULT_LIMIT)]
+    limit: u32,
+    /// Number of workflows to skip, for paging.
+    #[arg(long = "offset", default_value_t = 0)]
+    offset: u32,
+}
+
+impl From<WorkflowFilterArgs> for workflow::Filter {
+    fn from(args: WorkflowFilterArgs) -> Self {
+        let mut filter = workflow::Filter::default().with_page(args.limit, args.offset);
+        if let Some(status) = args.status {
+            filter = filter.with_status(status);
+        }
+        if let Some(name) = args.name {
+            filter = filter.with_name(name);
+        }
+        if let Some(since) = args.since {
+            filter = filter.with_since(since);
+        }
+        if let Some(until) = args.until {
+            filter = filter.with_until(until);
+        }
+        filter
+    }
+}
+
+/// Subcommands for working with stored workflows.
+#[derive(Debug, Subcommand)]
+pub enum WorkflowsCommand {
+    /// List workflows, most recently created first.
+    List {
+        /// RPC host / port arguments.
+        #[clap(flatten)]
+        args: RpcArgs,
+        /// Filter and paging arguments.
+        #[clap(flatten)]
+        filter: WorkflowFilterArgs,
+    },
+    /// Show a workflow's per-task progress and receipt Cids.
+    Show {
+        /// RPC host / port arguments.
+        #[clap(flatten)]
+        args: RpcArgs,
+        /// Cid or local name of the workflow to show.
+        #[arg(
+            value_name = "WORKFLOW",
+            index = 1,
+            required = true,
+            help = "Cid or local name of the workflow to show"
+        )]
+        workflow: String,
+    },
+}
+
+/// Subcommands for querying stored receipts.
+#[derive(Debug, Subcommand)]
+pub enum ReceiptsCommand {
+    /// Get a receipt, given its Cid.
+    Get {
+        /// RPC host / port arguments.
+        #[clap(flatten)]
+        args: RpcArgs,
+        /// Cid of the receipt.
+        #[arg(
+            value_name = "RECEIPT_CID",
+            value_parser = clap::value_parser!(Cid),
+            index = 1,
+            required = true,
+            help = "Cid of the receipt"
+        )]
+        cid: Cid,
+        /// Output encoding.
+        #[arg(long = "encoding", value_enum, default_value_t = ReceiptEncoding::DagJson)]
+        encoding: ReceiptEncoding,
+    },
+    /// List all receipts for a workflow, given its Cid.
+    List {
+        /// RPC host / port arguments.
+        #[clap(flatten)]
+        args: RpcArgs,
+        /// Cid of the workflow.
+        #[arg(
+            value_name = "WORKFLOW_CID",
+            value_parser = clap::value_parser!(Cid),
+            index = 1,
+            required = true,
+            help = "Cid of the workflow"
+        )]
+        workflow_cid: Cid,
+        /// Output encoding.
+        #[arg(long = "encoding", value_enum, default_value_t = ReceiptEncoding::DagJson)]
+        encoding: ReceiptEncoding,
+    },
+    /// Find receipts by invocation, instruction, issuer or operation.
+    Find {
+        /// RPC host / port arguments.
+        #[clap(flatten)]
+        args: RpcArgs,
+        /// Cid of the invocation the receipt ran.
+        #[arg(
+            long = "ran",
+            value_name = "CID",
+            value_parser = clap::value_parser!(Cid),
+            help = "Cid of the invocation the receipt ran [optional]"
+        )]
+        ran: Option<Cid>,
+        /// Cid of the instruction the receipt is for.
+        #[arg(
+            long = "instruction",
+            value_name = "CID",
+            value_parser = clap::value_parser!(Cid),
+            help = "Cid of the instruction the receipt is for [optional]"
+        )]
+        instruction: Option<Cid>,
+        /// Issuer (DID) of the receipt.
+        #[arg(
+            long = "issuer",
+            value_name = "DID",
+            help = "Issuer (DID) of the receipt [optional]"
+        )]
+        issuer: Option<String>,
+        /// Operation recorded in the receipt metadata, e.g. wasm/run.
+        #[arg(
+            long = "op",
+            value_name = "OP",
+            help = "Operation recorded in the receipt metadata, e.g. wasm/run [optional]"
+        )]
+        op: Option<String>,
+        /// Maximum number of receipts to return.
+        #[arg(long = "limit", default_value_t = receipt::query::DEFAULT_LIMIT)]
+        limit: u32,
+        /// Output encoding.
+        #[arg(long = "encoding", value_enum, default_value_t = ReceiptEncoding::DagJson)]
+        encoding: ReceiptEncoding,
+    },
+}
+
 /// CLI Argument types.
 #[derive(Debug, Subcommand)]
 pub enum Command {
@@ -207,21 +383,68 @@ pub enum Command {
             help = "Local name given to a workflow (optional)"
         )]
         name: Option<String>,
-        /// IPVM-configured workflow file to run.
+        /// IPVM-configured workflow file to run, or `-` to read from stdin.
         /// Supported:
         ///   - JSON (.json).
+        ///   - YAML (.yaml, .yml).
+        ///   - DAG-CBOR (.cbor, .dagcbor).
         #[arg(
             value_hint = clap::ValueHint::FilePath,
             value_name = "FILE",
             value_parser = clap::value_parser!(file::ReadWorkflow),
             index = 1,
             required = true,
-            help = r#"IPVM-configured workflow file to run.
+            help = r#"IPVM-configured workflow file to run, or `-` to read from stdin.
 Supported:
-  - JSON (.json)"#
+  - JSON (.json)
+  - YAML (.yaml, .yml)
+  - DAG-CBOR (.cbor, .dagcbor)"#
         )]
         workflow: file::ReadWorkflow,
+        /// Values for `{{name}}` placeholders in task arguments.
+        #[arg(
+            short = 'p',
+            long = "param",
+            value_name = "KEY=VALUE",
+            value_parser = parse_param,
+            help = "Value for a `{{KEY}}` placeholder in task arguments, parsed as JSON or taken as a string (repeatable)"
+        )]
+        params: Vec<(String, String)>,
+        /// Plan the workflow run without executing anything.
+        #[arg(
+            long = "plan",
+            help = "Print the batched execution plan of a workflow without executing anything"
+        )]
+        plan: bool,
+        /// Output the plan as a Graphviz DOT graph instead of a table.
+        #[arg(
+            long = "dot",
+            requires = "plan",
+            help = "Output the execution plan as a Graphviz DOT graph (requires --plan)"
+        )]
+        dot: bool,
+    },
+    /// Cancel a running workflow on the Homestar runtime.
+    Cancel {
+        /// RPC host / port arguments.
+        #[clap(flatten)]
+        args: RpcArgs,
+        /// Cid of the running workflow to cancel.
+        #[arg(
+            value_name = "WORKFLOW_CID",
+            value_parser = clap::value_parser!(Cid),
+            index = 1,
+            required = true,
+            help = "Cid of the running workflow to cancel"
+        )]
+        workflow_cid: Cid,
     },
+    /// List or show workflows stored by the Homestar runtime.
+    #[command(subcommand)]
+    Workflows(WorkflowsCommand),
+    /// Query receipts stored by the Homestar runtime.
+    #[command(subcommand)]
+    Receipts(ReceiptsCommand),
     /// Get node identity / information.
     Node {
         /// RPC host / port arguments.
@@ -240,6 +463,12 @@ impl Command {
             Command::Stop { .. } => "stop",
             Command::Ping { .. } => "ping",
             Command::Run { .. } => "run",
+            Command::Cancel { .. } => "cancel",
+            Command::Workflows(WorkflowsCommand::List { .. }) => "workflows list",
+            Command::Workflows(WorkflowsCommand::Show { .. }) => "workflows show",
+            Command::Receipts(ReceiptsCommand::Get { .. }) => "receipts get",
+            Command::Receipts(ReceiptsCommand::List { .. }) => "receipts list",
+            Command::Receipts(ReceiptsCommand::Find { .. }) => "receipts find",
             Command::Node { .. } => "node",
             Command::Info => "info",
         }
@@ -273,7 +502,34 @@ impl Command {
                 args,
                 name,
                 workflow: workflow_file,
+                params,
+                plan: true,
+                dot,
             } => {
+                let workflow_file = workflow_file.read_stdin()?.with_params(params);
+                let response = rt.block_on(async {
+                    let client = args.client().await?;
+                    let response = client
+                        .plan(name.map(|n| n.into()), workflow_file, dot)
+                        .await??;
+                    Ok::<Box<response::AckPlan>, Error>(response)
+                })?;
+
+                if dot {
+                    response.echo_dot()?;
+                } else {
+                    response.echo_table()?;
+                }
+                Ok(())
+            }
+            Command::Run {
+                args,
+                name,
+                workflow: workflow_file,
+                params,
+                ..
+            } => {
+                let workflow_file = workflow_file.read_stdin()?.with_params(params);
                 let response = rt.block_on(async {
                     let client = args.client().await?;
                     let response = client.run(name.map(|n| n.into()), workflow_file).await??;
@@ -283,6 +539,98 @@ impl Command {
                 response.echo_table()?;
                 Ok(())
             }
+            Command::Cancel { args, workflow_cid } => {
+                let response = rt.block_on(async {
+                    let client = args.client().await?;
+                    let response = client.cancel(workflow_cid).await??;
+                    Ok::<response::AckCancel, Error>(response)
+                })?;
+
+                response.echo_table()?;
+                Ok(())
+            }
+            Command::Workflows(WorkflowsCommand::List { args, filter }) => {
+                let response = rt.block_on(async {
+                    let client = args.client().await?;
+                    let response = client.list_workflows(filter.into()).await??;
+                    Ok::<response::AckWorkflows, Error>(response)
+                })?;
+
+                response.echo_table()?;
+                Ok(())
+            }
+            Command::Workflows(WorkflowsCommand::Show { args, workflow }) => {
+                let response = rt.block_on(async {
+                    let client = args.client().await?;
+                    let response = client.show_workflow(workflow.into()).await??;
+                    Ok::<Box<response::AckWorkflowDetails>, Error>(response)
+                })?;
+
+                response.echo_table()?;
+                Ok(())
+            }
+            Command::Receipts(ReceiptsCommand::Get {
+                args,
+                cid,
+                encoding,
+            }) => {
+                let response = rt.block_on(async {
+                    let client = args.client().await?;
+                    let response = client.get_receipt(cid, encoding).await??;
+                    Ok::<response::AckReceipts, Error>(response)
+                })?;
+
+                response.echo()?;
+                Ok(())
+            }
+            Command::Receipts(ReceiptsCommand::List {
+                args,
+                workflow_cid,
+                encoding,
+            }) => {
+                let response = rt.block_on(async {
+                    let client = args.client().await?;
+                    let response = client
+                        .list_receipts_for_workflow(workflow_cid, encoding)
+                        .await??;
+                    Ok::<response::AckReceipts, Error>(response)
+                })?;
+
+                response.echo()?;
+                Ok(())
+            }
+            Command::Receipts(ReceiptsCommand::Find {
+                args,
+                ran,
+                instruction,
+                issuer,
+                op,
+                limit,
+                encoding,
+            }) => {
+                let mut filter = ReceiptFilter::default().with_limit(limit);
+                if let Some(ran) = ran {
+                    filter = filter.with_ran(ran);
+                }
+                if let Some(instruction) = instruction {
+                    filter = filter.with_instruction(instruction);
+                }
+                if let Some(issuer) = issuer {
+                    filter = filter.with_issuer(issuer);
+                }
+                if let Some(op) = op {
+                    filter = filter.with_op(op);
+                }
+
+                let response = rt.block_on(async {
+                    let client = args.client().await?;
+                    let response = client.find_receipts(filter, encoding).await??;
+                    Ok::<response::AckReceipts, Error>(response)
+                })?;
+
+                response.echo()?;
+                Ok(())
+            }
             Command::Node { args } => {
                 let response = rt.block_on(async {
                     let client = args.client().await?;
@@ -298,6 +646,28 @@ impl Command {
     }
 }
 
+/// Parse a UTC datetime given as RFC 3339, `%Y-%m-%d %H:%M:%S` or
+/// `%Y-%m-%d`.
+fn parse_datetime(s: &str) -> Result<NaiveDateTime, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d").map(|date| date.and_time(NaiveTime::default()))
+        })
+        .map_err(|_| format!("invalid datetime: {s}"))
+}
+
+/// Parse a workflow parameter given as `key=value`.
+fn parse_param(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("invalid parameter, expected KEY=VALUE: {s}")),
+    }
+}
+
 impl RpcArgs {
     async fn client(&self) -> Result<Client, Error> {
         let addr = SocketAddr::new(self.host, self.port);
@@ -4,7 +4,7 @@ use crate::{
     db::utils::Health,
     settings,
     workflow::{self, StoredReceipt},
-    Receipt,
+    Receipt, ReceiptFilter,
 };
 use anyhow::Result;
 use byte_unit::{AdjustedByte, Byte, ByteUnit};
@@ -12,7 +12,7 @@ use diesel::{
     dsl::now,
     r2d2::{self, CustomizeConnection, ManageConnection},
     BelongingToDsl, Connection as SingleConnection, ExpressionMethods, OptionalExtension, QueryDsl,
-    RunQueryDsl, SelectableHelper, SqliteConnection,
+    RunQueryDsl, SelectableHelper, SqliteConnection, TextExpressionMethods,
 };
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use dotenvy::dotenv;
@@ -185,6 +185,9 @@ pub trait Database: Send + Sync + Clone {
 
     /// Find receipts given a set of [Instruction] [Pointer]s, which is indexed.
     ///
+    /// Receipts that aren't [cacheable] are ignored.
+    ///
+    /// [cacheable]: Receipt::cacheable
     /// [Instruction]: homestar_invocation::task::Instruction
     fn find_instruction_pointers(
         pointers: &Vec<Pointer>,
@@ -192,11 +195,15 @@ pub trait Database: Send + Sync + Clone {
     ) -> Result<Vec<Receipt>, diesel::result::Error> {
         schema::receipts::dsl::receipts
             .filter(schema::receipts::instruction.eq_any(pointers))
+            .filter(schema::receipts::cacheable.eq(true))
             .load(conn)
     }
 
     /// Find receipt for a given [Instruction] Cid, which is indexed.
     ///
+    /// Receipts that aren't [cacheable] are ignored.
+    ///
+    /// [cacheable]: Receipt::cacheable
     /// [Instruction]: homestar_invocation::task::Instruction
     fn find_instruction_by_cid(
         cid: Cid,
@@ -204,6 +211,7 @@ pub trait Database: Send + Sync + Clone {
     ) -> Result<Receipt, diesel::result::Error> {
         schema::receipts::dsl::receipts
             .filter(schema::receipts::instruction.eq(Pointer::new(cid)))
+            .filter(schema::receipts::cacheable.eq(true))
             .first(conn)
     }
 
@@ -228,6 +236,41 @@ pub trait Database: Send + Sync + Clone {
             .load(conn)
     }
 
+    /// Find receipts matching a [receipt query filter].
+    ///
+    /// Receipt metadata is stored encoded, so any operation filter is
+    /// applied after loading, before limiting results.
+    ///
+    /// [receipt query filter]: crate::ReceiptFilter
+    fn find_receipts(
+        filter: &ReceiptFilter,
+        conn: &mut Connection,
+    ) -> Result<Vec<Receipt>, diesel::result::Error> {
+        let mut query = schema::receipts::dsl::receipts
+            .select(Receipt::as_select())
+            .into_boxed();
+
+        if let Some(ran) = filter.ran {
+            query = query.filter(schema::receipts::ran.eq(Pointer::new(ran)));
+        }
+        if let Some(instruction) = filter.instruction {
+            query = query.filter(schema::receipts::instruction.eq(Pointer::new(instruction)));
+        }
+        if let Some(issuer) = &filter.issuer {
+            query = query.filter(schema::receipts::issuer.eq(issuer.clone()));
+        }
+        if filter.op.is_none() {
+            query = query.limit(filter.limit.into());
+        }
+
+        Ok(query
+            .load(conn)?
+            .into_iter()
+            .filter(|receipt| filter.matches_op(receipt))
+            .take(filter.limit as usize)
+            .collect())
+    }
+
     /// Store localized workflow cid and information, e.g. number of tasks.
     ///
     /// On conflicts, do nothing.
@@ -263,6 +306,19 @@ pub trait Database: Send + Sync + Clone {
         Ok(())
     }
 
+    /// Increment the number of (automatic) retries of a workflow, returning
+    /// the updated count.
+    fn increment_workflow_retries(
+        workflow_cid: Cid,
+        conn: &mut Connection,
+    ) -> Result<i32, diesel::result::Error> {
+        diesel::update(schema::workflows::dsl::workflows)
+            .filter(schema::workflows::cid.eq(Pointer::new(workflow_cid)))
+            .set(schema::workflows::retries.eq(schema::workflows::retries + 1))
+            .returning(schema::workflows::retries)
+            .get_result(conn)
+    }
+
     /// Store workflow Cid and [Receipt] Cid in the database for inner join.
     fn store_workflow_receipt(
         workflow_cid: Cid,
@@ -312,6 +368,71 @@ pub trait Database: Send + Sync + Clone {
             .get_result(conn)
     }
 
+    /// Select the most recently created workflow with the given local name.
+    fn select_workflow_by_name(
+        name: &str,
+        conn: &mut Connection,
+    ) -> Result<workflow::Stored, diesel::result::Error> {
+        schema::workflows::dsl::workflows
+            .filter(schema::workflows::name.eq(name))
+            .order(schema::workflows::created_at.desc())
+            .select(workflow::Stored::as_select())
+            .first(conn)
+    }
+
+    /// List workflows matching a [workflow::Filter], most recently created
+    /// first.
+    fn list_workflows(
+        filter: &workflow::Filter,
+        conn: &mut Connection,
+    ) -> Result<Vec<workflow::Stored>, diesel::result::Error> {
+        let mut query = schema::workflows::dsl::workflows
+            .select(workflow::Stored::as_select())
+            .into_boxed();
+
+        if let Some(status) = &filter.status {
+            query = query.filter(schema::workflows::status.eq(status.clone()));
+        }
+        if let Some(name) = &filter.name {
+            query = query.filter(schema::workflows::name.like(format!("%{name}%")));
+        }
+        if let Some(since) = filter.since_datetime() {
+            query = query.filter(schema::workflows::created_at.ge(since));
+        }
+        if let Some(until) = filter.until_datetime() {
+            query = query.filter(schema::workflows::created_at.le(until));
+        }
+
+        query
+            .order(schema::workflows::created_at.desc())
+            .limit(filter.limit.into())
+            .offset(filter.offset.into())
+            .load(conn)
+    }
+
+    /// Count the number of receipts stored for a workflow.
+    fn count_workflow_receipts(
+        workflow_cid: Cid,
+        conn: &mut Connection,
+    ) -> Result<i64, diesel::result::Error> {
+        schema::workflows_receipts::dsl::workflows_receipts
+            .filter(schema::workflows_receipts::workflow_cid.eq(Pointer::new(workflow_cid)))
+            .count()
+            .get_result(conn)
+    }
+
+    /// Find all [Receipt]s stored for a workflow.
+    fn find_workflow_receipts(
+        workflow_cid: Cid,
+        conn: &mut Connection,
+    ) -> Result<Vec<Receipt>, diesel::result::Error> {
+        schema::workflows_receipts::dsl::workflows_receipts
+            .inner_join(schema::receipts::table)
+            .filter(schema::workflows_receipts::workflow_cid.eq(Pointer::new(workflow_cid)))
+            .select(Receipt::as_select())
+            .load(conn)
+    }
+
     /// Return workflow information with number of receipts emitted.
     fn get_workflow_info(
         workflow_cid: Cid,
@@ -333,6 +454,204 @@ pub trait Database: Send + Sync + Clone {
         Ok((name, info))
     }
 
+    /// Store a UCAN token under its Cid, as referenced by the proofs of
+    /// tasks or other UCANs.
+    ///
+    /// On conflicts, do nothing.
+    fn store_ucan(
+        cid: Cid,
+        token: &str,
+        conn: &mut Connection,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::insert_into(schema::ucans::table)
+            .values((
+                schema::ucans::cid.eq(Pointer::new(cid)),
+                schema::ucans::token.eq(token),
+            ))
+            .on_conflict(schema::ucans::cid)
+            .do_nothing()
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Find a UCAN token by its Cid.
+    fn find_ucan(cid: Cid, conn: &mut Connection) -> Result<Option<String>, diesel::result::Error> {
+        schema::ucans::dsl::ucans
+            .filter(schema::ucans::cid.eq(Pointer::new(cid)))
+            .select(schema::ucans::token)
+            .first(conn)
+            .optional()
+    }
+
+    /// Store an IPLD block under its Cid.
+    ///
+    /// On conflicts, do nothing.
+    fn store_block(
+        cid: Cid,
+        data: &[u8],
+        conn: &mut Connection,
+    ) -> Result<(), diesel::result::Error> {
+        diesel::insert_into(schema::blocks::table)
+            .values((
+                schema::blocks::cid.eq(Pointer::new(cid)),
+                schema::blocks::data.eq(data),
+            ))
+            .on_conflict(schema::blocks::cid)
+            .do_nothing()
+            .execute(conn)?;
+
+        Ok(())
+    }
+
+    /// Find an IPLD block by its Cid.
+    fn find_block(
+        cid: Cid,
+        conn: &mut Connection,
+    ) -> Result<Option<Vec<u8>>, diesel::result::Error> {
+        schema::blocks::dsl::blocks
+            .filter(schema::blocks::cid.eq(Pointer::new(cid)))
+            .select(schema::blocks::data)
+            .first(conn)
+            .optional()
+    }
+
+    /// Store the IPLD blocks written by the task of a [Receipt], pinning
+    /// them with the [Receipt].
+    ///
+    /// On conflicts, do nothing.
+    fn store_receipt_blocks<'b>(
+        receipt_cid: Cid,
+        blocks: impl IntoIterator<Item = (&'b Cid, &'b Vec<u8>)>,
+        conn: &mut Connection,
+    ) -> Result<(), diesel::result::Error> {
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            for (cid, data) in blocks {
+                Self::store_block(*cid, data, conn)?;
+                diesel::insert_into(schema::receipts_blocks::table)
+                    .values((
+                        schema::receipts_blocks::receipt_cid.eq(Pointer::new(receipt_cid)),
+                        schema::receipts_blocks::block_cid.eq(Pointer::new(*cid)),
+                    ))
+                    .on_conflict((
+                        schema::receipts_blocks::receipt_cid,
+                        schema::receipts_blocks::block_cid,
+                    ))
+                    .do_nothing()
+                    .execute(conn)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Find the IPLD blocks pinned with a [Receipt], by their Cid.
+    fn find_receipt_blocks(
+        receipt_cid: Cid,
+        conn: &mut Connection,
+    ) -> Result<Vec<(Cid, Vec<u8>)>, diesel::result::Error> {
+        schema::receipts_blocks::dsl::receipts_blocks
+            .inner_join(schema::blocks::table)
+            .filter(schema::receipts_blocks::receipt_cid.eq(Pointer::new(receipt_cid)))
+            .select((schema::blocks::cid, schema::blocks::data))
+            .load::<(Pointer, Vec<u8>)>(conn)
+            .map(|blocks| {
+                blocks
+                    .into_iter()
+                    .map(|(pointer, data)| (pointer.cid(), data))
+                    .collect()
+            })
+    }
+
+    /// Find the value of a key in a key-value namespace.
+    fn find_kv(
+        namespace: &str,
+        key: &str,
+        conn: &mut Connection,
+    ) -> Result<Option<Vec<u8>>, diesel::result::Error> {
+        schema::kv::dsl::kv
+            .filter(schema::kv::namespace.eq(namespace))
+            .filter(schema::kv::key.eq(key))
+            .select(schema::kv::value)
+            .first(conn)
+            .optional()
+    }
+
+    /// Set the value of a key in a key-value namespace, unless the size of
+    /// the namespace's keys and values would exceed its quota, in bytes.
+    ///
+    /// Returns whether the value was set.
+    fn store_kv(
+        namespace: &str,
+        key: &str,
+        value: &[u8],
+        quota: u64,
+        conn: &mut Connection,
+    ) -> Result<bool, diesel::result::Error> {
+        let size = (key.len() + value.len()) as i64;
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let used: i64 = schema::kv::dsl::kv
+                .filter(schema::kv::namespace.eq(namespace))
+                .filter(schema::kv::key.ne(key))
+                .select(schema::kv::size)
+                .load::<i64>(conn)?
+                .into_iter()
+                .sum();
+            if used + size > quota as i64 {
+                return Ok(false);
+            }
+
+            diesel::insert_into(schema::kv::table)
+                .values((
+                    schema::kv::namespace.eq(namespace),
+                    schema::kv::key.eq(key),
+                    schema::kv::value.eq(value),
+                    schema::kv::size.eq(size),
+                ))
+                .on_conflict((schema::kv::namespace, schema::kv::key))
+                .do_update()
+                .set((schema::kv::value.eq(value), schema::kv::size.eq(size)))
+                .execute(conn)?;
+
+            Ok(true)
+        })
+    }
+
+    /// Delete a key from a key-value namespace, returning whether it was
+    /// set.
+    fn delete_kv(
+        namespace: &str,
+        key: &str,
+        conn: &mut Connection,
+    ) -> Result<bool, diesel::result::Error> {
+        diesel::delete(
+            schema::kv::dsl::kv
+                .filter(schema::kv::namespace.eq(namespace))
+                .filter(schema::kv::key.eq(key)),
+        )
+        .execute(conn)
+        .map(|deleted| deleted > 0)
+    }
+
+    /// Find the keys of a key-value namespace starting with a prefix, in
+    /// order.
+    fn find_kv_keys(
+        namespace: &str,
+        prefix: &str,
+        conn: &mut Connection,
+    ) -> Result<Vec<String>, diesel::result::Error> {
+        schema::kv::dsl::kv
+            .filter(schema::kv::namespace.eq(namespace))
+            .select(schema::kv::key)
+            .order(schema::kv::key.asc())
+            .load::<String>(conn)
+            .map(|keys| {
+                keys.into_iter()
+                    .filter(|key| key.starts_with(prefix))
+                    .collect()
+            })
+    }
+
     /// Update the local (view) name of a workflow.
     fn update_local_name(name: &str, conn: &mut Connection) -> Result<(), diesel::result::Error> {
         diesel::update(schema::workflows::dsl::workflows)
@@ -405,6 +724,9 @@ where
 mod test {
     use super::*;
     use crate::test_utils::db::MemoryDb;
+    use chrono::{Duration as ChronoDuration, Utc};
+    use indexmap::IndexMap;
+    use libipld::multihash::{Code, MultihashDigest};
 
     #[homestar_runtime_proc_macro::db_async_test]
     fn check_pragmas_memory_db() {
@@ -431,4 +753,196 @@ mod test {
 
         assert_eq!(busy_timeout, vec!["1000".to_string()]);
     }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn list_workflows_with_filter() {
+        let settings = TestSettings::load();
+
+        let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+        let mut conn = db.conn().unwrap();
+
+        let timestamp = Utc::now().naive_utc();
+        for (i, name) in ["alpha", "beta", "alphabet"].iter().enumerate() {
+            let cid = Cid::new_v1(0x55, Code::Sha3_256.digest(name.as_bytes()));
+            let stored = workflow::Stored::new(
+                Pointer::new(cid),
+                Some(name.to_string()),
+                1,
+                Default::default(),
+                timestamp - ChronoDuration::try_hours(i as i64).unwrap(),
+            );
+            MemoryDb::store_workflow(stored, &mut conn).unwrap();
+            if *name == "beta" {
+                MemoryDb::set_workflow_status(cid, workflow::Status::Completed, &mut conn).unwrap();
+            }
+        }
+
+        let all = MemoryDb::list_workflows(&workflow::Filter::default(), &mut conn).unwrap();
+        assert_eq!(
+            all.iter()
+                .map(|stored| stored.name.clone().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["alpha", "beta", "alphabet"]
+        );
+
+        let by_name =
+            MemoryDb::list_workflows(&workflow::Filter::default().with_name("alpha"), &mut conn)
+                .unwrap();
+        assert_eq!(by_name.len(), 2);
+
+        let by_status = MemoryDb::list_workflows(
+            &workflow::Filter::default().with_status(workflow::Status::Completed),
+            &mut conn,
+        )
+        .unwrap();
+        assert_eq!(by_status.len(), 1);
+        assert_eq!(by_status[0].name, Some("beta".to_string()));
+
+        let since = MemoryDb::list_workflows(
+            &workflow::Filter::default()
+                .with_since(timestamp - ChronoDuration::try_minutes(90).unwrap()),
+            &mut conn,
+        )
+        .unwrap();
+        assert_eq!(since.len(), 2);
+
+        let paged =
+            MemoryDb::list_workflows(&workflow::Filter::default().with_page(1, 1), &mut conn)
+                .unwrap();
+        assert_eq!(paged.len(), 1);
+        assert_eq!(paged[0].name, Some("beta".to_string()));
+
+        let latest = MemoryDb::select_workflow_by_name("alphabet", &mut conn).unwrap();
+        assert_eq!(latest.status, workflow::Status::Pending);
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn find_receipts_with_filter() {
+        let settings = TestSettings::load();
+
+        let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+        let mut conn = db.conn().unwrap();
+
+        let (_, receipt) = crate::test_utils::receipt::receipts();
+        MemoryDb::store_receipt(receipt.clone(), &mut conn).unwrap();
+
+        let ran = Cid::try_from(receipt.ran()).unwrap();
+        let by_ran =
+            MemoryDb::find_receipts(&ReceiptFilter::default().with_ran(ran), &mut conn).unwrap();
+        assert_eq!(by_ran, vec![receipt.clone()]);
+
+        let by_instruction = MemoryDb::find_receipts(
+            &ReceiptFilter::default().with_instruction(receipt.instruction().cid()),
+            &mut conn,
+        )
+        .unwrap();
+        assert_eq!(by_instruction, vec![receipt.clone()]);
+
+        let by_issuer = MemoryDb::find_receipts(
+            &ReceiptFilter::default().with_issuer("did:key:z6MkmissingIssuer"),
+            &mut conn,
+        )
+        .unwrap();
+        assert!(by_issuer.is_empty());
+
+        let by_op = MemoryDb::find_receipts(
+            &ReceiptFilter::default()
+                .with_instruction(receipt.instruction().cid())
+                .with_op("wasm/run"),
+            &mut conn,
+        )
+        .unwrap();
+        assert!(by_op.is_empty());
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn ignore_non_cacheable_receipts() {
+        let settings = TestSettings::load();
+
+        let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+        let mut conn = db.conn().unwrap();
+
+        let (_, receipt) = crate::test_utils::receipt::receipts();
+        let stored = MemoryDb::store_receipt(receipt.clone().non_cacheable(), &mut conn)
+            .unwrap()
+            .unwrap();
+        assert!(!stored.cacheable());
+
+        let instruction = receipt.instruction().to_owned();
+        assert!(
+            MemoryDb::find_instruction_pointers(&vec![instruction.clone()], &mut conn)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(MemoryDb::find_instruction_by_cid(instruction.cid(), &mut conn).is_err());
+        assert_eq!(
+            MemoryDb::find_receipt_by_cid(receipt.cid(), &mut conn).unwrap(),
+            stored
+        );
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn store_and_find_receipt_blocks() {
+        let settings = TestSettings::load();
+
+        let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+        let mut conn = db.conn().unwrap();
+
+        let (_, receipt) = crate::test_utils::receipt::receipts();
+        MemoryDb::store_receipt(receipt.clone(), &mut conn).unwrap();
+
+        let block = b"block".to_vec();
+        let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(&block));
+        let blocks = IndexMap::from([(cid, block.clone())]);
+        MemoryDb::store_receipt_blocks(receipt.cid(), &blocks, &mut conn).unwrap();
+        MemoryDb::store_receipt_blocks(receipt.cid(), &blocks, &mut conn).unwrap();
+
+        assert_eq!(
+            MemoryDb::find_block(cid, &mut conn).unwrap(),
+            Some(block.clone())
+        );
+        assert_eq!(
+            MemoryDb::find_receipt_blocks(receipt.cid(), &mut conn).unwrap(),
+            vec![(cid, block)]
+        );
+
+        let missing = Cid::new_v1(0x55, Code::Sha2_256.digest(b"missing"));
+        assert_eq!(MemoryDb::find_block(missing, &mut conn).unwrap(), None);
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn store_and_find_kv() {
+        let settings = TestSettings::load();
+
+        let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+        let mut conn = db.conn().unwrap();
+
+        assert!(MemoryDb::store_kv("ns", "count", b"1", 20, &mut conn).unwrap());
+        assert!(MemoryDb::store_kv("ns", "cursor", b"abc", 20, &mut conn).unwrap());
+        assert!(MemoryDb::store_kv("other", "count", b"2", 20, &mut conn).unwrap());
+        assert_eq!(
+            MemoryDb::find_kv("ns", "count", &mut conn).unwrap(),
+            Some(b"1".to_vec())
+        );
+        assert_eq!(
+            MemoryDb::find_kv_keys("ns", "c", &mut conn).unwrap(),
+            vec!["count".to_string(), "cursor".to_string()]
+        );
+
+        // Replacing a value only counts its new size against the quota.
+        assert!(MemoryDb::store_kv("ns", "count", b"1234", 20, &mut conn).unwrap());
+        assert!(!MemoryDb::store_kv("ns", "count", b"12345678", 20, &mut conn).unwrap());
+        assert_eq!(
+            MemoryDb::find_kv("ns", "count", &mut conn).unwrap(),
+            Some(b"1234".to_vec())
+        );
+
+        assert!(MemoryDb::delete_kv("ns", "count", &mut conn).unwrap());
+        assert!(!MemoryDb::delete_kv("ns", "count", &mut conn).unwrap());
+        assert_eq!(MemoryDb::find_kv("ns", "count", &mut conn).unwrap(), None);
+        assert_eq!(
+            MemoryDb::find_kv("other", "count", &mut conn).unwrap(),
+            Some(b"2".to_vec())
+        );
+    }
 }
@@ -1,5 +1,39 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    blocks (cid) {
+        cid -> Text,
+        data -> Binary,
+    }
+}
+
+diesel::table! {
+    dht_providers (key, provider) {
+        key -> Binary,
+        provider -> Binary,
+        addresses -> Text,
+        expires_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    dht_records (key) {
+        key -> Binary,
+        value -> Binary,
+        publisher -> Nullable<Binary>,
+        expires_at -> Nullable<BigInt>,
+    }
+}
+
+diesel::table! {
+    kv (namespace, key) {
+        namespace -> Text,
+        key -> Text,
+        value -> Binary,
+        size -> BigInt,
+    }
+}
+
 diesel::table! {
     receipts (cid) {
         cid -> Text,
@@ -10,6 +44,22 @@ diesel::table! {
         issuer -> Nullable<Text>,
         prf -> Binary,
         version -> Text,
+        signature -> Nullable<Binary>,
+        cacheable -> Bool,
+    }
+}
+
+diesel::table! {
+    receipts_blocks (receipt_cid, block_cid) {
+        receipt_cid -> Text,
+        block_cid -> Text,
+    }
+}
+
+diesel::table! {
+    ucans (cid) {
+        cid -> Text,
+        token -> Text,
     }
 }
 
@@ -33,11 +83,19 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(receipts_blocks -> blocks (block_cid));
+diesel::joinable!(receipts_blocks -> receipts (receipt_cid));
 diesel::joinable!(workflows_receipts -> receipts (receipt_cid));
 diesel::joinable!(workflows_receipts -> workflows (workflow_cid));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    blocks,
+    dht_providers,
+    dht_records,
+    kv,
     receipts,
+    receipts_blocks,
+    ucans,
     workflows,
     workflows_receipts,
 );
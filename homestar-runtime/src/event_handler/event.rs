@@ -384,11 +384,11 @@ impl Captured {
             )
         }
 
-        // short-circuit if no peers
+        // short-circuit if no peers, or the receipt isn't cacheable
         //
         // - don't gossip receipt
         // - don't store receipt or workflow info on DHT
-        if event_handler.connections.peers.is_empty() {
+        if event_handler.connections.peers.is_empty() || !receipt.cacheable() {
             return Ok((self.receipt, invocation_receipt));
         }
 
@@ -735,6 +735,19 @@ where
                 if let Ok((cid, receipt)) = captured.publish_and_notify(event_handler) {
                     #[cfg(not(feature = "test-utils"))]
                     {
+                        let blocks = event_handler
+                            .db
+                            .conn()
+                            .and_then(|mut conn| Ok(Db::find_receipt_blocks(cid, &mut conn)?))
+                            .unwrap_or_else(|err| {
+                                warn!(subject = "ipfs.put.block.err",
+                                      category = "handle_event",
+                                      error=?err,
+                                      cid=cid.to_string(),
+                                      "failed to find IPLD blocks pinned with receipt");
+                                vec![]
+                            });
+
                         // Spawn client call in the background, without awaiting.
                         let handle = Handle::current();
                         let ipfs = ipfs.clone();
@@ -767,6 +780,27 @@ where
                                     "failed to convert receipt to bytes"
                                 );
                             }
+
+                            for (block_cid, block) in blocks {
+                                match ipfs.put_block(block_cid, block).await {
+                                    Ok(put_cid) => {
+                                        info!(
+                                            subject = "ipfs.put.block",
+                                            category = "handle_event",
+                                            cid = put_cid,
+                                            receipt_cid = cid.to_string(),
+                                            "IPLD block stored and pinned"
+                                        );
+                                    }
+                                    Err(err) => {
+                                        warn!(subject = "ipfs.put.block.err",
+                                              category = "handle_event",
+                                              error=?err,
+                                              cid=block_cid.to_string(),
+                                              "failed to store IPLD block");
+                                    }
+                                }
+                            }
                         });
                     }
                 } else {
@@ -79,8 +79,19 @@ impl JsonSchema for ReceiptNotification {
             })),
             object: Some(Box::new(ObjectValidation {
                 properties: BTreeMap::from([
+                    ("fuel_consumed".to_owned(), <u64>::json_schema(gen)),
+                    (
+                        "logs".to_owned(),
+                        gen.subschema_for::<schema::IpldLinkStub>(),
+                    ),
+                    (
+                        "module".to_owned(),
+                        gen.subschema_for::<schema::IpldLinkStub>(),
+                    ),
                     ("name".to_owned(), <String>::json_schema(gen)),
+                    ("peak_memory".to_owned(), <u64>::json_schema(gen)),
                     ("replayed".to_owned(), <bool>::json_schema(gen)),
+                    ("wall_time".to_owned(), <u64>::json_schema(gen)),
                     (
                         "workflow".to_owned(),
                         gen.subschema_for::<schema::IpldLinkStub>(),
@@ -0,0 +1,121 @@
+//! Notification of log records captured from running tasks.
+
+use const_format::formatcp;
+use homestar_invocation::ipld::{schema, DagJson};
+use homestar_wasm::wasmtime::logs::LogRecord;
+use libipld::{ipld, Cid, Ipld};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, ObjectValidation, Schema, SchemaObject, SingleOrVec},
+    JsonSchema,
+};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    module_path,
+};
+
+/// A log record of a task, streamed out for websocket notifications as the
+/// task logs it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskLogNotification(Ipld);
+
+impl TaskLogNotification {
+    /// Obtain a reference to the inner Ipld value.
+    #[allow(dead_code)]
+    pub(crate) fn inner(&self) -> &Ipld {
+        &self.0
+    }
+
+    /// Create a new [TaskLogNotification].
+    pub(crate) fn with(workflow: Cid, instruction: Cid, record: &LogRecord) -> Self {
+        let data = ipld!({
+            "workflow": workflow,
+            "instruction": instruction,
+            "level": record.level().to_string(),
+            "context": record.context(),
+            "message": record.message(),
+        });
+        TaskLogNotification(data)
+    }
+}
+
+impl DagJson for TaskLogNotification where Ipld: From<TaskLogNotification> {}
+
+impl From<TaskLogNotification> for Ipld {
+    fn from(notification: TaskLogNotification) -> Self {
+        notification.0
+    }
+}
+
+impl From<Ipld> for TaskLogNotification {
+    fn from(ipld: Ipld) -> Self {
+        TaskLogNotification(ipld)
+    }
+}
+
+impl JsonSchema for TaskLogNotification {
+    fn schema_name() -> String {
+        "task_log_notification".to_owned()
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Borrowed(formatcp!("{}::TaskLogNotification", module_path!()))
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let level_schema = SchemaObject {
+            instance_type: Some(SingleOrVec::Single(InstanceType::String.into())),
+            metadata: Some(Box::new(Metadata {
+                description: Some("Level of the log record".to_string()),
+                ..Default::default()
+            })),
+            enum_values: Some(vec![
+                "trace".into(),
+                "debug".into(),
+                "info".into(),
+                "warn".into(),
+                "error".into(),
+                "critical".into(),
+            ]),
+            ..Default::default()
+        };
+
+        let schema = SchemaObject {
+            instance_type: Some(SingleOrVec::Single(InstanceType::Object.into())),
+            metadata: Some(Box::new(Metadata {
+                title: Some("Task log notification".to_string()),
+                description: Some(
+                    "A log record of a task associated with a running workflow".to_string(),
+                ),
+                ..Default::default()
+            })),
+            object: Some(Box::new(ObjectValidation {
+                properties: BTreeMap::from([
+                    (
+                        "workflow".to_owned(),
+                        gen.subschema_for::<schema::IpldLinkStub>(),
+                    ),
+                    (
+                        "instruction".to_owned(),
+                        gen.subschema_for::<schema::IpldLinkStub>(),
+                    ),
+                    ("level".to_owned(), Schema::Object(level_schema)),
+                    ("context".to_owned(), <String>::json_schema(gen)),
+                    ("message".to_owned(), <String>::json_schema(gen)),
+                ]),
+                required: BTreeSet::from([
+                    "workflow".to_string(),
+                    "instruction".to_string(),
+                    "level".to_string(),
+                    "context".to_string(),
+                    "message".to_string(),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+
+        schema.into()
+    }
+}
@@ -0,0 +1,45 @@
+//! Notification for workflow status changes.
+
+use crate::workflow;
+use faststr::FastStr;
+use homestar_invocation::ipld::DagJson;
+use libipld::{ipld, Cid, Ipld};
+
+/// A [Workflow] status change that is sent out for websocket
+/// notifications, e.g. when a workflow run is cancelled.
+///
+/// [Workflow]: homestar_workflow::Workflow
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkflowNotification(Ipld);
+
+impl WorkflowNotification {
+    /// Obtain a reference to the inner Ipld value.
+    #[allow(dead_code)]
+    pub(crate) fn inner(&self) -> &Ipld {
+        &self.0
+    }
+
+    /// Create a new [WorkflowNotification].
+    pub(crate) fn with(cid: Cid, name: Option<FastStr>, status: workflow::Status) -> Self {
+        let data = ipld!({
+            "workflow": cid,
+            "name": name.map_or(Ipld::Null, |name| Ipld::String(name.to_string())),
+            "status": status.to_string(),
+        });
+        WorkflowNotification(data)
+    }
+}
+
+impl DagJson for WorkflowNotification where Ipld: From<WorkflowNotification> {}
+
+impl From<WorkflowNotification> for Ipld {
+    fn from(notification: WorkflowNotification) -> Self {
+        notification.0
+    }
+}
+
+impl From<Ipld> for WorkflowNotification {
+    fn from(ipld: Ipld) -> Self {
+        WorkflowNotification(ipld)
+    }
+}
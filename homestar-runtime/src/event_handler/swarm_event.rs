@@ -6,6 +6,7 @@ use crate::event_handler::notification::{self, NetworkNotification};
 #[cfg(feature = "ipfs")]
 use crate::network::IpfsCli;
 use crate::{
+    authorization::verify_receipt,
     db::Database,
     event_handler::{
         cache::{self, CacheData, CacheValue},
@@ -47,6 +48,7 @@ pub(crate) use record::*;
 
 const RENDEZVOUS_PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/rendezvous/1.0.0");
 const RENDEZVOUS_NAMESPACE: &str = "homestar";
+const RECEIPTS_DROPPED_METRIC: &str = "network_receipts_dropped";
 
 /// Internal events within the [SwarmEvent] context related to finding results
 /// on the DHT.
@@ -629,6 +631,16 @@ async fn handle_swarm_event<DB: Database>(
                             receipt.cid()
                         );
 
+                        if let Err(err) = verify_receipt(&receipt) {
+                            warn!(subject = "libp2p.gossipsub.recv.err",
+                                  category = "handle_swarm_event",
+                                  peer_id = propagation_source.to_string(),
+                                  err=?err,
+                                  "dropping unverifiable receipt received over gossipsub");
+                            metrics::increment_counter!(RECEIPTS_DROPPED_METRIC, "source" => "pubsub");
+                            return;
+                        }
+
                         // Store gossiped receipt.
                         let _ = event_handler
                             .db
@@ -738,6 +750,23 @@ async fn handle_swarm_event<DB: Database>(
 
                             match decoded_record {
                                 DecodedRecord::Receipt(ReceiptRecord { peer_id, receipt }) => {
+                                    if let Err(err) = verify_receipt(&receipt) {
+                                        warn!(subject = "libp2p.kad.get_record.err",
+                                              category = "handle_swarm_event",
+                                              cid = receipt.cid().to_string(),
+                                              err=?err,
+                                              "dropping unverifiable receipt record found on DHT");
+                                        metrics::increment_counter!(RECEIPTS_DROPPED_METRIC, "source" => "dht");
+
+                                        if let Some(sender) = sender {
+                                            let _ = sender
+                                                .send_async(ResponseEvent::Found(Err(err.into())))
+                                                .await;
+                                        }
+
+                                        return;
+                                    }
+
                                     let response_event = ResponseEvent::Found(Ok(
                                         FoundEvent::Receipt(ReceiptEvent {
                                             peer_id,
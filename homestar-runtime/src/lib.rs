@@ -49,6 +49,7 @@
 //! [tokio console]: https://github.com/tokio-rs/console/tree/main/tokio-console
 //! [Wasmtime]: https://github.com/bytecodealliance/wasmtime
 
+mod authorization;
 pub mod channel;
 pub mod cli;
 pub mod daemon;
@@ -66,6 +67,7 @@ mod tasks;
 #[cfg(any(test, feature = "test-utils"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
 pub mod test_utils;
+mod tls;
 mod worker;
 pub mod workflow;
 
@@ -74,19 +76,30 @@ pub(crate) mod libp2p;
 pub use logger::*;
 pub(crate) mod metrics;
 #[cfg(feature = "websocket-notify")]
-pub use event_handler::notification::{network::NetworkNotification, receipt::ReceiptNotification};
+pub use event_handler::notification::{
+    network::NetworkNotification, receipt::ReceiptNotification, task_log::TaskLogNotification,
+};
 #[allow(unused_imports)]
 pub(crate) use event_handler::EventHandler;
 pub use network::webserver::{listener, PrometheusData};
-pub use receipt::{Receipt, RECEIPT_TAG, VERSION_KEY};
+pub use receipt::{
+    query::{Encoding as ReceiptEncoding, Filter as ReceiptFilter},
+    Receipt, RECEIPT_TAG, VERSION_KEY,
+};
 pub use runner::{NodeInfo, Runner};
 pub(crate) use scheduler::TaskScheduler;
 #[cfg(feature = "ipfs")]
 pub use settings::IpfsBuilder;
 pub use settings::{
-    Autonat, DatabaseBuilder, Dht, ExistingKeyPath, KeyType, Libp2p, Mdns, MetricsBuilder,
-    MonitoringBuilder, NetworkBuilder, NodeBuilder, PubkeyConfig, Pubsub, RNGSeed, Rendezvous,
-    RpcBuilder, Settings, SettingsBuilder, WebserverBuilder,
+    Authorization, AuthorizationBuilder, AuthorizationMode, Autonat, DatabaseBuilder, Dht,
+    DhtRecordStore, DidGrant, ExistingKeyPath, Http, HttpBuilder, KeyType, Kv, KvBuilder, KvScope,
+    Libp2p, Mdns, MetricsBuilder, ModuleCache, ModuleCacheBuilder, MonitoringBuilder,
+    NetworkBuilder, NodeBuilder, PubkeyConfig, Pubsub, RNGSeed, Rendezvous, RpcBuilder,
+    SecretGrant, Secrets, SecretsBuilder, Settings, SettingsBuilder, TaskExecution,
+    TaskExecutionBuilder, TaskHost, TaskHostBuilder, TaskLimits, TaskLimitsBuilder, TaskLogs,
+    TaskLogsBuilder, Tls, TlsBuilder, TokenGrant, WebserverAuth, WebserverAuthBuilder,
+    WebserverBuilder,
 };
+pub use tasks::{Executor, ExecutorInput, ExecutorRegistry, Usage};
 pub(crate) use worker::Worker;
 pub use workflow::WORKFLOW_TAG;
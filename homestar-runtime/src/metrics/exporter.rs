@@ -2,48 +2,72 @@
 
 #[cfg(feature = "monitoring")]
 use crate::metrics::node;
-use crate::settings;
+use crate::{
+    settings,
+    tls::{self, TlsAcceptor},
+};
 use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
 use metrics_util::layers::{PrefixLayer, Stack};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use tokio::runtime::Handle;
+use tokio::{net::TcpListener, runtime::Handle};
+use tracing::debug;
 
 /// Set up Prometheus buckets for matched metrics and install recorder.
 #[cfg(not(test))]
 pub(crate) fn setup_metrics_recorder(
     settings: &settings::Network,
 ) -> anyhow::Result<PrometheusHandle> {
-    setup_metrics_recorder_inner(settings.metrics.port)
+    setup_metrics_recorder_inner(settings.metrics.port, settings.metrics.tls.as_ref())
 }
 
 /// Set up Prometheus buckets for matched metrics and install recorder.
 #[cfg(test)]
 pub(crate) fn setup_metrics_recorder(
-    _settings: &settings::Network,
+    settings: &settings::Network,
 ) -> anyhow::Result<PrometheusHandle> {
     let port = port_selector::random_free_tcp_port().unwrap();
-    setup_metrics_recorder_inner(port)
+    setup_metrics_recorder_inner(port, settings.metrics.tls.as_ref())
 }
 
-fn setup_metrics_recorder_inner(port: u16) -> anyhow::Result<PrometheusHandle> {
+fn setup_metrics_recorder_inner(
+    port: u16,
+    tls: Option<&settings::Tls>,
+) -> anyhow::Result<PrometheusHandle> {
     const EXPONENTIAL_SECONDS: &[f64] = &[
         0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
     ];
 
     let socket = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
 
-    let (recorder, exporter) = PrometheusBuilder::new()
-        .set_buckets_for_metric(
-            Matcher::Suffix("_duration_seconds".to_string()),
-            EXPONENTIAL_SECONDS,
-        )?
-        .with_http_listener(socket)
-        .build()
-        .expect("failed to install recorder/exporter");
+    let builder = PrometheusBuilder::new().set_buckets_for_metric(
+        Matcher::Suffix("_duration_seconds".to_string()),
+        EXPONENTIAL_SECONDS,
+    )?;
 
-    let hdl = recorder.handle();
     let rt_hdl = Handle::current();
-    rt_hdl.spawn(exporter);
+    let recorder = if let Some(tls) = tls {
+        // The exporter's HTTP listener is plaintext only, so serve the
+        // scrape endpoint ourselves when TLS is configured.
+        let recorder = builder.build_recorder();
+        let acceptor = TlsAcceptor::start(tls)?;
+        let listener = std::net::TcpListener::bind(socket)?;
+        listener.set_nonblocking(true)?;
+        rt_hdl.spawn(serve_tls(
+            TcpListener::from_std(listener)?,
+            acceptor,
+            recorder.handle(),
+        ));
+        recorder
+    } else {
+        let (recorder, exporter) = builder
+            .with_http_listener(socket)
+            .build()
+            .expect("failed to install recorder/exporter");
+        rt_hdl.spawn(exporter);
+        recorder
+    };
+
+    let hdl = recorder.handle();
 
     Stack::new(recorder)
         .push(PrefixLayer::new("homestar"))
@@ -54,3 +78,46 @@ fn setup_metrics_recorder_inner(port: u16) -> anyhow::Result<PrometheusHandle> {
 
     Ok(hdl)
 }
+
+/// Serve rendered metrics over TLS on any path.
+async fn serve_tls(listener: TcpListener, acceptor: TlsAcceptor, hdl: PrometheusHandle) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _remote_addr)) => stream,
+            Err(err) => {
+                tls::accept_backoff(&err).await;
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let hdl = hdl.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    debug!(subject = "metrics.tls.err",
+                           category = "metrics",
+                           err=?err,
+                           "TLS handshake failed");
+                    return;
+                }
+            };
+
+            let svc = hyper::service::service_fn(move |_| {
+                let output = hdl.render();
+                async move { Ok::<_, hyper::Error>(hyper::Response::new(hyper::Body::from(output))) }
+            });
+
+            if let Err(err) = hyper::server::conn::Http::new()
+                .serve_connection(stream, svc)
+                .await
+            {
+                debug!(subject = "metrics.conn.err",
+                       category = "metrics",
+                       err=?err,
+                       "metrics connection failed");
+            }
+        });
+    }
+}
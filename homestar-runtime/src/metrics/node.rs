@@ -98,6 +98,11 @@ pub(crate) fn describe() {
         Unit::Bytes,
         "The bytes received since last refresh."
     );
+    describe_counter!(
+        "network_receipts_dropped",
+        Unit::Count,
+        "Receipts received over gossipsub or the DHT dropped as unverifiable."
+    );
 
     // Databsae metrics
     describe_counter!(
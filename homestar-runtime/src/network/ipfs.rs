@@ -6,10 +6,11 @@ use crate::settings;
 use anyhow::Result;
 use futures::TryStreamExt;
 use homestar_invocation::Receipt;
+use homestar_wasm::wasmtime::blocks::{DAG_CBOR, DAG_JSON};
 use http::uri::Scheme;
 use ipfs_api::{
-    request::{DagCodec, DagPut},
-    response::DagPutResponse,
+    request::{BlockPut, DagCodec, DagPut},
+    response::{BlockPutResponse, DagPutResponse},
     IpfsApi, IpfsClient,
 };
 use ipfs_api_backend_hyper::TryFromUri;
@@ -18,6 +19,7 @@ use std::{io::Cursor, sync::Arc};
 use url::Url;
 
 const SHA3_256: &str = "sha3-256";
+const SHA2_256: &str = "sha2-256";
 
 /// [IpfsClient]-wrapper.
 #[allow(missing_debug_implementations)]
@@ -60,6 +62,37 @@ impl IpfsCli {
             .map_err(Into::into)
     }
 
+    /// Retrieve a raw IPLD block by Cid.
+    pub(crate) async fn get_block(&self, cid: Cid) -> Result<Vec<u8>> {
+        self.0
+            .block_get(&cid.to_string())
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Put/Write a (CIDv1, SHA2-256) IPLD block into IPFS, pinning it.
+    pub(crate) async fn put_block(&self, cid: Cid, block: Vec<u8>) -> Result<String> {
+        let format = match cid.codec() {
+            DAG_CBOR => "dag-cbor",
+            DAG_JSON => "dag-json",
+            _ => "raw",
+        };
+        let options = BlockPut::builder()
+            .format(format)
+            .mhtype(SHA2_256)
+            .pin(true)
+            .build();
+
+        let BlockPutResponse { key, .. } = self
+            .0
+            .block_put_with_options(Cursor::new(block), options)
+            .await?;
+
+        Ok(key)
+    }
+
     /// Put/Write [Receipt] into IPFS.
     #[allow(dead_code)]
     pub(crate) async fn put_receipt(&self, receipt: Receipt<Ipld>) -> Result<String> {
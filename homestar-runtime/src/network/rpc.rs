@@ -3,10 +3,11 @@
 use crate::{
     channel::{AsyncChannel, AsyncChannelReceiver, AsyncChannelSender},
     runner::{self, file::ReadWorkflow, response, RpcSender},
-    settings,
+    settings, workflow, ReceiptEncoding, ReceiptFilter,
 };
 use faststr::FastStr;
 use futures::{future, StreamExt};
+use libipld::Cid;
 use std::{io, net::SocketAddr, sync::Arc, time::Duration};
 use stream_cancel::Valved;
 use tarpc::{
@@ -47,12 +48,74 @@ pub(crate) enum ServerMessage {
     ///
     /// [Workflow]: homestar_workflow::Workflow
     RunErr(runner::Error),
+    /// Message sent to plan a [Workflow] run by reading a [Workflow] file,
+    /// without executing anything, optionally rendering a [dot] graph.
+    ///
+    /// [dot]: <https://graphviz.org/doc/info/lang.html>
+    /// [Workflow]: homestar_workflow::Workflow
+    Plan((Option<FastStr>, ReadWorkflow, bool)),
+    /// Acknowledgement of a [Workflow] plan.
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    PlanAck(Box<response::AckPlan>),
     /// Message sent to the [Runner] to identify the node.
     ///
     /// [Runner]: crate::Runner
     NodeInfo,
     /// Acknowledgement of the node's identity/info.
     NodeInfoAck(response::AckNodeInfo),
+    /// Message sent to the [Runner] to cancel a running [Workflow], given
+    /// its Cid.
+    ///
+    /// [Runner]: crate::Runner
+    /// [Workflow]: homestar_workflow::Workflow
+    Cancel(Cid),
+    /// Acknowledgement of a cancelled [Workflow].
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    CancelAck(response::AckCancel),
+    /// Message sent to the [Runner] to list stored [Workflow]s.
+    ///
+    /// [Runner]: crate::Runner
+    /// [Workflow]: homestar_workflow::Workflow
+    ListWorkflows(workflow::Filter),
+    /// Acknowledgement of a [Workflow] listing.
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    ListWorkflowsAck(response::AckWorkflows),
+    /// Message sent to the [Runner] to show a stored [Workflow], given its
+    /// Cid or local name.
+    ///
+    /// [Runner]: crate::Runner
+    /// [Workflow]: homestar_workflow::Workflow
+    ShowWorkflow(FastStr),
+    /// Acknowledgement of a shown [Workflow].
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    ShowWorkflowAck(Box<response::AckWorkflowDetails>),
+    /// Message sent to the [Runner] to get a stored [Receipt], given its
+    /// Cid.
+    ///
+    /// [Receipt]: crate::Receipt
+    /// [Runner]: crate::Runner
+    GetReceipt((Cid, ReceiptEncoding)),
+    /// Message sent to the [Runner] to list stored [Receipt]s for a
+    /// [Workflow], given its Cid.
+    ///
+    /// [Receipt]: crate::Receipt
+    /// [Runner]: crate::Runner
+    /// [Workflow]: homestar_workflow::Workflow
+    ListReceipts((Cid, ReceiptEncoding)),
+    /// Message sent to the [Runner] to find stored [Receipt]s matching a
+    /// [ReceiptFilter].
+    ///
+    /// [Receipt]: crate::Receipt
+    /// [Runner]: crate::Runner
+    FindReceipts((ReceiptFilter, ReceiptEncoding)),
+    /// Acknowledgement of a [Receipt] query.
+    ///
+    /// [Receipt]: crate::Receipt
+    ReceiptsAck(response::AckReceipts),
     /// For skipping server messages.
     Skip,
 }
@@ -65,12 +128,39 @@ pub(crate) trait Interface {
         name: Option<FastStr>,
         workflow_file: ReadWorkflow,
     ) -> Result<Box<response::AckWorkflow>, Error>;
+    /// Plan a workflow run without executing anything.
+    async fn plan(
+        name: Option<FastStr>,
+        workflow_file: ReadWorkflow,
+        dot: bool,
+    ) -> Result<Box<response::AckPlan>, Error>;
     /// Ping the server.
     async fn ping() -> String;
     /// Stop the server.
     async fn stop() -> Result<(), Error>;
     /// Identify the node.
     async fn node_info() -> Result<response::AckNodeInfo, Error>;
+    /// Cancel a running workflow.
+    async fn cancel(workflow_cid: Cid) -> Result<response::AckCancel, Error>;
+    /// List stored workflows.
+    async fn list_workflows(filter: workflow::Filter) -> Result<response::AckWorkflows, Error>;
+    /// Show a stored workflow, given its Cid or local name.
+    async fn show_workflow(workflow: FastStr) -> Result<Box<response::AckWorkflowDetails>, Error>;
+    /// Get a stored receipt, given its Cid.
+    async fn get_receipt(
+        cid: Cid,
+        encoding: ReceiptEncoding,
+    ) -> Result<response::AckReceipts, Error>;
+    /// List stored receipts for a workflow, given its Cid.
+    async fn list_receipts_for_workflow(
+        workflow_cid: Cid,
+        encoding: ReceiptEncoding,
+    ) -> Result<response::AckReceipts, Error>;
+    /// Find stored receipts matching a filter.
+    async fn find_receipts(
+        filter: ReceiptFilter,
+        encoding: ReceiptEncoding,
+    ) -> Result<response::AckReceipts, Error>;
 }
 
 /// RPC server state information.
@@ -117,6 +207,35 @@ impl ServerHandler {
             timeout,
         }
     }
+
+    /// Send a receipt query to the [Runner] and await its acknowledgement.
+    ///
+    /// [Runner]: crate::Runner
+    async fn receipts_request(&self, msg: ServerMessage) -> Result<response::AckReceipts, Error> {
+        let (tx, rx) = AsyncChannel::oneshot();
+        self.runner_sender
+            .send_async((msg, Some(tx)))
+            .await
+            .map_err(|e| Error::FailureToSendOnChannel(e.to_string()))?;
+
+        let now = time::Instant::now();
+        select! {
+            Ok(msg) = rx.recv_async() => {
+                match msg {
+                    ServerMessage::ReceiptsAck(response) => Ok(response),
+                    ServerMessage::RunErr(err) => Err(Error::FromRunner(err.to_string())),
+                    _ => Err(Error::FailureToSendOnChannel("unexpected message".into())),
+                }
+            },
+            _ = time::sleep_until(now + self.timeout) => {
+                let s = format!("server timeout of {} ms reached", self.timeout.as_millis());
+                info!(subject = "rpc.timeout",
+                      category = "rpc",
+                      "{s}");
+                Err(Error::FailureToReceiveOnChannel(s))
+            }
+        }
+    }
 }
 
 #[tarpc::server]
@@ -154,6 +273,37 @@ impl Interface for ServerHandler {
 
         }
     }
+    async fn plan(
+        self,
+        _: context::Context,
+        name: Option<FastStr>,
+        workflow_file: ReadWorkflow,
+        dot: bool,
+    ) -> Result<Box<response::AckPlan>, Error> {
+        let (tx, rx) = AsyncChannel::oneshot();
+        self.runner_sender
+            .send_async((ServerMessage::Plan((name, workflow_file, dot)), Some(tx)))
+            .await
+            .map_err(|e| Error::FailureToSendOnChannel(e.to_string()))?;
+
+        let now = time::Instant::now();
+        select! {
+            Ok(msg) = rx.recv_async() => {
+                match msg {
+                    ServerMessage::PlanAck(response) => Ok(response),
+                    ServerMessage::RunErr(err) => Err(Error::FromRunner(err.to_string())),
+                    _ => Err(Error::FailureToSendOnChannel("unexpected message".into())),
+                }
+            },
+            _ = time::sleep_until(now + self.timeout) => {
+                let s = format!("server timeout of {} ms reached", self.timeout.as_millis());
+                info!(subject = "rpc.timeout",
+                      category = "rpc",
+                      "{s}");
+                Err(Error::FailureToReceiveOnChannel(s))
+            }
+        }
+    }
     async fn ping(self, _: context::Context) -> String {
         "pong".into()
     }
@@ -190,6 +340,120 @@ impl Interface for ServerHandler {
             }
         }
     }
+    async fn cancel(
+        self,
+        _: context::Context,
+        workflow_cid: Cid,
+    ) -> Result<response::AckCancel, Error> {
+        let (tx, rx) = AsyncChannel::oneshot();
+        self.runner_sender
+            .send_async((ServerMessage::Cancel(workflow_cid), Some(tx)))
+            .await
+            .map_err(|e| Error::FailureToSendOnChannel(e.to_string()))?;
+
+        let now = time::Instant::now();
+        select! {
+            Ok(msg) = rx.recv_async() => {
+                match msg {
+                    ServerMessage::CancelAck(response) => Ok(response),
+                    ServerMessage::RunErr(err) => Err(Error::FromRunner(err.to_string())),
+                    _ => Err(Error::FailureToSendOnChannel("unexpected message".into())),
+                }
+            },
+            _ = time::sleep_until(now + self.timeout) => {
+                let s = format!("server timeout of {} ms reached", self.timeout.as_millis());
+                info!(subject = "rpc.timeout",
+                      category = "rpc",
+                      "{s}");
+                Err(Error::FailureToReceiveOnChannel(s))
+            }
+        }
+    }
+    async fn list_workflows(
+        self,
+        _: context::Context,
+        filter: workflow::Filter,
+    ) -> Result<response::AckWorkflows, Error> {
+        let (tx, rx) = AsyncChannel::oneshot();
+        self.runner_sender
+            .send_async((ServerMessage::ListWorkflows(filter), Some(tx)))
+            .await
+            .map_err(|e| Error::FailureToSendOnChannel(e.to_string()))?;
+
+        let now = time::Instant::now();
+        select! {
+            Ok(msg) = rx.recv_async() => {
+                match msg {
+                    ServerMessage::ListWorkflowsAck(response) => Ok(response),
+                    ServerMessage::RunErr(err) => Err(Error::FromRunner(err.to_string())),
+                    _ => Err(Error::FailureToSendOnChannel("unexpected message".into())),
+                }
+            },
+            _ = time::sleep_until(now + self.timeout) => {
+                let s = format!("server timeout of {} ms reached", self.timeout.as_millis());
+                info!(subject = "rpc.timeout",
+                      category = "rpc",
+                      "{s}");
+                Err(Error::FailureToReceiveOnChannel(s))
+            }
+        }
+    }
+    async fn show_workflow(
+        self,
+        _: context::Context,
+        workflow: FastStr,
+    ) -> Result<Box<response::AckWorkflowDetails>, Error> {
+        let (tx, rx) = AsyncChannel::oneshot();
+        self.runner_sender
+            .send_async((ServerMessage::ShowWorkflow(workflow), Some(tx)))
+            .await
+            .map_err(|e| Error::FailureToSendOnChannel(e.to_string()))?;
+
+        let now = time::Instant::now();
+        select! {
+            Ok(msg) = rx.recv_async() => {
+                match msg {
+                    ServerMessage::ShowWorkflowAck(response) => Ok(response),
+                    ServerMessage::RunErr(err) => Err(Error::FromRunner(err.to_string())),
+                    _ => Err(Error::FailureToSendOnChannel("unexpected message".into())),
+                }
+            },
+            _ = time::sleep_until(now + self.timeout) => {
+                let s = format!("server timeout of {} ms reached", self.timeout.as_millis());
+                info!(subject = "rpc.timeout",
+                      category = "rpc",
+                      "{s}");
+                Err(Error::FailureToReceiveOnChannel(s))
+            }
+        }
+    }
+    async fn get_receipt(
+        self,
+        _: context::Context,
+        cid: Cid,
+        encoding: ReceiptEncoding,
+    ) -> Result<response::AckReceipts, Error> {
+        self.receipts_request(ServerMessage::GetReceipt((cid, encoding)))
+            .await
+    }
+    async fn list_receipts_for_workflow(
+        self,
+        _: context::Context,
+        workflow_cid: Cid,
+        encoding: ReceiptEncoding,
+    ) -> Result<response::AckReceipts, Error> {
+        self.receipts_request(ServerMessage::ListReceipts((workflow_cid, encoding)))
+            .await
+    }
+    async fn find_receipts(
+        self,
+        _: context::Context,
+        filter: ReceiptFilter,
+        encoding: ReceiptEncoding,
+    ) -> Result<response::AckReceipts, Error> {
+        self.receipts_request(ServerMessage::FindReceipts((filter, encoding)))
+            .await
+    }
 }
 
 impl Server {
@@ -307,4 +571,82 @@ impl Client {
     ) -> Result<Result<Box<response::AckWorkflow>, Error>, RpcError> {
         self.cli.run(self.ctx, name, workflow_file).await
     }
+
+    /// Plan a [Workflow] run, without executing anything.
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    pub async fn plan(
+        &self,
+        name: Option<FastStr>,
+        workflow_file: ReadWorkflow,
+        dot: bool,
+    ) -> Result<Result<Box<response::AckPlan>, Error>, RpcError> {
+        self.cli.plan(self.ctx, name, workflow_file, dot).await
+    }
+
+    /// Cancel a running [Workflow], given its Cid.
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    pub async fn cancel(
+        &self,
+        workflow_cid: Cid,
+    ) -> Result<Result<response::AckCancel, Error>, RpcError> {
+        self.cli.cancel(self.ctx, workflow_cid).await
+    }
+
+    /// List stored [Workflow]s matching a [workflow::Filter].
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    pub async fn list_workflows(
+        &self,
+        filter: workflow::Filter,
+    ) -> Result<Result<response::AckWorkflows, Error>, RpcError> {
+        self.cli.list_workflows(self.ctx, filter).await
+    }
+
+    /// Show a stored [Workflow], given its Cid or local name.
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    pub async fn show_workflow(
+        &self,
+        workflow: FastStr,
+    ) -> Result<Result<Box<response::AckWorkflowDetails>, Error>, RpcError> {
+        self.cli.show_workflow(self.ctx, workflow).await
+    }
+
+    /// Get a stored [Receipt], given its Cid.
+    ///
+    /// [Receipt]: crate::Receipt
+    pub async fn get_receipt(
+        &self,
+        cid: Cid,
+        encoding: ReceiptEncoding,
+    ) -> Result<Result<response::AckReceipts, Error>, RpcError> {
+        self.cli.get_receipt(self.ctx, cid, encoding).await
+    }
+
+    /// List stored [Receipt]s for a [Workflow], given its Cid.
+    ///
+    /// [Receipt]: crate::Receipt
+    /// [Workflow]: homestar_workflow::Workflow
+    pub async fn list_receipts_for_workflow(
+        &self,
+        workflow_cid: Cid,
+        encoding: ReceiptEncoding,
+    ) -> Result<Result<response::AckReceipts, Error>, RpcError> {
+        self.cli
+            .list_receipts_for_workflow(self.ctx, workflow_cid, encoding)
+            .await
+    }
+
+    /// Find stored [Receipt]s matching a [ReceiptFilter].
+    ///
+    /// [Receipt]: crate::Receipt
+    pub async fn find_receipts(
+        &self,
+        filter: ReceiptFilter,
+        encoding: ReceiptEncoding,
+    ) -> Result<Result<response::AckReceipts, Error>, RpcError> {
+        self.cli.find_receipts(self.ctx, filter, encoding).await
+    }
 }
@@ -4,10 +4,11 @@
 //! [Swarm]: libp2p::Swarm
 
 use crate::{
+    db::Database,
     network::{error::PubSubError, pubsub},
     settings, Receipt, RECEIPT_TAG, WORKFLOW_TAG,
 };
-use anyhow::{Context, Result};
+use anyhow::Result;
 use const_format::formatcp;
 use enum_assoc::Assoc;
 use faststr::FastStr;
@@ -23,11 +24,7 @@ use libp2p::{
     gossipsub::{self, MessageId, TopicHash},
     identify,
     identity::Keypair,
-    kad::{
-        self,
-        store::{MemoryStore, MemoryStoreConfig},
-    },
-    mdns,
+    kad, mdns,
     multiaddr::Protocol,
     noise, quic, rendezvous,
     request_response::{self, ProtocolSupport},
@@ -43,13 +40,15 @@ pub(crate) const HOMESTAR_PROTOCOL_VER: &str = formatcp!("homestar/{VERSION}");
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Build a new [Swarm] with a given transport and a tokio executor.
-pub(crate) async fn new(settings: &settings::Network) -> Result<Swarm<ComposedBehaviour>> {
-    let keypair = settings
-        .keypair_config
-        .keypair()
-        .with_context(|| "failed to generate/import keypair for libp2p".to_string())?;
+pub(crate) mod store;
+use store::DhtStore;
 
+/// Build a new [Swarm] with a given transport and a tokio executor.
+pub(crate) async fn new(
+    keypair: Keypair,
+    settings: &settings::Network,
+    db: impl Database + 'static,
+) -> Result<Swarm<ComposedBehaviour>> {
     let peer_id = keypair.public().to_peer_id();
     info!(
         subject = "swarm.init",
@@ -80,23 +79,22 @@ pub(crate) async fn new(settings: &settings::Network) -> Result<Swarm<ComposedBe
             }),
             kademlia: kad::Behaviour::with_config(
                 peer_id,
-                MemoryStore::with_config(
-                    peer_id,
-                    MemoryStoreConfig {
-                        // TODO: if below a better max, rely on cache-store or
-                        // blockstore to fetch result if requested directly.
-                        // 2gb at the moment
-                        max_value_bytes: 10 * 1024 * 1024,
-                        ..Default::default()
-                    },
-                ),
+                DhtStore::new(peer_id, settings.libp2p().dht(), db)?,
                 {
+                    let dht_settings = settings.libp2p().dht();
                     let mut cfg = kad::Config::default();
                     // Set max packet size for records put to the DHT.
                     // Currently set to 2gb.
                     cfg.set_max_packet_size(10 * 1024 * 1024);
                     // Only add peers to the routing table manually.
                     cfg.set_kbucket_inserts(kad::BucketInserts::Manual);
+                    cfg.set_record_ttl(Some(dht_settings.record_ttl));
+                    cfg.set_replication_interval(Some(dht_settings.record_replication_interval));
+                    cfg.set_publication_interval(Some(dht_settings.record_publication_interval));
+                    cfg.set_provider_record_ttl(Some(dht_settings.provider_record_ttl));
+                    cfg.set_provider_publication_interval(Some(
+                        dht_settings.provider_publication_interval,
+                    ));
                     cfg
                 },
             ),
@@ -321,7 +319,7 @@ pub(crate) struct ComposedBehaviour {
     /// [gossipsub::Behaviour] behaviour.
     pub(crate) gossipsub: Toggle<gossipsub::Behaviour>,
     /// In-memory [kademlia: kad::Behaviour] behaviour.
-    pub(crate) kademlia: kad::Behaviour<MemoryStore>,
+    pub(crate) kademlia: kad::Behaviour<DhtStore>,
     /// [request_response::Behaviour] CBOR-flavored behaviour.
     pub(crate) request_response: request_response::cbor::Behaviour<RequestResponseKey, Vec<u8>>,
     /// [mdns::tokio::Behaviour] behaviour.
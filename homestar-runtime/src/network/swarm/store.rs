@@ -0,0 +1,579 @@
+//! Kademlia [RecordStore] implementation, optionally persisted to the
+//! node's database.
+//!
+//! [RecordStore]: libp2p::kad::store::RecordStore
+
+use crate::{
+    channel::{BoundedChannelReceiver, BoundedChannelSender, Channel},
+    db::{schema, Connection, Database},
+    settings,
+};
+use anyhow::{anyhow, Result};
+use diesel::{
+    Connection as _, ExpressionMethods, Insertable, QueryDsl, Queryable, RunQueryDsl, Selectable,
+    SelectableHelper,
+};
+use libp2p::{
+    kad::{
+        store::{self, MemoryStore, MemoryStoreConfig, RecordStore},
+        ProviderRecord, Record, RecordKey,
+    },
+    Multiaddr, PeerId,
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// Source of database connections for persisting records.
+type ConnectionFn = Arc<dyn Fn() -> Result<Connection> + Send + Sync>;
+
+/// Maximum number of changes queued for the [StoreWriter], past which
+/// changes are dropped rather than stalling the swarm.
+const WRITE_QUEUE_SIZE: usize = 4096;
+
+/// Maximum number of queued changes written in a single transaction.
+const WRITE_BATCH_SIZE: usize = 256;
+
+/// Change to the record store, written through to the database.
+#[derive(Debug, Clone, PartialEq)]
+enum StoreOp {
+    /// Store a record, replacing any record with the same key.
+    PutRecord(StoredRecord),
+    /// Remove a record by its key.
+    RemoveRecord(Vec<u8>),
+    /// Replace all provider records for a key.
+    ReplaceProviders(Vec<u8>, Vec<StoredProvider>),
+}
+
+/// Writer of [StoreOp]s to the database on a dedicated thread, off the
+/// swarm's event loop.
+///
+/// Queued changes are coalesced by key and written in batches. Dropping
+/// the writer flushes the queue.
+struct StoreWriter {
+    tx: Option<BoundedChannelSender<StoreOp>>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl StoreWriter {
+    /// Spawn a [StoreWriter] over a source of database connections.
+    fn spawn(conn_fn: ConnectionFn) -> Result<Self> {
+        let (tx, rx) = Channel::with(WRITE_QUEUE_SIZE);
+        let handle = thread::Builder::new()
+            .name("dht-store-writer".to_string())
+            .spawn(move || write_batches(rx, conn_fn))?;
+
+        Ok(Self {
+            tx: Some(tx),
+            handle: Some(handle),
+        })
+    }
+
+    /// Queue a change, dropping it if the queue is full.
+    ///
+    /// The in-memory state remains authoritative for the running node.
+    fn send(&self, op: StoreOp) {
+        if let Some(Err(err)) = self.tx.as_ref().map(|tx| tx.try_send(op)) {
+            warn!(subject = "libp2p.kad.store.err",
+                  category = "libp2p.kad",
+                  err=?err,
+                  "unable to queue DHT record store change");
+        }
+    }
+}
+
+impl Drop for StoreWriter {
+    fn drop(&mut self) {
+        drop(self.tx.take());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Write queued changes in batches until the queue is closed and drained.
+fn write_batches(rx: BoundedChannelReceiver<StoreOp>, conn_fn: ConnectionFn) {
+    while let Ok(op) = rx.recv() {
+        let mut batch = vec![op];
+        batch.extend(rx.try_iter().take(WRITE_BATCH_SIZE - 1));
+
+        if let Err(err) = conn_fn().and_then(|mut conn| write_batch(coalesce(batch), &mut conn)) {
+            warn!(subject = "libp2p.kad.store.err",
+                  category = "libp2p.kad",
+                  err=?err,
+                  "unable to persist DHT record store changes");
+        }
+    }
+}
+
+/// Keep only the last change per record key and per provider key, in
+/// order.
+fn coalesce(batch: Vec<StoreOp>) -> Vec<StoreOp> {
+    let mut last = HashMap::new();
+    for (i, op) in batch.iter().enumerate() {
+        let key = match op {
+            StoreOp::PutRecord(record) => (true, record.key.clone()),
+            StoreOp::RemoveRecord(key) => (true, key.clone()),
+            StoreOp::ReplaceProviders(key, _) => (false, key.clone()),
+        };
+        last.insert(key, i);
+    }
+    let keep = last.into_values().collect::<HashSet<_>>();
+
+    batch
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| keep.contains(i))
+        .map(|(_, op)| op)
+        .collect()
+}
+
+/// Write a batch of changes in a single transaction.
+fn write_batch(batch: Vec<StoreOp>, conn: &mut Connection) -> Result<()> {
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        for op in batch {
+            match op {
+                StoreOp::PutRecord(record) => store_record(&record, conn)?,
+                StoreOp::RemoveRecord(key) => remove_record(&key, conn)?,
+                StoreOp::ReplaceProviders(key, providers) => {
+                    replace_providers(&key, &providers, conn)?
+                }
+            };
+        }
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+/// DHT record, as stored in the database.
+#[derive(Debug, Clone, PartialEq, Queryable, Insertable, Selectable)]
+#[diesel(table_name = crate::db::schema::dht_records, primary_key(key))]
+pub(crate) struct StoredRecord {
+    key: Vec<u8>,
+    value: Vec<u8>,
+    publisher: Option<Vec<u8>>,
+    expires_at: Option<i64>,
+}
+
+impl From<&Record> for StoredRecord {
+    fn from(record: &Record) -> Self {
+        Self {
+            key: record.key.to_vec(),
+            value: record.value.clone(),
+            publisher: record.publisher.map(|peer_id| peer_id.to_bytes()),
+            expires_at: record.expires.map(to_timestamp),
+        }
+    }
+}
+
+impl TryFrom<StoredRecord> for Record {
+    type Error = anyhow::Error;
+
+    fn try_from(stored: StoredRecord) -> Result<Self, Self::Error> {
+        Ok(Record {
+            key: RecordKey::from(stored.key),
+            value: stored.value,
+            publisher: stored
+                .publisher
+                .map(|bytes| PeerId::from_bytes(&bytes))
+                .transpose()?,
+            expires: stored.expires_at.map(from_timestamp).transpose()?,
+        })
+    }
+}
+
+/// DHT provider record, as stored in the database.
+#[derive(Debug, Clone, PartialEq, Queryable, Insertable, Selectable)]
+#[diesel(table_name = crate::db::schema::dht_providers, primary_key(key, provider))]
+pub(crate) struct StoredProvider {
+    key: Vec<u8>,
+    provider: Vec<u8>,
+    addresses: String,
+    expires_at: Option<i64>,
+}
+
+impl TryFrom<&ProviderRecord> for StoredProvider {
+    type Error = anyhow::Error;
+
+    fn try_from(record: &ProviderRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            key: record.key.to_vec(),
+            provider: record.provider.to_bytes(),
+            addresses: serde_json::to_string(
+                &record
+                    .addresses
+                    .iter()
+                    .map(|addr| addr.to_string())
+                    .collect::<Vec<_>>(),
+            )?,
+            expires_at: record.expires.map(to_timestamp),
+        })
+    }
+}
+
+impl TryFrom<StoredProvider> for ProviderRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(stored: StoredProvider) -> Result<Self, Self::Error> {
+        Ok(ProviderRecord {
+            key: RecordKey::from(stored.key),
+            provider: PeerId::from_bytes(&stored.provider)?,
+            expires: stored.expires_at.map(from_timestamp).transpose()?,
+            addresses: serde_json::from_str::<Vec<String>>(&stored.addresses)?
+                .into_iter()
+                .map(|addr| addr.parse::<Multiaddr>())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Kademlia record store, enforcing the configured [settings::Dht] limits.
+///
+/// Records and provider records are kept in memory and, if
+/// [settings::DhtRecordStore::Sqlite] is selected, written through to the
+/// database by a [StoreWriter] and loaded back on startup, so that they
+/// survive restarts and are republished by Kademlia.
+pub(crate) struct DhtStore {
+    memory: MemoryStore,
+    writer: Option<StoreWriter>,
+}
+
+impl fmt::Debug for DhtStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DhtStore")
+            .field("persistent", &self.writer.is_some())
+            .finish()
+    }
+}
+
+impl DhtStore {
+    /// Create a [DhtStore] based on the [settings::Dht] record store setting.
+    pub(crate) fn new(
+        peer_id: PeerId,
+        settings: &settings::Dht,
+        db: impl Database + 'static,
+    ) -> Result<Self> {
+        match settings.record_store {
+            settings::DhtRecordStore::Memory => Ok(Self::memory(peer_id, settings)),
+            settings::DhtRecordStore::Sqlite => Self::persistent(peer_id, settings, db),
+        }
+    }
+
+    /// Create an in-memory [DhtStore].
+    pub(crate) fn memory(peer_id: PeerId, settings: &settings::Dht) -> Self {
+        Self {
+            memory: MemoryStore::with_config(
+                peer_id,
+                MemoryStoreConfig {
+                    max_records: settings.max_records,
+                    max_value_bytes: settings.max_record_size,
+                    max_providers_per_key: settings.max_providers_per_key,
+                    max_provided_keys: settings.max_provided_keys,
+                },
+            ),
+            writer: None,
+        }
+    }
+
+    /// Create a [DhtStore] persisted to the database, loading all
+    /// unexpired records and provider records stored previously.
+    pub(crate) fn persistent(
+        peer_id: PeerId,
+        settings: &settings::Dht,
+        db: impl Database + 'static,
+    ) -> Result<Self> {
+        let mut store = Self::memory(peer_id, settings);
+        let conn = &mut db.conn()?;
+        let timestamp = to_timestamp(Instant::now());
+
+        for stored in load_records(timestamp, conn)? {
+            let record = Record::try_from(stored)?;
+            if let Err(err) = store.memory.put(record) {
+                warn!(subject = "libp2p.kad.store.err",
+                      category = "libp2p.kad",
+                      err=?err,
+                      "unable to load stored DHT record");
+            }
+        }
+
+        for stored in load_providers(timestamp, conn)? {
+            let record = ProviderRecord::try_from(stored)?;
+            if let Err(err) = store.memory.add_provider(record) {
+                warn!(subject = "libp2p.kad.store.err",
+                      category = "libp2p.kad",
+                      err=?err,
+                      "unable to load stored DHT provider record");
+            }
+        }
+
+        store.writer = Some(StoreWriter::spawn(Arc::new(move || db.conn()))?);
+        Ok(store)
+    }
+
+    /// Write the provider records currently held for a key through to the
+    /// database.
+    fn persist_providers(&self, key: &RecordKey) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+
+        match self
+            .memory
+            .providers(key)
+            .iter()
+            .map(StoredProvider::try_from)
+            .collect::<Result<Vec<_>>>()
+        {
+            Ok(providers) => writer.send(StoreOp::ReplaceProviders(key.to_vec(), providers)),
+            Err(err) => warn!(subject = "libp2p.kad.store.err",
+                              category = "libp2p.kad",
+                              err=?err,
+                              "unable to persist DHT provider records"),
+        }
+    }
+
+    /// Queue a database write if the store is persistent.
+    fn persist(&self, op: StoreOp) {
+        if let Some(writer) = &self.writer {
+            writer.send(op);
+        }
+    }
+}
+
+impl RecordStore for DhtStore {
+    type RecordsIter<'a> = <MemoryStore as RecordStore>::RecordsIter<'a>;
+    type ProvidedIter<'a> = <MemoryStore as RecordStore>::ProvidedIter<'a>;
+
+    fn get(&self, k: &RecordKey) -> Option<Cow<'_, Record>> {
+        self.memory
+            .get(k)
+            .filter(|record| !record.is_expired(Instant::now()))
+    }
+
+    fn put(&mut self, r: Record) -> store::Result<()> {
+        let stored = StoredRecord::from(&r);
+        self.memory.put(r)?;
+        self.persist(StoreOp::PutRecord(stored));
+        Ok(())
+    }
+
+    fn remove(&mut self, k: &RecordKey) {
+        self.memory.remove(k);
+        self.persist(StoreOp::RemoveRecord(k.to_vec()));
+    }
+
+    fn records(&self) -> Self::RecordsIter<'_> {
+        self.memory.records()
+    }
+
+    fn add_provider(&mut self, record: ProviderRecord) -> store::Result<()> {
+        let key = record.key.clone();
+        self.memory.add_provider(record)?;
+        self.persist_providers(&key);
+        Ok(())
+    }
+
+    fn providers(&self, key: &RecordKey) -> Vec<ProviderRecord> {
+        let now = Instant::now();
+        self.memory
+            .providers(key)
+            .into_iter()
+            .filter(|record| !record.is_expired(now))
+            .collect()
+    }
+
+    fn provided(&self) -> Self::ProvidedIter<'_> {
+        self.memory.provided()
+    }
+
+    fn remove_provider(&mut self, k: &RecordKey, p: &PeerId) {
+        self.memory.remove_provider(k, p);
+        self.persist_providers(k);
+    }
+}
+
+/// Store a record, replacing any record with the same key.
+fn store_record(
+    record: &StoredRecord,
+    conn: &mut Connection,
+) -> Result<usize, diesel::result::Error> {
+    diesel::replace_into(schema::dht_records::table)
+        .values(record)
+        .execute(conn)
+}
+
+/// Remove a record by its key.
+fn remove_record(key: &[u8], conn: &mut Connection) -> Result<usize, diesel::result::Error> {
+    diesel::delete(schema::dht_records::table.filter(schema::dht_records::key.eq(key)))
+        .execute(conn)
+}
+
+/// Load all unexpired records, removing those expired at `timestamp`
+/// (in milliseconds since the Unix epoch).
+fn load_records(
+    timestamp: i64,
+    conn: &mut Connection,
+) -> Result<Vec<StoredRecord>, diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(
+            schema::dht_records::table.filter(schema::dht_records::expires_at.le(timestamp)),
+        )
+        .execute(conn)?;
+
+        schema::dht_records::table
+            .select(StoredRecord::as_select())
+            .load(conn)
+    })
+}
+
+/// Replace all provider records for a key.
+fn replace_providers(
+    key: &[u8],
+    providers: &[StoredProvider],
+    conn: &mut Connection,
+) -> Result<usize, diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(schema::dht_providers::table.filter(schema::dht_providers::key.eq(key)))
+            .execute(conn)?;
+
+        diesel::insert_into(schema::dht_providers::table)
+            .values(providers)
+            .execute(conn)
+    })
+}
+
+/// Load all unexpired provider records, removing those expired at
+/// `timestamp` (in milliseconds since the Unix epoch).
+fn load_providers(
+    timestamp: i64,
+    conn: &mut Connection,
+) -> Result<Vec<StoredProvider>, diesel::result::Error> {
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::delete(
+            schema::dht_providers::table.filter(schema::dht_providers::expires_at.le(timestamp)),
+        )
+        .execute(conn)?;
+
+        schema::dht_providers::table
+            .select(StoredProvider::as_select())
+            .load(conn)
+    })
+}
+
+/// Convert an [Instant] into milliseconds since the Unix epoch.
+fn to_timestamp(instant: Instant) -> i64 {
+    let now = Instant::now();
+    let system_time = if instant >= now {
+        SystemTime::now() + instant.duration_since(now)
+    } else {
+        SystemTime::now() - now.duration_since(instant)
+    };
+
+    system_time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+/// Convert milliseconds since the Unix epoch into an [Instant].
+///
+/// Timestamps in the past map to the current [Instant], i.e. already expired.
+fn from_timestamp(timestamp: i64) -> Result<Instant> {
+    let millis = u64::try_from(timestamp).map_err(|_| anyhow!("invalid timestamp"))?;
+    let system_time = UNIX_EPOCH + Duration::from_millis(millis);
+    let now = Instant::now();
+    Ok(system_time
+        .duration_since(SystemTime::now())
+        .map_or(now, |duration| now + duration))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_utils::db::MemoryDb, Settings};
+
+    #[test]
+    fn records_and_providers_survive_restart() {
+        let mut settings = Settings::load().unwrap().node;
+        settings.network.libp2p.dht.record_store = settings::DhtRecordStore::Sqlite;
+        settings.network.libp2p.dht.max_records = 2;
+        let dht_settings = settings.network.libp2p.dht.clone();
+
+        let db = MemoryDb::setup_connection_pool(
+            &settings,
+            Some("records_and_providers_survive_restart".to_string()),
+        )
+        .unwrap();
+        let peer_id = PeerId::random();
+
+        let mut store = DhtStore::new(peer_id, &dht_settings, db.clone()).unwrap();
+        assert!(store.writer.is_some());
+
+        let mut record = Record::new(RecordKey::new(&"receipt"), b"receipt".to_vec());
+        record.publisher = Some(peer_id);
+        record.expires = Some(Instant::now() + Duration::from_secs(60));
+        store.put(record.clone()).unwrap();
+
+        let expired = Record {
+            expires: Some(Instant::now()),
+            ..Record::new(RecordKey::new(&"expired"), b"expired".to_vec())
+        };
+        store.put(expired.clone()).unwrap();
+        assert!(store.get(&expired.key).is_none());
+
+        // Enforces the configured record limit.
+        assert!(matches!(
+            store.put(Record::new(RecordKey::new(&"another"), vec![])),
+            Err(store::Error::MaxRecords)
+        ));
+
+        let provider = ProviderRecord::new(
+            RecordKey::new(&"workflow"),
+            peer_id,
+            vec!["/ip4/127.0.0.1/tcp/7000".parse().unwrap()],
+        );
+        store.add_provider(provider.clone()).unwrap();
+        // Flushes queued writes.
+        drop(store);
+
+        let store = DhtStore::new(peer_id, &dht_settings, db).unwrap();
+        let restored = store.get(&record.key).unwrap();
+        assert_eq!(restored.value, record.value);
+        assert_eq!(restored.publisher, Some(peer_id));
+        assert!(restored.expires.is_some());
+        assert!(store.get(&expired.key).is_none());
+        assert_eq!(store.records().count(), 1);
+
+        let providers = store.providers(&provider.key);
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].provider, peer_id);
+        assert_eq!(providers[0].addresses, provider.addresses);
+        assert_eq!(store.provided().count(), 1);
+    }
+
+    #[test]
+    fn coalesce_changes_by_key() {
+        let record = |key: &str| StoredRecord::from(&Record::new(RecordKey::new(&key), vec![]));
+        let batch = vec![
+            StoreOp::PutRecord(record("a")),
+            StoreOp::ReplaceProviders(b"a".to_vec(), vec![]),
+            StoreOp::PutRecord(record("b")),
+            StoreOp::RemoveRecord(record("a").key),
+        ];
+
+        assert_eq!(
+            coalesce(batch),
+            vec![
+                StoreOp::ReplaceProviders(b"a".to_vec(), vec![]),
+                StoreOp::PutRecord(record("b")),
+                StoreOp::RemoveRecord(record("a").key),
+            ]
+        );
+    }
+}
@@ -5,18 +5,26 @@ use crate::{
     ip, runner,
     runner::{DynamicNodeInfo, StaticNodeInfo, WsSender},
     settings,
+    tls::{self, TlsAcceptor},
+    workflow,
 };
 use anyhow::{anyhow, Result};
+use auth::{Authenticator, PermissionLayer};
 use faststr::FastStr;
 use futures::future::{self, Either};
 use homestar_wasm::io::Arg;
 use homestar_workflow::Workflow;
 use http::{
-    header::{AUTHORIZATION, CONTENT_TYPE},
+    header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE},
     method::Method,
+    StatusCode,
 };
-use jsonrpsee::server::{
-    middleware::http::ProxyGetRequestLayer, stop_channel, RandomStringIdProvider, ServerHandle,
+use jsonrpsee::{
+    server::{
+        middleware::http::ProxyGetRequestLayer, stop_channel, RandomStringIdProvider,
+        RpcServiceBuilder, ServerHandle,
+    },
+    Methods,
 };
 use libipld::Cid;
 use metrics_exporter_prometheus::PrometheusHandle;
@@ -30,12 +38,14 @@ use std::{
 #[cfg(feature = "websocket-notify")]
 use tokio::sync::broadcast;
 use tokio::{net::TcpListener, runtime::Handle, select};
+use tower::ServiceExt;
 use tower_http::{
     cors::{self, CorsLayer},
     sensitive_headers::SetSensitiveRequestHeadersLayer,
 };
 use tracing::{debug, error, info};
 
+mod auth;
 pub mod listener;
 #[cfg(feature = "websocket-notify")]
 pub(crate) mod notifier;
@@ -57,8 +67,9 @@ use rpc::{Context, JsonRpc};
 #[derive(Debug)]
 pub(crate) enum Message {
     RunErr(runner::Error),
-    /// Run a workflow, given a tuple of name, and [Workflow].
-    RunWorkflow((FastStr, Workflow<'static, Arg>)),
+    /// Run a workflow, given a tuple of name, [Workflow], and its
+    /// [workflow::Settings].
+    RunWorkflow((FastStr, Workflow<'static, Arg>, workflow::Settings)),
     /// Acknowledgement of a [Workflow] run.
     AckWorkflow((Cid, FastStr)),
     /// Message sent to the [Runner] to gather node information from the [EventHandler].
@@ -69,6 +80,10 @@ pub(crate) enum Message {
     /// Acknowledgement of a [Message::GetNodeInfo] request, receiving static and dynamic
     /// node information.
     AckNodeInfo((StaticNodeInfo, DynamicNodeInfo)),
+    /// Cancel a running [Workflow], given its Cid.
+    CancelWorkflow(Cid),
+    /// Acknowledgement of a cancelled [Workflow], given a tuple of Cid and name.
+    AckCancelWorkflow((Cid, FastStr)),
 }
 
 /// Server fields.
@@ -93,6 +108,10 @@ pub(crate) struct Server {
     sender_timeout: Duration,
     /// General timeout for the server.
     webserver_timeout: Duration,
+    /// Authenticator of clients connecting to the server.
+    authenticator: Authenticator,
+    /// TLS settings, with connections served over plaintext if unset.
+    tls: Option<settings::Tls>,
 }
 
 /// Server fields.
@@ -111,6 +130,10 @@ pub(crate) struct Server {
     sender_timeout: Duration,
     /// General timeout for the server.
     webserver_timeout: Duration,
+    /// Authenticator of clients connecting to the server.
+    authenticator: Authenticator,
+    /// TLS settings, with connections served over plaintext if unset.
+    tls: Option<settings::Tls>,
 }
 
 impl Server {
@@ -160,6 +183,8 @@ impl Server {
             workflow_msg_notifier: Notifier::new(msg_sender),
             sender_timeout: settings.websocket_sender_timeout,
             webserver_timeout: settings.timeout,
+            authenticator: Authenticator::new(&settings.auth)?,
+            tls: settings.tls.clone(),
         })
     }
 
@@ -192,6 +217,8 @@ impl Server {
             capacity: settings.websocket_capacity,
             sender_timeout: settings.websocket_sender_timeout,
             webserver_timeout: settings.timeout,
+            authenticator: Authenticator::new(&settings.auth)?,
+            tls: settings.tls.clone(),
         })
     }
 
@@ -210,6 +237,7 @@ impl Server {
             runner_sender,
             db,
             self.sender_timeout,
+            self.authenticator.clone(),
         ))
         .await?;
 
@@ -229,6 +257,7 @@ impl Server {
             runner_sender,
             db,
             self.sender_timeout,
+            self.authenticator.clone(),
         ))
         .await?;
         self.start_inner(module).await
@@ -256,6 +285,7 @@ impl Server {
         info!(
             subject = "webserver.start",
             category = "webserver",
+            tls = self.tls.is_some(),
             "webserver listening on {}/{}",
             self.v4_addr,
             self.v6_addr
@@ -266,7 +296,7 @@ impl Server {
             .allow_methods([Method::GET, Method::POST])
             // Allow requests from any origin
             .allow_origin(cors::Any)
-            .allow_headers([CONTENT_TYPE]);
+            .allow_headers([AUTHORIZATION, CONTENT_TYPE]);
 
         let middleware = tower::ServiceBuilder::new()
             .layer(ProxyGetRequestLayer::new("/health", rpc::HEALTH_ENDPOINT)?)
@@ -287,38 +317,84 @@ impl Server {
         let listener_v4 = TcpListener::bind(&self.v4_addr).await?;
         let listener_v6 = TcpListener::bind(&self.v6_addr).await?;
         let (stop_hdl, server_hdl) = stop_channel();
+        let tls = self.tls.as_ref().map(TlsAcceptor::start).transpose()?;
 
-        let svc = jsonrpsee::server::Server::builder()
+        let svc_builder = jsonrpsee::server::Server::builder()
             .custom_tokio_runtime(runtime_hdl.clone())
             .set_http_middleware(middleware)
             .set_id_provider(Box::new(RandomStringIdProvider::new(16)))
             .set_message_buffer_capacity(self.capacity as u32)
-            .to_service_builder()
-            .build(module.into_inner(), stop_hdl.clone());
+            .to_service_builder();
+        let methods: Methods = module.into_inner().into();
+        let authenticator = self.authenticator.clone();
+        let svc_stop_hdl = stop_hdl.clone();
+
+        // Clients are authenticated per HTTP request or WebSocket upgrade,
+        // with their permissions checked on every call made.
+        let svc = tower::service_fn(move |req: hyper::Request<hyper::Body>| {
+            let svc_builder = svc_builder.clone();
+            let methods = methods.clone();
+            let stop_hdl = svc_stop_hdl.clone();
+            let authenticator = authenticator.clone();
+            async move {
+                match authenticator.authenticate(req.headers()).await {
+                    Ok(permissions) => svc_builder
+                        .set_rpc_middleware(
+                            RpcServiceBuilder::new().layer(PermissionLayer::new(permissions)),
+                        )
+                        .build(methods, stop_hdl)
+                        .oneshot(req)
+                        .await
+                        .map_err(|err| anyhow!("{err}")),
+                    Err(err) => {
+                        debug!(subject = "webserver.auth.err",
+                               category = "webserver",
+                               err=?err,
+                               "rejected unauthenticated request");
+                        Ok(hyper::Response::builder()
+                            .status(StatusCode::UNAUTHORIZED)
+                            .header(WWW_AUTHENTICATE, "Bearer, DID")
+                            .body(hyper::Body::from(err.to_string()))?)
+                    }
+                }
+            }
+        });
 
         runtime_hdl.clone().spawn(async move {
             loop {
                 let stream = select! {
-                    result = listener_v4.accept() => {
-                        if let Ok((stream, _remote_addr)) = result {
-                            stream
-                        } else {
+                    result = listener_v4.accept() => match result {
+                        Ok((stream, _remote_addr)) => stream,
+                        Err(err) => {
+                            tls::accept_backoff(&err).await;
                             continue
                         }
-                    }
-                    result = listener_v6.accept() => {
-                        if let Ok((stream, _remote_addr)) = result {
-                            stream
-                        } else {
+                    },
+                    result = listener_v6.accept() => match result {
+                        Ok((stream, _remote_addr)) => stream,
+                        Err(err) => {
+                            tls::accept_backoff(&err).await;
                             continue
                         }
-                    }
+                    },
                     _ = stop_hdl.clone().shutdown() => break,
                 };
 
                 let svc = svc.clone();
                 let stop_hdl2 = stop_hdl.clone();
+                let tls = tls.clone();
                 runtime_hdl.spawn(async move {
+                    let stream = match tls::accept(tls.as_ref(), stream).await {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            debug!(subject = "webserver.tls.err",
+                                   category = "webserver",
+                                   err=?err,
+                                   "TLS handshake failed");
+                            return;
+                        }
+                    };
+
                     let conn = hyper::server::conn::Http::new()
                         .serve_connection(stream, svc)
                         .with_upgrades();
@@ -366,7 +442,8 @@ mod test {
     use metrics_exporter_prometheus::PrometheusBuilder;
     #[cfg(feature = "websocket-notify")]
     use notifier::Header;
-    use std::net::Ipv4Addr;
+    use std::{net::Ipv4Addr, path::PathBuf, sync::Arc};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
     async fn metrics_handle() -> PrometheusHandle {
         let port = port_selector::random_free_tcp_port().unwrap();
@@ -438,6 +515,125 @@ mod test {
         });
     }
 
+    #[homestar_runtime_proc_macro::runner_test]
+    fn ws_authenticate_clients() {
+        let TestRunner { runner, settings } = TestRunner::start();
+        runner.runtime.block_on(async {
+            let mut webserver_settings = settings.node().network().webserver().clone();
+            webserver_settings.auth = settings::WebserverAuthBuilder::default()
+                .enabled(true)
+                .tokens(vec![settings::TokenGrant::new(
+                    "monitor",
+                    vec!["read".to_string()],
+                )])
+                .build()
+                .unwrap();
+
+            let server = Server::new(&webserver_settings).unwrap();
+            let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+            let metrics_hdl = metrics_handle().await;
+            let (runner_tx, _runner_rx) = AsyncChannel::oneshot();
+            let _ws_hdl = server.start(runner_tx, metrics_hdl, db).await.unwrap();
+
+            let ws_url = format!("ws://{}", server.v4_addr);
+
+            // Unauthenticated clients can only call public methods.
+            let client = WsClientBuilder::default()
+                .build(ws_url.clone())
+                .await
+                .unwrap();
+            let ws_resp: serde_json::Value = client
+                .request(rpc::HEALTH_ENDPOINT, rpc_params![])
+                .await
+                .unwrap();
+            assert_eq!(ws_resp, serde_json::json!({"healthy": true }));
+            assert!(client
+                .request::<serde_json::Value, _>(rpc::DISCOVER_ENDPOINT, rpc_params![])
+                .await
+                .is_err());
+
+            // Unknown tokens are rejected on upgrade.
+            let mut headers = http::HeaderMap::new();
+            headers.insert(AUTHORIZATION, "Bearer unknown".parse().unwrap());
+            assert!(WsClientBuilder::default()
+                .set_headers(headers)
+                .build(ws_url.clone())
+                .await
+                .is_err());
+
+            // Tokens are scoped to their permissions.
+            let mut headers = http::HeaderMap::new();
+            headers.insert(AUTHORIZATION, "Bearer monitor".parse().unwrap());
+            let client = WsClientBuilder::default()
+                .set_headers(headers)
+                .build(ws_url)
+                .await
+                .unwrap();
+            assert!(client
+                .request::<serde_json::Value, _>(rpc::DISCOVER_ENDPOINT, rpc_params![])
+                .await
+                .is_ok());
+            assert!(client
+                .request::<serde_json::Value, _>(rpc::CANCEL_WORKFLOW_ENDPOINT, rpc_params!["id"])
+                .await
+                .is_err());
+        });
+    }
+
+    #[homestar_runtime_proc_macro::runner_test]
+    fn serve_over_tls() {
+        let TestRunner { runner, settings } = TestRunner::start();
+        runner.runtime.block_on(async {
+            let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/tls");
+            let mut webserver_settings = settings.node().network().webserver().clone();
+            webserver_settings.tls = Some(
+                settings::TlsBuilder::default()
+                    .cert_path(fixtures.join("server.pem"))
+                    .key_path(fixtures.join("server.key"))
+                    .build()
+                    .unwrap(),
+            );
+
+            let server = Server::new(&webserver_settings).unwrap();
+            let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+            let metrics_hdl = metrics_handle().await;
+            let (runner_tx, _runner_rx) = AsyncChannel::oneshot();
+            let _ws_hdl = server.start(runner_tx, metrics_hdl, db).await.unwrap();
+
+            let mut roots = rustls::RootCertStore::empty();
+            let ca = std::fs::read(fixtures.join("ca.pem")).unwrap();
+            for cert in rustls_pemfile::certs(&mut ca.as_slice()) {
+                roots.add(cert.unwrap()).unwrap();
+            }
+            let connector = tokio_rustls::TlsConnector::from(Arc::new(
+                rustls::ClientConfig::builder()
+                    .with_root_certificates(roots)
+                    .with_no_client_auth(),
+            ));
+
+            let stream = tokio::net::TcpStream::connect(server.v4_addr)
+                .await
+                .unwrap();
+            let mut stream = connector
+                .connect("localhost".try_into().unwrap(), stream)
+                .await
+                .unwrap();
+            stream
+                .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            let mut resp = String::new();
+            stream.read_to_string(&mut resp).await.unwrap();
+            assert!(resp.starts_with("HTTP/1.1 200 OK"));
+            assert!(resp.ends_with(r#"{"healthy":true}"#));
+
+            // Plaintext clients can't connect.
+            assert!(reqwest::get(format!("http://{}/health", server.v4_addr))
+                .await
+                .is_err());
+        });
+    }
+
     #[cfg(feature = "websocket-notify")]
     #[homestar_runtime_proc_macro::runner_test]
     async fn ws_subscribe_unsubscribe_network_events() {
@@ -0,0 +1,476 @@
+//! Authentication of webserver clients, via static bearer tokens or
+//! DID-signed challenges, and the per-method permissions checked on every
+//! JSON-RPC call.
+
+use super::rpc;
+use crate::{authorization::SUPPORTED_KEYS, settings};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use dashmap::DashMap;
+use futures::future::{self, Either, Ready};
+use http::{header::AUTHORIZATION, HeaderMap};
+use jsonrpsee::{
+    server::middleware::rpc::RpcServiceT,
+    types::{error::ErrorObject, Request},
+    MethodResponse,
+};
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use ucan::crypto::did::DidParser;
+
+/// Permission to call monitoring and read-only methods.
+pub(crate) const READ_PERMISSION: &str = "read";
+/// Permission to submit and cancel workflows.
+pub(crate) const RUN_PERMISSION: &str = "run";
+/// Permission to call every method.
+const ANY_PERMISSION: &str = "*";
+
+/// Authorization scheme for static bearer tokens.
+const BEARER_SCHEME: &str = "Bearer";
+/// Authorization scheme for DID-signed challenges.
+const DID_SCHEME: &str = "DID";
+
+/// JSON-RPC error code for calls without permission.
+pub(crate) const UNAUTHORIZED_CODE: i32 = -32001;
+
+/// Methods granted by [READ_PERMISSION].
+const READ_METHODS: &[&str] = &[
+    rpc::DISCOVER_ENDPOINT,
+    rpc::HEALTH_ENDPOINT,
+    rpc::METRICS_ENDPOINT,
+    rpc::NODE_INFO_ENDPOINT,
+    rpc::PLAN_WORKFLOW_ENDPOINT,
+    rpc::LIST_WORKFLOWS_ENDPOINT,
+    rpc::SHOW_WORKFLOW_ENDPOINT,
+    rpc::GET_RECEIPT_ENDPOINT,
+    rpc::LIST_RECEIPTS_FOR_WORKFLOW_ENDPOINT,
+    rpc::FIND_RECEIPTS_ENDPOINT,
+];
+
+/// Subscriptions granted by [READ_PERMISSION].
+#[cfg(feature = "websocket-notify")]
+const READ_SUBSCRIPTIONS: &[&str] = &[
+    rpc::SUBSCRIBE_NETWORK_EVENTS_ENDPOINT,
+    rpc::UNSUBSCRIBE_NETWORK_EVENTS_ENDPOINT,
+    rpc::SUBSCRIBE_TASK_LOGS_ENDPOINT,
+    rpc::UNSUBSCRIBE_TASK_LOGS_ENDPOINT,
+];
+#[cfg(not(feature = "websocket-notify"))]
+const READ_SUBSCRIPTIONS: &[&str] = &[];
+
+/// Methods granted by [RUN_PERMISSION].
+const RUN_METHODS: &[&str] = &[rpc::CANCEL_WORKFLOW_ENDPOINT];
+
+/// Subscriptions granted by [RUN_PERMISSION].
+#[cfg(feature = "websocket-notify")]
+const RUN_SUBSCRIPTIONS: &[&str] = &[
+    rpc::SUBSCRIBE_RUN_WORKFLOW_ENDPOINT,
+    rpc::UNSUBSCRIBE_RUN_WORKFLOW_ENDPOINT,
+];
+#[cfg(not(feature = "websocket-notify"))]
+const RUN_SUBSCRIPTIONS: &[&str] = &[];
+
+/// Maximum number of outstanding challenges, bounding the memory held for
+/// unauthenticated clients requesting them.
+const MAX_CHALLENGES: usize = 4096;
+
+/// Whether a method is granted by [READ_PERMISSION].
+fn read_method(method: &str) -> bool {
+    READ_METHODS.contains(&method) || READ_SUBSCRIPTIONS.contains(&method)
+}
+
+/// Whether a method is granted by [RUN_PERMISSION].
+fn run_method(method: &str) -> bool {
+    RUN_METHODS.contains(&method) || RUN_SUBSCRIPTIONS.contains(&method)
+}
+
+/// Error authenticating a client.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub(crate) enum AuthError {
+    /// `Authorization` header that can't be parsed.
+    #[error("malformed authorization header")]
+    Malformed,
+    /// Bearer token that isn't configured.
+    #[error("unknown bearer token")]
+    UnknownToken,
+    /// DID that isn't configured.
+    #[error("unknown DID: {0}")]
+    UnknownDid(String),
+    /// Challenge that wasn't issued, was already used, or expired.
+    #[error("unknown or expired challenge")]
+    InvalidChallenge,
+    /// Too many challenges outstanding to issue another.
+    #[error("too many outstanding challenges")]
+    TooManyChallenges,
+    /// Challenge signature not made by the DID.
+    #[error("invalid challenge signature for DID: {0}")]
+    InvalidSignature(String),
+}
+
+/// Methods a client may call.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Permissions {
+    /// Every method, when authentication is disabled.
+    All,
+    /// Methods matched by the granted permissions.
+    Granted(Arc<[String]>),
+}
+
+impl Permissions {
+    /// Check that a set of permissions is known, i.e. a group, [ANY_PERMISSION],
+    /// or a method name.
+    fn new(permissions: &[String]) -> Result<Self> {
+        if let Some(unknown) = permissions.iter().find(|permission| {
+            ![ANY_PERMISSION, READ_PERMISSION, RUN_PERMISSION].contains(&permission.as_str())
+                && !read_method(permission)
+                && !run_method(permission)
+        }) {
+            return Err(anyhow!("unknown webserver permission: {unknown}"));
+        }
+
+        Ok(Self::Granted(permissions.into()))
+    }
+
+    /// Whether a method may be called.
+    ///
+    /// Requesting a challenge is always allowed, as it's needed to
+    /// authenticate in the first place.
+    pub(crate) fn allows(&self, method: &str) -> bool {
+        match self {
+            Permissions::All => true,
+            Permissions::Granted(_) if method == rpc::AUTH_CHALLENGE_ENDPOINT => true,
+            Permissions::Granted(permissions) => {
+                permissions
+                    .iter()
+                    .any(|permission| match permission.as_str() {
+                        ANY_PERMISSION => true,
+                        READ_PERMISSION => read_method(method),
+                        RUN_PERMISSION => run_method(method),
+                        permission => permission == method,
+                    })
+            }
+        }
+    }
+}
+
+/// Authenticator of webserver clients, resolving their [Permissions] from
+/// the `Authorization` header of each HTTP request or WebSocket upgrade.
+#[derive(Clone)]
+pub(crate) struct Authenticator {
+    enabled: bool,
+    public: Permissions,
+    tokens: Arc<HashMap<String, Permissions>>,
+    dids: Arc<HashMap<String, Permissions>>,
+    challenges: Arc<DashMap<String, Instant>>,
+    challenge_ttl: Duration,
+}
+
+impl fmt::Debug for Authenticator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Authenticator")
+            .field("enabled", &self.enabled)
+            .field("public", &self.public)
+            .field("tokens", &self.tokens.len())
+            .field("dids", &self.dids.keys())
+            .field("challenge_ttl", &self.challenge_ttl)
+            .finish()
+    }
+}
+
+impl Authenticator {
+    /// Create a new [Authenticator], checking configured permissions and
+    /// DIDs.
+    pub(crate) fn new(settings: &settings::WebserverAuth) -> Result<Self> {
+        let tokens = settings
+            .tokens
+            .iter()
+            .map(|grant| Ok((grant.token.clone(), Permissions::new(&grant.permissions)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let mut did_parser = DidParser::new(SUPPORTED_KEYS);
+        let dids = settings
+            .dids
+            .iter()
+            .map(|grant| {
+                did_parser
+                    .parse(&grant.did)
+                    .map_err(|err| anyhow!("unsupported webserver DID {}: {err}", grant.did))?;
+                Ok((grant.did.clone(), Permissions::new(&grant.permissions)?))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        Ok(Self {
+            enabled: settings.enabled,
+            public: Permissions::new(&settings.public)?,
+            tokens: tokens.into(),
+            dids: dids.into(),
+            challenges: DashMap::new().into(),
+            challenge_ttl: settings.challenge_ttl,
+        })
+    }
+
+    /// Issue a single-use challenge for a DID to sign, dropping any expired
+    /// ones.
+    ///
+    /// Fails if [MAX_CHALLENGES] unexpired challenges are outstanding.
+    pub(crate) fn challenge(&self) -> Result<String, AuthError> {
+        let now = Instant::now();
+        self.challenges.retain(|_, expires| *expires > now);
+        if self.challenges.len() >= MAX_CHALLENGES {
+            return Err(AuthError::TooManyChallenges);
+        }
+
+        let challenge = URL_SAFE_NO_PAD.encode(rand::thread_rng().gen::<[u8; 32]>());
+        self.challenges
+            .insert(challenge.clone(), now + self.challenge_ttl);
+        Ok(challenge)
+    }
+
+    /// Time a challenge is valid for after being issued.
+    pub(crate) fn challenge_ttl(&self) -> Duration {
+        self.challenge_ttl
+    }
+
+    /// Resolve the [Permissions] of a client from its request headers.
+    ///
+    /// Clients without an `Authorization` header get the public
+    /// permissions, while invalid credentials are rejected.
+    pub(crate) async fn authenticate(&self, headers: &HeaderMap) -> Result<Permissions, AuthError> {
+        if !self.enabled {
+            return Ok(Permissions::All);
+        }
+
+        let Some(header) = headers.get(AUTHORIZATION) else {
+            return Ok(self.public.clone());
+        };
+
+        let header = header.to_str().map_err(|_| AuthError::Malformed)?;
+        match header.split_once(' ') {
+            Some((BEARER_SCHEME, token)) => self
+                .tokens
+                .get(token.trim())
+                .cloned()
+                .ok_or(AuthError::UnknownToken),
+            Some((DID_SCHEME, credentials)) => {
+                let mut parts = credentials.split_whitespace();
+                let (Some(did), Some(challenge), Some(signature), None) =
+                    (parts.next(), parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(AuthError::Malformed);
+                };
+
+                self.verify_challenge(did, challenge, signature).await
+            }
+            _ => Err(AuthError::Malformed),
+        }
+    }
+
+    async fn verify_challenge(
+        &self,
+        did: &str,
+        challenge: &str,
+        signature: &str,
+    ) -> Result<Permissions, AuthError> {
+        let permissions = self
+            .dids
+            .get(did)
+            .cloned()
+            .ok_or_else(|| AuthError::UnknownDid(did.to_string()))?;
+
+        match self.challenges.remove(challenge) {
+            Some((_, expires)) if expires > Instant::now() => (),
+            _ => return Err(AuthError::InvalidChallenge),
+        }
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| AuthError::Malformed)?;
+        let key = DidParser::new(SUPPORTED_KEYS)
+            .parse(did)
+            .map_err(|_| AuthError::UnknownDid(did.to_string()))?;
+
+        key.verify(challenge.as_bytes(), &signature)
+            .await
+            .map(|_| permissions)
+            .map_err(|_| AuthError::InvalidSignature(did.to_string()))
+    }
+}
+
+/// RPC middleware layer rejecting calls not allowed by a client's
+/// [Permissions].
+#[derive(Clone, Debug)]
+pub(crate) struct PermissionLayer(Permissions);
+
+impl PermissionLayer {
+    /// Create a new [PermissionLayer] for a client's [Permissions].
+    pub(crate) fn new(permissions: Permissions) -> Self {
+        Self(permissions)
+    }
+}
+
+impl<S> tower::Layer<S> for PermissionLayer {
+    type Service = PermissionService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        PermissionService {
+            service,
+            permissions: self.0.clone(),
+        }
+    }
+}
+
+/// RPC service checking [Permissions] before every call.
+#[derive(Debug)]
+pub(crate) struct PermissionService<S> {
+    service: S,
+    permissions: Permissions,
+}
+
+impl<'a, S> RpcServiceT<'a> for PermissionService<S>
+where
+    S: RpcServiceT<'a>,
+{
+    type Future = Either<S::Future, Ready<MethodResponse>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        if self.permissions.allows(request.method_name()) {
+            Either::Left(self.service.call(request))
+        } else {
+            let err = ErrorObject::owned(
+                UNAUTHORIZED_CODE,
+                format!("not permitted to call {}", request.method_name()),
+                None::<()>,
+            );
+            Either::Right(future::ready(MethodResponse::error(request.id, err)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::authorization::NodeKey;
+    use http::HeaderValue;
+    use libp2p::identity::Keypair;
+    use ucan::crypto::KeyMaterial;
+
+    fn authorization(value: &str) -> HeaderMap {
+        HeaderMap::from_iter([(AUTHORIZATION, HeaderValue::from_str(value).unwrap())])
+    }
+
+    #[tokio::test]
+    async fn authenticate_tokens_and_dids() {
+        let key = NodeKey::new(Keypair::generate_ed25519());
+        let did = key.did().unwrap();
+
+        let settings = settings::WebserverAuth {
+            enabled: true,
+            tokens: vec![settings::TokenGrant::new(
+                "monitor",
+                vec![READ_PERMISSION.to_string()],
+            )],
+            dids: vec![settings::DidGrant::new(
+                did.clone(),
+                vec![
+                    RUN_PERMISSION.to_string(),
+                    rpc::NODE_INFO_ENDPOINT.to_string(),
+                ],
+            )],
+            ..Default::default()
+        };
+        let authenticator = Authenticator::new(&settings).unwrap();
+
+        let public = authenticator.authenticate(&HeaderMap::new()).await.unwrap();
+        assert!(public.allows(rpc::HEALTH_ENDPOINT));
+        assert!(public.allows(rpc::AUTH_CHALLENGE_ENDPOINT));
+        assert!(!public.allows(rpc::METRICS_ENDPOINT));
+
+        let monitor = authenticator
+            .authenticate(&authorization("Bearer monitor"))
+            .await
+            .unwrap();
+        assert!(monitor.allows(rpc::METRICS_ENDPOINT));
+        assert!(!monitor.allows(rpc::CANCEL_WORKFLOW_ENDPOINT));
+        #[cfg(feature = "websocket-notify")]
+        {
+            assert!(monitor.allows(rpc::SUBSCRIBE_NETWORK_EVENTS_ENDPOINT));
+            assert!(monitor.allows(rpc::SUBSCRIBE_TASK_LOGS_ENDPOINT));
+            assert!(!monitor.allows(rpc::SUBSCRIBE_RUN_WORKFLOW_ENDPOINT));
+        }
+        assert_eq!(
+            authenticator
+                .authenticate(&authorization("Bearer other"))
+                .await,
+            Err(AuthError::UnknownToken)
+        );
+
+        let challenge = authenticator.challenge().unwrap();
+        let signature = URL_SAFE_NO_PAD.encode(key.sign(challenge.as_bytes()).await.unwrap());
+        let header = authorization(&format!("DID {did} {challenge} {signature}"));
+        let operator = authenticator.authenticate(&header).await.unwrap();
+        #[cfg(feature = "websocket-notify")]
+        assert!(operator.allows(rpc::SUBSCRIBE_RUN_WORKFLOW_ENDPOINT));
+        assert!(operator.allows(rpc::CANCEL_WORKFLOW_ENDPOINT));
+        assert!(operator.allows(rpc::NODE_INFO_ENDPOINT));
+        assert!(!operator.allows(rpc::METRICS_ENDPOINT));
+
+        // Challenges are single-use.
+        assert_eq!(
+            authenticator.authenticate(&header).await,
+            Err(AuthError::InvalidChallenge)
+        );
+
+        let challenge = authenticator.challenge().unwrap();
+        let signature = URL_SAFE_NO_PAD.encode(key.sign(b"other").await.unwrap());
+        assert_eq!(
+            authenticator
+                .authenticate(&authorization(&format!(
+                    "DID {did} {challenge} {signature}"
+                )))
+                .await,
+            Err(AuthError::InvalidSignature(did))
+        );
+
+        let disabled = Authenticator::new(&settings::WebserverAuth::default()).unwrap();
+        assert_eq!(
+            disabled
+                .authenticate(&authorization("Bearer other"))
+                .await
+                .unwrap(),
+            Permissions::All
+        );
+
+        let unknown = settings::WebserverAuth {
+            public: vec!["write".to_string()],
+            ..Default::default()
+        };
+        assert!(Authenticator::new(&unknown).is_err());
+    }
+
+    #[test]
+    fn cap_outstanding_challenges() {
+        let settings = settings::WebserverAuth {
+            enabled: true,
+            ..Default::default()
+        };
+        let authenticator = Authenticator::new(&settings).unwrap();
+
+        for _ in 0..MAX_CHALLENGES {
+            authenticator.challenge().unwrap();
+        }
+        assert_eq!(authenticator.challenge(), Err(AuthError::TooManyChallenges));
+
+        // Expired challenges no longer count towards the cap.
+        let expired = Instant::now() - Duration::from_secs(1);
+        authenticator
+            .challenges
+            .iter_mut()
+            .for_each(|mut expires| *expires = expired);
+        assert!(authenticator.challenge().is_ok());
+    }
+}
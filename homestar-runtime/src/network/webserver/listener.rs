@@ -1,11 +1,17 @@
 //! Listener for incoming requests types.
 
+use crate::{receipt, workflow, ReceiptEncoding, ReceiptFilter};
 use anyhow::anyhow;
 use faststr::FastStr;
 use homestar_invocation::ipld::{DagCbor, DagJson};
 use homestar_wasm::io::Arg;
-use homestar_workflow::Workflow;
-use libipld::{serde::from_ipld, Ipld};
+use homestar_workflow::{Params, Workflow};
+use libipld::{
+    json::DagJsonCodec,
+    prelude::Codec,
+    serde::{from_ipld, to_ipld},
+    Cid, Ipld,
+};
 use names::{Generator, Name};
 use serde::{de, Deserialize, Deserializer, Serialize};
 use serde_json::value::RawValue;
@@ -13,6 +19,8 @@ use std::collections::BTreeMap;
 
 const NAME_KEY: &str = "name";
 const WORKFLOW_KEY: &str = "workflow";
+const SETTINGS_KEY: &str = "settings";
+const PARAMS_KEY: &str = "params";
 
 /// A [Workflow] run command via a WebSocket channel for JSON inputs.
 ///
@@ -24,6 +32,10 @@ pub struct JsonRun<'a> {
     pub(crate) name: FastStr,
     #[serde(deserialize_with = "from_raw_value")]
     pub(crate) workflow: Workflow<'a, Arg>,
+    #[serde(default)]
+    pub(crate) settings: workflow::Settings,
+    #[serde(default, deserialize_with = "from_raw_params")]
+    pub(crate) params: Params,
 }
 
 fn default_name() -> FastStr {
@@ -42,19 +54,48 @@ where
     Workflow::from_json(raw_value.get().as_bytes()).map_err(de::Error::custom)
 }
 
+/// Parse workflow [Params] as DAG-JSON values, e.g. for links.
+fn from_raw_params<'de, D>(deserializer: D) -> Result<Params, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let raw_params: BTreeMap<String, &RawValue> = Deserialize::deserialize(deserializer)?;
+    raw_params
+        .into_iter()
+        .map(|(name, value)| {
+            let value = DagJsonCodec
+                .decode(value.get().as_bytes())
+                .map_err(de::Error::custom)?;
+            Ok((name, value))
+        })
+        .collect()
+}
+
 /// A [Workflow] run command via a WebSocket channel for CBOR inputs.
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct CborRun<'a> {
     pub(crate) name: FastStr,
     pub(crate) workflow: Workflow<'a, Arg>,
+    pub(crate) settings: workflow::Settings,
+    pub(crate) params: Params,
 }
 
 impl<'a> From<CborRun<'a>> for Ipld {
     fn from(run: CborRun<'a>) -> Self {
-        Ipld::Map(BTreeMap::from([
+        let mut map = BTreeMap::from([
             ("name".into(), Ipld::String(run.name.as_str().to_string())),
             ("workflow".into(), run.workflow.into()),
-        ]))
+        ]);
+        if let Ok(settings) = to_ipld(run.settings) {
+            map.insert(SETTINGS_KEY.into(), settings);
+        }
+        if !run.params.is_empty() {
+            map.insert(
+                PARAMS_KEY.into(),
+                Ipld::Map(run.params.into_iter().collect()),
+            );
+        }
+        Ipld::Map(map)
     }
 }
 
@@ -73,9 +114,21 @@ impl<'a> TryFrom<Ipld> for CborRun<'a> {
                 .ok_or_else(|| anyhow!("missing {WORKFLOW_KEY}"))?
                 .to_owned(),
         )?;
+        let settings = map
+            .get(SETTINGS_KEY)
+            .map(|ipld| from_ipld(ipld.to_owned()))
+            .transpose()?
+            .unwrap_or_default();
+        let params = match map.get(PARAMS_KEY) {
+            Some(Ipld::Map(params)) => params.to_owned(),
+            Some(_) => return Err(anyhow!("{PARAMS_KEY} must be a map")),
+            None => Params::new(),
+        };
         Ok(CborRun {
             name: FastStr::from(name),
             workflow,
+            settings,
+            params,
         })
     }
 }
@@ -95,6 +148,116 @@ impl<'a, 'de> Deserialize<'de> for CborRun<'a> {
     }
 }
 
+/// Plan a [Workflow] run, without executing anything, optionally rendering
+/// a [dot] graph.
+///
+/// [dot]: <https://graphviz.org/doc/info/lang.html>
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct PlanWorkflow<'a> {
+    #[serde(default)]
+    pub(crate) name: Option<FastStr>,
+    #[serde(deserialize_with = "from_raw_value")]
+    pub(crate) workflow: Workflow<'a, Arg>,
+    #[serde(default, deserialize_with = "from_raw_params")]
+    pub(crate) params: Params,
+    #[serde(default)]
+    pub(crate) dot: bool,
+}
+
+/// Cancel a running [Workflow] via a WebSocket channel, given its Cid.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct CancelWorkflow {
+    #[serde(deserialize_with = "from_cid_str")]
+    pub(crate) cid: Cid,
+}
+
+/// Show a stored [Workflow], given its Cid or local name.
+///
+/// [Workflow]: homestar_workflow::Workflow
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct ShowWorkflow {
+    pub(crate) workflow: String,
+}
+
+/// Get a stored [Receipt], given its Cid.
+///
+/// [Receipt]: crate::Receipt
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct GetReceipt {
+    #[serde(deserialize_with = "from_cid_str")]
+    pub(crate) cid: Cid,
+    #[serde(default)]
+    pub(crate) encoding: ReceiptEncoding,
+}
+
+/// List stored [Receipt]s for a [Workflow], given its Cid.
+///
+/// [Receipt]: crate::Receipt
+/// [Workflow]: homestar_workflow::Workflow
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct ListReceiptsForWorkflow {
+    #[serde(deserialize_with = "from_cid_str")]
+    pub(crate) workflow: Cid,
+    #[serde(default)]
+    pub(crate) encoding: ReceiptEncoding,
+}
+
+/// Subscribe to the log records of the tasks of a [Workflow], given its Cid.
+///
+/// [Workflow]: homestar_workflow::Workflow
+#[cfg(feature = "websocket-notify")]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub(crate) struct SubscribeTaskLogs {
+    #[serde(deserialize_with = "from_cid_str")]
+    pub(crate) workflow: Cid,
+}
+
+/// Find stored [Receipt]s matching a filter.
+///
+/// [Receipt]: crate::Receipt
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub(crate) struct FindReceipts {
+    #[serde(deserialize_with = "from_opt_cid_str")]
+    pub(crate) ran: Option<Cid>,
+    #[serde(deserialize_with = "from_opt_cid_str")]
+    pub(crate) instruction: Option<Cid>,
+    pub(crate) issuer: Option<String>,
+    pub(crate) op: Option<String>,
+    pub(crate) limit: Option<u32>,
+    pub(crate) encoding: ReceiptEncoding,
+}
+
+impl From<FindReceipts> for (ReceiptFilter, ReceiptEncoding) {
+    fn from(find: FindReceipts) -> Self {
+        let filter = ReceiptFilter {
+            ran: find.ran,
+            instruction: find.instruction,
+            issuer: find.issuer,
+            op: find.op,
+            limit: find.limit.unwrap_or(receipt::query::DEFAULT_LIMIT),
+        };
+        (filter, find.encoding)
+    }
+}
+
+fn from_cid_str<'de, D>(deserializer: D) -> Result<Cid, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = Deserialize::deserialize(deserializer)?;
+    Cid::try_from(s).map_err(de::Error::custom)
+}
+
+fn from_opt_cid_str<'de, D>(deserializer: D) -> Result<Option<Cid>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: Option<String> = Deserialize::deserialize(deserializer)?;
+    s.map(|s| Cid::try_from(s).map_err(de::Error::custom))
+        .transpose()
+}
+
 /// Filter metrics by prefix.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct MetricsPrefix {
@@ -132,6 +295,8 @@ mod test {
         let run = JsonRun {
             name: "test".into(),
             workflow: workflow.clone(),
+            settings: workflow::Settings::default(),
+            params: Params::new(),
         };
 
         let run_str = format!(
@@ -141,6 +306,31 @@ mod test {
 
         let post_run = serde_json::from_str(&run_str).unwrap();
         assert_eq!(run, post_run);
+
+        let run_str = format!(
+            r#"{{"name": "test","workflow": {},"settings": {{"retries": 4}}}}"#,
+            workflow.to_json_string().unwrap()
+        );
+
+        let post_run: JsonRun<'_> = serde_json::from_str(&run_str).unwrap();
+        assert_eq!(post_run.settings.retries, 4);
+        assert_eq!(post_run.settings.timeout, run.settings.timeout);
+
+        let run_str = format!(
+            r#"{{"name": "test","workflow": {},"params": {{"n": 1, "s": "a", "l": {{"/": "{}"}}}}}}"#,
+            workflow.to_json_string().unwrap(),
+            workflow.clone().to_cid().unwrap()
+        );
+
+        let post_run: JsonRun<'_> = serde_json::from_str(&run_str).unwrap();
+        assert_eq!(
+            post_run.params,
+            Params::from([
+                ("n".to_string(), Ipld::Integer(1)),
+                ("s".to_string(), Ipld::String("a".to_string())),
+                ("l".to_string(), Ipld::Link(workflow.to_cid().unwrap())),
+            ])
+        );
     }
 
     #[test]
@@ -149,7 +339,10 @@ mod test {
             fs::read_to_string("tests/fixtures/test-workflow-image-pipeline.json").unwrap();
         let json: serde_json::Value = serde_json::from_str(&workflow_str).unwrap();
         let json_string = serde_json::to_string(&json).unwrap();
-        let run_str = format!(r#"{{"name": "test","workflow": {}}}"#, json_string);
+        let run_str = format!(
+            r#"{{"name": "test","workflow": {},"params": {{"n": 1}}}}"#,
+            json_string
+        );
         let run1: CborRun<'_> = DagJson::from_json_string(run_str).unwrap();
 
         let path = PathBuf::from("./fixtures/test.cbor");
@@ -161,5 +354,6 @@ mod test {
         let cbor_file = fs::read(path).unwrap();
         let run2: CborRun<'_> = DagCbor::from_cbor(&cbor_file).unwrap();
         assert_eq!(run1, run2);
+        assert_eq!(run2.params.get("n"), Some(&Ipld::Integer(1)));
     }
 }
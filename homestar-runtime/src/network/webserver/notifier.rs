@@ -47,12 +47,14 @@ where
     }
 }
 
-/// Subscription type: either directed via a Cid or an event subscription string.
+/// Subscription type: either directed via a Cid, an event subscription
+/// string, or to the task logs of a workflow by its Cid.
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub(crate) enum SubscriptionTyp {
     EventSub(String),
     Cid(Cid),
+    TaskLogs(Cid),
 }
 
 /// A header for a message to be sent to a WebSocket client.
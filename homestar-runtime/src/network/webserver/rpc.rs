@@ -3,12 +3,13 @@
 #[cfg(feature = "websocket-notify")]
 use super::notifier::{self, Header, Notifier, SubscriptionTyp};
 #[allow(unused_imports)]
-use super::{listener, prom::PrometheusData, Message};
+use super::{auth::Authenticator, listener, prom::PrometheusData, Message};
 #[cfg(feature = "websocket-notify")]
 use crate::channel::{AsyncChannel, AsyncChannelReceiver};
 use crate::{
     db::Database,
-    runner::{NodeInfo, WsSender},
+    runner::{self, response, NodeInfo, WsSender},
+    workflow,
 };
 #[cfg(feature = "websocket-notify")]
 use anyhow::anyhow;
@@ -62,6 +63,22 @@ pub(crate) const HEALTH_ENDPOINT: &str = "health";
 pub(crate) const METRICS_ENDPOINT: &str = "metrics";
 /// Node information endpoint.
 pub(crate) const NODE_INFO_ENDPOINT: &str = "node";
+/// Plan a workflow run without executing anything.
+pub(crate) const PLAN_WORKFLOW_ENDPOINT: &str = "plan_workflow";
+/// Cancel a running workflow.
+pub(crate) const CANCEL_WORKFLOW_ENDPOINT: &str = "cancel_workflow";
+/// List stored workflows.
+pub(crate) const LIST_WORKFLOWS_ENDPOINT: &str = "list_workflows";
+/// Show a stored workflow with per-task progress.
+pub(crate) const SHOW_WORKFLOW_ENDPOINT: &str = "show_workflow";
+/// Get a stored receipt.
+pub(crate) const GET_RECEIPT_ENDPOINT: &str = "get_receipt";
+/// List stored receipts for a workflow.
+pub(crate) const LIST_RECEIPTS_FOR_WORKFLOW_ENDPOINT: &str = "list_receipts_for_workflow";
+/// Find stored receipts matching a filter.
+pub(crate) const FIND_RECEIPTS_ENDPOINT: &str = "find_receipts";
+/// Issue a challenge for a DID to sign, authenticating the client.
+pub(crate) const AUTH_CHALLENGE_ENDPOINT: &str = "auth_challenge";
 /// Run a workflow and subscribe to that workflow's events.
 #[cfg(feature = "websocket-notify")]
 pub(crate) const SUBSCRIBE_RUN_WORKFLOW_ENDPOINT: &str = "subscribe_run_workflow";
@@ -74,6 +91,12 @@ pub(crate) const SUBSCRIBE_NETWORK_EVENTS_ENDPOINT: &str = "subscribe_network_ev
 /// Unsubscribe from network events.
 #[cfg(feature = "websocket-notify")]
 pub(crate) const UNSUBSCRIBE_NETWORK_EVENTS_ENDPOINT: &str = "unsubscribe_network_events";
+/// Subscribe to the log records of a workflow's tasks.
+#[cfg(feature = "websocket-notify")]
+pub(crate) const SUBSCRIBE_TASK_LOGS_ENDPOINT: &str = "subscribe_task_logs";
+/// Unsubscribe from the log records of a workflow's tasks.
+#[cfg(feature = "websocket-notify")]
+pub(crate) const UNSUBSCRIBE_TASK_LOGS_ENDPOINT: &str = "unsubscribe_task_logs";
 
 /// Context for RPC methods.
 #[cfg(feature = "websocket-notify")]
@@ -84,6 +107,7 @@ pub(crate) struct Context<DB: Database> {
     workflow_msg_notifier: Notifier<notifier::Message>,
     runner_sender: WsSender,
     sender_timeout: Duration,
+    authenticator: Authenticator,
     workflow_listeners: Arc<DashMap<SubscriptionId<'static>, (Cid, FastStr)>>,
 }
 
@@ -95,6 +119,7 @@ pub(crate) struct Context<DB: Database> {
     metrics_hdl: PrometheusHandle,
     runner_sender: WsSender,
     sender_timeout: Duration,
+    authenticator: Authenticator,
 }
 
 impl<DB> Context<DB>
@@ -111,6 +136,7 @@ where
         runner_sender: WsSender,
         db: DB,
         sender_timeout: Duration,
+        authenticator: Authenticator,
     ) -> Self {
         Self {
             db,
@@ -119,6 +145,7 @@ where
             workflow_msg_notifier,
             runner_sender,
             sender_timeout,
+            authenticator,
             workflow_listeners: DashMap::new().into(),
         }
     }
@@ -130,12 +157,14 @@ where
         runner_sender: WsSender,
         db: DB,
         sender_timeout: Duration,
+        authenticator: Authenticator,
     ) -> Self {
         Self {
             db,
             metrics_hdl,
             runner_sender,
             sender_timeout,
+            authenticator,
         }
     }
 }
@@ -169,6 +198,14 @@ where
 
         module.register_method(DISCOVER_ENDPOINT, |_, _| serde_json::json!(API_SCHEMA_DOC))?;
 
+        module.register_method(AUTH_CHALLENGE_ENDPOINT, |_, ctx| {
+            let challenge = ctx.authenticator.challenge().map_err(busy_err)?;
+            Ok::<_, ErrorObject<'_>>(serde_json::json!({
+                "challenge": challenge,
+                "expires_in": ctx.authenticator.challenge_ttl().as_secs(),
+            }))
+        })?;
+
         module.register_async_method(HEALTH_ENDPOINT, |_, ctx| async move {
             match ctx.db.conn() {
                 Ok(mut conn) => {
@@ -219,6 +256,92 @@ where
             }
         })?;
 
+        module.register_async_method(PLAN_WORKFLOW_ENDPOINT, |params, ctx| async move {
+            let listener::PlanWorkflow {
+                name,
+                workflow,
+                params,
+                dot,
+            } = params.parse()?;
+            let workflow = workflow.with_params(&params).map_err(invalid_params_err)?;
+
+            runner::plan_workflow(workflow, name, dot, ctx.db.clone())
+                .map(|response| serde_json::json!(response))
+                .map_err(|err| internal_err(err.to_string()))
+        })?;
+
+        module.register_async_method(CANCEL_WORKFLOW_ENDPOINT, |params, ctx| async move {
+            let listener::CancelWorkflow { cid } = params.parse()?;
+            let (tx, rx) = crate::channel::AsyncChannel::oneshot();
+            ctx.runner_sender
+                .send_async((Message::CancelWorkflow(cid), Some(tx)))
+                .await
+                .map_err(|err| internal_err(err.to_string()))?;
+
+            match rx.recv_async().await {
+                Ok(Message::AckCancelWorkflow((cid, name))) => Ok(serde_json::json!(
+                    response::CancelledWorkflow::new(cid, name)
+                )),
+                Ok(Message::RunErr(err)) => Err(internal_err(err.to_string())),
+                _ => {
+                    error!(
+                        subject = "call.cancel_workflow",
+                        category = "jsonrpc.call",
+                        sub = CANCEL_WORKFLOW_ENDPOINT,
+                        cid = cid.to_string(),
+                        "did not acknowledge message in time"
+                    );
+                    Err(internal_err(format!("failed to cancel workflow {cid}")))
+                }
+            }
+        })?;
+
+        module.register_async_method(LIST_WORKFLOWS_ENDPOINT, |params, ctx| async move {
+            let filter: workflow::Filter = match params.as_str() {
+                Some(_) => params.parse()?,
+                None => workflow::Filter::default(),
+            };
+
+            runner::list_workflows(filter, ctx.db.clone())
+                .map(|response| serde_json::json!(response))
+                .map_err(|err| internal_err(err.to_string()))
+        })?;
+
+        module.register_async_method(SHOW_WORKFLOW_ENDPOINT, |params, ctx| async move {
+            let listener::ShowWorkflow { workflow } = params.parse()?;
+            runner::show_workflow(&workflow, ctx.db.clone())
+                .map(|response| serde_json::json!(response))
+                .map_err(|err| internal_err(err.to_string()))
+        })?;
+
+        module.register_async_method(GET_RECEIPT_ENDPOINT, |params, ctx| async move {
+            let listener::GetReceipt { cid, encoding } = params.parse()?;
+            runner::get_receipt(cid, encoding, ctx.db.clone())
+                .and_then(|response| response.to_json_value())
+                .map_err(|err| internal_err(err.to_string()))
+        })?;
+
+        module.register_async_method(
+            LIST_RECEIPTS_FOR_WORKFLOW_ENDPOINT,
+            |params, ctx| async move {
+                let listener::ListReceiptsForWorkflow { workflow, encoding } = params.parse()?;
+                runner::list_receipts_for_workflow(workflow, encoding, ctx.db.clone())
+                    .and_then(|response| response.to_json_value())
+                    .map_err(|err| internal_err(err.to_string()))
+            },
+        )?;
+
+        module.register_async_method(FIND_RECEIPTS_ENDPOINT, |params, ctx| async move {
+            let find: listener::FindReceipts = match params.as_str() {
+                Some(_) => params.parse()?,
+                None => listener::FindReceipts::default(),
+            };
+            let (filter, encoding) = find.into();
+            runner::find_receipts(filter, encoding, ctx.db.clone())
+                .and_then(|response| response.to_json_value())
+                .map_err(|err| internal_err(err.to_string()))
+        })?;
+
         #[cfg(feature = "websocket-notify")]
         module.register_subscription(
             SUBSCRIBE_NETWORK_EVENTS_ENDPOINT,
@@ -239,45 +362,81 @@ where
             },
         )?;
 
+        #[cfg(feature = "websocket-notify")]
+        module.register_subscription(
+            SUBSCRIBE_TASK_LOGS_ENDPOINT,
+            SUBSCRIBE_TASK_LOGS_ENDPOINT,
+            UNSUBSCRIBE_TASK_LOGS_ENDPOINT,
+            |params, pending, ctx| async move {
+                match params.parse::<listener::SubscribeTaskLogs>() {
+                    Ok(listener::SubscribeTaskLogs { workflow }) => {
+                        let sink = pending.accept().await?;
+                        let rx = ctx.workflow_msg_notifier.inner().subscribe();
+                        let stream = BroadcastStream::new(rx);
+                        Self::handle_task_logs_subscription(sink, stream, ctx, workflow).await?;
+                    }
+                    Err(err) => {
+                        warn!(subject = "subscription.task_logs.err",
+                              category = "jsonrpc.subscription",
+                              err=?err,
+                              "failed to parse task logs params");
+                        let _ = pending.reject(err).await;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
         #[cfg(feature = "websocket-notify")]
         module.register_subscription(
             SUBSCRIBE_RUN_WORKFLOW_ENDPOINT,
             SUBSCRIBE_RUN_WORKFLOW_ENDPOINT,
             UNSUBSCRIBE_RUN_WORKFLOW_ENDPOINT,
             |params, pending, ctx| async move {
-                match params.one::<listener::JsonRun<'_>>() {
-                    Ok(listener::JsonRun { name, workflow }) => {
+                let run = match params.one::<listener::JsonRun<'_>>() {
+                    Ok(listener::JsonRun {
+                        name,
+                        workflow,
+                        settings,
+                        params,
+                    }) => Ok((name, workflow, settings, params)),
+                    Err(_err) => params.one::<listener::CborRun<'_>>().map(
+                        |listener::CborRun {
+                             name,
+                             workflow,
+                             settings,
+                             params,
+                         }| (name, workflow, settings, params),
+                    ),
+                };
+
+                // Substitute parameters before the workflow Cid is computed.
+                let run = run.and_then(|(name, workflow, settings, params)| {
+                    workflow
+                        .with_params(&params)
+                        .map(|workflow| (name, workflow, settings))
+                        .map_err(invalid_params_err)
+                });
+
+                match run {
+                    Ok((name, workflow, settings)) => {
                         let (tx, rx) = AsyncChannel::oneshot();
                         ctx.runner_sender
                             .send_async((
-                                Message::RunWorkflow((name.clone(), workflow.clone())),
+                                Message::RunWorkflow((name.clone(), workflow.clone(), settings)),
                                 Some(tx),
                             ))
                             .await?;
 
                         Self::handle_run_workflow(name, workflow, rx, ctx, pending).await?;
                     }
-
-                    Err(_err) => match params.one::<listener::CborRun<'_>>() {
-                        Ok(listener::CborRun { name, workflow }) => {
-                            let (tx, rx) = AsyncChannel::oneshot();
-                            ctx.runner_sender
-                                .send_async((
-                                    Message::RunWorkflow((name.clone(), workflow.clone())),
-                                    Some(tx),
-                                ))
-                                .await?;
-
-                            Self::handle_run_workflow(name, workflow, rx, ctx, pending).await?;
-                        }
-                        Err(err) => {
-                            warn!(subject = "subscription.workflow.err",
+                    Err(err) => {
+                        warn!(subject = "subscription.workflow.err",
                               category = "jsonrpc.subscription",
                               err=?err,
                               "failed to parse run workflow params");
-                            let _ = pending.reject(err).await;
-                        }
-                    },
+                        let _ = pending.reject(err).await;
+                    }
                 }
                 Ok(())
             },
@@ -373,6 +532,59 @@ where
         Ok(())
     }
 
+    #[cfg(feature = "websocket-notify")]
+    async fn handle_task_logs_subscription(
+        sink: SubscriptionSink,
+        mut stream: BroadcastStream<notifier::Message>,
+        ctx: Arc<Context<DB>>,
+        workflow: Cid,
+    ) -> Result<()> {
+        let rt_hdl = Handle::current();
+        rt_hdl.spawn(async move {
+            loop {
+                select! {
+                    _ = sink.closed() => {
+                        break Ok(());
+                    }
+                    next_msg = stream.next() => {
+                        let msg = match next_msg {
+                            Some(Ok(notifier::Message {
+                                header: Header {
+                                    subscription: SubscriptionTyp::TaskLogs(cid),
+                                    ..
+                                },
+                                payload,
+                            })) if cid == workflow => payload,
+                            Some(Ok(_)) => continue,
+                            Some(Err(err)) => {
+                                error!(subject = "subscription.task_logs.err",
+                                       category = "jsonrpc.subscription",
+                                       err=?err,
+                                       "subscription stream error");
+                                break Err(err.into());
+                            }
+                            None => break Ok(()),
+                        };
+                        let sub_msg = SubscriptionMessage::from_json(&msg)?;
+                        match sink.send_timeout(sub_msg, ctx.sender_timeout).await {
+                            Ok(()) => (),
+                            Err(SendTimeoutError::Closed(_)) => {
+                                break Err(anyhow!("subscription sink closed"));
+                            }
+                            Err(SendTimeoutError::Timeout(_)) => {
+                                error!(subject = "subscription.task_logs.err",
+                                      category = "jsonrpc.subscription",
+                                      "subscription sink timed out");
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
     #[cfg(feature = "websocket-notify")]
     async fn handle_workflow_subscription(
         sink: SubscriptionSink,
@@ -456,3 +668,7 @@ fn internal_err<'a, T: ToString>(msg: T) -> ErrorObject<'a> {
 fn busy_err<'a, T: ToString>(msg: T) -> ErrorObject<'a> {
     ErrorObject::owned(ErrorCode::ServerIsBusy.code(), msg.to_string(), None::<()>)
 }
+
+fn invalid_params_err<'a, T: ToString>(msg: T) -> ErrorObject<'a> {
+    ErrorObject::owned(ErrorCode::InvalidParams.code(), msg.to_string(), None::<()>)
+}
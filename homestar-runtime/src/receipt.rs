@@ -25,6 +25,7 @@ use serde::{Deserialize, Serialize};
 use std::{collections::BTreeMap, fmt};
 
 pub(crate) mod metadata;
+pub(crate) mod query;
 
 /// General version key for receipts.
 pub const VERSION_KEY: &str = "version";
@@ -38,6 +39,7 @@ const OUT_KEY: &str = "out";
 const ISSUER_KEY: &str = "iss";
 const METADATA_KEY: &str = "meta";
 const PROOF_KEY: &str = "prf";
+const SIGNATURE_KEY: &str = "s";
 
 /// Receipt for [Invocation], including it's own Cid and a Cid for an [Instruction].
 ///
@@ -57,6 +59,8 @@ pub struct Receipt {
     issuer: Option<Issuer>,
     prf: UcanPrf,
     version: String,
+    signature: Option<Vec<u8>>,
+    cacheable: bool,
 }
 
 impl fmt::Display for Receipt {
@@ -85,9 +89,21 @@ impl Receipt {
             issuer: invocation_receipt.issuer().to_owned(),
             prf: invocation_receipt.prf().to_owned(),
             version: consts::INVOCATION_VERSION.to_string(),
+            signature: invocation_receipt.signature().map(|sig| sig.to_vec()),
+            cacheable: true,
         }
     }
 
+    /// Mark the [Receipt] as not cacheable, e.g. for a task that failed
+    /// on this node, so that it's neither shared with peers nor used to
+    /// resolve its [Instruction] again.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub(crate) fn non_cacheable(mut self) -> Self {
+        self.cacheable = false;
+        self
+    }
+
     /// Return a runtime [Receipt] given an [Instruction] [Pointer] and
     /// [UCAN Invocation Receipt].
     ///
@@ -182,6 +198,24 @@ impl Receipt {
         DagCborCodec.encode(&ipld)
     }
 
+    /// Get the [Issuer] of the [Receipt], if any.
+    pub fn issuer(&self) -> Option<&Issuer> {
+        self.issuer.as_ref()
+    }
+
+    /// Get the [Issuer]'s signature over the [Receipt], if signed.
+    pub fn signature(&self) -> Option<&[u8]> {
+        self.signature.as_deref()
+    }
+
+    /// Whether the [Receipt] may be shared with peers and used to resolve
+    /// its [Instruction].
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub fn cacheable(&self) -> bool {
+        self.cacheable
+    }
+
     /// Return semver [Version] of [Receipt].
     pub fn version(&self) -> Result<Version, semver::Error> {
         Version::parse(&self.version)
@@ -208,31 +242,30 @@ impl TryFrom<Vec<u8>> for Receipt {
 
 impl From<Receipt> for InvocationReceipt<Ipld> {
     fn from(receipt: Receipt) -> Self {
-        InvocationReceipt::new(
+        let invocation_receipt = InvocationReceipt::new(
             receipt.ran,
             receipt.out,
             receipt.meta.0,
             receipt.issuer,
             receipt.prf,
-        )
+        );
+
+        match receipt.signature {
+            Some(signature) => invocation_receipt.with_signature(signature),
+            None => invocation_receipt,
+        }
     }
 }
 
 impl From<&Receipt> for InvocationReceipt<Ipld> {
     fn from(receipt: &Receipt) -> Self {
-        InvocationReceipt::new(
-            receipt.ran.clone(),
-            receipt.out.clone(),
-            receipt.meta.0.clone(),
-            receipt.issuer.clone(),
-            receipt.prf.clone(),
-        )
+        InvocationReceipt::from(receipt.clone())
     }
 }
 
 impl From<Receipt> for Ipld {
     fn from(receipt: Receipt) -> Self {
-        Ipld::Map(BTreeMap::from([
+        let mut map = BTreeMap::from([
             (CID_KEY.into(), receipt.cid.into()),
             (RAN_KEY.into(), receipt.ran.into()),
             (INSTRUCTION_KEY.into(), receipt.instruction.into()),
@@ -247,7 +280,13 @@ impl From<Receipt> for Ipld {
             ),
             (PROOF_KEY.into(), receipt.prf.into()),
             (VERSION_KEY.into(), receipt.version.into()),
-        ]))
+        ]);
+
+        if let Some(signature) = receipt.signature {
+            map.insert(SIGNATURE_KEY.into(), Ipld::Bytes(signature));
+        }
+
+        Ipld::Map(map)
     }
 }
 
@@ -281,8 +320,8 @@ impl TryFrom<Ipld> for Receipt {
                 Ipld::Null => None,
                 ipld => Some(ipld),
             })
-            .and_then(|ipld| from_ipld(ipld.to_owned()).ok())
-            .map(Issuer::new);
+            .map(|ipld| Issuer::try_from(ipld.to_owned()))
+            .transpose()?;
         let prf = map
             .get(PROOF_KEY)
             .ok_or_else(|| anyhow!("missing {PROOF_KEY}"))?;
@@ -291,6 +330,11 @@ impl TryFrom<Ipld> for Receipt {
                 .ok_or_else(|| anyhow!("missing {VERSION_KEY}"))?
                 .to_owned(),
         )?;
+        let signature = match map.get(SIGNATURE_KEY) {
+            Some(Ipld::Bytes(bytes)) => Some(bytes.to_owned()),
+            Some(Ipld::Null) | None => None,
+            Some(ipld) => return Err(anyhow!("unexpected {SIGNATURE_KEY}: {ipld:?}")),
+        };
 
         Ok(Receipt {
             cid: Pointer::new(cid),
@@ -301,6 +345,8 @@ impl TryFrom<Ipld> for Receipt {
             issuer,
             prf: UcanPrf::try_from(prf)?,
             version,
+            signature,
+            cacheable: true,
         })
     }
 }
@@ -12,3 +12,39 @@ pub(crate) const WORKFLOW_KEY: &str = "workflow";
 /// Associated metadata key for a workflow name, which
 /// will either be some identifier, or the Cid of the workflow.
 pub(crate) const WORKFLOW_NAME_KEY: &str = "name";
+
+/// Metadata key for the fuel consumed by an executed task.
+pub(crate) const FUEL_CONSUMED_KEY: &str = "fuel_consumed";
+
+/// Metadata key for the peak memory (in bytes) of an executed task.
+pub(crate) const PEAK_MEMORY_KEY: &str = "peak_memory";
+
+/// Metadata key for the wall-clock time (in milliseconds) an executed task
+/// took.
+pub(crate) const WALL_TIME_KEY: &str = "wall_time";
+
+/// Metadata key for the Cid of the module an executed task ran.
+pub(crate) const MODULE_KEY: &str = "module";
+
+/// Metadata key for the outbound HTTP requests (`METHOD URL`) an executed
+/// task made.
+pub(crate) const HTTP_REQUESTS_KEY: &str = "http_requests";
+
+/// Metadata key for links to the IPLD blocks an executed task wrote, pinned
+/// with its receipt.
+pub(crate) const BLOCKS_KEY: &str = "blocks";
+
+/// Metadata key for the operations (e.g. `get key`, `set key <value cid>`)
+/// an executed task made on its key-value store.
+pub(crate) const KV_KEY: &str = "kv";
+
+/// Metadata key for the names of the secrets an executed task read, never
+/// their values.
+pub(crate) const SECRETS_KEY: &str = "secrets";
+
+/// Metadata key for a link to the block of log records an executed task
+/// logged, pinned with its receipt.
+pub(crate) const LOGS_KEY: &str = "logs";
+
+/// Metadata key for whether an executed task ran deterministically.
+pub(crate) const DETERMINISTIC_KEY: &str = "deterministic";
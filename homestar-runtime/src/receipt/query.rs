@@ -0,0 +1,142 @@
+//! Filter and output encoding for querying stored [receipts].
+//!
+//! [receipts]: crate::Receipt
+
+use super::Receipt;
+use anyhow::Result;
+use homestar_invocation::receipt::metadata::OP_KEY;
+use libipld::{
+    cbor::DagCborCodec, json::DagJsonCodec, prelude::Codec, serde::from_ipld, Cid, Ipld,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Default maximum number of [Receipt]s returned by a query.
+pub(crate) const DEFAULT_LIMIT: u32 = 100;
+
+/// Encoding of [Receipt]s returned to clients.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+pub enum Encoding {
+    /// [DAG-JSON] encoding.
+    ///
+    /// [DAG-JSON]: <https://ipld.io/specs/codecs/dag-json/spec/>
+    #[default]
+    #[serde(rename = "dag-json")]
+    #[value(name = "dag-json")]
+    DagJson,
+    /// [DAG-CBOR] encoding.
+    ///
+    /// [DAG-CBOR]: <https://ipld.io/specs/codecs/dag-cbor/spec/>
+    #[serde(rename = "dag-cbor")]
+    #[value(name = "dag-cbor")]
+    DagCbor,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::DagJson => write!(f, "dag-json"),
+            Encoding::DagCbor => write!(f, "dag-cbor"),
+        }
+    }
+}
+
+impl Encoding {
+    /// Encode an Ipld value with this encoding.
+    pub(crate) fn encode(&self, ipld: &Ipld) -> Result<Vec<u8>> {
+        match self {
+            Encoding::DagJson => DagJsonCodec.encode(ipld),
+            Encoding::DagCbor => DagCborCodec.encode(ipld),
+        }
+    }
+}
+
+/// Filter for finding stored [Receipt]s.
+///
+/// All set fields must match.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Filter {
+    /// Only include receipts for the [Invocation] with this Cid.
+    ///
+    /// [Invocation]: homestar_invocation::Invocation
+    pub(crate) ran: Option<Cid>,
+    /// Only include receipts for the [Instruction] with this Cid.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub(crate) instruction: Option<Cid>,
+    /// Only include receipts issued by this principal (DID).
+    pub(crate) issuer: Option<String>,
+    /// Only include receipts whose metadata records this operation.
+    pub(crate) op: Option<String>,
+    /// Maximum number of receipts to return.
+    pub(crate) limit: u32,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            ran: None,
+            instruction: None,
+            issuer: None,
+            op: None,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+}
+
+impl Filter {
+    /// Filter by the Cid of the [Invocation] a receipt ran.
+    ///
+    /// [Invocation]: homestar_invocation::Invocation
+    pub fn with_ran(mut self, ran: Cid) -> Self {
+        self.ran = Some(ran);
+        self
+    }
+
+    /// Filter by [Instruction] Cid.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub fn with_instruction(mut self, instruction: Cid) -> Self {
+        self.instruction = Some(instruction);
+        self
+    }
+
+    /// Filter by issuer.
+    pub fn with_issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Filter by operation, e.g. `wasm/run`.
+    pub fn with_op(mut self, op: impl Into<String>) -> Self {
+        self.op = Some(op.into());
+        self
+    }
+
+    /// Set the maximum number of receipts returned.
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Check if a [Receipt]'s metadata matches the operation filter, if
+    /// one is set.
+    ///
+    /// Operations are kept in the (encoded) receipt metadata, so they
+    /// can't be matched within the database query itself.
+    pub(crate) fn matches_op(&self, receipt: &Receipt) -> bool {
+        match &self.op {
+            None => true,
+            Some(op) => match receipt.meta() {
+                Ipld::Map(meta) => meta
+                    .get(OP_KEY)
+                    .and_then(|ipld| from_ipld::<String>(ipld.to_owned()).ok())
+                    .is_some_and(|receipt_op| &receipt_op == op),
+                _ => false,
+            },
+        }
+    }
+}
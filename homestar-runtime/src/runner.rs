@@ -1,28 +1,34 @@
 //! General [Runner] interface for working across multiple workers
 //! and executing workflows.
 
+#[cfg(feature = "websocket-notify")]
+use crate::event_handler::notification::{self, WorkflowNotification};
 #[cfg(feature = "ipfs")]
 use crate::network::IpfsCli;
 use crate::{
+    authorization::{NodeKey, ProofVerifier},
     channel::{AsyncChannel, AsyncChannelReceiver, AsyncChannelSender},
     db::Database,
     event_handler::{Event, EventHandler},
     network::{rpc, swarm, webserver},
     settings,
-    tasks::Fetch,
+    tasks::{ExecutorRegistry, Fetch},
     worker::WorkerMessage,
     workflow::{self, Resource},
-    Db, Receipt, Settings, Worker,
+    Db, Receipt, ReceiptEncoding, ReceiptFilter, Settings, TaskScheduler, Worker,
 };
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use atomic_refcell::AtomicRefCell;
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, Utc};
 use dashmap::DashMap;
 use faststr::FastStr;
 use fnv::FnvHashSet;
 use futures::{future::poll_fn, FutureExt};
-use homestar_invocation::Pointer;
-use homestar_wasm::io::Arg;
+use homestar_invocation::{ipld::DagCbor, Pointer};
+use homestar_wasm::{
+    io::Arg,
+    wasmtime::{ComponentCache, State},
+};
 use homestar_workflow::Workflow;
 use jsonrpsee::server::ServerHandle;
 use libipld::Cid;
@@ -57,6 +63,11 @@ const HOMESTAR_THREAD: &str = "homestar-runtime";
 /// Type alias for a [DashMap] containing running worker [JoinHandle]s.
 pub(crate) type RunningWorkerSet = DashMap<Cid, (JoinHandle<Result<()>>, delay_queue::Key)>;
 
+/// Type alias for a [DashMap] containing workflows eligible for automatic
+/// retries, alongside their settings, name, and number of retries attempted.
+pub(crate) type RetryableWorkflowSet =
+    DashMap<Cid, (Workflow<'static, Arg>, workflow::Settings, FastStr, u32)>;
+
 /// Type alias for a [DashMap] containing running task [AbortHandle]s.
 pub(crate) type RunningTaskSet = DashMap<Cid, Vec<AbortHandle>>;
 
@@ -110,8 +121,13 @@ impl ModifiedSet for RunningTaskSet {
 #[derive(Debug)]
 pub struct Runner {
     event_sender: Arc<AsyncChannelSender<Event>>,
+    executors: Arc<ExecutorRegistry>,
     expiration_queue: Rc<AtomicRefCell<DelayQueue<Cid>>>,
+    module_cache: ComponentCache<State>,
     node_info: StaticNodeInfo,
+    node_key: NodeKey,
+    retry_queue: Rc<AtomicRefCell<DelayQueue<Cid>>>,
+    retryable_workflows: RetryableWorkflowSet,
     running_tasks: Arc<RunningTaskSet>,
     running_workers: RunningWorkerSet,
     pub(crate) runtime: tokio::runtime::Runtime,
@@ -144,6 +160,17 @@ impl Runner {
     /// Initialize and start the Homestar [Runner] / runtime.
     #[cfg(not(test))]
     pub fn start(settings: Settings, db: impl Database + 'static) -> Result<()> {
+        Self::start_with_executors(settings, db, ExecutorRegistry::default())
+    }
+
+    /// Initialize and start the Homestar [Runner] / runtime, with an
+    /// [ExecutorRegistry] of task executors for abilities beyond `wasm/run`.
+    #[cfg(not(test))]
+    pub fn start_with_executors(
+        settings: Settings,
+        db: impl Database + 'static,
+        executors: ExecutorRegistry,
+    ) -> Result<()> {
         let runtime = runtime::Builder::new_multi_thread()
             .enable_all()
             .thread_name_fn(|| {
@@ -153,26 +180,46 @@ impl Runner {
             })
             .build()?;
 
-        Self::init(settings, db.clone(), runtime)?.serve(db)
+        Self::init(settings, db.clone(), executors, runtime)?.serve(db)
     }
 
     /// Initialize and start the Homestar [Runner] / runtime.
     #[cfg(test)]
     pub fn start(settings: Settings, db: impl Database + 'static) -> Result<Self> {
+        Self::start_with_executors(settings, db, ExecutorRegistry::default())
+    }
+
+    /// Initialize and start the Homestar [Runner] / runtime, with an
+    /// [ExecutorRegistry] of task executors for abilities beyond `wasm/run`.
+    #[cfg(test)]
+    pub fn start_with_executors(
+        settings: Settings,
+        db: impl Database + 'static,
+        executors: ExecutorRegistry,
+    ) -> Result<Self> {
         let runtime = runtime::Builder::new_current_thread()
             .enable_all()
             .build()?;
 
-        let runner = Self::init(settings, db, runtime)?;
+        let runner = Self::init(settings, db, executors, runtime)?;
         Ok(runner)
     }
 
     fn init(
         settings: Settings,
         db: impl Database + 'static,
+        executors: ExecutorRegistry,
         runtime: tokio::runtime::Runtime,
     ) -> Result<Self> {
-        let swarm = runtime.block_on(swarm::new(settings.node().network()))?;
+        let keypair = settings
+            .node()
+            .network()
+            .keypair_config
+            .keypair()
+            .with_context(|| "failed to generate/import keypair for libp2p".to_string())?;
+        let node_key = NodeKey::new(keypair.clone());
+
+        let swarm = runtime.block_on(swarm::new(keypair, settings.node().network(), db.clone()))?;
         let peer_id = *swarm.local_peer_id();
 
         let webserver = webserver::Server::new(settings.node().network().webserver())?;
@@ -202,10 +249,22 @@ impl Runner {
         #[cfg(not(feature = "ipfs"))]
         let _event_handler_hdl = runtime.spawn(event_handler.start());
 
+        let module_cache = ComponentCache::new(
+            settings.node.module_cache.dir.clone(),
+            settings.node.module_cache.max_size,
+            settings.node.task_execution.interruption,
+        )
+        .with_context(|| "failed to set up the cache of compiled Wasm modules".to_string())?;
+
         Ok(Self {
             event_sender,
+            executors: executors.into(),
             expiration_queue: Rc::new(AtomicRefCell::new(DelayQueue::new())),
+            module_cache,
             node_info: StaticNodeInfo::new(peer_id),
+            node_key,
+            retry_queue: Rc::new(AtomicRefCell::new(DelayQueue::new())),
+            retryable_workflows: DashMap::new(),
             running_tasks: DashMap::new().into(),
             running_workers: DashMap::new(),
             runtime,
@@ -281,6 +340,36 @@ impl Runner {
                                        "sending workflow_run message to rpc server");
                                 let _ = oneshot_tx.send_async(msg).await;
                             },
+                            Ok(ControlFlow::Continue(msg @ rpc::ServerMessage::PlanAck(_))) => {
+                                debug!(subject = "rpc.ack",
+                                       category = "rpc",
+                                       "sending workflow_plan message to rpc server");
+                                let _ = oneshot_tx.send_async(msg).await;
+                            },
+                            Ok(ControlFlow::Continue(msg @ rpc::ServerMessage::CancelAck(_))) => {
+                                debug!(subject = "rpc.ack",
+                                       category = "rpc",
+                                       "sending workflow_cancel message to rpc server");
+                                let _ = oneshot_tx.send_async(msg).await;
+                            },
+                            Ok(ControlFlow::Continue(msg @ rpc::ServerMessage::ListWorkflowsAck(_))) => {
+                                debug!(subject = "rpc.ack",
+                                       category = "rpc",
+                                       "sending workflows_list message to rpc server");
+                                let _ = oneshot_tx.send_async(msg).await;
+                            },
+                            Ok(ControlFlow::Continue(msg @ rpc::ServerMessage::ShowWorkflowAck(_))) => {
+                                debug!(subject = "rpc.ack",
+                                       category = "rpc",
+                                       "sending workflows_show message to rpc server");
+                                let _ = oneshot_tx.send_async(msg).await;
+                            },
+                            Ok(ControlFlow::Continue(msg @ rpc::ServerMessage::ReceiptsAck(_))) => {
+                                debug!(subject = "rpc.ack",
+                                       category = "rpc",
+                                       "sending receipts message to rpc server");
+                                let _ = oneshot_tx.send_async(msg).await;
+                            },
                             Err(err) => {
                                 error!(subject = "rpc.err",
                                        category = "rpc",
@@ -293,12 +382,10 @@ impl Runner {
                     }
                     Ok(msg) = ws_receiver.recv_async() => {
                         match msg {
-                            (webserver::Message::RunWorkflow((name, workflow)), Some(oneshot_tx)) => {
+                            (webserver::Message::RunWorkflow((name, workflow, workflow_settings)), Some(oneshot_tx)) => {
                                 info!(subject = "workflow",
                                       category = "workflow.run",
                                       "running workflow: {}", name);
-                                // TODO: Parse this from the workflow data itself.
-                                let workflow_settings = workflow::Settings::default();
                                 match self.run_worker(
                                     workflow,
                                     workflow_settings,
@@ -336,6 +423,25 @@ impl Runner {
                                 };
                                 let _ = oneshot_tx.send_async(webserver::Message::AckNodeInfo((self.node_info.clone(), dyn_node_info))).await;
                             }
+                            (webserver::Message::CancelWorkflow(cid), Some(oneshot_tx)) => {
+                                info!(subject = "workflow",
+                                      category = "workflow.cancel",
+                                      cid = cid.to_string(),
+                                      "cancelling workflow");
+                                match self.cancel_worker(cid, db.clone()) {
+                                    Ok(stored) => {
+                                        let name = stored.name.unwrap_or_else(|| cid.to_string());
+                                        let _ = oneshot_tx.send_async(webserver::Message::AckCancelWorkflow((cid, name.into()))).await;
+                                    }
+                                    Err(err) => {
+                                        error!(subject = "jsonrpc.err",
+                                               category = "jsonrpc",
+                                               err=?err,
+                                               "error handling ws message");
+                                        let _ = oneshot_tx.send_async(webserver::Message::RunErr(err.into())).await;
+                                    }
+                                }
+                            }
                             _ => ()
                         }
                     }
@@ -345,6 +451,13 @@ impl Runner {
                         match msg {
                             WorkerMessage::Dropped(cid) => {
                                 let _ = self.abort_worker(cid);
+                                if let Err(err) = self.schedule_retry(cid, db.clone()) {
+                                    error!(subject = "worker.retry.err",
+                                           category = "worker",
+                                           cid = cid.to_string(),
+                                           err=?err,
+                                           "error scheduling workflow retry");
+                                }
                             },
                         }
                     }
@@ -362,7 +475,46 @@ impl Runner {
                         info!(subject = "worker.expired",
                               category = "worker",
                               "worker expired, aborting");
-                        let _ = self.abort_worker(*expired.get_ref());
+                        let cid = *expired.get_ref();
+                        let _ = self.mark_stuck(cid, db.clone());
+                        let _ = self.abort_worker(cid);
+                    },
+                    // Handle workflows due for a retry.
+                    Some(due) = poll_fn(
+                        |ctx| match self.retry_queue.try_borrow_mut() {
+                            Ok(mut queue) => queue.poll_expired(ctx),
+                            Err(_) => Poll::Pending,
+                        }
+                    ) => {
+                        let cid = *due.get_ref();
+                        if let Some((workflow, workflow_settings, name)) = self
+                            .retryable_workflows
+                            .get(&cid)
+                            .map(|entry| {
+                                let (workflow, settings, name, _retries) = entry.value();
+                                (workflow.clone(), settings.clone(), name.clone())
+                            })
+                        {
+                            info!(subject = "worker.retry",
+                                  category = "worker",
+                                  cid = cid.to_string(),
+                                  "retrying workflow, resuming from last completed step");
+                            if let Err(err) = self.run_worker(
+                                workflow,
+                                workflow_settings,
+                                self.settings.node.network().libp2p().dht(),
+                                Some(name),
+                                runner_worker_tx.clone(),
+                                db.clone(),
+                            ).await {
+                                error!(subject = "worker.retry.err",
+                                       category = "worker",
+                                       cid = cid.to_string(),
+                                       err=?err,
+                                       "error retrying workflow");
+                                self.retryable_workflows.remove(&cid);
+                            }
+                        }
                     },
                     // Handle shutdown signal.
                     _ = Self::shutdown_signal() => {
@@ -462,7 +614,7 @@ impl Runner {
         self.abort_tasks();
     }
 
-    /// Cleanup all workers, tasks, and the expiration queue.
+    /// Cleanup all workers, tasks, and the expiration and retry queues.
     #[allow(dead_code)]
     fn cleanup_workers(&self) -> Result<()> {
         self.running_workers.clear();
@@ -470,6 +622,11 @@ impl Runner {
             .try_borrow_mut()
             .map_err(|e| anyhow!("failed to borrow expiration queue: {e}"))?
             .clear();
+        self.retryable_workflows.clear();
+        self.retry_queue
+            .try_borrow_mut()
+            .map_err(|e| anyhow!("failed to borrow retry queue: {e}"))?
+            .clear();
         self.cleanup_tasks();
         Ok(())
     }
@@ -518,6 +675,105 @@ impl Runner {
         }
     }
 
+    /// Mark a running workflow as [workflow::Status::Stuck], e.g. when it
+    /// has timed out.
+    fn mark_stuck(&self, cid: Cid, db: impl Database) -> Result<()> {
+        let mut conn = db.conn()?;
+        if Db::select_workflow(cid, &mut conn)?.status == workflow::Status::Running {
+            Db::set_workflow_status(cid, workflow::Status::Stuck, &mut conn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Schedule an automatic retry of a workflow whose worker has been
+    /// dropped, if it failed or got stuck and has retries left in its
+    /// [workflow::Settings].
+    ///
+    /// Workflows that completed, were cancelled, or ran out of retries are
+    /// no longer tracked for retries. Returns the delay before the retry,
+    /// if one was scheduled.
+    fn schedule_retry(&self, cid: Cid, db: impl Database) -> Result<Option<time::Duration>> {
+        let Some(mut entry) = self.retryable_workflows.get_mut(&cid) else {
+            return Ok(None);
+        };
+
+        let mut conn = db.conn()?;
+        let status = Db::select_workflow(cid, &mut conn)?.status;
+        let (_workflow, settings, _name, attempted) = entry.value_mut();
+        if !matches!(status, workflow::Status::Stuck | workflow::Status::Failed)
+            || *attempted >= settings.retries
+        {
+            if *attempted >= settings.retries {
+                warn!(
+                    subject = "worker.retry",
+                    category = "worker",
+                    cid = cid.to_string(),
+                    retries = *attempted,
+                    "workflow retries exhausted"
+                );
+            }
+            drop(entry);
+            self.retryable_workflows.remove(&cid);
+            return Ok(None);
+        }
+
+        *attempted += 1;
+        let delay = settings.retry_delay(*attempted);
+        let _ = Db::increment_workflow_retries(cid, &mut conn)?;
+        self.retry_queue
+            .try_borrow_mut()
+            .map_err(|e| anyhow!("failed to borrow retry queue: {e}"))?
+            .insert(cid, delay);
+
+        info!(
+            subject = "worker.retry",
+            category = "worker",
+            cid = cid.to_string(),
+            retry = *attempted,
+            delay_ms = delay.as_millis() as u64,
+            "scheduled workflow retry"
+        );
+
+        Ok(Some(delay))
+    }
+
+    /// Cancel a running worker given a workflow Cid, aborting its tasks,
+    /// marking the workflow as [workflow::Status::Cancelled] in the database,
+    /// and notifying any subscribers that the run has ended.
+    fn cancel_worker(&self, cid: Cid, db: impl Database) -> Result<workflow::Stored> {
+        match self.running_workers.get(&cid) {
+            Some(worker) if !worker.value().0.is_finished() => (),
+            _ => bail!("workflow {cid} is not running"),
+        }
+
+        self.abort_worker(cid)?;
+
+        let mut conn = db.conn()?;
+        Db::set_workflow_status(cid, workflow::Status::Cancelled, &mut conn)?;
+        let stored = Db::select_workflow(cid, &mut conn)?;
+
+        #[cfg(feature = "websocket-notify")]
+        notification::emit_workflow_status(
+            self.webserver.workflow_msg_notifier(),
+            cid,
+            WorkflowNotification::with(
+                cid,
+                stored.name.as_ref().map(|name| name.to_owned().into()),
+                workflow::Status::Cancelled,
+            ),
+        );
+
+        info!(
+            subject = "workflow.cancel",
+            category = "workflow",
+            cid = cid.to_string(),
+            "workflow cancelled"
+        );
+
+        Ok(stored)
+    }
+
     /// Captures shutdown signals for [Runner].
     #[allow(dead_code)]
     #[cfg(not(windows))]
@@ -666,8 +922,8 @@ impl Runner {
                     "RPC run command received, running workflow"
                 );
                 let (workflow, workflow_settings) =
-                    workflow_file.validate_and_parse().await.with_context(|| {
-                        format!("failed to validate/parse workflow @ path: {workflow_file}",)
+                    workflow_file.validate_and_parse().await.map_err(|e| {
+                        anyhow!("failed to validate/parse workflow @ path: {workflow_file}: {e}")
                     })?;
 
                 let data = self
@@ -690,6 +946,96 @@ impl Runner {
                     ),
                 ))))
             }
+            rpc::ServerMessage::Plan((name, workflow_file, dot)) => {
+                info!(
+                    subject = "rpc.command",
+                    category = "rpc",
+                    "RPC plan command received, planning workflow"
+                );
+                let (workflow, _) = workflow_file.validate_and_parse().await.map_err(|e| {
+                    anyhow!("failed to validate/parse workflow @ path: {workflow_file}: {e}")
+                })?;
+
+                Ok(ControlFlow::Continue(rpc::ServerMessage::PlanAck(
+                    Box::new(plan_workflow(workflow, name, dot, db)?),
+                )))
+            }
+            rpc::ServerMessage::Cancel(cid) => {
+                info!(
+                    subject = "rpc.command",
+                    category = "rpc",
+                    cid = cid.to_string(),
+                    "RPC cancel command received, cancelling workflow"
+                );
+
+                let stored = self.cancel_worker(cid, db)?;
+                Ok(ControlFlow::Continue(rpc::ServerMessage::CancelAck(
+                    response::AckCancel::new(
+                        cid,
+                        stored.name.unwrap_or_else(|| cid.to_string()).into(),
+                        stored.status,
+                        Utc::now().naive_utc(),
+                    ),
+                )))
+            }
+            rpc::ServerMessage::ListWorkflows(filter) => {
+                info!(
+                    subject = "rpc.command",
+                    category = "rpc",
+                    "RPC workflows list command received, listing workflows"
+                );
+
+                Ok(ControlFlow::Continue(rpc::ServerMessage::ListWorkflowsAck(
+                    list_workflows(filter, db)?,
+                )))
+            }
+            rpc::ServerMessage::ShowWorkflow(workflow) => {
+                info!(
+                    subject = "rpc.command",
+                    category = "rpc",
+                    workflow = workflow.as_str(),
+                    "RPC workflows show command received, showing workflow"
+                );
+
+                Ok(ControlFlow::Continue(rpc::ServerMessage::ShowWorkflowAck(
+                    Box::new(show_workflow(&workflow, db)?),
+                )))
+            }
+            rpc::ServerMessage::GetReceipt((cid, encoding)) => {
+                info!(
+                    subject = "rpc.command",
+                    category = "rpc",
+                    cid = cid.to_string(),
+                    "RPC get receipt command received, getting receipt"
+                );
+
+                Ok(ControlFlow::Continue(rpc::ServerMessage::ReceiptsAck(
+                    get_receipt(cid, encoding, db)?,
+                )))
+            }
+            rpc::ServerMessage::ListReceipts((workflow_cid, encoding)) => {
+                info!(
+                    subject = "rpc.command",
+                    category = "rpc",
+                    cid = workflow_cid.to_string(),
+                    "RPC list receipts command received, listing workflow receipts"
+                );
+
+                Ok(ControlFlow::Continue(rpc::ServerMessage::ReceiptsAck(
+                    list_receipts_for_workflow(workflow_cid, encoding, db)?,
+                )))
+            }
+            rpc::ServerMessage::FindReceipts((filter, encoding)) => {
+                info!(
+                    subject = "rpc.command",
+                    category = "rpc",
+                    "RPC find receipts command received, finding receipts"
+                );
+
+                Ok(ControlFlow::Continue(rpc::ServerMessage::ReceiptsAck(
+                    find_receipts(filter, encoding, db)?,
+                )))
+            }
             msg => {
                 warn!(
                     subject = "rpc.command",
@@ -712,18 +1058,34 @@ impl Runner {
         runner_sender: AsyncChannelSender<WorkerMessage>,
         db: impl Database + 'static,
     ) -> Result<WorkflowData> {
+        let retryable = (workflow_settings.retries > 0).then(|| workflow.clone());
+        let verifier = ProofVerifier::new(self.settings.node.authorization(), self.node_key.did()?);
+        #[cfg(feature = "ipfs")]
+        let verifier = verifier.with_ipfs(IpfsCli::new(self.settings.node.network.ipfs())?);
+
         let worker = {
             Worker::new(
                 workflow,
                 workflow_settings,
                 network_settings.clone().to_owned(),
+                self.settings.node.task_limits.clone(),
+                &verifier,
                 name,
                 self.event_sender(),
                 runner_sender,
                 db.clone(),
+                self.executors.clone(),
+                Some(self.node_key.clone()),
             )
             .await?
+            .with_task_execution(self.settings.node.task_execution.clone())
+            .with_task_host(self.settings.node.task_host.clone())
+            .with_module_cache(self.module_cache.clone())
         };
+        #[cfg(feature = "ipfs")]
+        let worker = worker.with_ipfs(IpfsCli::new(self.settings.node.network.ipfs())?);
+        #[cfg(feature = "websocket-notify")]
+        let worker = worker.with_log_notifier(self.webserver.workflow_msg_notifier());
 
         // Deliberate use of Arc::clone for readability, could just be
         // `clone`, as the underlying type is an `Arc`.
@@ -733,6 +1095,21 @@ impl Runner {
         let workflow_settings = worker.workflow_settings.clone();
         let timestamp = worker.workflow_started;
 
+        // Track workflow for automatic retries, keeping the number of
+        // retries attempted so far if it's already being retried.
+        if let Some(workflow) = retryable {
+            self.retryable_workflows
+                .entry(initial_info.cid)
+                .or_insert_with(|| {
+                    (
+                        workflow,
+                        (*workflow_settings).clone(),
+                        workflow_name.clone(),
+                        0,
+                    )
+                });
+        }
+
         // Spawn worker, which initializees the scheduler and runs
         // the workflow.
         info!(
@@ -840,6 +1217,110 @@ fn find_receipt_info_by_pointers(
     Ok(receipt_info)
 }
 
+/// List stored [Workflow]s matching a [workflow::Filter], along with their
+/// progress.
+///
+/// [Workflow]: homestar_workflow::Workflow
+pub(crate) fn list_workflows(
+    filter: workflow::Filter,
+    db: impl Database,
+) -> Result<response::AckWorkflows> {
+    let mut conn = db.conn()?;
+    let workflows = Db::list_workflows(&filter, &mut conn)?
+        .iter()
+        .map(|stored| {
+            let progress_count = Db::count_workflow_receipts(stored.cid.cid(), &mut conn)?;
+            Ok(response::WorkflowSummary::new(
+                stored,
+                progress_count as u32,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(response::AckWorkflows::new(
+        workflows,
+        filter.limit,
+        filter.offset,
+    ))
+}
+
+/// Show a stored [Workflow], given its Cid or local name, with per-task
+/// progress and receipt Cids.
+///
+/// [Workflow]: homestar_workflow::Workflow
+pub(crate) fn show_workflow(
+    workflow: &str,
+    db: impl Database,
+) -> Result<response::AckWorkflowDetails> {
+    let mut conn = db.conn()?;
+    let stored = match Cid::try_from(workflow) {
+        Ok(cid) => Db::select_workflow(cid, &mut conn),
+        Err(_) => Db::select_workflow_by_name(workflow, &mut conn),
+    }
+    .with_context(|| format!("workflow {workflow} not found"))?;
+    let receipts = Db::find_workflow_receipts(stored.cid.cid(), &mut conn)?;
+
+    Ok(response::AckWorkflowDetails::new(&stored, receipts))
+}
+
+/// Plan a run of a [Workflow] without executing anything, reporting its
+/// batched schedule, steps skipped due to stored receipts, and promises to
+/// be resolved over the network.
+///
+/// [Workflow]: homestar_workflow::Workflow
+pub(crate) fn plan_workflow(
+    workflow: Workflow<'static, Arg>,
+    name: Option<FastStr>,
+    dot: bool,
+    db: impl Database,
+) -> Result<response::AckPlan> {
+    let num_tasks = workflow.len();
+    // Need to take ownership here to get the cid.
+    let cid = workflow.to_owned().to_cid()?;
+    let name = name.unwrap_or_else(|| FastStr::from_string(cid.to_string()));
+
+    let graph = workflow::Builder::new(workflow).graph()?;
+    let plan = TaskScheduler::plan(&graph, &mut db.conn()?)?;
+    let dot = dot.then(|| graph.to_dot(&name)).transpose()?;
+
+    Ok(response::AckPlan::new(
+        cid, name, num_tasks, &graph, plan, dot,
+    ))
+}
+
+/// Get a stored [Receipt], given its Cid, encoded for response.
+pub(crate) fn get_receipt(
+    cid: Cid,
+    encoding: ReceiptEncoding,
+    db: impl Database,
+) -> Result<response::AckReceipts> {
+    let receipt = Db::find_receipt_by_cid(cid, &mut db.conn()?)
+        .with_context(|| format!("receipt {cid} not found"))?;
+    response::AckReceipts::with_receipt(receipt, encoding)
+}
+
+/// List all stored [Receipt]s for a [Workflow], encoded for response.
+///
+/// [Workflow]: homestar_workflow::Workflow
+pub(crate) fn list_receipts_for_workflow(
+    workflow_cid: Cid,
+    encoding: ReceiptEncoding,
+    db: impl Database,
+) -> Result<response::AckReceipts> {
+    let receipts = Db::find_workflow_receipts(workflow_cid, &mut db.conn()?)?;
+    response::AckReceipts::with_receipts(receipts, encoding)
+}
+
+/// Find stored [Receipt]s matching a [ReceiptFilter], encoded for response.
+pub(crate) fn find_receipts(
+    filter: ReceiptFilter,
+    encoding: ReceiptEncoding,
+    db: impl Database,
+) -> Result<response::AckReceipts> {
+    let receipts = Db::find_receipts(&filter, &mut db.conn()?)?;
+    response::AckReceipts::with_receipts(receipts, encoding)
+}
+
 /// Internal Workflow data used for wrapper.
 struct WorkflowData {
     info: Arc<workflow::Info>,
@@ -1010,6 +1491,108 @@ mod test {
         assert!(runner.running_tasks.is_empty());
     }
 
+    #[homestar_runtime_proc_macro::runner_test]
+    fn cancel_running_worker() {
+        let TestRunner { runner, settings } = TestRunner::start();
+        let builder = WorkerBuilder::new(settings.node);
+        let db = builder.db();
+
+        let workflow_cid = runner.runtime.block_on(async {
+            let fetch_fn = builder.fetch_fn();
+            let worker = builder.build().await;
+            let workflow_cid = worker.workflow_info.cid;
+            let workflow_timeout = worker.workflow_settings.timeout;
+            let handle = runner
+                .runtime
+                .spawn(worker.run(runner.running_tasks(), fetch_fn));
+            let delay_key = runner
+                .expiration_queue
+                .try_borrow_mut()
+                .unwrap()
+                .insert(workflow_cid, workflow_timeout);
+            runner
+                .running_workers
+                .insert(workflow_cid, (handle, delay_key));
+
+            workflow_cid
+        });
+
+        let stored = runner.cancel_worker(workflow_cid, db.clone()).unwrap();
+        assert_eq!(stored.status, workflow::Status::Cancelled);
+        assert!(runner.running_workers.is_empty());
+        assert!(runner.expiration_queue.try_borrow_mut().unwrap().is_empty());
+
+        // A workflow that is no longer running cannot be cancelled again.
+        assert!(runner.cancel_worker(workflow_cid, db).is_err());
+    }
+
+    #[homestar_runtime_proc_macro::runner_test]
+    fn schedule_retries_for_failed_workflow() {
+        let TestRunner { runner, settings } = TestRunner::start();
+        let workflow_settings = workflow::Settings {
+            retries: 2,
+            ..Default::default()
+        };
+        let builder =
+            WorkerBuilder::new(settings.node).with_workflow_settings(workflow_settings.clone());
+        let db = builder.db();
+        let workflow = builder.workflow();
+        let track = |runner: &Runner, cid| {
+            runner.retryable_workflows.insert(
+                cid,
+                (
+                    workflow.clone(),
+                    workflow_settings.clone(),
+                    "test".into(),
+                    0,
+                ),
+            )
+        };
+
+        runner.runtime.block_on(async {
+            let worker = builder.build().await;
+            let workflow_cid = worker.workflow_info.cid;
+            drop(worker);
+            let mut conn = db.conn().unwrap();
+
+            // Completed workflows are no longer tracked for retries.
+            track(&runner, workflow_cid);
+            Db::set_workflow_status(workflow_cid, workflow::Status::Completed, &mut conn).unwrap();
+            assert!(runner
+                .schedule_retry(workflow_cid, db.clone())
+                .unwrap()
+                .is_none());
+            assert!(runner.retryable_workflows.is_empty());
+
+            // Failed and stuck workflows are retried with backoff, until
+            // retries are exhausted.
+            track(&runner, workflow_cid);
+            Db::set_workflow_status(workflow_cid, workflow::Status::Failed, &mut conn).unwrap();
+            assert_eq!(
+                runner.schedule_retry(workflow_cid, db.clone()).unwrap(),
+                Some(workflow_settings.retry_delay(1))
+            );
+            Db::set_workflow_status(workflow_cid, workflow::Status::Stuck, &mut conn).unwrap();
+            assert_eq!(
+                runner.schedule_retry(workflow_cid, db.clone()).unwrap(),
+                Some(workflow_settings.retry_delay(2))
+            );
+            assert_eq!(runner.retry_queue.try_borrow_mut().unwrap().len(), 2);
+            assert_eq!(
+                Db::select_workflow(workflow_cid, &mut conn)
+                    .unwrap()
+                    .retries,
+                2
+            );
+
+            assert!(runner
+                .schedule_retry(workflow_cid, db.clone())
+                .unwrap()
+                .is_none());
+            assert!(runner.retryable_workflows.is_empty());
+        });
+    }
+
     #[homestar_runtime_proc_macro::runner_test]
     fn gc_while_workers_still_running() {
         let TestRunner { runner, settings } = TestRunner::start();
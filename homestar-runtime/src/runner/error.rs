@@ -13,6 +13,9 @@ pub(crate) enum Error {
     /// Unsupported workflow type.
     #[error("unsupported workflow file type: {0}")]
     UnsupportedWorkflow(String),
+    /// Invalid workflow parameters.
+    #[error("invalid workflow parameters: {0}")]
+    InvalidParams(#[from] homestar_workflow::ParamError),
     /// Propagated IO error.
     #[error("error reading data: {0}")]
     Io(#[from] io::Error),
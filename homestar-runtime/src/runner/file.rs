@@ -4,18 +4,39 @@
 
 use super::Error;
 use crate::workflow;
-use homestar_invocation::ipld::DagJson;
+use anyhow::anyhow;
+use homestar_invocation::ipld::{DagCbor, DagJson};
 use homestar_wasm::io::Arg;
-use homestar_workflow::Workflow;
+use homestar_workflow::{Params, Workflow};
+use libipld::{cbor::DagCborCodec, json::DagJsonCodec, prelude::Codec, serde::from_ipld, Ipld};
 use serde::{Deserialize, Serialize};
-use std::{ffi::OsStr, fmt, path::PathBuf, str::FromStr};
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fmt,
+    io::{self, Read},
+    path::PathBuf,
+    str::FromStr,
+};
 use tokio::fs;
 
+/// Key for optional [workflow::Settings] within a workflow file.
+const SETTINGS_KEY: &str = "settings";
+
+/// File path standing for reading a workflow from stdin.
+const STDIN: &str = "-";
+
 /// Data structure for a workflow file path.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadWorkflow {
     /// Workflow file to run.
     file: PathBuf,
+    /// Workflow data read from stdin, if the file is `-`.
+    #[serde(default)]
+    stdin: Option<Vec<u8>>,
+    /// Raw values for workflow parameters, by name.
+    #[serde(default)]
+    params: BTreeMap<String, String>,
 }
 
 impl FromStr for ReadWorkflow {
@@ -24,40 +45,175 @@ impl FromStr for ReadWorkflow {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Ok(Self {
             file: s.parse().map_err(|e| format!("{e}"))?,
+            stdin: None,
+            params: BTreeMap::new(),
         })
     }
 }
 
 impl fmt::Display for ReadWorkflow {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{:?}", self.file)
+        if self.is_stdin() {
+            write!(f, "stdin")
+        } else {
+            write!(f, "{:?}", self.file)
+        }
     }
 }
 
-impl ReadWorkflow {
-    /// Validate and parse the workflow file.
+/// Supported workflow file formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// [DAG-JSON], also used for files without an extension.
     ///
-    /// Validation is currently limited to checking the file extension,
-    /// or attempting to treat the file as JSON if no extension is provided.
-    pub(crate) async fn validate_and_parse<'a>(
-        &self,
-    ) -> Result<(Workflow<'a, Arg>, workflow::Settings), Error> {
-        match self.file.extension().and_then(OsStr::to_str) {
-            None | Some("json") => {
-                let data = fs::read_to_string(&self.file.canonicalize()?).await?;
-                // TODO: Parse this from the workflow data/file itself.
-                let workflow_settings = workflow::Settings::default();
+    /// [DAG-JSON]: <https://ipld.io/specs/codecs/dag-json/spec/>
+    Json,
+    /// YAML, mapped onto the same shape as [DAG-JSON].
+    ///
+    /// [DAG-JSON]: <https://ipld.io/specs/codecs/dag-json/spec/>
+    Yaml,
+    /// Binary [DAG-CBOR].
+    ///
+    /// [DAG-CBOR]: <https://ipld.io/specs/codecs/dag-cbor/spec/>
+    Cbor,
+}
+
+impl Format {
+    /// Detect the format of workflow data without a file extension, i.e.
+    /// from stdin.
+    ///
+    /// JSON objects are treated as JSON, any other text as YAML, and binary
+    /// data as DAG-CBOR.
+    fn detect(data: &[u8]) -> Self {
+        match std::str::from_utf8(data) {
+            Ok(text) if text.trim_start().starts_with('{') => Format::Json,
+            Ok(_) => Format::Yaml,
+            Err(_) => Format::Cbor,
+        }
+    }
+
+    /// Parse a [Workflow] and its [workflow::Settings] from data in this
+    /// format.
+    fn parse<'a>(&self, data: Vec<u8>) -> Result<(Workflow<'a, Arg>, workflow::Settings), Error> {
+        match self {
+            Format::Json => {
+                let json: serde_json::Value =
+                    serde_json::from_slice(&data).map_err(anyhow::Error::new)?;
+                parse_json(json)
+            }
+            Format::Yaml => {
+                let json: serde_json::Value = serde_yaml::from_slice(&data)
+                    .map_err(|e| anyhow!("invalid YAML workflow: {e}"))?;
+                parse_json(json)
+            }
+            Format::Cbor => {
+                let ipld: Ipld = DagCborCodec
+                    .decode(&data)
+                    .map_err(|e| anyhow!("invalid DAG-CBOR workflow: {e}"))?;
+                let workflow_settings = match &ipld {
+                    Ipld::Map(map) => match map.get(SETTINGS_KEY) {
+                        Some(settings) => from_ipld(settings.to_owned())
+                            .map_err(|e| anyhow!("invalid workflow settings: {e}"))?,
+                        None => workflow::Settings::default(),
+                    },
+                    _ => workflow::Settings::default(),
+                };
                 Ok((
-                    DagJson::from_json_string(data).map_err(anyhow::Error::new)?,
+                    Workflow::from_cbor(&data).map_err(anyhow::Error::new)?,
                     workflow_settings,
                 ))
             }
+        }
+    }
+}
 
-            Some(ext) => Err(Error::UnsupportedWorkflow(ext.to_string())),
+impl ReadWorkflow {
+    /// Whether or not the workflow is read from stdin.
+    pub(crate) fn is_stdin(&self) -> bool {
+        self.file.as_os_str() == STDIN
+    }
+
+    /// Read the workflow from stdin if the file is `-`, so it can be sent
+    /// along to the runtime.
+    pub(crate) fn read_stdin(mut self) -> io::Result<Self> {
+        if self.is_stdin() {
+            let mut data = Vec::new();
+            io::stdin().read_to_end(&mut data)?;
+            self.stdin = Some(data);
         }
+
+        Ok(self)
+    }
+
+    /// Set raw values for workflow parameters, given as `key=value` pairs.
+    pub(crate) fn with_params(
+        mut self,
+        params: impl IntoIterator<Item = (String, String)>,
+    ) -> Self {
+        self.params.extend(params);
+        self
+    }
+
+    /// Workflow [Params], where values are parsed as DAG-JSON, falling back
+    /// to plain strings.
+    fn params(&self) -> Params {
+        self.params
+            .iter()
+            .map(|(name, value)| {
+                let value = DagJsonCodec
+                    .decode(value.as_bytes())
+                    .unwrap_or_else(|_| Ipld::String(value.to_owned()));
+                (name.to_owned(), value)
+            })
+            .collect()
+    }
+
+    /// Validate and parse the workflow file, substituting any parameters.
+    ///
+    /// The format is chosen by file extension, treating files without an
+    /// extension as JSON, or detected from the data when read from stdin.
+    pub(crate) async fn validate_and_parse<'a>(
+        &self,
+    ) -> Result<(Workflow<'a, Arg>, workflow::Settings), Error> {
+        let (workflow, workflow_settings) = if self.is_stdin() {
+            let data = self
+                .stdin
+                .clone()
+                .ok_or_else(|| anyhow!("no workflow data read from stdin"))?;
+            Format::detect(&data).parse(data)?
+        } else {
+            let format = match self.file.extension().and_then(OsStr::to_str) {
+                None | Some("json") => Format::Json,
+                Some("yaml" | "yml") => Format::Yaml,
+                Some("cbor" | "dagcbor") => Format::Cbor,
+                Some(ext) => return Err(Error::UnsupportedWorkflow(ext.to_string())),
+            };
+
+            let data = fs::read(&self.file.canonicalize()?).await?;
+            format.parse(data)?
+        };
+
+        Ok((workflow.with_params(&self.params())?, workflow_settings))
     }
 }
 
+/// Parse a [Workflow] and its [workflow::Settings], from the optional
+/// `settings` key, from JSON(-shaped) data.
+fn parse_json<'a>(
+    json: serde_json::Value,
+) -> Result<(Workflow<'a, Arg>, workflow::Settings), Error> {
+    let workflow_settings = match json.get(SETTINGS_KEY) {
+        Some(settings) => serde_json::from_value(settings.to_owned())
+            .map_err(|e| anyhow!("invalid workflow settings: {e}"))?,
+        None => workflow::Settings::default(),
+    };
+
+    Ok((
+        DagJson::from_json_string(json.to_string()).map_err(anyhow::Error::new)?,
+        workflow_settings,
+    ))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -88,7 +244,11 @@ mod test {
         let workflow = Workflow::new(vec![task1, task2]);
 
         workflow.to_file(path.display().to_string()).unwrap();
-        let workflow_file = ReadWorkflow { file: path.clone() };
+        let workflow_file = ReadWorkflow {
+            file: path.clone(),
+            stdin: None,
+            params: BTreeMap::new(),
+        };
 
         let (validated_workflow, _settings) = workflow_file.validate_and_parse().await.unwrap();
 
@@ -99,6 +259,8 @@ mod test {
         let new_path = PathBuf::from("./fixtures/test.txt");
         let workflow_file = ReadWorkflow {
             file: new_path.clone(),
+            stdin: None,
+            params: BTreeMap::new(),
         };
         let error = workflow_file.validate_and_parse().await;
         assert_eq!(
@@ -111,6 +273,8 @@ mod test {
         let new_path = PathBuf::from("./fixtures/test_fam");
         let workflow_file = ReadWorkflow {
             file: new_path.clone(),
+            stdin: None,
+            params: BTreeMap::new(),
         };
         let (newly_validated_workflow, _settings) =
             workflow_file.validate_and_parse().await.unwrap();
@@ -132,10 +296,157 @@ mod test {
         let workflow = Workflow::new(vec![task]);
 
         workflow.to_file(path.display().to_string()).unwrap();
-        let workflow_file = ReadWorkflow { file: path.clone() };
+        let workflow_file = ReadWorkflow {
+            file: path.clone(),
+            stdin: None,
+            params: BTreeMap::new(),
+        };
 
         let (validated_workflow, _settings) = workflow_file.validate_and_parse().await.unwrap();
 
         assert_eq!(workflow, validated_workflow);
     }
+
+    #[tokio::test]
+    async fn validate_and_parse_workflow_with_settings() {
+        let path = PathBuf::from("./fixtures/test_settings.json");
+        let config = Resources::default();
+        let (instruction, _) = test_utils::wasm_instruction_with_nonce::<Arg>();
+
+        let task = Task::new(
+            RunInstruction::Expanded(instruction.clone()),
+            config.into(),
+            UcanPrf::default(),
+        );
+
+        let workflow = Workflow::new(vec![task]);
+        let mut json: serde_json::Value =
+            serde_json::from_str(&workflow.clone().to_json_string().unwrap()).unwrap();
+        json[SETTINGS_KEY] = serde_json::json!({"retries": 2, "timeout": 30});
+        fs::write(&path, json.to_string()).await.unwrap();
+
+        let workflow_file = ReadWorkflow {
+            file: path.clone(),
+            stdin: None,
+            params: BTreeMap::new(),
+        };
+        let (validated_workflow, settings) = workflow_file.validate_and_parse().await.unwrap();
+
+        assert_eq!(workflow, validated_workflow);
+        assert_eq!(settings.retries, 2);
+        assert_eq!(settings.timeout, std::time::Duration::from_secs(30));
+        assert_eq!(
+            settings.retry_initial_delay,
+            workflow::Settings::default().retry_initial_delay
+        );
+        fs::remove_file(path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn validate_and_parse_yaml_and_cbor_workflows() {
+        let config = Resources::default();
+        let (instruction1, instruction2, _) = test_utils::related_wasm_instructions::<Arg>();
+        let task1 = Task::new(
+            RunInstruction::Expanded(instruction1),
+            config.clone().into(),
+            UcanPrf::default(),
+        );
+        let task2 = Task::new(
+            RunInstruction::Expanded(instruction2),
+            config.into(),
+            UcanPrf::default(),
+        );
+        let workflow = Workflow::new(vec![task1, task2]);
+
+        // YAML, mapping onto the DAG-JSON shape.
+        let mut json: serde_json::Value =
+            serde_json::from_str(&workflow.clone().to_json_string().unwrap()).unwrap();
+        json[SETTINGS_KEY] = serde_json::json!({"retries": 1});
+        let yaml = serde_yaml::to_string(&json).unwrap();
+        let path = PathBuf::from("./fixtures/test_workflow.yaml");
+        fs::write(&path, &yaml).await.unwrap();
+        let workflow_file = ReadWorkflow {
+            file: path.clone(),
+            stdin: None,
+            params: BTreeMap::new(),
+        };
+        let (validated_workflow, settings) = workflow_file.validate_and_parse().await.unwrap();
+        assert_eq!(workflow, validated_workflow);
+        assert_eq!(settings.retries, 1);
+        fs::remove_file(path).await.unwrap();
+
+        // DAG-CBOR.
+        let path = PathBuf::from("./fixtures/test_workflow.dagcbor");
+        let cbor = workflow.clone().to_cbor().unwrap();
+        fs::write(&path, &cbor).await.unwrap();
+        let workflow_file = ReadWorkflow {
+            file: path.clone(),
+            stdin: None,
+            params: BTreeMap::new(),
+        };
+        let (validated_workflow, settings) = workflow_file.validate_and_parse().await.unwrap();
+        assert_eq!(workflow, validated_workflow);
+        assert_eq!(settings, workflow::Settings::default());
+        fs::remove_file(path).await.unwrap();
+
+        // Stdin, detecting each format.
+        for data in [json.to_string().into_bytes(), yaml.into_bytes(), cbor] {
+            let workflow_file = ReadWorkflow {
+                file: PathBuf::from(STDIN),
+                stdin: Some(data),
+                params: BTreeMap::new(),
+            };
+            let (validated_workflow, _settings) = workflow_file.validate_and_parse().await.unwrap();
+            assert_eq!(workflow, validated_workflow);
+        }
+
+        let workflow_file: ReadWorkflow = STDIN.parse().unwrap();
+        assert!(workflow_file.is_stdin());
+        assert!(workflow_file.validate_and_parse().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn validate_and_parse_workflow_with_params() {
+        let path = PathBuf::from("./fixtures/test_params.json");
+        let (instruction, _) = test_utils::wasm_instruction_with_nonce::<Arg>();
+        let task = |instruction| {
+            Task::new(
+                RunInstruction::Expanded(instruction),
+                Resources::default().into(),
+                UcanPrf::default(),
+            )
+        };
+        let with_args = |args: serde_json::Value| {
+            let mut json: serde_json::Value = serde_json::from_str(
+                &Workflow::new(vec![task(instruction.clone())])
+                    .to_json_string()
+                    .unwrap(),
+            )
+            .unwrap();
+            json["tasks"][0]["run"]["input"]["args"] = args;
+            json
+        };
+
+        let template = with_args(serde_json::json!(["{{n}}", "{{name}}", "id-{{n}}"]));
+        fs::write(&path, template.to_string()).await.unwrap();
+
+        let workflow_file: ReadWorkflow = path.display().to_string().parse().unwrap();
+        let err = workflow_file.validate_and_parse().await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid workflow parameters: missing value for workflow parameter `n`, used by task at index 0"
+        );
+
+        let workflow_file = workflow_file.with_params([
+            ("n".to_string(), "7".to_string()),
+            ("name".to_string(), "seven".to_string()),
+        ]);
+        let (validated_workflow, _settings) = workflow_file.validate_and_parse().await.unwrap();
+        let expected: Workflow<'_, Arg> = DagJson::from_json_string(
+            with_args(serde_json::json!([7, "seven", "id-7"])).to_string(),
+        )
+        .unwrap();
+        assert_eq!(validated_workflow, expected);
+        fs::remove_file(path).await.unwrap();
+    }
 }
@@ -4,13 +4,18 @@
 use crate::{
     cli::show::{self, ApplyStyle},
     runner::WorkflowReceiptInfo,
+    scheduler::{ExecutionGraph, Plan},
     workflow::{self, IndexedResources},
+    Receipt, ReceiptEncoding,
 };
 use chrono::NaiveDateTime;
 use faststr::FastStr;
-use libipld::Cid;
+use homestar_invocation::receipt::metadata::OP_KEY;
+use itertools::Itertools;
+use libipld::{serde::from_ipld, Cid, Ipld};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{fmt, net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, fmt, io::Write, net::SocketAddr, sync::Arc};
 use tabled::{
     builder::Builder,
     col,
@@ -117,6 +122,476 @@ impl show::ConsoleTable for AckWorkflow {
     }
 }
 
+/// Workflow information specified for response / display upon
+/// acknowledgement of cancelling a running workflow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Tabled)]
+pub struct AckCancel {
+    pub(crate) cid: Cid,
+    pub(crate) name: FastStr,
+    pub(crate) status: workflow::Status,
+    pub(crate) timestamp: String,
+}
+
+impl fmt::Display for AckCancel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cid: {}, status: {}, timestamp: {}",
+            self.cid, self.status, self.timestamp
+        )
+    }
+}
+
+impl AckCancel {
+    /// Cancelled workflow information for response / display.
+    pub(crate) fn new(
+        cid: Cid,
+        name: FastStr,
+        status: workflow::Status,
+        timestamp: NaiveDateTime,
+    ) -> Self {
+        Self {
+            cid,
+            name,
+            status,
+            timestamp: timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+impl show::ConsoleTable for AckCancel {
+    fn table(&self) -> show::Output {
+        Table::new(vec![self]).default_with_title("cancel")
+    }
+
+    fn echo_table(&self) -> Result<(), std::io::Error> {
+        self.table().echo()
+    }
+}
+
+/// Workflow information for response upon cancelling a running workflow
+/// over JSON-RPC.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CancelledWorkflow {
+    pub(crate) cid: String,
+    pub(crate) name: String,
+    pub(crate) status: workflow::Status,
+}
+
+impl CancelledWorkflow {
+    /// Cancelled workflow information for response.
+    pub(crate) fn new(cid: Cid, name: FastStr) -> Self {
+        Self {
+            cid: cid.to_string(),
+            name: name.to_string(),
+            status: workflow::Status::Cancelled,
+        }
+    }
+}
+
+/// Summary of a stored workflow for response / display when listing or
+/// showing workflows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Tabled, JsonSchema)]
+pub struct WorkflowSummary {
+    pub(crate) cid: String,
+    pub(crate) name: String,
+    pub(crate) status: workflow::Status,
+    pub(crate) progress: String,
+    pub(crate) retries: i32,
+    pub(crate) created_at: String,
+    pub(crate) completed_at: String,
+}
+
+impl WorkflowSummary {
+    /// Summarize a stored workflow, given the number of receipts it has
+    /// produced so far.
+    pub(crate) fn new(stored: &workflow::Stored, progress_count: u32) -> Self {
+        let cid = stored.cid.cid().to_string();
+        Self {
+            name: stored.name.clone().unwrap_or_else(|| cid.clone()),
+            cid,
+            status: stored.status.clone(),
+            progress: format!("{}/{}", progress_count, stored.num_tasks),
+            retries: stored.retries,
+            created_at: stored.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            completed_at: stored.completed_at.map_or_else(
+                || "-".to_string(),
+                |at| at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            ),
+        }
+    }
+}
+
+/// A page of stored workflows for response / display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AckWorkflows {
+    pub(crate) workflows: Vec<WorkflowSummary>,
+    pub(crate) limit: u32,
+    pub(crate) offset: u32,
+}
+
+impl fmt::Display for AckWorkflows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "workflows: {}, limit: {}, offset: {}",
+            self.workflows.len(),
+            self.limit,
+            self.offset
+        )
+    }
+}
+
+impl AckWorkflows {
+    /// Page of workflows for response / display.
+    pub(crate) fn new(workflows: Vec<WorkflowSummary>, limit: u32, offset: u32) -> Self {
+        Self {
+            workflows,
+            limit,
+            offset,
+        }
+    }
+}
+
+impl show::ConsoleTable for AckWorkflows {
+    fn table(&self) -> show::Output {
+        if self.workflows.is_empty() {
+            let mut builder = Builder::default();
+            builder.push_record(["<none>".to_string()]);
+            builder.build().default_with_title("workflows")
+        } else {
+            Table::new(&self.workflows).default_with_title("workflows")
+        }
+    }
+
+    fn echo_table(&self) -> Result<(), std::io::Error> {
+        self.table().echo()
+    }
+}
+
+/// Progress of a single task within a workflow for response / display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Tabled, JsonSchema)]
+pub struct TaskProgress {
+    pub(crate) instruction: String,
+    pub(crate) op: String,
+    pub(crate) resources: String,
+    pub(crate) status: String,
+    pub(crate) receipt: String,
+}
+
+/// Workflow details, including per-task progress, for response / display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AckWorkflowDetails {
+    pub(crate) workflow: WorkflowSummary,
+    pub(crate) tasks: Vec<TaskProgress>,
+}
+
+impl fmt::Display for AckWorkflowDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cid: {}, status: {}, progress: {}",
+            self.workflow.cid, self.workflow.status, self.workflow.progress
+        )
+    }
+}
+
+impl AckWorkflowDetails {
+    /// Workflow details for response / display, matching stored receipts
+    /// to tasks (in workflow order) by their instruction Cid.
+    pub(crate) fn new(stored: &workflow::Stored, receipts: Vec<Receipt>) -> Self {
+        let progress_count = receipts.len() as u32;
+        let receipts: HashMap<Cid, Receipt> = receipts
+            .into_iter()
+            .map(|receipt| (receipt.instruction().cid(), receipt))
+            .collect();
+
+        let tasks = stored
+            .resources
+            .inner()
+            .iter()
+            .map(|(instruction, rscs)| {
+                let receipt = receipts.get(instruction);
+                let op = receipt
+                    .and_then(|receipt| match receipt.meta() {
+                        Ipld::Map(meta) => meta.get(OP_KEY).cloned(),
+                        _ => None,
+                    })
+                    .and_then(|op| from_ipld::<String>(op).ok())
+                    .unwrap_or_else(|| "-".to_string());
+
+                TaskProgress {
+                    instruction: instruction.to_string(),
+                    op,
+                    resources: rscs.iter().map(|rsc| rsc.to_string()).join(", "),
+                    status: if receipt.is_some() {
+                        "completed".to_string()
+                    } else {
+                        "pending".to_string()
+                    },
+                    receipt: receipt.map_or_else(|| "-".to_string(), |r| r.cid().to_string()),
+                }
+            })
+            .collect();
+
+        Self {
+            workflow: WorkflowSummary::new(stored, progress_count),
+            tasks,
+        }
+    }
+}
+
+impl show::ConsoleTable for AckWorkflowDetails {
+    fn table(&self) -> show::Output {
+        show::Output::new(Table::new(vec![&self.workflow]).to_string())
+    }
+
+    fn echo_table(&self) -> Result<(), std::io::Error> {
+        let table = self.table();
+        let tasks_table = if self.tasks.is_empty() {
+            let mut builder = Builder::default();
+            builder.push_record(["Tasks".to_string()]);
+            builder.push_record(["<none>".to_string()]);
+            builder.build()
+        } else {
+            Table::new(&self.tasks)
+        };
+
+        let tbl = col![table, tasks_table].default_with_title("workflow");
+        tbl.echo()
+    }
+}
+
+/// Planned instruction within a batched step of a workflow for response /
+/// display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Tabled, JsonSchema)]
+pub struct PlanStep {
+    pub(crate) step: usize,
+    pub(crate) instruction: String,
+    pub(crate) op: String,
+    pub(crate) resources: String,
+    pub(crate) receipted: bool,
+    pub(crate) status: String,
+}
+
+/// Workflow plan, i.e. what a run would execute, for response / display.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AckPlan {
+    pub(crate) cid: String,
+    pub(crate) name: String,
+    pub(crate) num_tasks: u32,
+    pub(crate) num_steps: usize,
+    pub(crate) skipped_steps: usize,
+    pub(crate) steps: Vec<PlanStep>,
+    pub(crate) promises_to_resolve: Vec<String>,
+    pub(crate) dot: Option<String>,
+}
+
+impl fmt::Display for AckPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cid: {}, steps: {}, skipped: {}, promises to resolve: {}",
+            self.cid,
+            self.num_steps,
+            self.skipped_steps,
+            self.promises_to_resolve.len()
+        )
+    }
+}
+
+impl AckPlan {
+    /// Workflow plan for response / display, given the workflow's
+    /// [ExecutionGraph] and how it would be scheduled.
+    pub(crate) fn new(
+        cid: Cid,
+        name: FastStr,
+        num_tasks: u32,
+        graph: &ExecutionGraph<'_>,
+        plan: Plan,
+        dot: Option<String>,
+    ) -> Self {
+        let (receipted, skipped_steps) = (&plan.receipted, plan.skipped_steps);
+        let steps = graph
+            .schedule
+            .iter()
+            .enumerate()
+            .flat_map(|(step, batch)| {
+                batch.iter().map(move |node| {
+                    let instruction = node.name().to_string();
+                    let receipted =
+                        Cid::try_from(node.name()).is_ok_and(|cid| receipted.contains(&cid));
+                    let resources = Cid::try_from(node.name())
+                        .ok()
+                        .and_then(|cid| graph.indexed_resources.get(&cid))
+                        .map(|rscs| rscs.iter().map(|rsc| rsc.to_string()).join(", "))
+                        .unwrap_or_default();
+
+                    PlanStep {
+                        step,
+                        instruction,
+                        op: node.inner().instruction.op().to_string(),
+                        resources,
+                        receipted,
+                        status: if step < skipped_steps {
+                            "skip".to_string()
+                        } else {
+                            "run".to_string()
+                        },
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            cid: cid.to_string(),
+            name: name.to_string(),
+            num_tasks,
+            num_steps: graph.schedule.len(),
+            skipped_steps: plan.skipped_steps,
+            steps,
+            promises_to_resolve: plan
+                .promises_to_resolve
+                .iter()
+                .map(|cid| cid.to_string())
+                .collect(),
+            dot,
+        }
+    }
+
+    /// Write the plan as a [dot] graph to stdout, if rendered.
+    ///
+    /// [dot]: <https://graphviz.org/doc/info/lang.html>
+    pub fn echo_dot(&self) -> Result<(), std::io::Error> {
+        let mut stdout = std::io::stdout().lock();
+        if let Some(dot) = &self.dot {
+            stdout.write_all(dot.as_bytes())?;
+        }
+        stdout.flush()
+    }
+}
+
+impl show::ConsoleTable for AckPlan {
+    fn table(&self) -> show::Output {
+        let mut builder = Builder::default();
+        builder.push_record(["cid", "name", "num_tasks", "num_steps", "skipped_steps"]);
+        builder.push_record([
+            self.cid.clone(),
+            self.name.clone(),
+            self.num_tasks.to_string(),
+            self.num_steps.to_string(),
+            self.skipped_steps.to_string(),
+        ]);
+        show::Output::new(builder.build().to_string())
+    }
+
+    fn echo_table(&self) -> Result<(), std::io::Error> {
+        let table = self.table();
+        let steps_table = Table::new(&self.steps);
+
+        let mut promises_table_builder = Builder::default();
+        promises_table_builder.push_record(["Promises to Resolve".to_string()]);
+        for promise in &self.promises_to_resolve {
+            promises_table_builder.push_record([promise.to_string()]);
+        }
+
+        // If there are no promises to resolve, add a placeholder row.
+        if promises_table_builder.count_records() == 1 {
+            promises_table_builder.push_record(["<none>".to_string()]);
+        }
+
+        let promises_table = promises_table_builder.build();
+
+        let tbl = col![table, steps_table, promises_table].default_with_title("plan");
+        tbl.echo()
+    }
+}
+
+/// Stored receipts encoded as [DAG-JSON] or [DAG-CBOR] for response /
+/// output.
+///
+/// A single receipt is encoded as is, while a set of receipts is encoded
+/// as a list.
+///
+/// [DAG-JSON]: <https://ipld.io/specs/codecs/dag-json/spec/>
+/// [DAG-CBOR]: <https://ipld.io/specs/codecs/dag-cbor/spec/>
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AckReceipts {
+    pub(crate) encoding: ReceiptEncoding,
+    pub(crate) count: u32,
+    pub(crate) data: Vec<u8>,
+}
+
+impl fmt::Display for AckReceipts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "receipts: {}, encoding: {}, bytes: {}",
+            self.count,
+            self.encoding,
+            self.data.len()
+        )
+    }
+}
+
+impl AckReceipts {
+    /// Encode a single receipt for response / output.
+    pub(crate) fn with_receipt(
+        receipt: Receipt,
+        encoding: ReceiptEncoding,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            encoding,
+            count: 1,
+            data: encoding.encode(&Ipld::from(receipt))?,
+        })
+    }
+
+    /// Encode a list of receipts for response / output.
+    pub(crate) fn with_receipts(
+        receipts: Vec<Receipt>,
+        encoding: ReceiptEncoding,
+    ) -> anyhow::Result<Self> {
+        let count = receipts.len() as u32;
+        let ipld = Ipld::List(receipts.into_iter().map(Ipld::from).collect());
+        Ok(Self {
+            encoding,
+            count,
+            data: encoding.encode(&ipld)?,
+        })
+    }
+
+    /// Encoded receipt(s).
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Encoded receipt(s) as a JSON value, for JSON-RPC responses.
+    ///
+    /// DAG-CBOR data is wrapped as [DAG-JSON bytes].
+    ///
+    /// [DAG-JSON bytes]: <https://ipld.io/specs/codecs/dag-json/spec/#bytes>
+    pub(crate) fn to_json_value(&self) -> anyhow::Result<serde_json::Value> {
+        match self.encoding {
+            ReceiptEncoding::DagJson => Ok(serde_json::from_slice(&self.data)?),
+            ReceiptEncoding::DagCbor => {
+                let wrapped = ReceiptEncoding::DagJson.encode(&Ipld::Bytes(self.data.clone()))?;
+                Ok(serde_json::from_slice(&wrapped)?)
+            }
+        }
+    }
+
+    /// Write encoded receipt(s) to stdout.
+    pub fn echo(&self) -> Result<(), std::io::Error> {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&self.data)?;
+        if self.encoding == ReceiptEncoding::DagJson {
+            writeln!(stdout)?;
+        }
+        stdout.flush()
+    }
+}
+
 /// Ping response for display.
 #[derive(Debug, Tabled)]
 pub(crate) struct Ping {
@@ -10,13 +10,14 @@ use crate::{
     Db,
 };
 use anyhow::{anyhow, Result};
-use dagga::Node;
+use dagga::{dot::DagLegend, Node};
 use fnv::FnvHashSet;
 use futures::future::BoxFuture;
 use homestar_invocation::{task, Pointer};
 use homestar_wasm::io::Arg;
 use homestar_workflow::LinkMap;
 use indexmap::IndexMap;
+use itertools::Itertools;
 use libipld::Cid;
 use std::{str::FromStr, sync::Arc};
 use tokio::sync::RwLock;
@@ -46,6 +47,38 @@ pub(crate) struct ExecutionGraph<'a> {
     pub(crate) indexed_resources: IndexedResources,
 }
 
+impl ExecutionGraph<'static> {
+    /// Render the batched [Schedule] as a [dot] graph, to be read by
+    /// graphviz, etc.
+    ///
+    /// [dot]: <https://graphviz.org/doc/info/lang.html>
+    pub(crate) fn to_dot(&self, name: &str) -> Result<String> {
+        let legend = DagLegend::new(self.schedule.iter().flatten()).with_name(name);
+        let mut buf = Vec::new();
+        dot2::render(&legend, &mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+}
+
+/// Dry-run outcome of scheduling an [ExecutionGraph], given the
+/// [Receipts] already stored, without fetching resources or executing
+/// anything.
+///
+/// [Receipts]: crate::Receipt
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Plan {
+    /// Number of leading batches skipped, as the last of them (and so
+    /// everything before it) has already been receipted.
+    pub(crate) skipped_steps: usize,
+    /// Instructions within the [ExecutionGraph] with stored receipts.
+    pub(crate) receipted: FnvHashSet<Cid>,
+    /// Awaited Cids outside of the [Workflow] without stored receipts,
+    /// which would be resolved over the network.
+    ///
+    /// [Workflow]: homestar_workflow::Workflow
+    pub(crate) promises_to_resolve: Vec<Cid>,
+}
+
 /// Scheduler for a series of tasks, including what's run,
 /// what's left to run, and data structures to track resources
 /// and what's been executed in memory.
@@ -210,6 +243,64 @@ impl<'a> TaskScheduler<'a> {
         })
     }
 
+    /// Plan a run of the [ExecutionGraph] for a [Workflow], mirroring
+    /// [TaskScheduler::init] with database lookups only, i.e. no resources
+    /// are fetched and no [Swarm]/DHT queries are made.
+    ///
+    /// [Swarm]: crate::network::swarm
+    /// [Workflow]: homestar_workflow::Workflow
+    pub(crate) fn plan(graph: &ExecutionGraph<'a>, conn: &mut Connection) -> Result<Plan> {
+        let mut plan = Plan::default();
+        for (idx, vec) in graph.schedule.iter().enumerate().rev() {
+            let pointers = vec
+                .iter()
+                .map(|node| Ok(Pointer::new(Cid::from_str(node.name())?)))
+                .collect::<Result<Vec<_>>>()?;
+
+            if let Ok(found) = Db::find_instruction_pointers(&pointers, conn) {
+                plan.receipted
+                    .extend(found.iter().map(|receipt| receipt.instruction().cid()));
+
+                if found.len() == vec.len() {
+                    plan.skipped_steps = idx + 1;
+                    break;
+                }
+            }
+        }
+
+        let out_flow = graph
+            .awaiting
+            .iter()
+            .filter_map(|(in_or_out_flow, cid)| match in_or_out_flow {
+                workflow::Origin::InFlow => None,
+                workflow::Origin::OutFlow => Some(*cid),
+            })
+            .unique()
+            .collect::<Vec<_>>();
+
+        let found: FnvHashSet<Cid> = Db::find_instruction_pointers(
+            &out_flow
+                .iter()
+                .map(|cid| Pointer::new(*cid))
+                .collect::<Vec<_>>(),
+            conn,
+        )
+        .map(|found| {
+            found
+                .iter()
+                .map(|receipt| receipt.instruction().cid())
+                .collect()
+        })
+        .unwrap_or_default();
+
+        plan.promises_to_resolve = out_flow
+            .into_iter()
+            .filter(|cid| !found.contains(cid))
+            .collect();
+
+        Ok(plan)
+    }
+
     /// Get the number of tasks that have already ran in the [Workflow].
     ///
     /// [Workflow]: homestar_workflow::Workflow
@@ -447,6 +538,59 @@ mod test {
         assert_eq!(ctx.resume_step, None);
     }
 
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn plan_with_receipted_instruction() {
+        let settings = TestSettings::load();
+        let config = Resources::default();
+        let (instruction1, instruction2, _) = test_utils::related_wasm_instructions::<Arg>();
+        let task1 = Task::new(
+            RunInstruction::Expanded(instruction1.clone()),
+            config.clone().into(),
+            UcanPrf::default(),
+        );
+        let task2 = Task::new(
+            RunInstruction::Expanded(instruction2.clone()),
+            config.into(),
+            UcanPrf::default(),
+        );
+
+        let db = MemoryDb::setup_connection_pool(&settings.node, None).unwrap();
+        let mut conn = db.conn().unwrap();
+
+        let workflow = Workflow::new(vec![task1.clone(), task2.clone()]);
+        let graph = workflow::Builder::new(workflow.clone()).graph().unwrap();
+        let plan = TaskScheduler::plan(&graph, &mut conn).unwrap();
+        assert_eq!(plan.skipped_steps, 0);
+        assert!(plan.receipted.is_empty());
+        assert!(plan.promises_to_resolve.is_empty());
+
+        let invocation_receipt = InvocationReceipt::new(
+            Invocation::new(task1.clone()).try_into().unwrap(),
+            task::Result::Ok(Ipld::Integer(4)),
+            Ipld::Null,
+            None,
+            UcanPrf::default(),
+        );
+        let receipt = Receipt::try_with(
+            instruction1.clone().try_into().unwrap(),
+            &invocation_receipt,
+        )
+        .unwrap();
+        MemoryDb::store_receipt(receipt, &mut conn).unwrap();
+
+        let plan = TaskScheduler::plan(&graph, &mut conn).unwrap();
+        assert_eq!(plan.skipped_steps, 1);
+        assert_eq!(
+            plan.receipted,
+            FnvHashSet::from_iter([instruction1.to_cid().unwrap()])
+        );
+        assert_eq!(graph.schedule.len(), 2);
+
+        let dot = graph.to_dot("plan").unwrap();
+        assert!(dot.starts_with("digraph plan"));
+        assert!(dot.contains(&instruction2.to_cid().unwrap().to_string()));
+    }
+
     #[test]
     fn duplicate_task_no_nonce() {
         let config = Resources::default();
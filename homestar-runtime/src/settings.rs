@@ -2,6 +2,8 @@
 
 use config::{Config, ConfigError, Environment, File};
 use derive_builder::Builder;
+use homestar_invocation::{consts, task::Resources};
+use homestar_wasm::wasmtime::{logs::MAX_LOG_SIZE, HttpPolicy, Interruption, SandboxPolicy};
 use http::Uri;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr, DurationMilliSeconds, DurationSeconds};
@@ -16,7 +18,7 @@ use std::{
 
 mod libp2p_config;
 mod pubkey_config;
-pub use libp2p_config::{Autonat, Dht, Libp2p, Mdns, Pubsub, Rendezvous};
+pub use libp2p_config::{Autonat, Dht, DhtRecordStore, Libp2p, Mdns, Pubsub, Rendezvous};
 pub use pubkey_config::{ExistingKeyPath, KeyType, PubkeyConfig, RNGSeed};
 
 #[cfg(target_os = "windows")]
@@ -61,6 +63,210 @@ pub struct Node {
     /// Shutdown timeout.
     #[serde_as(as = "DurationSeconds<u64>")]
     pub(crate) shutdown_timeout: Duration,
+    /// Node-wide maximums on the resources a task may request.
+    #[serde(default)]
+    pub(crate) task_limits: TaskLimits,
+    /// How tasks are executed.
+    #[serde(default)]
+    pub(crate) task_execution: TaskExecution,
+    /// Host interfaces tasks may use.
+    #[serde(default)]
+    pub(crate) task_host: TaskHost,
+    /// Verification of the UCAN proofs tasks are invoked with.
+    #[serde(default)]
+    pub(crate) authorization: Authorization,
+    /// Cache of compiled Wasm modules.
+    #[serde(default)]
+    pub(crate) module_cache: ModuleCache,
+}
+
+/// Cache of compiled Wasm modules, shared by all tasks of the node.
+///
+/// Compiled modules are kept in memory, and, if a directory is set, on disk
+/// across restarts.
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct ModuleCache {
+    /// Directory compiled modules are stored in, if any.
+    pub(crate) dir: Option<PathBuf>,
+    /// Maximum size of the directory in bytes, beyond which the least
+    /// recently used modules are evicted.
+    pub(crate) max_size: u64,
+}
+
+/// Verification of the UCAN proofs (`prf`) a task is invoked with, checking
+/// that they delegate the capability for the task's resource and ability to
+/// this node.
+#[derive(Builder, Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct Authorization {
+    /// Whether proofs are verified, and if so, whether unauthorized tasks
+    /// are rejected or only logged.
+    pub(crate) mode: AuthorizationMode,
+    /// DIDs trusted as the root of a delegation chain, besides the node's
+    /// own DID.
+    pub(crate) trusted_issuers: Vec<String>,
+}
+
+/// Mode of UCAN proof verification.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthorizationMode {
+    /// Proofs aren't verified.
+    #[default]
+    Off,
+    /// Proofs are verified, with unauthorized tasks logged but still run.
+    Audit,
+    /// Proofs are verified, with workflows containing unauthorized tasks
+    /// rejected.
+    Enforce,
+}
+
+/// Node-wide maximums on the [Resources] a task may request via its
+/// metadata, also used for any limit a task doesn't set.
+///
+/// [Resources]: homestar_invocation::task::Resources
+#[serde_as]
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct TaskLimits {
+    /// Maximum fuel for a task.
+    pub(crate) max_fuel: u64,
+    /// Maximum memory for a task in bytes.
+    pub(crate) max_memory: u64,
+    /// Maximum number of table elements for a task.
+    pub(crate) max_table_elements: u32,
+    /// Maximum wall-clock time for a task in milliseconds.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub(crate) max_time: Duration,
+}
+
+/// How tasks are executed: the WASI sandbox they run in, whether they run
+/// deterministically, and how long-running ones are interrupted.
+#[derive(Builder, Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct TaskExecution {
+    /// WASI sandbox policy for tasks, and the most a task may request via
+    /// its metadata. WASI is disabled by default.
+    pub(crate) sandbox: SandboxPolicy,
+    /// Whether tasks execute deterministically, unless set by their
+    /// metadata.
+    pub(crate) deterministic: bool,
+    /// How long-running tasks are interrupted: by metering `fuel`, by
+    /// checking `epoch`s, which preempts tasks and kills them precisely at
+    /// their time limit, or `both`.
+    pub(crate) interruption: Interruption,
+}
+
+/// Host interfaces tasks may use beyond WASI, under `homestar:host`.
+#[derive(Builder, Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct TaskHost {
+    /// Outbound HTTP requests tasks may make.
+    pub(crate) http: Http,
+    /// Log records captured from tasks.
+    pub(crate) logs: TaskLogs,
+    /// Key-value store tasks may read and write.
+    pub(crate) kv: Kv,
+    /// Secrets tasks may read.
+    pub(crate) secrets: Secrets,
+}
+
+/// Outbound HTTP requests tasks may make through the `homestar:host/http`
+/// interface.
+///
+/// No hosts are allowed by default.
+#[serde_as]
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct Http {
+    /// Hosts requests may be sent to, either as `host`, `host:port`, or
+    /// `*.domain` to allow any subdomain of `domain`.
+    pub(crate) allowed_hosts: Vec<String>,
+    /// Methods requests may use.
+    pub(crate) allowed_methods: Vec<String>,
+    /// Maximum size of a response body in bytes.
+    pub(crate) max_response_size: u64,
+    /// Timeout of a request, including reading its response, in
+    /// milliseconds.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub(crate) timeout: Duration,
+}
+
+/// Log records captured from tasks' guest Wasm components, streamed to
+/// `subscribe_task_logs` subscribers.
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct TaskLogs {
+    /// Maximum size of the records captured per task in bytes, past which
+    /// records are dropped. Each record counts a fixed overhead on top of
+    /// its context and message.
+    pub(crate) max_size: usize,
+    /// Whether to link a block of the captured records from the receipt of
+    /// each task that logged any.
+    pub(crate) receipt: bool,
+}
+
+/// Key-value store tasks may read and write through the `homestar:host/kv`
+/// interface, backed by the node's database.
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct Kv {
+    /// Maximum size of the keys and values of a namespace, in bytes.
+    pub(crate) quota: u64,
+    /// Scope of the namespaces tasks share.
+    pub(crate) scope: KvScope,
+}
+
+/// Scope of the key-value namespace a task reads and writes.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KvScope {
+    /// Shared by the tasks of a workflow.
+    #[default]
+    Workflow,
+    /// Shared by the tasks of a workflow with the same issuer, i.e. the
+    /// issuer of the UCAN proof authorizing a task. Tasks without a
+    /// verified issuer are denied the store.
+    Issuer,
+}
+
+/// Secrets, e.g. API tokens, tasks may read through the
+/// `homestar:host/secrets` interface, by name, if requested in their
+/// metadata.
+///
+/// Secrets are read from files in a directory, named after the secret, or
+/// from environment variables, prefixed and named after the secret in
+/// uppercase. No secrets are granted by default.
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct Secrets {
+    /// Directory of secret files, if any.
+    pub(crate) dir: Option<PathBuf>,
+    /// Prefix of secret environment variables.
+    pub(crate) env_prefix: String,
+    /// Secrets granted to workflow issuers.
+    pub(crate) grants: Vec<SecretGrant>,
+}
+
+/// Secret and the workflow issuers it's granted to.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SecretGrant {
+    /// Name of the secret.
+    pub(crate) name: String,
+    /// DIDs of the issuers of the UCAN proofs authorizing tasks allowed to
+    /// read the secret, or `*` for any task, including tasks without a
+    /// verified issuer.
+    pub(crate) issuers: Vec<String>,
 }
 
 /// Database-related settings for a homestar node.
@@ -145,6 +351,8 @@ pub struct Ipfs {
 pub struct Metrics {
     /// Metrics port for prometheus scraping.
     pub(crate) port: u16,
+    /// TLS for the scrape endpoint, served over plaintext HTTP if unset.
+    pub(crate) tls: Option<Tls>,
 }
 
 /// RPC server settings.
@@ -187,6 +395,109 @@ pub struct Webserver {
     /// Websocket-server send timeout.
     #[serde_as(as = "DurationMilliSeconds<u64>")]
     pub(crate) websocket_sender_timeout: Duration,
+    /// Authentication of webserver clients.
+    pub(crate) auth: WebserverAuth,
+    /// TLS for HTTP and WebSocket (`wss://`) clients, served over
+    /// plaintext if unset.
+    pub(crate) tls: Option<Tls>,
+}
+
+/// Authentication of JSON-RPC/WebSocket clients, via static bearer tokens
+/// or DID-signed challenges, each scoped to a set of permissions.
+///
+/// Permissions are either a group of methods, `read` for monitoring and
+/// `run` for workflow submission and cancellation, `*` for every method,
+/// or a single method name.
+#[serde_as]
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct WebserverAuth {
+    /// Whether clients must authenticate to call any non-public method.
+    pub(crate) enabled: bool,
+    /// Permissions of unauthenticated clients.
+    pub(crate) public: Vec<String>,
+    /// Static bearer tokens, sent as `Authorization: Bearer <token>`.
+    pub(crate) tokens: Vec<TokenGrant>,
+    /// DIDs authenticating with a signed challenge, sent as
+    /// `Authorization: DID <did> <challenge> <signature>`.
+    pub(crate) dids: Vec<DidGrant>,
+    /// Time a challenge is valid for after being issued.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub(crate) challenge_ttl: Duration,
+}
+
+/// Static bearer token and its permissions.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TokenGrant {
+    /// Bearer token.
+    pub(crate) token: String,
+    /// Permissions granted to the token.
+    pub(crate) permissions: Vec<String>,
+}
+
+/// DID and its permissions.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct DidGrant {
+    /// `did:key` DID of the client.
+    pub(crate) did: String,
+    /// Permissions granted to the DID.
+    pub(crate) permissions: Vec<String>,
+}
+
+/// TLS settings for a server endpoint.
+///
+/// Certificates and keys are reloaded when their files change on disk,
+/// without dropping open connections.
+#[serde_as]
+#[derive(Builder, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[builder(default)]
+#[serde(default)]
+pub struct Tls {
+    /// Path to the PEM-encoded certificate chain.
+    pub(crate) cert_path: PathBuf,
+    /// Path to the PEM-encoded private key.
+    pub(crate) key_path: PathBuf,
+    /// Path to PEM-encoded CA certificates to verify client certificates
+    /// against. Client certificates are required if set.
+    pub(crate) client_ca_path: Option<PathBuf>,
+    /// Interval between checks of the files for changes in milliseconds.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub(crate) reload_interval: Duration,
+    /// Time allowed for a client to complete the TLS handshake in
+    /// milliseconds.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub(crate) handshake_timeout: Duration,
+}
+
+impl TokenGrant {
+    /// Create a new [TokenGrant].
+    pub fn new(token: impl Into<String>, permissions: Vec<String>) -> Self {
+        Self {
+            token: token.into(),
+            permissions,
+        }
+    }
+}
+
+impl SecretGrant {
+    /// Create a new [SecretGrant].
+    pub fn new(name: impl Into<String>, issuers: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            issuers,
+        }
+    }
+}
+
+impl DidGrant {
+    /// Create a new [DidGrant].
+    pub fn new(did: impl Into<String>, permissions: Vec<String>) -> Self {
+        Self {
+            did: did.into(),
+            permissions,
+        }
+    }
 }
 
 impl Default for Node {
@@ -197,10 +508,119 @@ impl Default for Node {
             monitoring: Default::default(),
             network: Default::default(),
             db: Default::default(),
+            task_limits: Default::default(),
+            task_execution: Default::default(),
+            task_host: Default::default(),
+            authorization: Default::default(),
+            module_cache: Default::default(),
+        }
+    }
+}
+
+impl Default for ModuleCache {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            max_size: 512 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for TaskLimits {
+    fn default() -> Self {
+        Self {
+            max_fuel: u64::MAX,
+            max_memory: consts::WASM_MAX_MEMORY,
+            max_table_elements: 10_000,
+            max_time: Duration::from_millis(100_000),
+        }
+    }
+}
+
+impl Default for TaskLogs {
+    fn default() -> Self {
+        Self {
+            max_size: MAX_LOG_SIZE,
+            receipt: false,
+        }
+    }
+}
+
+impl Default for Kv {
+    fn default() -> Self {
+        Self {
+            quota: 1024 * 1024,
+            scope: KvScope::default(),
+        }
+    }
+}
+
+impl Default for Secrets {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            env_prefix: "HOMESTAR_SECRET_".to_string(),
+            grants: vec![],
+        }
+    }
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: vec![],
+            allowed_methods: vec!["GET".to_string(), "HEAD".to_string()],
+            max_response_size: 1024 * 1024,
+            timeout: Duration::from_millis(10_000),
         }
     }
 }
 
+impl From<&Http> for HttpPolicy {
+    fn from(http: &Http) -> Self {
+        HttpPolicy::default()
+            .with_allowed_hosts(http.allowed_hosts.clone())
+            .with_allowed_methods(http.allowed_methods.clone())
+            .with_max_response_size(http.max_response_size)
+            .with_timeout(http.timeout)
+    }
+}
+
+impl TaskLimits {
+    /// Cap a task's requested [Resources] to these maximums, filling in any
+    /// limit the task didn't set.
+    pub(crate) fn cap(&self, requested: &Resources) -> Resources {
+        Resources::new(
+            requested
+                .fuel()
+                .map_or(self.max_fuel, |f| f.min(self.max_fuel)),
+            requested
+                .memory()
+                .map_or(self.max_memory, |m| m.min(self.max_memory)),
+            requested
+                .time()
+                .map_or(self.max_time, |t| t.min(self.max_time)),
+        )
+    }
+}
+
+impl TaskExecution {
+    /// Restrict a task's requested [SandboxPolicy] to the node's, using the
+    /// node's policy if the task didn't request one.
+    pub(crate) fn sandbox(&self, requested: Option<&SandboxPolicy>) -> SandboxPolicy {
+        requested.map_or_else(
+            || self.sandbox.clone(),
+            |requested| self.sandbox.restrict(requested),
+        )
+    }
+
+    /// Whether a task executes deterministically, as requested by its
+    /// metadata, or otherwise by default for the node.
+    pub(crate) fn deterministic(&self, requested: Option<bool>) -> bool {
+        requested.unwrap_or(self.deterministic)
+    }
+}
+
 impl Node {
     /// Monitoring settings getter.
     pub fn monitoring(&self) -> &Monitoring {
@@ -216,6 +636,26 @@ impl Node {
     pub fn shutdown_timeout(&self) -> Duration {
         self.shutdown_timeout
     }
+
+    /// Node-wide task resource limits.
+    pub fn task_limits(&self) -> &TaskLimits {
+        &self.task_limits
+    }
+
+    /// Task execution settings.
+    pub fn task_execution(&self) -> &TaskExecution {
+        &self.task_execution
+    }
+
+    /// Task host interface settings.
+    pub fn task_host(&self) -> &TaskHost {
+        &self.task_host
+    }
+
+    /// UCAN proof verification settings.
+    pub fn authorization(&self) -> &Authorization {
+        &self.authorization
+    }
 }
 
 impl Default for Database {
@@ -296,7 +736,10 @@ impl Default for Ipfs {
 
 impl Default for Metrics {
     fn default() -> Self {
-        Self { port: 4000 }
+        Self {
+            port: 4000,
+            tls: None,
+        }
     }
 }
 
@@ -320,6 +763,32 @@ impl Default for Webserver {
             timeout: Duration::new(120, 0),
             websocket_capacity: 2048,
             websocket_sender_timeout: Duration::from_millis(30_000),
+            auth: WebserverAuth::default(),
+            tls: None,
+        }
+    }
+}
+
+impl Default for Tls {
+    fn default() -> Self {
+        Self {
+            cert_path: PathBuf::new(),
+            key_path: PathBuf::new(),
+            client_ca_path: None,
+            reload_interval: Duration::from_millis(5_000),
+            handshake_timeout: Duration::from_millis(10_000),
+        }
+    }
+}
+
+impl Default for WebserverAuth {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            public: vec!["health".to_string()],
+            tokens: Vec::new(),
+            dids: Vec::new(),
+            challenge_ttl: Duration::from_secs(60),
         }
     }
 }
@@ -398,6 +867,7 @@ fn home_dir() -> PathBuf {
 #[cfg(test)]
 mod test {
     use super::*;
+    use homestar_wasm::wasmtime::Preopen;
 
     #[test]
     fn defaults() {
@@ -424,6 +894,8 @@ mod test {
         default_modded_settings.shutdown_timeout = Duration::from_secs(20);
         default_modded_settings.network.libp2p.node_addresses =
             vec!["/ip4/127.0.0.1/tcp/9998/ws".to_string().try_into().unwrap()];
+        default_modded_settings.network.libp2p.dht.record_store = DhtRecordStore::Sqlite;
+        default_modded_settings.network.libp2p.dht.max_records = 2048;
         assert_eq!(settings.node(), &default_modded_settings);
     }
 
@@ -445,6 +917,182 @@ mod test {
         assert_eq!(settings.node.db.max_pool_size, 1);
     }
 
+    #[test]
+    fn cap_task_resources() {
+        let limits = TaskLimits {
+            max_fuel: 1000,
+            max_memory: 1024,
+            max_table_elements: 10,
+            max_time: Duration::from_millis(500),
+        };
+
+        let requested = Resources::new(u64::MAX, 512, Duration::from_millis(100));
+        assert_eq!(
+            limits.cap(&requested),
+            Resources::new(1000, 512, Duration::from_millis(100))
+        );
+
+        let unset = Resources::from_meta(&libipld::Ipld::Null).unwrap();
+        assert_eq!(
+            limits.cap(&unset),
+            Resources::new(1000, 1024, Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn task_sandbox() {
+        let settings = Settings::build(Some("fixtures/settings-sandbox.toml".into()))
+            .expect("setting file in test fixtures");
+
+        let sandbox = SandboxPolicy::enabled()
+            .with_clocks(true)
+            .with_random(true, Some(42))
+            .with_env("LANG", "C")
+            .with_preopen(Preopen::new("/srv/homestar", "/data", false));
+        assert_eq!(settings.node.task_execution.sandbox, sandbox);
+        assert_eq!(settings.node.task_execution.sandbox(None), sandbox);
+
+        let requested = SandboxPolicy::enabled()
+            .with_random(true, None)
+            .with_preopen(Preopen::new("", "/data", true));
+        assert_eq!(
+            settings.node.task_execution.sandbox(Some(&requested)),
+            SandboxPolicy::enabled()
+                .with_random(true, Some(42))
+                .with_env("LANG", "C")
+                .with_preopen(Preopen::new("/srv/homestar", "/data", false))
+        );
+        assert!(!TaskExecution::default()
+            .sandbox(Some(&requested))
+            .is_enabled());
+
+        assert!(settings.node.task_execution.deterministic(None));
+        assert!(!settings.node.task_execution.deterministic(Some(false)));
+        assert!(!TaskExecution::default().deterministic(None));
+    }
+
+    #[test]
+    fn task_interruption() {
+        let settings = Settings::build(Some("fixtures/settings-interruption.toml".into()))
+            .expect("setting file in test fixtures");
+        assert_eq!(
+            settings.node.task_execution.interruption,
+            Interruption::Epoch
+        );
+        assert_eq!(TaskExecution::default().interruption, Interruption::Fuel);
+    }
+
+    #[test]
+    fn task_logs() {
+        let settings = Settings::build(Some("fixtures/settings-task-logs.toml".into()))
+            .expect("setting file in test fixtures");
+        assert_eq!(
+            settings.node.task_host.logs,
+            TaskLogsBuilder::default()
+                .max_size(1024)
+                .receipt(true)
+                .build()
+                .unwrap()
+        );
+        assert_eq!(TaskHost::default().logs.max_size, MAX_LOG_SIZE);
+    }
+
+    #[test]
+    fn task_kv() {
+        let settings = Settings::build(Some("fixtures/settings-kv.toml".into()))
+            .expect("setting file in test fixtures");
+        assert_eq!(
+            settings.node.task_host.kv,
+            KvBuilder::default()
+                .quota(4096)
+                .scope(KvScope::Issuer)
+                .build()
+                .unwrap()
+        );
+        assert_eq!(TaskHost::default().kv.scope, KvScope::Workflow);
+    }
+
+    #[test]
+    fn task_secrets() {
+        let settings = Settings::build(Some("fixtures/settings-secrets.toml".into()))
+            .expect("setting file in test fixtures");
+        assert_eq!(
+            settings.node.task_host.secrets,
+            SecretsBuilder::default()
+                .dir(Some(PathBuf::from("/run/secrets")))
+                .env_prefix("SECRET_".to_string())
+                .grants(vec![SecretGrant::new(
+                    "api_token",
+                    vec!["did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK".to_string()],
+                )])
+                .build()
+                .unwrap()
+        );
+        assert!(TaskHost::default().secrets.grants.is_empty());
+    }
+
+    #[test]
+    fn task_http() {
+        let settings = Settings::build(Some("fixtures/settings-http.toml".into()))
+            .expect("setting file in test fixtures");
+
+        let http = HttpBuilder::default()
+            .allowed_hosts(vec![
+                "api.internal:8080".to_string(),
+                "*.svc.internal".to_string(),
+            ])
+            .allowed_methods(vec!["GET".to_string(), "POST".to_string()])
+            .max_response_size(4096)
+            .timeout(Duration::from_millis(2_500))
+            .build()
+            .unwrap();
+        assert_eq!(settings.node.task_host.http, http);
+        assert_eq!(
+            HttpPolicy::from(&http),
+            HttpPolicy::default()
+                .with_allowed_hosts(http.allowed_hosts.clone())
+                .with_allowed_methods(http.allowed_methods.clone())
+                .with_max_response_size(4096)
+                .with_timeout(Duration::from_millis(2_500))
+        );
+    }
+
+    #[test]
+    fn module_cache() {
+        let settings = Settings::build(Some("fixtures/settings-module-cache.toml".into()))
+            .expect("setting file in test fixtures");
+
+        let module_cache = ModuleCacheBuilder::default()
+            .dir(Some(PathBuf::from("/tmp/homestar/modules")))
+            .max_size(1024 * 1024)
+            .build()
+            .unwrap();
+        assert_eq!(settings.node.module_cache, module_cache);
+        assert_eq!(Node::default().module_cache.dir, None);
+    }
+
+    #[test]
+    fn webserver_auth() {
+        let settings = Settings::build(Some("fixtures/settings-webserver-auth.toml".into()))
+            .expect("setting file in test fixtures");
+
+        let auth = WebserverAuthBuilder::default()
+            .enabled(true)
+            .public(vec!["health".to_string(), "rpc_discover".to_string()])
+            .tokens(vec![TokenGrant::new(
+                "monitor-token",
+                vec!["read".to_string()],
+            )])
+            .dids(vec![DidGrant::new(
+                "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK",
+                vec!["run".to_string(), "node".to_string()],
+            )])
+            .challenge_ttl(Duration::from_secs(30))
+            .build()
+            .unwrap();
+        assert_eq!(settings.node.network.webserver.auth, auth);
+    }
+
     #[test]
     fn import_existing_key() {
         // Test using a key not containing curve parameters
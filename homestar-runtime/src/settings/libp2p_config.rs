@@ -95,6 +95,42 @@ pub struct Dht {
     ///
     /// [workflow::Info]: crate::workflow::Info
     pub(crate) workflow_quorum: usize,
+    /// Backend for storing DHT records and provider records.
+    pub(crate) record_store: DhtRecordStore,
+    /// Maximum number of records kept in the record store.
+    pub(crate) max_records: usize,
+    /// Maximum size of a record value, in bytes.
+    pub(crate) max_record_size: usize,
+    /// Maximum number of provider records kept per key.
+    pub(crate) max_providers_per_key: usize,
+    /// Maximum number of keys this node provides.
+    pub(crate) max_provided_keys: usize,
+    /// Expiration (TTL) of records.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub(crate) record_ttl: Duration,
+    /// Interval for replicating stored records to the closest peers.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub(crate) record_replication_interval: Duration,
+    /// Interval for republishing records this node published.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub(crate) record_publication_interval: Duration,
+    /// Expiration (TTL) of provider records.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub(crate) provider_record_ttl: Duration,
+    /// Interval for republishing provider records of this node.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub(crate) provider_publication_interval: Duration,
+}
+
+/// Backend for storing DHT records.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DhtRecordStore {
+    /// In-memory store, emptied on restart.
+    #[default]
+    Memory,
+    /// Store persisted in the node's database, surviving restarts.
+    Sqlite,
 }
 
 /// mDNS settings.
@@ -227,6 +263,16 @@ impl Default for Dht {
             p2p_provider_timeout: Duration::from_millis(10000),
             receipt_quorum: 2,
             workflow_quorum: 3,
+            record_store: DhtRecordStore::default(),
+            max_records: 1024,
+            max_record_size: 10 * 1024 * 1024,
+            max_providers_per_key: 20,
+            max_provided_keys: 1024,
+            record_ttl: Duration::from_secs(36 * 60 * 60),
+            record_replication_interval: Duration::from_secs(60 * 60),
+            record_publication_interval: Duration::from_secs(24 * 60 * 60),
+            provider_record_ttl: Duration::from_secs(24 * 60 * 60),
+            provider_publication_interval: Duration::from_secs(12 * 60 * 60),
         }
     }
 }
@@ -1,25 +1,28 @@
 //! Module for working with task-types and task-specific functionality.
 
 use anyhow::{anyhow, Result};
-use enum_assoc::Assoc;
 use std::path::PathBuf;
 
+mod blocks;
 mod fetch;
+mod kv;
+#[cfg(feature = "websocket-notify")]
+mod logs;
+mod registry;
+mod secrets;
 mod wasm;
 
+pub(crate) use blocks::BlockResolver;
 pub(crate) use fetch::*;
+pub(crate) use kv::KvResolver;
+#[cfg(feature = "websocket-notify")]
+pub(crate) use logs::LogStreamer;
+pub use registry::{Executor, ExecutorInput, ExecutorRegistry, Usage};
+pub(crate) use secrets::SecretProvider;
 pub(crate) use wasm::*;
 
-const WASM_OP: &str = "wasm/run";
-
-/// First-class registered task-types.
-#[derive(Debug, Clone, Assoc)]
-#[func(pub fn ability(s: &str) -> Option<Self>)]
-pub(crate) enum RegisteredTasks {
-    /// Basic `wasm/run` task-type.
-    #[assoc(ability = WASM_OP)]
-    WasmRun,
-}
+/// Ability of the basic, built-in Wasm task-type.
+pub(crate) const WASM_OP: &str = "wasm/run";
 
 /// Trait for loading files for different task-types directly.
 #[allow(dead_code)]
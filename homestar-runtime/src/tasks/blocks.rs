@@ -0,0 +1,132 @@
+//! [BlockStore] resolving IPLD blocks read by Wasm tasks from the local
+//! database, falling back to the network (IPFS).
+
+#[cfg(feature = "ipfs")]
+use crate::network::IpfsCli;
+use crate::{db::Database, Db};
+#[cfg(feature = "ipfs")]
+use anyhow::anyhow;
+use anyhow::Result;
+use async_trait::async_trait;
+use homestar_wasm::wasmtime::blocks::BlockStore;
+#[cfg(feature = "ipfs")]
+use libipld::multihash::{Code, MultihashDigest};
+use libipld::Cid;
+use std::fmt;
+#[cfg(feature = "ipfs")]
+use tracing::debug;
+
+/// [BlockStore] over the node's [Database], with blocks resolved over the
+/// network cached locally.
+///
+/// Blocks missing from the IPFS node are retrieved from its peers.
+#[derive(Clone)]
+pub(crate) struct BlockResolver<DB: Database> {
+    db: DB,
+    #[cfg(feature = "ipfs")]
+    ipfs: Option<IpfsCli>,
+}
+
+impl<DB> fmt::Debug for BlockResolver<DB>
+where
+    DB: Database,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockResolver").finish_non_exhaustive()
+    }
+}
+
+impl<DB> BlockResolver<DB>
+where
+    DB: Database,
+{
+    /// Create a new [BlockResolver] resolving blocks from the local
+    /// database only.
+    pub(crate) fn new(db: DB) -> Self {
+        Self {
+            db,
+            #[cfg(feature = "ipfs")]
+            ipfs: None,
+        }
+    }
+
+    /// Resolve blocks missing locally from IPFS.
+    #[cfg(feature = "ipfs")]
+    pub(crate) fn with_ipfs(mut self, ipfs: Option<IpfsCli>) -> Self {
+        self.ipfs = ipfs;
+        self
+    }
+
+    #[cfg(feature = "ipfs")]
+    async fn fetch(&self, cid: &Cid) -> Result<Option<Vec<u8>>> {
+        let Some(ipfs) = &self.ipfs else {
+            return Ok(None);
+        };
+
+        debug!(
+            subject = "blocks.fetch",
+            category = "blocks",
+            cid = cid.to_string(),
+            "attempting to fetch block from IPFS"
+        );
+
+        let block = ipfs.get_block(*cid).await?;
+        let code = Code::try_from(cid.hash().code())?;
+        if code.digest(&block) != *cid.hash() {
+            return Err(anyhow!("block fetched for {cid} does not match its hash"));
+        }
+
+        Db::store_block(*cid, &block, &mut self.db.conn()?)?;
+        Ok(Some(block))
+    }
+
+    #[cfg(not(feature = "ipfs"))]
+    async fn fetch(&self, _cid: &Cid) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl<DB> BlockStore for BlockResolver<DB>
+where
+    DB: Database + 'static,
+{
+    async fn get(&self, cid: &Cid) -> Result<Option<Vec<u8>>> {
+        match Db::find_block(*cid, &mut self.db.conn()?)? {
+            Some(block) => Ok(Some(block)),
+            None => self.fetch(cid).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{db::MemoryDb, receipt};
+    use indexmap::IndexMap;
+    use libipld::multihash::{Code, MultihashDigest};
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn get_stored_blocks() {
+        let settings = TestSettings::load();
+        let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+        let mut conn = db.conn().unwrap();
+
+        let (_, receipt) = receipt::receipts();
+        MemoryDb::store_receipt(receipt.clone(), &mut conn).unwrap();
+        let block = b"block".to_vec();
+        let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(&block));
+        MemoryDb::store_receipt_blocks(
+            receipt.cid(),
+            &IndexMap::from([(cid, block.clone())]),
+            &mut conn,
+        )
+        .unwrap();
+
+        let resolver = BlockResolver::new(db);
+        assert_eq!(resolver.get(&cid).await.unwrap(), Some(block));
+
+        let missing = Cid::new_v1(0x55, Code::Sha2_256.digest(b"missing"));
+        assert_eq!(resolver.get(&missing).await.unwrap(), None);
+    }
+}
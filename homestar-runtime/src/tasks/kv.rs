@@ -0,0 +1,102 @@
+//! [KvStore] reading and writing the key-value namespace of Wasm tasks in
+//! the local database.
+
+use crate::{
+    db::{Connection, Database},
+    Db,
+};
+use async_trait::async_trait;
+use homestar_wasm::wasmtime::kv::{KvError, KvStore};
+use std::fmt;
+
+/// [KvStore] over a namespace of the node's [Database], limited to a quota.
+#[derive(Clone)]
+pub(crate) struct KvResolver<DB: Database> {
+    db: DB,
+    namespace: String,
+    quota: u64,
+}
+
+impl<DB> fmt::Debug for KvResolver<DB>
+where
+    DB: Database,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KvResolver")
+            .field("namespace", &self.namespace)
+            .field("quota", &self.quota)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<DB> KvResolver<DB>
+where
+    DB: Database,
+{
+    /// Create a new [KvResolver] over a namespace, limited to `quota`
+    /// bytes of keys and values.
+    pub(crate) fn new(db: DB, namespace: impl Into<String>, quota: u64) -> Self {
+        Self {
+            db,
+            namespace: namespace.into(),
+            quota,
+        }
+    }
+
+    fn conn(&self) -> Result<Connection, KvError> {
+        self.db
+            .conn()
+            .map_err(|err| KvError::Unavailable(err.to_string()))
+    }
+}
+
+fn unavailable(err: diesel::result::Error) -> KvError {
+    KvError::Unavailable(err.to_string())
+}
+
+#[async_trait]
+impl<DB> KvStore for KvResolver<DB>
+where
+    DB: Database + 'static,
+{
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, KvError> {
+        Db::find_kv(&self.namespace, key, &mut self.conn()?).map_err(unavailable)
+    }
+
+    async fn set(&self, key: &str, value: &[u8]) -> Result<(), KvError> {
+        match Db::store_kv(&self.namespace, key, value, self.quota, &mut self.conn()?) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(KvError::QuotaExceeded(self.quota)),
+            Err(err) => Err(unavailable(err)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool, KvError> {
+        Db::delete_kv(&self.namespace, key, &mut self.conn()?).map_err(unavailable)
+    }
+
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>, KvError> {
+        Db::find_kv_keys(&self.namespace, prefix, &mut self.conn()?).map_err(unavailable)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::db::MemoryDb;
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn namespaced_kv() {
+        let settings = TestSettings::load();
+        let db = MemoryDb::setup_connection_pool(settings.node(), None).unwrap();
+
+        let kv = KvResolver::new(db.clone(), "workflow:a", 8);
+        let other = KvResolver::new(db, "workflow:b", 8);
+        kv.set("count", b"1").await.unwrap();
+        assert_eq!(kv.get("count").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(other.get("count").await.unwrap(), None);
+        assert_eq!(kv.set("cursor", b"1").await, Err(KvError::QuotaExceeded(8)));
+        assert_eq!(kv.keys("").await.unwrap(), vec!["count".to_string()]);
+        assert!(kv.delete("count").await.unwrap());
+    }
+}
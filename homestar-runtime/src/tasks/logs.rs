@@ -0,0 +1,53 @@
+//! [LogSink] streaming the log records of Wasm tasks to WebSocket
+//! subscribers of their workflow's task logs.
+
+use crate::{
+    event_handler::notification::{self, TaskLogNotification},
+    network::webserver::notifier::{self, Notifier},
+};
+use homestar_wasm::wasmtime::logs::{LogRecord, LogSink};
+use libipld::Cid;
+use std::fmt;
+
+/// [LogSink] streaming the log records of a task, by the [Cid]s of its
+/// workflow and instruction.
+#[derive(Clone)]
+pub(crate) struct LogStreamer {
+    notifier: Notifier<notifier::Message>,
+    workflow: Cid,
+    instruction: Cid,
+}
+
+impl fmt::Debug for LogStreamer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LogStreamer")
+            .field("workflow", &self.workflow)
+            .field("instruction", &self.instruction)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LogStreamer {
+    /// Create a new [LogStreamer] for a task.
+    pub(crate) fn new(
+        notifier: Notifier<notifier::Message>,
+        workflow: Cid,
+        instruction: Cid,
+    ) -> Self {
+        Self {
+            notifier,
+            workflow,
+            instruction,
+        }
+    }
+}
+
+impl LogSink for LogStreamer {
+    fn log(&self, record: &LogRecord) {
+        notification::emit_task_log(
+            self.notifier.clone(),
+            self.workflow,
+            TaskLogNotification::with(self.workflow, self.instruction, record),
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Registry of [Executor]s, matched on an [Instruction]'s ability (`op`).
+//!
+//! [Instruction]: homestar_invocation::task::Instruction
+
+use super::{WasmExecutor, WASM_OP};
+use anyhow::Result;
+use async_trait::async_trait;
+use homestar_invocation::task::{
+    self,
+    instruction::{Args, Input},
+    Resources,
+};
+use homestar_wasm::io::Arg;
+use libipld::Ipld;
+use std::{any::Any, collections::HashMap, fmt, sync::Arc};
+use url::Url;
+
+/// Input handed over to an [Executor] for running a single [Instruction].
+///
+/// Anything specific to an [Executor], e.g. the host interfaces of Wasm
+/// tasks, is handed over as its [context].
+///
+/// [Instruction]: homestar_invocation::task::Instruction
+/// [context]: ExecutorInput::context
+#[derive(Clone)]
+pub struct ExecutorInput {
+    resource: Url,
+    content: Option<Vec<u8>>,
+    fun: String,
+    args: Args<Arg>,
+    resources: Resources,
+    context: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl fmt::Debug for ExecutorInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecutorInput")
+            .field("resource", &self.resource)
+            .field("fun", &self.fun)
+            .field("args", &self.args)
+            .field("resources", &self.resources)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ExecutorInput {
+    /// Create a new [ExecutorInput].
+    pub(crate) fn new(
+        resource: Url,
+        content: Option<Vec<u8>>,
+        fun: String,
+        args: Args<Arg>,
+        resources: Resources,
+    ) -> Self {
+        Self {
+            resource,
+            content,
+            fun,
+            args,
+            resources,
+            context: None,
+        }
+    }
+
+    /// Set the context specific to the [Executor] running the
+    /// [Instruction].
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub(crate) fn with_context<T>(mut self, context: T) -> Self
+    where
+        T: Any + Send + Sync,
+    {
+        self.context = Some(Arc::new(context));
+        self
+    }
+
+    /// Resource [Url] of the [Instruction].
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub fn resource(&self) -> &Url {
+        &self.resource
+    }
+
+    /// Fetched content of the resource, only available if the [Executor]
+    /// requires it via [Executor::fetch_resource].
+    pub fn content(&self) -> Option<&[u8]> {
+        self.content.as_deref()
+    }
+
+    /// Function name of the [Instruction]'s input.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub fn fun(&self) -> &str {
+        &self.fun
+    }
+
+    /// Resolved [Args] of the [Instruction].
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub fn args(&self) -> &Args<Arg> {
+        &self.args
+    }
+
+    /// Resolved [Args] of the [Instruction] as [Ipld] values, with the
+    /// outputs of awaited tasks unwrapped from their [task::Result]s.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub fn ipld_args(&self) -> Vec<Ipld> {
+        self.args
+            .inner()
+            .iter()
+            .map(|input| match input {
+                Input::Arg(result) => Ipld::from(result.inner().to_owned()),
+                input => Ipld::from(input.to_owned()),
+            })
+            .collect()
+    }
+
+    /// [Resources] (fuel, memory, and time) the [Instruction] is limited to,
+    /// as requested by its task and capped by the node.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    /// Context specific to the [Executor] running the [Instruction], if
+    /// set and of type `T`.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    pub fn context<T>(&self) -> Option<&T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.context.as_deref()?.downcast_ref()
+    }
+
+    /// Break [ExecutorInput] into its owned parts.
+    pub fn into_parts(self) -> (Url, Option<Vec<u8>>, String, Args<Arg>) {
+        (self.resource, self.content, self.fun, self.args)
+    }
+}
+
+/// Resources consumed by an [Executor] running an [Instruction], recorded
+/// with its [Receipt].
+///
+/// Anything specific to an [Executor], e.g. the side effects of Wasm tasks,
+/// is attached as its [extension].
+///
+/// [Instruction]: homestar_invocation::task::Instruction
+/// [Receipt]: crate::Receipt
+/// [extension]: Usage::extension
+#[derive(Clone, Default)]
+pub struct Usage {
+    fuel: Option<u64>,
+    memory: Option<u64>,
+    extension: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl fmt::Debug for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Usage")
+            .field("fuel", &self.fuel)
+            .field("memory", &self.memory)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Usage {
+    /// Create a new [Usage] from fuel consumed and peak memory (in bytes).
+    pub fn new(fuel: Option<u64>, memory: Option<u64>) -> Self {
+        Self {
+            fuel,
+            memory,
+            extension: None,
+        }
+    }
+
+    /// Attach usage specific to the [Executor].
+    pub fn with_extension<T>(mut self, extension: T) -> Self
+    where
+        T: Any + Send + Sync,
+    {
+        self.extension = Some(Arc::new(extension));
+        self
+    }
+
+    /// Fuel consumed, if metered by the [Executor].
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    /// Peak memory (in bytes), if metered by the [Executor].
+    pub fn memory(&self) -> Option<u64> {
+        self.memory
+    }
+
+    /// Usage specific to the [Executor], if attached and of type `T`.
+    pub fn extension<T>(&self) -> Option<&T>
+    where
+        T: Any + Send + Sync,
+    {
+        self.extension.as_deref()?.downcast_ref()
+    }
+}
+
+/// Executor for a task-type, i.e. an ability like `wasm/run`.
+///
+/// Outputs are turned into [Receipt]s, stored, and used to resolve awaited
+/// promises, exactly like outputs of Wasm tasks.
+///
+/// [Receipt]: crate::Receipt
+#[async_trait]
+pub trait Executor: fmt::Debug + Send + Sync {
+    /// Whether or not the [Instruction]'s resource has to be fetched before
+    /// execution.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    fn fetch_resource(&self) -> bool {
+        false
+    }
+
+    /// Execute an [Instruction] with resolved arguments.
+    ///
+    /// An `Err` is an execution failure, recorded in a non-cacheable error
+    /// receipt and handled according to the task's error policy
+    /// (`on_error`).
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    async fn execute(&self, input: ExecutorInput) -> Result<task::Result<Arg>>;
+
+    /// Execute an [Instruction], like [Executor::execute], also reporting
+    /// the resources it consumed, whether it failed or not.
+    ///
+    /// Executors without metering report an empty [Usage].
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    async fn execute_metered(&self, input: ExecutorInput) -> (Result<task::Result<Arg>>, Usage) {
+        (self.execute(input).await, Usage::default())
+    }
+}
+
+/// Registry of [Executor]s, keyed by ability.
+///
+/// The default registry contains the `wasm/run` executor.
+#[derive(Debug, Clone)]
+pub struct ExecutorRegistry {
+    executors: HashMap<String, Arc<dyn Executor>>,
+}
+
+impl Default for ExecutorRegistry {
+    fn default() -> Self {
+        Self::empty().with(WASM_OP, WasmExecutor)
+    }
+}
+
+impl ExecutorRegistry {
+    /// Create a registry without any [Executor]s.
+    pub fn empty() -> Self {
+        Self {
+            executors: HashMap::new(),
+        }
+    }
+
+    /// Register an [Executor] for an ability, returning any previously
+    /// registered one.
+    pub fn register<E>(
+        &mut self,
+        ability: impl Into<String>,
+        executor: E,
+    ) -> Option<Arc<dyn Executor>>
+    where
+        E: Executor + 'static,
+    {
+        self.executors.insert(ability.into(), Arc::new(executor))
+    }
+
+    /// Register an [Executor] for an ability, builder-style.
+    pub fn with<E>(mut self, ability: impl Into<String>, executor: E) -> Self
+    where
+        E: Executor + 'static,
+    {
+        self.register(ability, executor);
+        self
+    }
+
+    /// Get the [Executor] registered for an ability.
+    pub fn get(&self, ability: &str) -> Option<Arc<dyn Executor>> {
+        self.executors.get(ability).cloned()
+    }
+
+    /// Check if an ability has a registered [Executor].
+    pub fn contains(&self, ability: &str) -> bool {
+        self.executors.contains_key(ability)
+    }
+
+    /// Registered abilities.
+    pub fn abilities(&self) -> impl Iterator<Item = &str> {
+        self.executors.keys().map(|ability| ability.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Echo;
+
+    #[async_trait]
+    impl Executor for Echo {
+        async fn execute(&self, input: ExecutorInput) -> Result<task::Result<Arg>> {
+            let (_, _, _, args) = input.into_parts();
+            Ok(task::Result::Ok(Arg::Ipld(args.into())))
+        }
+    }
+
+    #[test]
+    fn default_and_registered_executors() {
+        let registry = ExecutorRegistry::default();
+        assert!(registry.contains(WASM_OP));
+        assert!(registry.get(WASM_OP).unwrap().fetch_resource());
+        assert!(registry.get("test/echo").is_none());
+
+        let registry = registry.with("test/echo", Echo);
+        assert!(registry.contains("test/echo"));
+        assert!(!registry.get("test/echo").unwrap().fetch_resource());
+
+        let mut abilities = registry.abilities().collect::<Vec<_>>();
+        abilities.sort();
+        assert_eq!(abilities, vec!["test/echo", WASM_OP]);
+
+        let mut registry = ExecutorRegistry::empty();
+        assert!(registry.register("test/echo", Echo).is_none());
+        assert!(registry.register("test/echo", Echo).is_some());
+        assert!(!registry.contains(WASM_OP));
+    }
+
+    #[test]
+    fn executor_specific_context() {
+        let input = ExecutorInput::new(
+            Url::parse("ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu")
+                .unwrap(),
+            None,
+            "echo".to_string(),
+            Args::new(vec![]),
+            Resources::default(),
+        );
+        assert!(input.context::<u32>().is_none());
+
+        let input = input.with_context(7u32);
+        assert_eq!(input.context::<u32>(), Some(&7));
+        assert!(input.context::<String>().is_none());
+    }
+
+    #[test]
+    fn executor_specific_usage() {
+        let usage = Usage::new(Some(1), None);
+        assert!(usage.extension::<u32>().is_none());
+
+        let usage = usage.with_extension(7u32);
+        assert_eq!(usage.fuel(), Some(1));
+        assert_eq!(usage.extension::<u32>(), Some(&7));
+        assert!(usage.extension::<String>().is_none());
+    }
+}
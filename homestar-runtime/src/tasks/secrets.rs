@@ -0,0 +1,140 @@
+//! [SecretStore] reading secrets granted to the issuer of a workflow from
+//! node-local files or environment variables.
+
+use crate::settings;
+use homestar_wasm::wasmtime::secrets::{Secret, SecretError, SecretStore};
+use std::{env, fs, path::PathBuf};
+use tracing::warn;
+
+/// Grant of a secret to any task, verified issuer or not.
+const ANY_ISSUER: &str = "*";
+
+/// [SecretStore] of the secrets granted to a workflow issuer.
+#[derive(Debug, Clone)]
+pub(crate) struct SecretProvider {
+    dir: Option<PathBuf>,
+    env_prefix: String,
+    granted: Vec<String>,
+}
+
+impl SecretProvider {
+    /// Create a new [SecretProvider] of the secrets granted to `issuer`,
+    /// the verified issuer of a task, if any.
+    pub(crate) fn new(settings: &settings::Secrets, issuer: Option<&str>) -> Self {
+        let granted = settings
+            .grants
+            .iter()
+            .filter(|grant| {
+                grant.issuers.iter().any(|granted| {
+                    granted == ANY_ISSUER || issuer.is_some_and(|issuer| granted == issuer)
+                })
+            })
+            .map(|grant| grant.name.clone())
+            .collect();
+
+        Self {
+            dir: settings.dir.clone(),
+            env_prefix: settings.env_prefix.clone(),
+            granted,
+        }
+    }
+
+    fn read_file(&self, name: &str) -> Option<Secret> {
+        let path = self.dir.as_ref()?.join(name);
+        match fs::read_to_string(path) {
+            Ok(value) => Some(Secret::new(value.trim_end_matches(['\r', '\n']))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!(subject = "secrets.read.err",
+                      category = "secrets",
+                      name = name,
+                      err=?err,
+                      "failed to read secret file");
+                None
+            }
+        }
+    }
+
+    fn read_env(&self, name: &str) -> Option<Secret> {
+        let var = format!(
+            "{}{}",
+            self.env_prefix,
+            name.to_uppercase().replace('-', "_")
+        );
+        env::var(var).ok().map(Secret::new)
+    }
+}
+
+/// Whether a secret name is made of ASCII alphanumerics, `_` and `-`
+/// only, so it never escapes the directory of secret files.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+impl SecretStore for SecretProvider {
+    fn get(&self, name: &str) -> Result<Secret, SecretError> {
+        if !valid_name(name) || !self.granted.iter().any(|granted| granted == name) {
+            return Err(SecretError::Denied(name.to_string()));
+        }
+
+        self.read_file(name)
+            .or_else(|| self.read_env(name))
+            .ok_or_else(|| SecretError::NotFound(name.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::settings::{SecretGrant, SecretsBuilder};
+
+    const ISSUER: &str = "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK";
+
+    #[test]
+    fn granted_secrets() {
+        let dir = env::temp_dir().join(format!("homestar-secrets-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("file-token"), "from-file\n").unwrap();
+        env::set_var("HOMESTAR_TEST_SECRET_ENV_TOKEN", "from-env");
+
+        let settings = SecretsBuilder::default()
+            .dir(Some(dir.clone()))
+            .env_prefix("HOMESTAR_TEST_SECRET_".to_string())
+            .grants(vec![
+                SecretGrant::new("file-token", vec![ISSUER.to_string()]),
+                SecretGrant::new("env-token", vec![ANY_ISSUER.to_string()]),
+                SecretGrant::new("../file-token", vec![ISSUER.to_string()]),
+                SecretGrant::new("unset-token", vec![ISSUER.to_string()]),
+            ])
+            .build()
+            .unwrap();
+
+        let provider = SecretProvider::new(&settings, Some(ISSUER));
+        assert_eq!(provider.get("file-token"), Ok(Secret::new("from-file")));
+        assert_eq!(provider.get("env-token"), Ok(Secret::new("from-env")));
+        assert_eq!(
+            provider.get("../file-token"),
+            Err(SecretError::Denied("../file-token".to_string()))
+        );
+        assert_eq!(
+            provider.get("other"),
+            Err(SecretError::Denied("other".to_string()))
+        );
+        assert_eq!(
+            provider.get("unset-token"),
+            Err(SecretError::NotFound("unset-token".to_string()))
+        );
+
+        let unverified = SecretProvider::new(&settings, None);
+        assert_eq!(
+            unverified.get("file-token"),
+            Err(SecretError::Denied("file-token".to_string()))
+        );
+        assert_eq!(unverified.get("env-token"), Ok(Secret::new("from-env")));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
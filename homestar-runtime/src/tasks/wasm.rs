@@ -2,13 +2,31 @@
 //!
 //! [tasks]: homestar_invocation::Task
 
-use super::FileLoad;
-use homestar_invocation::task::instruction::Args;
+use super::{Executor, ExecutorInput, FileLoad, Usage};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use homestar_invocation::{
+    consts,
+    task::{self, instruction::Args, Resources},
+};
 use homestar_wasm::{
     io::{Arg, Output},
-    wasmtime::{world::Env, Error as WasmRuntimeError, State, World},
+    wasmtime::{
+        blocks::BlockStore,
+        kv::KvStore,
+        limits::StoreLimitsAsync,
+        logs::{LogRecord, LogSink, MAX_LOG_SIZE},
+        secrets::SecretStore,
+        world::Env,
+        ComponentCache, Determinism, Error as WasmRuntimeError, HttpPolicy, Interruption,
+        LimitError, SandboxError, SandboxPolicy, State, World,
+    },
 };
-use tracing::Instrument;
+use indexmap::IndexMap;
+use libipld::{Cid, Ipld};
+use std::sync::Arc;
+use tokio::time;
+use tracing::{debug_span, Instrument};
 
 #[allow(dead_code)]
 #[allow(missing_debug_implementations)]
@@ -24,6 +42,16 @@ impl WasmContext {
         })
     }
 
+    /// Create a [WasmContext] compiling modules via a [ComponentCache].
+    pub(crate) fn with_cache(
+        data: State,
+        cache: ComponentCache<State>,
+    ) -> Result<Self, WasmRuntimeError> {
+        Ok(Self {
+            env: World::with_cache(data, cache)?,
+        })
+    }
+
     /// Instantiate environment via [World] and execute on [Args].
     #[allow(dead_code)]
     pub(crate) async fn run<'a>(
@@ -32,17 +60,332 @@ impl WasmContext {
         fun_name: &'a str,
         args: Args<Arg>,
     ) -> Result<Output, WasmRuntimeError> {
-        let env = World::instantiate_with_current_env(bytes, fun_name, &mut self.env).await?;
-        env.execute(args).in_current_span().await
+        let output = match World::instantiate_with_current_env(bytes, fun_name, &mut self.env).await
+        {
+            Ok(env) => env.execute(args).in_current_span().await,
+            Err(err) => Err(err),
+        };
+        self.env.capture_output();
+
+        output.map_err(|err| self.env.limit_error(err))
+    }
+
+    /// Name of a secret read so far whose value is contained in an output.
+    pub(crate) fn leaked_secret(&self, output: &Ipld) -> Option<String> {
+        self.env.leaked_secret(output)
+    }
+
+    /// [Usage] of the environment's fuel and memory so far, with its
+    /// [WasmUsage] attached.
+    pub(crate) fn usage(&self) -> Usage {
+        Usage::new(
+            self.env
+                .interruption()
+                .fuel()
+                .then(|| self.env.fuel_consumed()),
+            Some(self.env.memory_consumed()),
+        )
+        .with_extension(WasmUsage {
+            http_requests: self.env.http_requests().to_vec(),
+            blocks: self.env.written_blocks().to_owned(),
+            kv_ops: self.env.kv_ops().iter().map(ToString::to_string).collect(),
+            secrets: self.env.secrets_accessed(),
+            deterministic: self.env.determinism().is_some(),
+            logs: self.env.logs().to_vec(),
+            dropped_logs: self.env.dropped_logs(),
+        })
+    }
+}
+
+/// Side effects made by a Wasm task through its host interfaces, attached
+/// to its [Usage] and recorded with its [Receipt].
+///
+/// [Receipt]: crate::Receipt
+#[derive(Debug, Clone, Default)]
+pub(crate) struct WasmUsage {
+    http_requests: Vec<String>,
+    blocks: IndexMap<Cid, Vec<u8>>,
+    kv_ops: Vec<String>,
+    secrets: Vec<String>,
+    deterministic: bool,
+    logs: Vec<LogRecord>,
+    dropped_logs: usize,
+}
+
+impl WasmUsage {
+    /// Record the log records captured, and how many were dropped past the
+    /// maximum size.
+    #[cfg(test)]
+    pub(crate) fn with_logs(mut self, logs: Vec<LogRecord>, dropped: usize) -> Self {
+        self.logs = logs;
+        self.dropped_logs = dropped;
+        self
+    }
+
+    /// Outbound HTTP requests made, as `METHOD URL`.
+    pub(crate) fn http_requests(&self) -> &[String] {
+        &self.http_requests
+    }
+
+    /// IPLD blocks written, by [Cid], to be pinned with the [Receipt].
+    ///
+    /// [Receipt]: crate::Receipt
+    pub(crate) fn blocks(&self) -> &IndexMap<Cid, Vec<u8>> {
+        &self.blocks
+    }
+
+    /// Operations on the key-value store, e.g. `get key`.
+    pub(crate) fn kv_ops(&self) -> &[String] {
+        &self.kv_ops
+    }
+
+    /// Names of the secrets read, never their values.
+    pub(crate) fn secrets(&self) -> &[String] {
+        &self.secrets
+    }
+
+    /// Whether execution was deterministic.
+    pub(crate) fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Log records captured.
+    pub(crate) fn logs(&self) -> &[LogRecord] {
+        &self.logs
+    }
+
+    /// Number of log records dropped past the maximum size.
+    pub(crate) fn dropped_logs(&self) -> usize {
+        self.dropped_logs
     }
 }
 
 impl FileLoad for WasmContext {}
 
+/// Host interfaces and limits of a Wasm task, handed over to the
+/// [WasmExecutor] as the context of its [ExecutorInput].
+#[derive(Debug, Clone)]
+pub(crate) struct WasmHost {
+    max_table_elements: u32,
+    sandbox: SandboxPolicy,
+    http: HttpPolicy,
+    blocks: Option<Arc<dyn BlockStore>>,
+    kv: Option<Arc<dyn KvStore>>,
+    secret_names: Vec<String>,
+    secrets: Option<Arc<dyn SecretStore>>,
+    module_cache: Option<ComponentCache<State>>,
+    determinism: Option<Determinism>,
+    interruption: Interruption,
+    max_log_size: usize,
+    log_sink: Option<Arc<dyn LogSink>>,
+}
+
+impl Default for WasmHost {
+    fn default() -> Self {
+        Self::new(10_000, SandboxPolicy::default())
+    }
+}
+
+impl WasmHost {
+    /// Create a new [WasmHost] limited to `max_table_elements`, granting
+    /// the WASI interfaces of a [SandboxPolicy].
+    pub(crate) fn new(max_table_elements: u32, sandbox: SandboxPolicy) -> Self {
+        Self {
+            max_table_elements,
+            sandbox,
+            http: HttpPolicy::default(),
+            blocks: None,
+            kv: None,
+            secret_names: vec![],
+            secrets: None,
+            module_cache: None,
+            determinism: None,
+            interruption: Interruption::default(),
+            max_log_size: MAX_LOG_SIZE,
+            log_sink: None,
+        }
+    }
+
+    /// Set the [HttpPolicy] of outbound HTTP requests the task may make.
+    pub(crate) fn with_http(mut self, http: HttpPolicy) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Set the [BlockStore] IPLD blocks read by the task are resolved from.
+    pub(crate) fn with_blocks(mut self, blocks: Arc<dyn BlockStore>) -> Self {
+        self.blocks = Some(blocks);
+        self
+    }
+
+    /// Set the [KvStore] the task reads and writes keys in.
+    pub(crate) fn with_kv(mut self, kv: Arc<dyn KvStore>) -> Self {
+        self.kv = Some(kv);
+        self
+    }
+
+    /// Set the [SecretStore] the task reads secrets from, and the names of
+    /// the secrets it requested in its metadata.
+    pub(crate) fn with_secrets(
+        mut self,
+        names: Vec<String>,
+        secrets: Arc<dyn SecretStore>,
+    ) -> Self {
+        self.secret_names = names;
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Set the [ComponentCache] Wasm modules are compiled via.
+    pub(crate) fn with_module_cache(mut self, module_cache: ComponentCache<State>) -> Self {
+        self.module_cache = Some(module_cache);
+        self
+    }
+
+    /// Execute the task deterministically.
+    pub(crate) fn with_determinism(mut self, determinism: Determinism) -> Self {
+        self.determinism = Some(determinism);
+        self
+    }
+
+    /// Set how the task is interrupted, unless set by the [ComponentCache].
+    pub(crate) fn with_interruption(mut self, interruption: Interruption) -> Self {
+        self.interruption = interruption;
+        self
+    }
+
+    /// Set the maximum size, in bytes, of the log records captured from the
+    /// task.
+    pub(crate) fn with_max_log_size(mut self, max_log_size: usize) -> Self {
+        self.max_log_size = max_log_size;
+        self
+    }
+
+    /// Set the [LogSink] log records captured from the task are streamed
+    /// to.
+    #[allow(dead_code)]
+    pub(crate) fn with_log_sink(mut self, log_sink: Arc<dyn LogSink>) -> Self {
+        self.log_sink = Some(log_sink);
+        self
+    }
+
+    /// [State] of a task limited to `resources`, with its host interfaces.
+    fn state(self, resources: &Resources) -> Result<State> {
+        let limits = StoreLimitsAsync::new(
+            Some(resources.memory().unwrap_or(consts::WASM_MAX_MEMORY) as usize),
+            Some(self.max_table_elements),
+        );
+        let mut state = State::new(u64::MAX, limits);
+        if let Some(fuel) = resources.fuel() {
+            state.set_fuel(fuel);
+        }
+        state.set_interruption(self.interruption);
+        state.set_max_log_size(self.max_log_size);
+        if let Some(log_sink) = self.log_sink {
+            state.set_log_sink(log_sink);
+        }
+        if let Some(time) = resources.time() {
+            state.set_time_limit(time);
+        }
+        state.set_http(self.http);
+        if let Some(blocks) = self.blocks {
+            state.set_blocks(blocks);
+        }
+        if let Some(kv) = self.kv {
+            state.set_kv(kv);
+        }
+        if let Some(secrets) = self.secrets {
+            state.set_secrets(self.secret_names, secrets);
+        }
+        state
+            .set_sandbox(self.sandbox)
+            .and_then(|_| match self.determinism {
+                Some(determinism) => state.set_determinism(determinism),
+                None => Ok(()),
+            })
+            .map_err(|err| anyhow!("cannot execute wasm module: {:#}", err))?;
+
+        Ok(state)
+    }
+}
+
+/// [Executor] for the basic `wasm/run` task-type.
+///
+/// Tasks are run with the [WasmHost] set as the context of their
+/// [ExecutorInput], if any, or with a default one.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct WasmExecutor;
+
+#[async_trait]
+impl Executor for WasmExecutor {
+    fn fetch_resource(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, input: ExecutorInput) -> Result<task::Result<Arg>> {
+        self.execute_metered(input).await.0
+    }
+
+    async fn execute_metered(&self, input: ExecutorInput) -> (Result<task::Result<Arg>>, Usage) {
+        let resources = input.resources().to_owned();
+        let host = input.context::<WasmHost>().cloned().unwrap_or_default();
+        let module_cache = host.module_cache.clone();
+        let (_, content, fun, args) = input.into_parts();
+        let Some(wasm) = content else {
+            return (Err(anyhow!("resource not available")), Usage::default());
+        };
+
+        let state = match host.state(&resources) {
+            Ok(state) => state,
+            Err(err) => return (Err(err), Usage::default()),
+        };
+        let wasm_ctx = match module_cache {
+            Some(cache) => WasmContext::with_cache(state, cache),
+            None => WasmContext::new(state),
+        };
+        let mut wasm_ctx = match wasm_ctx {
+            Ok(wasm_ctx) => wasm_ctx,
+            Err(err) => {
+                return (
+                    Err(anyhow!("cannot execute wasm module: {:#}", err)),
+                    Usage::default(),
+                )
+            }
+        };
+
+        let run = wasm_ctx
+            .run(wasm, &fun, args)
+            .instrument(debug_span!("wasm_run").or_current());
+
+        let output = match resources.time() {
+            Some(time) => time::timeout(time, run)
+                .await
+                .unwrap_or_else(|_| Err(LimitError::Timeout(time).into())),
+            None => run.await,
+        }
+        .map_err(|err| match err {
+            WasmRuntimeError::LimitExceeded(err) => anyhow!(err),
+            WasmRuntimeError::Sandbox(err) => anyhow!(err),
+            err => anyhow!("cannot execute wasm module: {:#}", err),
+        })
+        .and_then(|output| Ok(Ipld::try_from(output)?))
+        // Tasks may not return the secrets they read.
+        .and_then(|output| match wasm_ctx.leaked_secret(&output) {
+            Some(name) => Err(anyhow!(SandboxError::LeakedSecret(name))),
+            None => Ok(task::Result::Ok(Arg::Ipld(output))),
+        });
+
+        (output, wasm_ctx.usage())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use std::path::PathBuf;
+    use homestar_invocation::task::{instruction::Input, Resources};
+    use homestar_wasm::wasmtime::{Interruption, SandboxPolicy};
+    use std::{path::PathBuf, time::Duration};
+    use url::Url;
 
     fn fixtures(file: &str) -> PathBuf {
         PathBuf::from(format!(
@@ -59,4 +402,228 @@ mod test {
 
         assert!(!wat.is_empty());
     }
+
+    #[tokio::test]
+    async fn execute_with_task_resources() {
+        let wat = WasmContext::load(fixtures("example_add_component.wat"))
+            .await
+            .unwrap();
+        let input = |resources| {
+            ExecutorInput::new(
+                Url::parse("ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu")
+                    .unwrap(),
+                Some(wat.clone()),
+                "add_two".to_string(),
+                Args::new(vec![Input::Ipld(Ipld::Integer(1))]),
+                resources,
+            )
+        };
+
+        let output = WasmExecutor
+            .execute(input(Resources::default()))
+            .await
+            .unwrap();
+        assert_eq!(output, task::Result::Ok(Arg::Ipld(Ipld::Integer(3))));
+
+        let err = WasmExecutor
+            .execute(input(Resources::new(
+                1,
+                consts::WASM_MAX_MEMORY,
+                Duration::from_secs(10),
+            )))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<LimitError>(),
+            Some(&LimitError::OutOfFuel(1))
+        );
+    }
+
+    #[tokio::test]
+    async fn execute_metered_reports_usage() {
+        let wat = WasmContext::load(fixtures("example_add_component.wat"))
+            .await
+            .unwrap();
+        let input = |resources| {
+            ExecutorInput::new(
+                Url::parse("ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu")
+                    .unwrap(),
+                Some(wat.clone()),
+                "add_two".to_string(),
+                Args::new(vec![Input::Ipld(Ipld::Integer(1))]),
+                resources,
+            )
+        };
+
+        let (output, usage) = WasmExecutor
+            .execute_metered(input(Resources::default()))
+            .await;
+        assert_eq!(
+            output.unwrap(),
+            task::Result::Ok(Arg::Ipld(Ipld::Integer(3)))
+        );
+        assert!(usage.fuel().is_some_and(|fuel| fuel > 0));
+        assert!(usage.memory().is_some_and(|memory| memory > 0));
+
+        let (output, usage) = WasmExecutor
+            .execute_metered(input(Resources::new(
+                1,
+                consts::WASM_MAX_MEMORY,
+                Duration::from_secs(10),
+            )))
+            .await;
+        assert!(output.is_err());
+        assert_eq!(usage.fuel(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn execute_with_epoch_interruption() {
+        let wat = WasmContext::load(fixtures("example_add_component.wat"))
+            .await
+            .unwrap();
+        let input = ExecutorInput::new(
+            Url::parse("ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu")
+                .unwrap(),
+            Some(wat),
+            "add_two".to_string(),
+            Args::new(vec![Input::Ipld(Ipld::Integer(1))]),
+            Resources::new(1, consts::WASM_MAX_MEMORY, Duration::from_secs(10)),
+        )
+        .with_context(WasmHost::default().with_interruption(Interruption::Epoch));
+
+        // Fuel isn't metered, so neither limited nor reported.
+        let (output, usage) = WasmExecutor.execute_metered(input).await;
+        assert_eq!(
+            output.unwrap(),
+            task::Result::Ok(Arg::Ipld(Ipld::Integer(3)))
+        );
+        assert_eq!(usage.fuel(), None);
+    }
+
+    #[tokio::test]
+    async fn execute_with_module_cache() {
+        let wat = WasmContext::load(fixtures("example_add_component.wat"))
+            .await
+            .unwrap();
+        let dir = std::env::temp_dir().join(format!("homestar-modules-{}", std::process::id()));
+        let cache = ComponentCache::new(Some(dir.clone()), u64::MAX, Interruption::Fuel).unwrap();
+        let input = |resources| {
+            ExecutorInput::new(
+                Url::parse("ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu")
+                    .unwrap(),
+                Some(wat.clone()),
+                "add_two".to_string(),
+                Args::new(vec![Input::Ipld(Ipld::Integer(1))]),
+                resources,
+            )
+            .with_context(WasmHost::default().with_module_cache(cache.clone()))
+        };
+
+        for _ in 0..2 {
+            let output = WasmExecutor
+                .execute(input(Resources::default()))
+                .await
+                .unwrap();
+            assert_eq!(output, task::Result::Ok(Arg::Ipld(Ipld::Integer(3))));
+        }
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let err = WasmExecutor
+            .execute(input(Resources::new(
+                1,
+                consts::WASM_MAX_MEMORY,
+                Duration::from_secs(10),
+            )))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<LimitError>(),
+            Some(&LimitError::OutOfFuel(1))
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn execute_with_sandbox_policy() {
+        let wat = WasmContext::load(fixtures("example_wasi_random_component.wat"))
+            .await
+            .unwrap();
+        let input = |sandbox| {
+            ExecutorInput::new(
+                Url::parse("ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu")
+                    .unwrap(),
+                Some(wat.clone()),
+                "random".to_string(),
+                Args::new(vec![]),
+                Resources::default(),
+            )
+            .with_context(WasmHost::new(10_000, sandbox))
+        };
+
+        let err = WasmExecutor
+            .execute(input(SandboxPolicy::default()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<SandboxError>(),
+            Some(SandboxError::Denied { .. })
+        ));
+
+        let seeded = SandboxPolicy::enabled().with_random(true, Some(42));
+        let output = WasmExecutor.execute(input(seeded.clone())).await.unwrap();
+        assert_eq!(output, WasmExecutor.execute(input(seeded)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reject_outputs_leaking_secrets() {
+        use homestar_wasm::wasmtime::secrets::{Secret, SecretError};
+
+        #[derive(Debug)]
+        struct Token(Option<&'static str>);
+
+        impl SecretStore for Token {
+            fn get(&self, name: &str) -> Result<Secret, SecretError> {
+                self.0
+                    .map(Secret::new)
+                    .ok_or_else(|| SecretError::Denied(name.to_string()))
+            }
+        }
+
+        let wat = WasmContext::load(fixtures("example_secrets_component.wat"))
+            .await
+            .unwrap();
+        let input = |token| {
+            ExecutorInput::new(
+                Url::parse("ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu")
+                    .unwrap(),
+                Some(wat.clone()),
+                "leak".to_string(),
+                Args::new(vec![]),
+                Resources::default(),
+            )
+            .with_context(
+                WasmHost::default().with_secrets(vec!["token".to_string()], Arc::new(Token(token))),
+            )
+        };
+
+        let (output, usage) = WasmExecutor.execute_metered(input(Some("hunter2"))).await;
+        let err = output.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<SandboxError>(),
+            Some(SandboxError::LeakedSecret(name)) if name == "token"
+        ));
+        assert!(!err.to_string().contains("hunter2"));
+        assert_eq!(
+            usage.extension::<WasmUsage>().unwrap().secrets(),
+            ["token".to_string()]
+        );
+
+        // Secrets that aren't read aren't checked against.
+        let output = WasmExecutor.execute(input(None)).await.unwrap();
+        assert_eq!(
+            output,
+            task::Result::Ok(Arg::Ipld(Ipld::String("token".to_string())))
+        );
+    }
 }
@@ -4,6 +4,7 @@ use super::{db::MemoryDb, event};
 #[cfg(feature = "ipfs")]
 use crate::network::IpfsCli;
 use crate::{
+    authorization::{NodeKey, ProofVerifier},
     channel::AsyncChannelSender,
     db::Database,
     event_handler::Event,
@@ -11,7 +12,7 @@ use crate::{
     tasks::Fetch,
     worker::WorkerMessage,
     workflow::{self, Resource},
-    Settings, Worker,
+    ExecutorRegistry, Settings, Worker,
 };
 use fnv::FnvHashSet;
 use futures::{future::BoxFuture, FutureExt};
@@ -50,6 +51,18 @@ pub(crate) struct WorkerBuilder<'a> {
     workflow_settings: workflow::Settings,
     /// Network settings.
     network_settings: settings::Dht,
+    /// Node-wide task resource limits.
+    task_limits: settings::TaskLimits,
+    /// Node-wide task execution settings.
+    task_execution: settings::TaskExecution,
+    /// Node-wide task host interface settings.
+    task_host: settings::TaskHost,
+    /// Verifier of task UCAN proofs.
+    verifier: ProofVerifier,
+    /// Registry of task executors.
+    executors: ExecutorRegistry,
+    /// Key to sign receipts with.
+    node_key: Option<NodeKey>,
 }
 
 /// Utility structure for building out [Worker]s for testing purposes.
@@ -71,6 +84,18 @@ pub(crate) struct WorkerBuilder<'a> {
     workflow_settings: workflow::Settings,
     /// Network settings.
     network_settings: settings::Dht,
+    /// Node-wide task resource limits.
+    task_limits: settings::TaskLimits,
+    /// Node-wide task execution settings.
+    task_execution: settings::TaskExecution,
+    /// Node-wide task host interface settings.
+    task_host: settings::TaskHost,
+    /// Verifier of task UCAN proofs.
+    verifier: ProofVerifier,
+    /// Registry of task executors.
+    executors: ExecutorRegistry,
+    /// Key to sign receipts with.
+    node_key: Option<NodeKey>,
 }
 
 impl<'a> WorkerBuilder<'a> {
@@ -108,6 +133,12 @@ impl<'a> WorkerBuilder<'a> {
             workflow,
             workflow_settings: workflow::Settings::default(),
             network_settings: settings.network.libp2p.dht,
+            task_limits: settings.task_limits,
+            task_execution: settings.task_execution,
+            task_host: settings.task_host,
+            verifier: ProofVerifier::default(),
+            executors: ExecutorRegistry::default(),
+            node_key: None,
         }
     }
 
@@ -118,13 +149,19 @@ impl<'a> WorkerBuilder<'a> {
             self.workflow,
             self.workflow_settings,
             self.network_settings,
+            self.task_limits,
+            &self.verifier,
             self.name,
             self.event_sender.into(),
             self.runner_sender,
             self.db,
+            self.executors.into(),
+            self.node_key,
         )
         .await
         .unwrap()
+        .with_task_execution(self.task_execution)
+        .with_task_host(self.task_host)
     }
 
     /// Fetch-function closure for the [Worker]/[Scheduler] to use.
@@ -207,6 +244,42 @@ impl<'a> WorkerBuilder<'a> {
         self
     }
 
+    /// Build a [Worker] with a specific [ExecutorRegistry].
+    #[allow(dead_code)]
+    pub(crate) fn with_executors(mut self, executors: ExecutorRegistry) -> Self {
+        self.executors = executors;
+        self
+    }
+
+    /// Build a [Worker] with specific node-wide [settings::TaskLimits].
+    #[allow(dead_code)]
+    pub(crate) fn with_task_limits(mut self, task_limits: settings::TaskLimits) -> Self {
+        self.task_limits = task_limits;
+        self
+    }
+
+    /// Build a [Worker] with a specific [ProofVerifier].
+    #[allow(dead_code)]
+    pub(crate) fn with_verifier(mut self, verifier: ProofVerifier) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Build a [Worker] signing receipts with a [NodeKey].
+    #[allow(dead_code)]
+    pub(crate) fn with_node_key(mut self, node_key: NodeKey) -> Self {
+        self.node_key = Some(node_key);
+        self
+    }
+
+    /// Build a [Worker] over a specific [MemoryDb], e.g. shared with a
+    /// previous run of the same workflow.
+    #[allow(dead_code)]
+    pub(crate) fn with_db(mut self, db: MemoryDb) -> Self {
+        self.db = db;
+        self
+    }
+
     /// Build a [Worker] with a specific [workflow::Settings].
     #[allow(dead_code)]
     pub(crate) fn with_workflow_settings(mut self, workflow_settings: workflow::Settings) -> Self {
@@ -0,0 +1,335 @@
+//! TLS termination for the webserver and metrics endpoints, with
+//! certificates reloaded when their files change on disk.
+
+use crate::settings;
+use anyhow::{anyhow, Context, Result};
+use rustls::{
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    RootCertStore, ServerConfig,
+};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::Path,
+    sync::{Arc, PoisonError, RwLock, Weak},
+    time::{Duration, SystemTime},
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::server::TlsStream;
+use tokio_util::either::Either;
+use tracing::{info, warn};
+
+/// Delay before accepting connections again after a listener error.
+const ACCEPT_ERROR_DELAY: Duration = Duration::from_secs(1);
+
+/// Stream that's TLS-terminated if TLS is configured, plaintext otherwise.
+pub(crate) type MaybeTlsStream<IO> = Either<TlsStream<IO>, IO>;
+
+/// Acceptor of TLS connections, whose [ServerConfig] is reloaded when the
+/// configured certificate, key, or client CA files change.
+///
+/// Handshakes in progress and open connections keep the configuration they
+/// started with.
+#[derive(Clone)]
+#[allow(missing_debug_implementations)]
+pub(crate) struct TlsAcceptor {
+    config: Arc<RwLock<Arc<ServerConfig>>>,
+    handshake_timeout: Duration,
+}
+
+impl TlsAcceptor {
+    /// Load the TLS configuration and spawn a task reloading it on change,
+    /// which stops once every clone of the acceptor is dropped.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub(crate) fn start(settings: &settings::Tls) -> Result<Self> {
+        let config = Arc::new(RwLock::new(Arc::new(server_config(settings)?)));
+        tokio::spawn(reload_on_change(settings.clone(), Arc::downgrade(&config)));
+        Ok(Self {
+            config,
+            handshake_timeout: settings.handshake_timeout,
+        })
+    }
+
+    /// Perform a TLS handshake on an accepted stream, failing if the client
+    /// doesn't complete it within the configured timeout.
+    pub(crate) async fn accept<IO>(&self, stream: IO) -> io::Result<TlsStream<IO>>
+    where
+        IO: AsyncRead + AsyncWrite + Unpin,
+    {
+        let config = Arc::clone(&self.config.read().unwrap_or_else(PoisonError::into_inner));
+        tokio::time::timeout(
+            self.handshake_timeout,
+            tokio_rustls::TlsAcceptor::from(config).accept(stream),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "TLS handshake timed out",
+            ))
+        })
+    }
+}
+
+/// Perform a TLS handshake on an accepted stream if an acceptor is given,
+/// passing the stream through as plaintext otherwise.
+pub(crate) async fn accept<IO>(
+    acceptor: Option<&TlsAcceptor>,
+    stream: IO,
+) -> io::Result<MaybeTlsStream<IO>>
+where
+    IO: AsyncRead + AsyncWrite + Unpin,
+{
+    match acceptor {
+        Some(acceptor) => acceptor.accept(stream).await.map(Either::Left),
+        None => Ok(Either::Right(stream)),
+    }
+}
+
+/// Back off after a listener fails to accept a connection, unless only that
+/// connection failed, rather than spinning on errors like running out of
+/// file descriptors.
+pub(crate) async fn accept_backoff(err: &io::Error) {
+    if matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    ) {
+        return;
+    }
+
+    warn!(
+        subject = "tls.accept.err",
+        category = "tls",
+        err=?err,
+        "failed to accept connection, backing off"
+    );
+    tokio::time::sleep(ACCEPT_ERROR_DELAY).await;
+}
+
+async fn reload_on_change(settings: settings::Tls, config: Weak<RwLock<Arc<ServerConfig>>>) {
+    let mut modified = modified_times(&settings);
+    let mut interval = tokio::time::interval(settings.reload_interval);
+    // The first tick completes immediately.
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let Some(config) = config.upgrade() else {
+            break;
+        };
+
+        let current = modified_times(&settings);
+        if current == modified {
+            continue;
+        }
+        modified = current;
+
+        match server_config(&settings) {
+            Ok(server_config) => {
+                *config.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(server_config);
+                info!(
+                    subject = "tls.reload",
+                    category = "tls",
+                    cert = %settings.cert_path.display(),
+                    "reloaded TLS certificates"
+                );
+            }
+            Err(err) => warn!(
+                subject = "tls.reload.err",
+                category = "tls",
+                err=?err,
+                cert = %settings.cert_path.display(),
+                "failed to reload TLS certificates, keeping previous ones"
+            ),
+        }
+    }
+}
+
+fn modified_times(settings: &settings::Tls) -> Vec<Option<SystemTime>> {
+    [
+        Some(&settings.cert_path),
+        Some(&settings.key_path),
+        settings.client_ca_path.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+    .collect()
+}
+
+fn server_config(settings: &settings::Tls) -> Result<ServerConfig> {
+    let builder = ServerConfig::builder();
+    let builder = if let Some(ca_path) = &settings.client_ca_path {
+        let mut roots = RootCertStore::empty();
+        for cert in certs(ca_path)? {
+            roots.add(cert)?;
+        }
+        builder.with_client_cert_verifier(WebPkiClientVerifier::builder(Arc::new(roots)).build()?)
+    } else {
+        builder.with_no_client_auth()
+    };
+
+    Ok(builder.with_single_cert(
+        certs(&settings.cert_path)?,
+        private_key(&settings.key_path)?,
+    )?)
+}
+
+fn certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = rustls_pemfile::certs(&mut reader(path)?).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        Err(anyhow!("no certificates found in {}", path.display()))
+    } else {
+        Ok(certs)
+    }
+}
+
+fn private_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    rustls_pemfile::private_key(&mut reader(path)?)?
+        .ok_or_else(|| anyhow!("no private key found in {}", path.display()))
+}
+
+fn reader(path: &Path) -> Result<BufReader<File>> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    Ok(BufReader::new(file))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rustls::{pki_types::ServerName, ClientConfig};
+    use std::{path::PathBuf, time::Duration};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::TlsConnector;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/tls")
+            .join(name)
+    }
+
+    fn client_config(with_client_cert: bool) -> Arc<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        for cert in certs(&fixture("ca.pem")).unwrap() {
+            roots.add(cert).unwrap();
+        }
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+        let config = if with_client_cert {
+            builder
+                .with_client_auth_cert(
+                    certs(&fixture("client.pem")).unwrap(),
+                    private_key(&fixture("client.key")).unwrap(),
+                )
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+        Arc::new(config)
+    }
+
+    /// Connect to the acceptor, returning the certificate it presented.
+    async fn connect(
+        acceptor: &TlsAcceptor,
+        client_config: Arc<ClientConfig>,
+    ) -> io::Result<CertificateDer<'static>> {
+        let (client, server) = tokio::io::duplex(16 * 1024);
+        let acceptor = acceptor.clone();
+        let server = tokio::spawn(async move {
+            let mut stream = acceptor.accept(server).await?;
+            stream.write_all(b"pong").await?;
+            stream.shutdown().await
+        });
+
+        let server_name = ServerName::try_from("localhost").unwrap();
+        let mut stream = TlsConnector::from(client_config)
+            .connect(server_name, client)
+            .await?;
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await?;
+        server.await.unwrap()?;
+        assert_eq!(buf, b"pong");
+
+        Ok(stream.get_ref().1.peer_certificates().unwrap()[0]
+            .clone()
+            .into_owned())
+    }
+
+    #[tokio::test]
+    async fn reload_certificates_on_change() {
+        let dir = std::env::temp_dir().join(format!("homestar-tls-{}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(fixture("server.pem"), dir.join("cert.pem")).unwrap();
+        fs::copy(fixture("server.key"), dir.join("key.pem")).unwrap();
+
+        let settings = settings::TlsBuilder::default()
+            .cert_path(dir.join("cert.pem"))
+            .key_path(dir.join("key.pem"))
+            .reload_interval(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let acceptor = TlsAcceptor::start(&settings).unwrap();
+
+        let cert = connect(&acceptor, client_config(false)).await.unwrap();
+        assert_eq!(cert, certs(&fixture("server.pem")).unwrap()[0]);
+
+        fs::copy(fixture("server_2.key"), dir.join("key.pem")).unwrap();
+        fs::copy(fixture("server_2.pem"), dir.join("cert.pem")).unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let cert = connect(&acceptor, client_config(false)).await.unwrap();
+        assert_eq!(cert, certs(&fixture("server_2.pem")).unwrap()[0]);
+
+        // Unreadable files keep the previous certificates.
+        fs::write(dir.join("cert.pem"), "not a certificate").unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let cert = connect(&acceptor, client_config(false)).await.unwrap();
+        assert_eq!(cert, certs(&fixture("server_2.pem")).unwrap()[0]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn verify_client_certificates() {
+        let settings = settings::TlsBuilder::default()
+            .cert_path(fixture("server.pem"))
+            .key_path(fixture("server.key"))
+            .client_ca_path(Some(fixture("ca.pem")))
+            .build()
+            .unwrap();
+        let acceptor = TlsAcceptor::start(&settings).unwrap();
+
+        assert!(connect(&acceptor, client_config(true)).await.is_ok());
+        assert!(connect(&acceptor, client_config(false)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn time_out_stalled_handshakes() {
+        let settings = settings::TlsBuilder::default()
+            .cert_path(fixture("server.pem"))
+            .key_path(fixture("server.key"))
+            .handshake_timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let acceptor = TlsAcceptor::start(&settings).unwrap();
+
+        // The client never sends a ClientHello.
+        let (_client, server) = tokio::io::duplex(16 * 1024);
+        let err = acceptor.accept(server).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn fail_on_missing_files() {
+        let settings = settings::TlsBuilder::default()
+            .cert_path(fixture("missing.pem"))
+            .key_path(fixture("server.key"))
+            .build()
+            .unwrap();
+        assert!(TlsAcceptor::start(&settings).is_err());
+    }
+}
@@ -4,39 +4,75 @@
 //! [Workflow]: homestar_workflow::Workflow
 //! [EventHandler]: crate::EventHandler
 
-#[cfg(feature = "websocket-notify")]
-use crate::event_handler::event::Replay;
+#[cfg(feature = "ipfs")]
+use crate::network::IpfsCli;
 use crate::{
+    authorization::{sign_receipt, Issuers, NodeKey, ProofVerifier},
     channel::AsyncChannelSender,
     db::Database,
     event_handler::{event::Captured, Event},
-    receipt::metadata::{REPLAYED_KEY, WORKFLOW_KEY, WORKFLOW_NAME_KEY},
+    receipt::metadata::{
+        BLOCKS_KEY, DETERMINISTIC_KEY, FUEL_CONSUMED_KEY, HTTP_REQUESTS_KEY, KV_KEY, LOGS_KEY,
+        MODULE_KEY, PEAK_MEMORY_KEY, REPLAYED_KEY, SECRETS_KEY, WALL_TIME_KEY, WORKFLOW_KEY,
+        WORKFLOW_NAME_KEY,
+    },
     runner::{ModifiedSet, RunningTaskSet},
     scheduler::ExecutionGraph,
-    settings,
-    tasks::{RegisteredTasks, WasmContext},
+    settings::{self, KvScope},
+    tasks::{
+        BlockResolver, ExecutorInput, ExecutorRegistry, KvResolver, SecretProvider, Usage,
+        WasmHost, WasmUsage, WASM_OP,
+    },
     workflow::{self, Resource},
     Db, Receipt, TaskScheduler,
 };
+#[cfg(feature = "websocket-notify")]
+use crate::{
+    event_handler::event::Replay,
+    network::webserver::notifier::{self, Notifier},
+    tasks::LogStreamer,
+};
 use anyhow::{anyhow, Context, Result};
 use chrono::NaiveDateTime;
 use faststr::FastStr;
 use fnv::FnvHashSet;
 use futures::{future::BoxFuture, FutureExt};
 use homestar_invocation::{
-    authority::UcanPrf, ipld::DagCbor, receipt::metadata::OP_KEY, task, Pointer,
-    Receipt as InvocationReceipt,
+    authority::UcanPrf,
+    ipld::DagCbor,
+    pointer::{Await, AwaitResult},
+    receipt::metadata::OP_KEY,
+    task::{
+        self,
+        instruction::{Args, Input},
+        OnError,
+    },
+    Pointer, Receipt as InvocationReceipt,
 };
 use homestar_wasm::{
-    io::{Arg, Output},
-    wasmtime::State,
+    io::Arg,
+    wasmtime::{
+        blocks::{block_cid, DAG_CBOR},
+        secrets::SecretStore,
+        ComponentCache, Determinism, HttpPolicy, SandboxPolicy, State,
+    },
 };
 use homestar_workflow::Workflow;
 use indexmap::IndexMap;
-use libipld::{Cid, Ipld};
-use std::{collections::BTreeMap, sync::Arc};
+use libipld::{
+    cbor::DagCborCodec,
+    multihash::{Code, MultihashDigest},
+    prelude::Codec,
+    Cid, Ipld,
+};
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tokio::task::JoinSet;
-use tracing::{debug, debug_span, error, info, info_span, instrument, Instrument};
+use tracing::{debug, debug_span, error, info, info_span, instrument, warn, Instrument};
+use url::Url;
 
 mod poller;
 mod resolver;
@@ -47,7 +83,47 @@ use self::resolver::DHTResolver;
 
 /// [JoinSet] of tasks run by a [Worker].
 #[allow(dead_code)]
-pub(crate) type TaskSet = JoinSet<anyhow::Result<(Output, Pointer, Pointer, Ipld, Ipld)>>;
+pub(crate) type TaskSet = JoinSet<
+    anyhow::Result<(
+        Outcome,
+        Pointer,
+        Pointer,
+        Ipld,
+        Ipld,
+        IndexMap<Cid, Vec<u8>>,
+    )>,
+>;
+
+/// Outcome of a task run by a [Worker], captured in its receipt.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Outcome {
+    /// Task was executed, returning a result.
+    Executed(task::Result<Arg>),
+    /// Task failed during execution, handled via its [OnError] policy.
+    Failed(Arg, OnError),
+    /// Task was not executed, as the result of a task it awaits on did not
+    /// match the awaited branch, e.g. `await/ok` on a failed task.
+    Skipped(Arg),
+}
+
+impl Outcome {
+    /// Output of the task to be stored in its receipt, where failed and
+    /// skipped tasks have an `error` output.
+    fn output(&self) -> task::Result<Ipld> {
+        match self {
+            Outcome::Executed(task::Result::Ok(output)) => task::Result::Ok(output.clone().into()),
+            Outcome::Executed(task::Result::Error(output)) => {
+                task::Result::Error(output.clone().into())
+            }
+            Outcome::Executed(task::Result::Just(output)) => {
+                task::Result::Just(output.clone().into())
+            }
+            Outcome::Failed(output, _) | Outcome::Skipped(output) => {
+                task::Result::Error(output.clone().into())
+            }
+        }
+    }
+}
 
 /// Messages sent to [Worker] from [Runner].
 ///
@@ -82,8 +158,30 @@ pub(crate) struct Worker<'a, DB: Database> {
     pub(crate) workflow_settings: Arc<workflow::Settings>,
     /// Network settings.
     pub(crate) network_settings: Arc<settings::Dht>,
+    /// Node-wide maximums on the resources a task may request.
+    pub(crate) task_limits: Arc<settings::TaskLimits>,
+    /// How tasks are executed.
+    pub(crate) task_execution: Arc<settings::TaskExecution>,
+    /// Host interfaces tasks may use.
+    pub(crate) task_host: Arc<settings::TaskHost>,
     /// [NaiveDateTime] of when the [Workflow] was started.
     pub(crate) workflow_started: NaiveDateTime,
+    /// [ExecutorRegistry] to look up task executors by ability.
+    pub(crate) executors: Arc<ExecutorRegistry>,
+    /// [NodeKey] to sign receipts with, if any.
+    pub(crate) node_key: Option<NodeKey>,
+    /// [Issuers] of the tasks authorized by their proofs.
+    pub(crate) issuers: Arc<Issuers>,
+    /// [ComponentCache] of compiled Wasm modules shared with other workers,
+    /// if any.
+    pub(crate) module_cache: Option<ComponentCache<State>>,
+    /// [IpfsCli] to resolve IPLD blocks read by tasks with, if any.
+    #[cfg(feature = "ipfs")]
+    pub(crate) ipfs: Option<IpfsCli>,
+    /// [Notifier] to stream the log records of tasks to WebSocket
+    /// subscribers with, if any.
+    #[cfg(feature = "websocket-notify")]
+    pub(crate) log_notifier: Option<Notifier<notifier::Message>>,
 }
 
 impl<'a, DB> Worker<'a, DB>
@@ -94,15 +192,20 @@ where
     ///
     /// TODO: integrate settings within workflow
     #[allow(dead_code)]
+    #[allow(clippy::too_many_arguments)]
     pub(crate) async fn new<S: Into<FastStr>>(
         workflow: Workflow<'a, Arg>,
         settings: workflow::Settings,
         network_settings: settings::Dht,
+        task_limits: settings::TaskLimits,
+        verifier: &ProofVerifier,
         // Name would be runner specific, separated from core workflow spec.
         name: Option<S>,
         event_sender: Arc<AsyncChannelSender<Event>>,
         runner_sender: AsyncChannelSender<WorkerMessage>,
         db: DB,
+        executors: Arc<ExecutorRegistry>,
+        node_key: Option<NodeKey>,
     ) -> Result<Worker<'a, DB>> {
         let workflow_len = workflow.len();
         // Need to take ownership here to get the cid.
@@ -110,6 +213,8 @@ where
 
         let builder = workflow::Builder::new(workflow);
         let graph = builder.graph()?;
+        let issuers = verifier.verify_graph(&graph, &db).await?;
+
         let name = name
             .map(|n| n.into())
             .unwrap_or(FastStr::from_string(workflow_cid.to_string()));
@@ -135,9 +240,54 @@ where
             workflow_settings: settings.into(),
             workflow_started: timestamp,
             network_settings: network_settings.into(),
+            task_limits: task_limits.into(),
+            task_execution: Default::default(),
+            task_host: Default::default(),
+            executors,
+            node_key,
+            issuers: issuers.into(),
+            module_cache: None,
+            #[cfg(feature = "ipfs")]
+            ipfs: None,
+            #[cfg(feature = "websocket-notify")]
+            log_notifier: None,
         })
     }
 
+    /// Execute tasks as set by node-wide [settings::TaskExecution].
+    pub(crate) fn with_task_execution(mut self, task_execution: settings::TaskExecution) -> Self {
+        self.task_execution = task_execution.into();
+        self
+    }
+
+    /// Provide tasks with host interfaces as set by node-wide
+    /// [settings::TaskHost].
+    pub(crate) fn with_task_host(mut self, task_host: settings::TaskHost) -> Self {
+        self.task_host = task_host.into();
+        self
+    }
+
+    /// Compile Wasm modules via a [ComponentCache] shared with other
+    /// workers.
+    pub(crate) fn with_module_cache(mut self, module_cache: ComponentCache<State>) -> Self {
+        self.module_cache = Some(module_cache);
+        self
+    }
+
+    /// Resolve IPLD blocks read by tasks, and missing locally, from IPFS.
+    #[cfg(feature = "ipfs")]
+    pub(crate) fn with_ipfs(mut self, ipfs: IpfsCli) -> Self {
+        self.ipfs = Some(ipfs);
+        self
+    }
+
+    /// Stream the log records of tasks to WebSocket subscribers.
+    #[cfg(feature = "websocket-notify")]
+    pub(crate) fn with_log_notifier(mut self, notifier: Notifier<notifier::Message>) -> Self {
+        self.log_notifier = Some(notifier);
+        self
+    }
+
     /// Run [Worker]'s tasks in task-queue with access to the [Db] object
     /// to use connections from the Database pool per run.
     ///
@@ -162,6 +312,16 @@ where
     where
         F: FnOnce(FnvHashSet<Resource>) -> BoxFuture<'a, Result<IndexMap<Resource, Vec<u8>>>>,
     {
+        // Only fetch resources needed by executors, e.g. Wasm modules.
+        let fetchable = self.fetchable_resources();
+        let fetch_fn = move |rscs: FnvHashSet<Resource>| {
+            fetch_fn(
+                rscs.into_iter()
+                    .filter(|rsc| matches!(rsc, Resource::Cid(_)) || fetchable.contains(rsc))
+                    .collect(),
+            )
+        };
+
         match TaskScheduler::init(
             self.graph.clone(), // Arc'ed
             &mut self.db.conn()?,
@@ -241,11 +401,98 @@ where
                        category = "worker.run",
                        err=?err,
                        "error initializing scheduler");
+                // Mark as stuck, so the workflow can be resumed later on.
+                Db::set_workflow_status(
+                    self.workflow_info.cid,
+                    workflow::Status::Stuck,
+                    &mut self.db.conn()?,
+                )?;
                 Err(anyhow!("error initializing scheduler"))
             }
         }
     }
 
+    /// Resources of [Instruction]s whose [Executor]s need them fetched
+    /// before execution.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    /// [Executor]: crate::Executor
+    fn fetchable_resources(&self) -> FnvHashSet<Resource> {
+        self.graph
+            .schedule
+            .iter()
+            .flatten()
+            .filter_map(|node| {
+                let instruction = &node.inner().instruction;
+                self.executors
+                    .get(&instruction.op().to_string())
+                    .filter(|executor| executor.fetch_resource())
+                    .map(|_| Resource::Url(instruction.resource().to_owned()))
+            })
+            .collect()
+    }
+
+    /// Host interfaces and limits of a Wasm task, given its [Instruction]'s
+    /// [Cid] and requested sandbox policy, determinism, and secrets.
+    ///
+    /// [Instruction]: homestar_invocation::task::Instruction
+    fn wasm_host(
+        &self,
+        instruction_cid: Cid,
+        sandbox: Option<&SandboxPolicy>,
+        deterministic: Option<bool>,
+        secret_names: Vec<String>,
+    ) -> WasmHost {
+        let blocks = BlockResolver::new(self.db.clone());
+        #[cfg(feature = "ipfs")]
+        let blocks = blocks.with_ipfs(self.ipfs.clone());
+        // Scoped by the verified issuer of the task, if any.
+        let issuer = self.issuers.get(&instruction_cid);
+        let secrets: Arc<dyn SecretStore> = Arc::new(SecretProvider::new(
+            &self.task_host.secrets,
+            issuer.map(String::as_str),
+        ));
+
+        let mut host = WasmHost::new(
+            self.task_limits.max_table_elements,
+            self.task_execution.sandbox(sandbox),
+        )
+        .with_interruption(self.task_execution.interruption)
+        .with_max_log_size(self.task_host.logs.max_size)
+        .with_http(HttpPolicy::from(&self.task_host.http))
+        .with_blocks(Arc::new(blocks))
+        .with_secrets(secret_names, secrets);
+        // Tasks without a namespace, i.e. without a verified issuer when
+        // scoped by issuer, are denied the store.
+        if let Some(kv_namespace) =
+            kv_namespace(self.task_host.kv.scope, self.workflow_info.cid(), issuer)
+        {
+            host = host.with_kv(Arc::new(KvResolver::new(
+                self.db.clone(),
+                kv_namespace,
+                self.task_host.kv.quota,
+            )));
+        }
+        if let Some(module_cache) = self.module_cache.clone() {
+            host = host.with_module_cache(module_cache);
+        }
+        // Seeded by the instruction, so that deterministic tasks produce the
+        // same receipt wherever they run.
+        if self.task_execution.deterministic(deterministic) {
+            host = host.with_determinism(Determinism::from_cid(&instruction_cid));
+        }
+        #[cfg(feature = "websocket-notify")]
+        if let Some(notifier) = self.log_notifier.clone() {
+            host = host.with_log_sink(Arc::new(LogStreamer::new(
+                notifier,
+                self.workflow_info.cid(),
+                instruction_cid,
+            )));
+        }
+
+        host
+    }
+
     #[allow(unused_mut)]
     #[instrument(skip_all)]
     async fn run_queue(
@@ -309,6 +556,10 @@ where
                 let fun = parsed.fun().ok_or_else(|| anyhow!("no function defined"))?;
 
                 let args = parsed.into_args();
+                let awaited_branches = awaited_branches(&args);
+                let on_error = vertice.on_error;
+                let task_resources = self.task_limits.cap(&vertice.resources);
+                let logs_receipt = self.task_host.logs.receipt;
                 let receipt_meta =
                     Ipld::Map(BTreeMap::from([(OP_KEY.into(), fun.to_string().into())]));
 
@@ -321,116 +572,190 @@ where
                     ),
                 ]));
 
-                match RegisteredTasks::ability(&instruction.op().to_string()) {
-                    Some(RegisteredTasks::WasmRun) => {
-                        let wasm = scheduler
+                let op = instruction.op().to_string();
+                let Some(executor) = self.executors.get(&op) else {
+                    error!(
+                        subject = "worker.run.task.err",
+                        category = "worker.run",
+                        "no valid task/instruction-type referenced by operation: {}",
+                        op
+                    );
+                    continue;
+                };
+
+                let content = if executor.fetch_resource() {
+                    Some(
+                        scheduler
                             .resources
                             .read()
                             .await
                             .get(&Resource::Url(rsc.to_owned()))
                             .ok_or_else(|| anyhow!("resource not available"))?
-                            .to_owned();
+                            .to_owned(),
+                    )
+                } else {
+                    None
+                };
 
-                        let instruction_ptr = Pointer::try_from(instruction)?;
-                        let state = State::default();
-                        let mut wasm_ctx = WasmContext::new(state)?;
+                let module = module_cid(rsc, content.as_deref());
+                let rsc = rsc.to_owned();
+                let instruction_ptr = Pointer::try_from(instruction)?;
+                // Host interfaces are only wired up for Wasm tasks.
+                let host = (op == WASM_OP).then(|| {
+                    self.wasm_host(
+                        instruction_ptr.cid(),
+                        vertice.sandbox.as_ref(),
+                        vertice.deterministic,
+                        vertice.secrets,
+                    )
+                });
 
-                        let db = self.db.clone();
-                        let linkmap = scheduler.linkmap.clone();
-                        let resources = scheduler.resources.clone();
-                        let workflow_cid = self.workflow_info.cid();
+                let db = self.db.clone();
+                let linkmap = scheduler.linkmap.clone();
+                let resources = scheduler.resources.clone();
+                let workflow_cid = self.workflow_info.cid();
 
-                        let resolved = args.resolve(move |cid| {
-                            info!(
-                                subject = "worker.resolve_cid",
-                                category = "worker.run",
-                                workflow_cid = workflow_cid.to_string(),
-                                cid = cid.to_string(),
-                                "attempting to resolve workflow args by cid"
-                            );
+                let resolved = args.resolve(move |cid| {
+                    info!(
+                        subject = "worker.resolve_cid",
+                        category = "worker.run",
+                        workflow_cid = workflow_cid.to_string(),
+                        cid = cid.to_string(),
+                        "attempting to resolve workflow args by cid"
+                    );
 
-                            cid.resolve(linkmap.clone(), resources.clone(), db.clone())
-                                .boxed()
-                        });
-
-                        let handle = task_set.spawn(async move {
-                             match resolved.await {
-                                Ok(inst_result) => {
-                                    match wasm_ctx.run(wasm, &fun, inst_result).instrument({
-                                        debug_span!("wasm_run").or_current()
-                                    }).await {
-                                        Ok(output) => Ok((
-                                            output,
-                                            instruction_ptr,
-                                            invocation_ptr,
-                                            receipt_meta,
-                                            additional_meta)),
-                                        Err(err) => Err(
-                                            anyhow!("cannot execute wasm module: {:#?}", err))
-                                            .with_context(|| {
-                                                format!("not able to run fn {fun} for cid: {instruction_ptr}, in workflow {workflow_cid}")
-                                            }),
-                                    }
-                                },
+                    cid.resolve(linkmap.clone(), resources.clone(), db.clone())
+                        .boxed()
+                });
+
+                let handle = task_set.spawn(async move {
+                    match resolved.await {
+                        Ok(inst_result) => {
+                            if let Some(unmatched) = unmatched_await(&awaited_branches, &inst_result) {
+                                return Ok((
+                                    Outcome::Skipped(unmatched),
+                                    instruction_ptr,
+                                    invocation_ptr,
+                                    receipt_meta,
+                                    additional_meta,
+                                    IndexMap::new()));
+                            }
+
+                            let input = ExecutorInput::new(
+                                rsc,
+                                content,
+                                fun.clone(),
+                                inst_result,
+                                task_resources,
+                            );
+                            let input = match host {
+                                Some(host) => input.with_context(host),
+                                None => input,
+                            };
+                            let started = Instant::now();
+                            let (result, usage) = executor.execute_metered(input).instrument({
+                                debug_span!("execute_task", op).or_current()
+                            }).await;
+                            let wall_time = started.elapsed();
+
+                            let outcome = match result {
+                                Ok(output) => Outcome::Executed(output),
                                 Err(err) => {
-                                    Err(anyhow!("error resolving cid: {:#?}", err))
-                                        .with_context(|| {
-                                            format!("not able to resolve instruction: {instruction_ptr}, in workflow {workflow_cid}")
-                                        })
+                                    warn!(subject = "worker.run.task.failed",
+                                          category = "worker.run",
+                                          workflow_cid = workflow_cid.to_string(),
+                                          instruction_cid = instruction_ptr.to_string(),
+                                          err = format!("{:#}", err),
+                                          "task failed, running fn {fun}");
+                                    Outcome::Failed(Arg::Ipld(Ipld::String(format!("{:#}", err))), on_error)
                                 }
-                            }
+                            };
+
+                            let wasm_usage = usage.extension::<WasmUsage>();
+                            let mut blocks = wasm_usage
+                                .map(|wasm_usage| wasm_usage.blocks().to_owned())
+                                .unwrap_or_default();
+                            let logs = wasm_usage
+                                .filter(|_| logs_receipt)
+                                .and_then(logs_block)
+                                .map(|(cid, block)| {
+                                    blocks.insert(cid, block);
+                                    cid
+                                });
+                            let deterministic =
+                                wasm_usage.is_some_and(|wasm_usage| wasm_usage.deterministic());
+
+                            Ok((
+                                outcome,
+                                instruction_ptr,
+                                invocation_ptr,
+                                // Deterministic receipts leave out usage
+                                // that depends on the node, so they're
+                                // reproducible.
+                                with_usage(receipt_meta, &usage, (!deterministic).then_some(wall_time), module, logs),
+                                with_usage(additional_meta, &usage, Some(wall_time), module, logs),
+                                blocks))
+                        },
+                        Err(err) => {
+                            Err(anyhow!("error resolving cid: {:#?}", err))
+                                .with_context(|| {
+                                    format!("not able to resolve instruction: {instruction_ptr}, in workflow {workflow_cid}")
+                                })
                         }
-                        .instrument({
-                            info_span!("spawn_workflow_tasks").or_current()
-                        }));
-
-                        handles.push(handle);
                     }
-                    None => error!(
-                        subject = "worker.run.task.err",
-                        category = "worker.run",
-                        "no valid task/instruction-type referenced by operation: {}",
-                        instruction.op()
-                    ),
                 }
+                .instrument({
+                    info_span!("spawn_workflow_tasks").or_current()
+                }));
+
+                handles.push(handle);
             }
 
             // Concurrently add handles to Runner's running set.
             running_tasks.append_or_insert(self.workflow_info.cid(), handles);
             while let Some(res) = task_set.join_next().await {
-                let (executed, instruction_ptr, invocation_ptr, receipt_meta, add_meta) = match res
-                {
-                    Ok(Ok(data)) => data,
-                    Ok(Err(err)) => {
-                        error!(
-                            subject = "worker.run.task.err",
-                            category = "worker.run",
-                            err = format!("{:#?}", err),
-                            "error in running task"
-                        );
-                        break;
-                    }
-                    Err(err) => {
-                        error!(
-                            subject = "worker.run.task.err",
-                            category = "worker.run",
-                            err = format!("{:#?}", err),
-                            "error in running task"
-                        );
-                        break;
-                    }
-                };
+                let (outcome, instruction_ptr, invocation_ptr, receipt_meta, add_meta, blocks) =
+                    match res {
+                        Ok(Ok(data)) => data,
+                        Ok(Err(err)) => {
+                            error!(
+                                subject = "worker.run.task.err",
+                                category = "worker.run",
+                                err = format!("{:#?}", err),
+                                "error in running task"
+                            );
+                            return self.fail(err);
+                        }
+                        Err(err) => {
+                            error!(
+                                subject = "worker.run.task.err",
+                                category = "worker.run",
+                                err = format!("{:#?}", err),
+                                "error in running task"
+                            );
+                            return self.fail(err.into());
+                        }
+                    };
 
-                let output_to_store = Ipld::try_from(executed)?;
                 let invocation_receipt = InvocationReceipt::new(
                     invocation_ptr,
-                    task::Result::Ok(output_to_store),
+                    outcome.output(),
                     receipt_meta,
                     None,
                     UcanPrf::default(),
                 );
+                let invocation_receipt = match self.node_key {
+                    Some(ref node_key) => sign_receipt(node_key, invocation_receipt)?,
+                    None => invocation_receipt,
+                };
 
                 let receipt = Receipt::try_with(instruction_ptr, &invocation_receipt)?;
+                // Failures may be transient, or specific to this node, so
+                // they're kept to this run rather than shared or reused.
+                let receipt = match outcome {
+                    Outcome::Failed(..) | Outcome::Skipped(_) => receipt.non_cacheable(),
+                    Outcome::Executed(_) => receipt,
+                };
 
                 scheduler
                     .linkmap
@@ -450,6 +775,9 @@ where
                 let instruction_cid = receipt.instruction().cid();
                 let stored_receipt =
                     Db::commit_receipt(self.workflow_info.cid, receipt, &mut self.db.conn()?)?;
+                if !blocks.is_empty() {
+                    Db::store_receipt_blocks(stored_receipt.cid(), &blocks, &mut self.db.conn()?)?;
+                }
 
                 debug!(
                     subject = "db.commit_receipt",
@@ -474,6 +802,12 @@ where
                         Some(add_meta),
                     )))
                     .await;
+
+                if let Outcome::Failed(_, OnError::Fail) = outcome {
+                    return self.fail(anyhow!(
+                        "task for instruction {instruction_cid} failed, with `fail` as its error policy"
+                    ));
+                }
             }
         }
 
@@ -490,6 +824,218 @@ where
 
         Ok(())
     }
+
+    /// Mark the workflow as [workflow::Status::Failed], returning the task
+    /// error that caused it.
+    fn fail(&self, err: anyhow::Error) -> Result<()> {
+        Db::set_workflow_status(
+            self.workflow_info.cid,
+            workflow::Status::Failed,
+            &mut self.db.conn()?,
+        )?;
+
+        info!(
+            subject = "worker.end_workflow",
+            category = "worker.run",
+            workflow_cid = self.workflow_info.cid.to_string(),
+            "workflow failed"
+        );
+
+        Err(err.context("workflow failed"))
+    }
+}
+
+/// Branches of [Await]ed promises, by position, of a task's [Args].
+fn awaited_branches(args: &Args<Arg>) -> Vec<Option<AwaitResult>> {
+    args.inner()
+        .iter()
+        .map(|input| match input {
+            Input::Deferred(awaiting) => Some(awaiting.result().to_owned()),
+            Input::Ipld(ipld) => Await::try_from(ipld)
+                .ok()
+                .map(|awaiting| awaiting.result().to_owned()),
+            Input::Arg(_) => None,
+        })
+        .collect()
+}
+
+/// Find the first resolved input whose result does not match its awaited
+/// branch, returning the error output for the task that awaits it.
+///
+/// Awaiting `await/ok` on a failed task propagates its error, and awaiting
+/// `await/error` on a successful task is unmatched, like a `catch` without
+/// anything to catch.
+fn unmatched_await(branches: &[Option<AwaitResult>], resolved: &Args<Arg>) -> Option<Arg> {
+    branches
+        .iter()
+        .zip(resolved.inner())
+        .find_map(|(branch, input)| match (branch, input) {
+            (Some(AwaitResult::Ok), Input::Arg(task::Result::Error(err))) => Some(err.to_owned()),
+            (Some(AwaitResult::Error), Input::Arg(task::Result::Ok(_) | task::Result::Just(_))) => {
+                Some(Arg::Ipld(Ipld::String(
+                    "awaited task did not fail, nothing to handle".to_string(),
+                )))
+            }
+            _ => None,
+        })
+}
+
+/// Cid of the module a task runs, taken from an `ipfs://` resource or
+/// otherwise computed from the resource's fetched content.
+fn module_cid(rsc: &Url, content: Option<&[u8]>) -> Option<Cid> {
+    match rsc.scheme() {
+        "ipfs" => rsc.host_str().and_then(|cid| Cid::try_from(cid).ok()),
+        _ => content.map(|bytes| Cid::new_v1(0x55, Code::Sha3_256.digest(bytes))),
+    }
+}
+
+/// DAG-CBOR block of the log records an executed task logged, with the
+/// number of records dropped, if it logged any.
+fn logs_block(usage: &WasmUsage) -> Option<(Cid, Vec<u8>)> {
+    if usage.logs().is_empty() {
+        return None;
+    }
+
+    let records = usage
+        .logs()
+        .iter()
+        .map(|record| {
+            Ipld::Map(BTreeMap::from([
+                ("level".into(), record.level().to_string().into()),
+                ("context".into(), record.context().into()),
+                ("message".into(), record.message().into()),
+            ]))
+        })
+        .collect();
+    let ipld = Ipld::Map(BTreeMap::from([
+        ("records".into(), Ipld::List(records)),
+        (
+            "dropped".into(),
+            Ipld::Integer(usage.dropped_logs() as i128),
+        ),
+    ]));
+
+    match DagCborCodec
+        .encode(&ipld)
+        .map_err(|err| anyhow!(err))
+        .and_then(|block| Ok((block_cid(DAG_CBOR, &block)?, block)))
+    {
+        Ok(block) => Some(block),
+        Err(err) => {
+            warn!(
+                subject = "worker.logs.err",
+                category = "worker.run",
+                err = format!("{:#}", err),
+                "unable to encode task logs as a block"
+            );
+            None
+        }
+    }
+}
+
+/// Namespace of the key-value store of a task, scoped by its workflow, and
+/// by its verified issuer if the [KvScope] is [KvScope::Issuer].
+///
+/// Returns `None` if scoped by issuer, but the task has no verified issuer.
+fn kv_namespace(scope: KvScope, workflow_cid: Cid, issuer: Option<&String>) -> Option<String> {
+    match (scope, issuer) {
+        (KvScope::Workflow, _) => Some(format!("workflow:{workflow_cid}")),
+        (KvScope::Issuer, Some(issuer)) => Some(format!("workflow:{workflow_cid}:issuer:{issuer}")),
+        (KvScope::Issuer, None) => None,
+    }
+}
+
+/// Record the resources consumed by an executed task within its metadata,
+/// with a link to the block of its log records, if any, and the side
+/// effects of Wasm tasks.
+///
+/// Fuel consumed, peak memory, and wall time depend on the node executing
+/// the task, and are only recorded if its wall time is given.
+fn with_usage(
+    meta: Ipld,
+    usage: &Usage,
+    wall_time: Option<Duration>,
+    module: Option<Cid>,
+    logs: Option<Cid>,
+) -> Ipld {
+    let Ipld::Map(mut meta) = meta else {
+        return meta;
+    };
+
+    if let Some(wall_time) = wall_time {
+        if let Some(fuel) = usage.fuel() {
+            meta.insert(FUEL_CONSUMED_KEY.into(), Ipld::Integer(fuel.into()));
+        }
+        if let Some(memory) = usage.memory() {
+            meta.insert(PEAK_MEMORY_KEY.into(), Ipld::Integer(memory.into()));
+        }
+        meta.insert(
+            WALL_TIME_KEY.into(),
+            Ipld::Integer(wall_time.as_millis() as i128),
+        );
+    }
+    if let Some(module) = module {
+        meta.insert(MODULE_KEY.into(), Ipld::Link(module));
+    }
+    if let Some(logs) = logs {
+        meta.insert(LOGS_KEY.into(), Ipld::Link(logs));
+    }
+    if let Some(wasm_usage) = usage.extension::<WasmUsage>() {
+        if !wasm_usage.http_requests().is_empty() {
+            meta.insert(
+                HTTP_REQUESTS_KEY.into(),
+                Ipld::List(
+                    wasm_usage
+                        .http_requests()
+                        .iter()
+                        .map(|request| Ipld::String(request.to_owned()))
+                        .collect(),
+                ),
+            );
+        }
+        if !wasm_usage.blocks().is_empty() {
+            meta.insert(
+                BLOCKS_KEY.into(),
+                Ipld::List(
+                    wasm_usage
+                        .blocks()
+                        .keys()
+                        .map(|cid| Ipld::Link(*cid))
+                        .collect(),
+                ),
+            );
+        }
+        if !wasm_usage.kv_ops().is_empty() {
+            meta.insert(
+                KV_KEY.into(),
+                Ipld::List(
+                    wasm_usage
+                        .kv_ops()
+                        .iter()
+                        .map(|op| Ipld::String(op.to_owned()))
+                        .collect(),
+                ),
+            );
+        }
+        if !wasm_usage.secrets().is_empty() {
+            meta.insert(
+                SECRETS_KEY.into(),
+                Ipld::List(
+                    wasm_usage
+                        .secrets()
+                        .iter()
+                        .map(|name| Ipld::String(name.to_owned()))
+                        .collect(),
+                ),
+            );
+        }
+        meta.insert(
+            DETERMINISTIC_KEY.into(),
+            Ipld::Bool(wasm_usage.deterministic()),
+        );
+    }
+
+    Ipld::Map(meta)
 }
 
 impl<'a, DB> Drop for Worker<'a, DB>
@@ -512,7 +1058,11 @@ mod test {
         workflow::{IndexedResources, Status},
     };
     use homestar_invocation::{
-        task::{instruction::RunInstruction, Resources},
+        pointer::{Await, AwaitResult},
+        task::{
+            instruction::{Ability, Input, RunInstruction},
+            Instruction, Resources,
+        },
         Invocation, Task,
     };
 
@@ -531,7 +1081,10 @@ mod test {
 
         let (tx, rx) = test_utils::event::setup_event_channel(settings.clone().node);
 
-        let builder = WorkerBuilder::new(settings.node).with_event_sender(tx);
+        let node_key = NodeKey::new(libp2p::identity::Keypair::generate_ed25519());
+        let builder = WorkerBuilder::new(settings.node)
+            .with_event_sender(tx)
+            .with_node_key(node_key.clone());
         let fetch_fn = builder.fetch_fn();
         let workflow = builder.workflow();
         let db = builder.db();
@@ -586,6 +1139,14 @@ mod test {
                     assert_eq!(cid, worker_workflow_cid)
                 }
                 Event::CapturedReceipt(Captured { receipt, .. }) => {
+                    // Receipts are signed by the node.
+                    let stored_receipt = MemoryDb::find_receipt_by_cid(receipt, &mut conn).unwrap();
+                    assert_eq!(
+                        stored_receipt.issuer().unwrap().to_string(),
+                        node_key.did().unwrap()
+                    );
+                    assert!(crate::authorization::verify_receipt(&stored_receipt).is_ok());
+
                     let stored = workflow::Stored::default(Pointer::new(workflow_cid), 2);
                     let mut info = workflow::Info::default(stored);
                     info.increment_progress(receipt);
@@ -716,6 +1277,7 @@ mod test {
         let (_next_receipt, wf_info) = match next_run_receipt {
             Event::CapturedReceipt(Captured {
                 receipt: next_receipt,
+                metadata,
                 ..
             }) => {
                 let next_receipt = MemoryDb::find_receipt_by_cid(next_receipt, &mut conn).unwrap();
@@ -725,6 +1287,28 @@ mod test {
 
                 assert_ne!(next_receipt, receipt);
 
+                let Ipld::Map(meta) = next_receipt.meta() else {
+                    panic!("receipt metadata is not a map")
+                };
+                assert!(
+                    matches!(meta.get(FUEL_CONSUMED_KEY), Some(Ipld::Integer(fuel)) if *fuel > 0)
+                );
+                assert!(
+                    matches!(meta.get(PEAK_MEMORY_KEY), Some(Ipld::Integer(memory)) if *memory > 0)
+                );
+                assert!(matches!(meta.get(WALL_TIME_KEY), Some(Ipld::Integer(_))));
+                assert_eq!(
+                    meta.get(MODULE_KEY),
+                    Some(&Ipld::Link(
+                        module_cid(instruction2.resource(), None).unwrap()
+                    ))
+                );
+
+                let Some(Ipld::Map(metadata)) = metadata else {
+                    panic!("notification metadata is not a map")
+                };
+                assert_eq!(metadata.get(FUEL_CONSUMED_KEY), meta.get(FUEL_CONSUMED_KEY));
+
                 (next_receipt, info)
             }
             _ => panic!("Wrong event type"),
@@ -859,4 +1443,505 @@ mod test {
         assert_eq!(workflow_info.progress.len(), 2);
         assert!(rx.try_recv().is_err());
     }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn run_tasks_with_registered_executor() {
+        #[derive(Debug)]
+        struct Double;
+
+        #[async_trait::async_trait]
+        impl crate::Executor for Double {
+            async fn execute(&self, input: ExecutorInput) -> Result<task::Result<Arg>> {
+                assert_eq!(input.fun(), "double");
+                assert!(input.content().is_none());
+                match input.ipld_args().first() {
+                    Some(Ipld::Integer(i)) => Ok(task::Result::Ok(Arg::Ipld(Ipld::Integer(i * 2)))),
+                    _ => Err(anyhow!("expected an integer argument")),
+                }
+            }
+        }
+
+        let settings = TestSettings::load();
+        let resource = url::Url::parse("https://example.com/double").unwrap();
+        let instruction1 = Instruction::<Arg>::new(
+            resource.clone(),
+            Ability::from("test/double"),
+            Input::Ipld(Ipld::Map(BTreeMap::from([
+                ("func".into(), Ipld::String("double".to_string())),
+                ("args".into(), Ipld::List(vec![Ipld::Integer(21)])),
+            ]))),
+        );
+        let promise = Await::new(
+            Pointer::new(instruction1.clone().to_cid().unwrap()),
+            AwaitResult::Ok,
+        );
+        let instruction2 = Instruction::<Arg>::new(
+            resource,
+            Ability::from("test/double"),
+            Input::Ipld(Ipld::Map(BTreeMap::from([
+                ("func".into(), Ipld::String("double".to_string())),
+                ("args".into(), Ipld::List(vec![promise.into()])),
+            ]))),
+        );
+
+        let config = Resources::default();
+        let task1 = Task::new(
+            RunInstruction::Expanded(instruction1.clone()),
+            config.clone().into(),
+            UcanPrf::default(),
+        );
+        let task2 = Task::new(
+            RunInstruction::Expanded(instruction2.clone()),
+            config.into(),
+            UcanPrf::default(),
+        );
+
+        let (tx, rx) = test_utils::event::setup_event_channel(settings.node.clone());
+        let builder = WorkerBuilder::new(settings.node)
+            .with_event_sender(tx)
+            .with_tasks(vec![task1, task2])
+            .with_executors(ExecutorRegistry::default().with("test/double", Double));
+        let fetch_fn = builder.fetch_fn();
+        let db = builder.db();
+        let worker = builder.build().await;
+        let workflow_cid = worker.workflow_info.cid;
+
+        // The (non-IPFS) resource is not fetched for non-Wasm executors.
+        assert!(worker.fetchable_resources().is_empty());
+
+        let running_tasks = Arc::new(RunningTaskSet::new());
+        worker.run(running_tasks.clone(), fetch_fn).await.unwrap();
+
+        let mut conn = db.conn().unwrap();
+        let mut outputs = vec![];
+        while let Ok(event) = rx.recv_async().await {
+            if let Event::CapturedReceipt(Captured { receipt, .. }) = event {
+                let receipt = MemoryDb::find_receipt_by_cid(receipt, &mut conn).unwrap();
+                outputs.push(receipt.output().clone());
+            }
+        }
+
+        assert_eq!(
+            outputs,
+            vec![
+                task::Result::Ok(Ipld::Integer(42)),
+                task::Result::Ok(Ipld::Integer(84))
+            ]
+        );
+
+        let workflow_stored = MemoryDb::select_workflow(workflow_cid, &mut conn).unwrap();
+        assert_eq!(workflow_stored.status, Status::Completed);
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn run_tasks_with_error_handling() {
+        #[derive(Debug)]
+        struct Double;
+
+        #[async_trait::async_trait]
+        impl crate::Executor for Double {
+            async fn execute(&self, input: ExecutorInput) -> Result<task::Result<Arg>> {
+                match input.ipld_args().first() {
+                    Some(Ipld::Integer(i)) => Ok(task::Result::Ok(Arg::Ipld(Ipld::Integer(i * 2)))),
+                    _ => Err(anyhow!("expected an integer argument")),
+                }
+            }
+        }
+
+        #[derive(Debug)]
+        struct Catch;
+
+        #[async_trait::async_trait]
+        impl crate::Executor for Catch {
+            async fn execute(&self, input: ExecutorInput) -> Result<task::Result<Arg>> {
+                match input.ipld_args().first() {
+                    Some(Ipld::String(err)) => Ok(task::Result::Ok(Arg::Ipld(Ipld::String(
+                        format!("caught: {err}"),
+                    )))),
+                    _ => Err(anyhow!("expected an error to catch")),
+                }
+            }
+        }
+
+        let instruction = |ability: &str, fun: &str, arg: Ipld| {
+            Instruction::<Arg>::new(
+                url::Url::parse("https://example.com/test").unwrap(),
+                Ability::from(ability),
+                Input::Ipld(Ipld::Map(BTreeMap::from([
+                    ("func".into(), Ipld::String(fun.to_string())),
+                    ("args".into(), Ipld::List(vec![arg])),
+                ]))),
+            )
+        };
+        let task = |instruction: Instruction<'static, Arg>, on_error: OnError| {
+            let mut meta = match Ipld::from(Resources::default()) {
+                Ipld::Map(meta) => meta,
+                _ => unreachable!(),
+            };
+            meta.insert("on_error".into(), on_error.into());
+            Task::new(
+                RunInstruction::Expanded(instruction),
+                Ipld::Map(meta),
+                UcanPrf::default(),
+            )
+        };
+        let executors = ExecutorRegistry::default()
+            .with("test/double", Double)
+            .with("test/catch", Catch);
+
+        // Continue on error, where the error is caught by a task awaiting
+        // `await/error`, and propagated to a task awaiting `await/ok`.
+        let failing = instruction("test/double", "double", Ipld::String("a".into()));
+        let failing_ptr = Pointer::new(failing.clone().to_cid().unwrap());
+        let catching = instruction(
+            "test/catch",
+            "catch",
+            Await::new(failing_ptr.clone(), AwaitResult::Error).into(),
+        );
+        let doubling = instruction(
+            "test/double",
+            "double",
+            Await::new(failing_ptr, AwaitResult::Ok).into(),
+        );
+
+        let settings = TestSettings::load();
+        let (tx, rx) = test_utils::event::setup_event_channel(settings.node.clone());
+        let builder = WorkerBuilder::new(settings.node.clone())
+            .with_event_sender(tx)
+            .with_tasks(vec![
+                task(failing, OnError::Continue),
+                task(catching, OnError::Fail),
+                task(doubling, OnError::Fail),
+            ])
+            .with_executors(executors.clone());
+        let fetch_fn = builder.fetch_fn();
+        let db = builder.db();
+        let worker = builder.build().await;
+        let workflow_cid = worker.workflow_info.cid;
+
+        worker
+            .run(Arc::new(RunningTaskSet::new()), fetch_fn)
+            .await
+            .unwrap();
+
+        let mut conn = db.conn().unwrap();
+        let mut outputs = vec![];
+        while let Ok(event) = rx.recv_async().await {
+            if let Event::CapturedReceipt(Captured { receipt, .. }) = event {
+                let receipt = MemoryDb::find_receipt_by_cid(receipt, &mut conn).unwrap();
+                outputs.push(receipt.output().clone());
+            }
+        }
+
+        let error = Ipld::String("expected an integer argument".into());
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs[0], task::Result::Error(error.clone()));
+        assert!(outputs.contains(&task::Result::Ok(Ipld::String(
+            "caught: expected an integer argument".into()
+        ))));
+        assert!(outputs.contains(&task::Result::Error(error.clone())));
+
+        let workflow_stored = MemoryDb::select_workflow(workflow_cid, &mut conn).unwrap();
+        assert_eq!(workflow_stored.status, Status::Completed);
+
+        // Fail fast, still storing the error receipt.
+        let failing = instruction("test/double", "double", Ipld::String("b".into()));
+        let builder = WorkerBuilder::new(settings.node)
+            .with_tasks(vec![task(failing, OnError::Fail)])
+            .with_executors(executors);
+        let fetch_fn = builder.fetch_fn();
+        let db = builder.db();
+        let worker = builder.build().await;
+        let workflow_cid = worker.workflow_info.cid;
+
+        assert!(worker
+            .run(Arc::new(RunningTaskSet::new()), fetch_fn)
+            .await
+            .is_err());
+
+        let mut conn = db.conn().unwrap();
+        let workflow_stored = MemoryDb::select_workflow(workflow_cid, &mut conn).unwrap();
+        assert_eq!(workflow_stored.status, Status::Failed);
+        assert_eq!(
+            MemoryDb::count_workflow_receipts(workflow_cid, &mut conn).unwrap(),
+            1
+        );
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn reproduce_deterministic_receipts() {
+        let mut settings = TestSettings::load();
+        settings.node.task_execution.deterministic = true;
+        let db_url = settings.node.db.url.clone().unwrap();
+
+        // Run the same workflow on two nodes, each with its own database.
+        let mut runs = vec![];
+        for node in 0..2 {
+            settings.node.db.url = Some(format!("{node}-{db_url}"));
+            let (tx, rx) = test_utils::event::setup_event_channel(settings.node.clone());
+            let node_key = NodeKey::new(libp2p::identity::Keypair::generate_ed25519());
+            let builder = WorkerBuilder::new(settings.node.clone())
+                .with_event_sender(tx)
+                .with_node_key(node_key.clone());
+            let fetch_fn = builder.fetch_fn();
+            let db = builder.db();
+            let worker = builder.build().await;
+            worker
+                .run(Arc::new(RunningTaskSet::new()), fetch_fn)
+                .await
+                .unwrap();
+
+            let mut conn = db.conn().unwrap();
+            let mut receipts = BTreeMap::new();
+            while let Ok(event) = rx.recv_async().await {
+                if let Event::CapturedReceipt(Captured { receipt, .. }) = event {
+                    let receipt = MemoryDb::find_receipt_by_cid(receipt, &mut conn).unwrap();
+                    assert_eq!(
+                        receipt.issuer().unwrap().to_string(),
+                        node_key.did().unwrap()
+                    );
+                    assert!(crate::authorization::verify_receipt(&receipt).is_ok());
+                    assert!(receipt.meta().get(WALL_TIME_KEY).is_err());
+                    assert_eq!(
+                        receipt.meta().get(DETERMINISTIC_KEY).unwrap(),
+                        &Ipld::Bool(true)
+                    );
+                    // Receipts are issued and signed by each node, so only
+                    // what they ran, output, and their metadata reproduce.
+                    receipts.insert(
+                        receipt.instruction().cid(),
+                        (
+                            receipt.ran(),
+                            receipt.output().to_owned(),
+                            receipt.meta().to_owned(),
+                        ),
+                    );
+                }
+            }
+            assert_eq!(receipts.len(), 2);
+            runs.push(receipts);
+        }
+
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[test]
+    fn scope_kv_namespaces() {
+        let workflow_cid = Cid::new_v1(DAG_CBOR, Code::Sha3_256.digest(b"workflow"));
+        let issuer = "did:key:z6MkqN5".to_string();
+
+        assert_eq!(
+            kv_namespace(KvScope::Workflow, workflow_cid, Some(&issuer)),
+            Some(format!("workflow:{workflow_cid}"))
+        );
+        assert_eq!(
+            kv_namespace(KvScope::Issuer, workflow_cid, Some(&issuer)),
+            Some(format!("workflow:{workflow_cid}:issuer:{issuer}"))
+        );
+        assert_eq!(kv_namespace(KvScope::Issuer, workflow_cid, None), None);
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn deny_kv_to_tasks_without_issuer_when_scoped_by_issuer() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Debug, Default)]
+        struct Counter(AtomicUsize);
+
+        #[async_trait::async_trait]
+        impl crate::Executor for Arc<Counter> {
+            async fn execute(&self, _input: ExecutorInput) -> Result<task::Result<Arg>> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(task::Result::Ok(Arg::Ipld(Ipld::Integer(42))))
+            }
+        }
+
+        let task = |resource: &str, ability: &str, func: &str| {
+            Task::new(
+                RunInstruction::Expanded(Instruction::<Arg>::new(
+                    url::Url::parse(resource).unwrap(),
+                    Ability::from(ability),
+                    Input::Ipld(Ipld::Map(BTreeMap::from([
+                        ("func".into(), Ipld::String(func.to_string())),
+                        ("args".into(), Ipld::List(vec![])),
+                    ]))),
+                )),
+                Resources::default().into(),
+                UcanPrf::default(),
+            )
+        };
+        let kv_resource =
+            url::Url::parse("ipfs://bafybeiabbxwf2vn4j3zm7bbojr6rt6k7o6v6h3q3g6y5q3gky4zpbqvgeu")
+                .unwrap();
+        let wat = std::fs::read(format!(
+            "{}/../homestar-wasm/fixtures/example_kv_component.wat",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+
+        let counter = Arc::new(Counter::default());
+        let mut settings = TestSettings::load();
+        settings.node.task_host.kv.scope = KvScope::Issuer;
+        let (tx, rx) = test_utils::event::setup_event_channel(settings.node.clone());
+        let builder = WorkerBuilder::new(settings.node)
+            .with_event_sender(tx)
+            .with_tasks(vec![
+                task("https://example.com/test", "test/counter", "counter"),
+                task(kv_resource.as_str(), "wasm/run", "get"),
+            ])
+            .with_executors(ExecutorRegistry::default().with("test/counter", counter.clone()));
+        let fetch_fn = move |_rscs: FnvHashSet<Resource>| {
+            async move { Ok(IndexMap::from([(Resource::Url(kv_resource), wat)])) }.boxed()
+        };
+        let db = builder.db();
+        let worker = builder.build().await;
+        let workflow_cid = worker.workflow_info.cid;
+
+        worker
+            .run(Arc::new(RunningTaskSet::new()), fetch_fn)
+            .await
+            .unwrap();
+
+        let mut conn = db.conn().unwrap();
+        let mut outputs = vec![];
+        while let Ok(event) = rx.recv_async().await {
+            if let Event::CapturedReceipt(Captured { receipt, .. }) = event {
+                let receipt = MemoryDb::find_receipt_by_cid(receipt, &mut conn).unwrap();
+                outputs.push(receipt.output().clone());
+            }
+        }
+
+        // Only calls to the store are denied, so tasks that don't use it
+        // still run.
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(outputs.len(), 2);
+        assert!(outputs.contains(&task::Result::Ok(Ipld::Integer(42))));
+        assert!(outputs.contains(&task::Result::Ok(Ipld::String(
+            "no key-value store for this task".to_string()
+        ))));
+
+        let workflow_stored = MemoryDb::select_workflow(workflow_cid, &mut conn).unwrap();
+        assert_eq!(workflow_stored.status, Status::Completed);
+    }
+
+    #[homestar_runtime_proc_macro::db_async_test]
+    fn retry_failed_task() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Fails on its first run only.
+        #[derive(Debug, Default)]
+        struct Flaky(AtomicUsize);
+
+        #[async_trait::async_trait]
+        impl crate::Executor for Arc<Flaky> {
+            async fn execute(&self, _input: ExecutorInput) -> Result<task::Result<Arg>> {
+                match self.0.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(anyhow!("transient failure")),
+                    _ => Ok(task::Result::Ok(Arg::Ipld(Ipld::Integer(42)))),
+                }
+            }
+        }
+
+        let flaky = Arc::new(Flaky::default());
+        let instruction = Instruction::<Arg>::new(
+            url::Url::parse("https://example.com/test").unwrap(),
+            Ability::from("test/flaky"),
+            Input::Ipld(Ipld::Map(BTreeMap::from([
+                ("func".into(), Ipld::String("flaky".to_string())),
+                ("args".into(), Ipld::List(vec![])),
+            ]))),
+        );
+        let instruction_cid = instruction.clone().to_cid().unwrap();
+        let task = Task::new(
+            RunInstruction::Expanded(instruction),
+            Resources::default().into(),
+            UcanPrf::default(),
+        );
+
+        let settings = TestSettings::load();
+        let workflow_settings = workflow::Settings {
+            retries: 1,
+            ..Default::default()
+        };
+        let builder = WorkerBuilder::new(settings.node.clone())
+            .with_tasks(vec![task.clone()])
+            .with_workflow_settings(workflow_settings.clone())
+            .with_executors(ExecutorRegistry::default().with("test/flaky", flaky.clone()));
+        let fetch_fn = builder.fetch_fn();
+        let db = builder.db();
+        let worker = builder.build().await;
+        let workflow_cid = worker.workflow_info.cid;
+
+        assert!(worker
+            .run(Arc::new(RunningTaskSet::new()), fetch_fn)
+            .await
+            .is_err());
+
+        let mut conn = db.conn().unwrap();
+        let workflow_stored = MemoryDb::select_workflow(workflow_cid, &mut conn).unwrap();
+        assert_eq!(workflow_stored.status, Status::Failed);
+        assert!(MemoryDb::find_instruction_by_cid(instruction_cid, &mut conn).is_err());
+
+        // Retried as the runner does, re-running the failed task rather
+        // than resuming from its error receipt.
+        let builder = WorkerBuilder::new(settings.node)
+            .with_db(db.clone())
+            .with_tasks(vec![task])
+            .with_workflow_settings(workflow_settings)
+            .with_executors(ExecutorRegistry::default().with("test/flaky", flaky.clone()));
+        let fetch_fn = builder.fetch_fn();
+        let worker = builder.build().await;
+        assert_eq!(worker.workflow_info.cid, workflow_cid);
+
+        worker
+            .run(Arc::new(RunningTaskSet::new()), fetch_fn)
+            .await
+            .unwrap();
+
+        assert_eq!(flaky.0.load(Ordering::SeqCst), 2);
+        let workflow_stored = MemoryDb::select_workflow(workflow_cid, &mut conn).unwrap();
+        assert_eq!(workflow_stored.status, Status::Completed);
+        assert_eq!(
+            MemoryDb::find_instruction_by_cid(instruction_cid, &mut conn)
+                .unwrap()
+                .output(),
+            &task::Result::Ok(Ipld::Integer(42))
+        );
+        assert_eq!(
+            MemoryDb::count_workflow_receipts(workflow_cid, &mut conn).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn link_task_logs_block() {
+        use homestar_wasm::wasmtime::logs::{Level, LogRecord};
+
+        let wasm_usage = WasmUsage::default();
+        assert!(logs_block(&wasm_usage).is_none());
+
+        let wasm_usage = wasm_usage.with_logs(vec![LogRecord::new(Level::Warn, "ctx", "hello")], 2);
+        let (cid, block) = logs_block(&wasm_usage).unwrap();
+        assert_eq!(cid.codec(), DAG_CBOR);
+        let ipld: Ipld = DagCborCodec.decode(&block).unwrap();
+        assert_eq!(ipld.get("dropped").unwrap(), &Ipld::Integer(2));
+        assert_eq!(
+            ipld.get("records")
+                .unwrap()
+                .get(0)
+                .unwrap()
+                .get("level")
+                .unwrap(),
+            &Ipld::String("warn".into())
+        );
+
+        let meta = with_usage(
+            Ipld::Map(BTreeMap::new()),
+            &Usage::new(None, None).with_extension(wasm_usage),
+            Some(Duration::ZERO),
+            None,
+            Some(cid),
+        );
+        assert_eq!(meta.get(LOGS_KEY).unwrap(), &Ipld::Link(cid));
+    }
 }
@@ -16,13 +16,17 @@ use diesel::{
     AsExpression, FromSqlRow,
 };
 use homestar_invocation::{
+    authority::UcanPrf,
     task::{
         instruction::{Parse, Parsed, RunInstruction},
-        Instruction,
+        Instruction, OnError, Resources,
     },
     Invocation, Pointer,
 };
-use homestar_wasm::io::Arg;
+use homestar_wasm::{
+    io::Arg,
+    wasmtime::{secrets, Determinism, SandboxPolicy},
+};
 use homestar_workflow::Workflow;
 use indexmap::IndexMap;
 use itertools::Itertools;
@@ -33,10 +37,12 @@ use tracing::debug;
 use url::Url;
 
 pub(crate) mod error;
+pub mod filter;
 mod info;
 pub mod settings;
 
 pub(crate) use error::Error;
+pub use filter::Filter;
 pub(crate) use info::{Info, Stored, StoredReceipt};
 pub use info::{Status, StatusMapping, WORKFLOW_TAG};
 #[allow(unused_imports)]
@@ -109,6 +115,12 @@ pub(crate) struct Vertex<'a> {
     pub(crate) instruction: Instruction<'a, Arg>,
     pub(crate) parsed: Parsed<Arg>,
     pub(crate) invocation: Pointer,
+    pub(crate) on_error: OnError,
+    pub(crate) resources: Resources,
+    pub(crate) sandbox: Option<SandboxPolicy>,
+    pub(crate) deterministic: Option<bool>,
+    pub(crate) secrets: Vec<String>,
+    pub(crate) prf: UcanPrf,
 }
 
 /// [Origin] of a [Cid] being in/not-in a [Workflow] itself.
@@ -143,15 +155,28 @@ impl Promises {
 }
 
 impl<'a> Vertex<'a> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         instruction: Instruction<'a, Arg>,
         parsed: Parsed<Arg>,
         invocation: Pointer,
+        on_error: OnError,
+        resources: Resources,
+        sandbox: Option<SandboxPolicy>,
+        deterministic: Option<bool>,
+        secrets: Vec<String>,
+        prf: UcanPrf,
     ) -> Vertex<'a> {
         Vertex {
             instruction,
             parsed,
             invocation,
+            on_error,
+            resources,
+            sandbox,
+            deterministic,
+            secrets,
+            prf,
         }
     }
 }
@@ -218,6 +243,14 @@ impl<'a> Builder<'a> {
 
                     // Clone as we're owning the struct going backward.
                     let ptr: Pointer = Invocation::<Arg>::from(task.clone()).try_into()?;
+                    let on_error = OnError::from_meta(task.meta())
+                        .map_err(|e| anyhow!("invalid task error policy: {e}"))?;
+                    let task_resources = Resources::from_meta(task.meta())
+                        .map_err(|e| anyhow!("invalid task resources: {e}"))?;
+                    let sandbox = SandboxPolicy::from_meta(task.meta())?;
+                    let deterministic = Determinism::from_meta(task.meta())?;
+                    let secrets = secrets::from_meta(task.meta())?;
+                    let prf = task.prf().to_owned();
 
                     let RunInstruction::Expanded(instr) = task.into_instruction() else {
                         bail!("workflow tasks/instructions must be expanded / inlined")
@@ -249,9 +282,19 @@ impl<'a> Builder<'a> {
                             .or_insert_with(|| vec![Resource::Cid(cid.to_owned())]);
                     });
 
-                    let node = Node::new(Vertex::new(instr.to_owned(), parsed, ptr))
-                        .with_name(instr_cid.to_string())
-                        .with_result(i);
+                    let node = Node::new(Vertex::new(
+                        instr.to_owned(),
+                        parsed,
+                        ptr,
+                        on_error,
+                        task_resources,
+                        sandbox,
+                        deterministic,
+                        secrets,
+                        prf,
+                    ))
+                    .with_name(instr_cid.to_string())
+                    .with_result(i);
 
                     if !reads.is_empty() {
                         dag.add_node(node.with_reads(reads.clone()));
@@ -0,0 +1,93 @@
+//! Filter for querying stored [Workflow]s.
+//!
+//! [Workflow]: homestar_workflow::Workflow
+
+use super::Status;
+use chrono::{DateTime, NaiveDateTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Default number of workflows returned per page.
+pub const DEFAULT_LIMIT: u32 = 25;
+
+/// Filter and paging options for listing stored [Workflow]s.
+///
+/// Timestamps are given as seconds since the Unix epoch (UTC).
+///
+/// [Workflow]: homestar_workflow::Workflow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Filter {
+    /// Only include workflows with the given [Status].
+    pub(crate) status: Option<Status>,
+    /// Only include workflows whose local name contains this value.
+    pub(crate) name: Option<String>,
+    /// Only include workflows created at or after this timestamp.
+    pub(crate) since: Option<i64>,
+    /// Only include workflows created at or before this timestamp.
+    pub(crate) until: Option<i64>,
+    /// Maximum number of workflows to return.
+    pub(crate) limit: u32,
+    /// Number of workflows to skip, for paging.
+    pub(crate) offset: u32,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            status: None,
+            name: None,
+            since: None,
+            until: None,
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+        }
+    }
+}
+
+impl Filter {
+    /// Filter by workflow [Status].
+    pub fn with_status(mut self, status: Status) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Filter by (partial) local workflow name.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Filter by workflows created at or after a given timestamp.
+    pub fn with_since(mut self, since: NaiveDateTime) -> Self {
+        self.since = Some(since.and_utc().timestamp());
+        self
+    }
+
+    /// Filter by workflows created at or before a given timestamp.
+    pub fn with_until(mut self, until: NaiveDateTime) -> Self {
+        self.until = Some(until.and_utc().timestamp());
+        self
+    }
+
+    /// Set the page size and offset.
+    pub fn with_page(mut self, limit: u32, offset: u32) -> Self {
+        self.limit = limit;
+        self.offset = offset;
+        self
+    }
+
+    /// Lower-bound creation timestamp as a [NaiveDateTime].
+    pub(crate) fn since_datetime(&self) -> Option<NaiveDateTime> {
+        self.since
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .map(|dt| dt.naive_utc())
+    }
+
+    /// Upper-bound creation timestamp as a [NaiveDateTime].
+    pub(crate) fn until_datetime(&self) -> Option<NaiveDateTime> {
+        self.until
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+            .map(|dt| dt.naive_utc())
+    }
+}
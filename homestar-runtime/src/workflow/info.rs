@@ -17,8 +17,9 @@ use diesel::{Associations, Identifiable, Insertable, Queryable, Selectable};
 use faststr::FastStr;
 use homestar_invocation::{ipld::DagJson, Pointer};
 use libipld::{cbor::DagCborCodec, prelude::Codec, serde::from_ipld, Cid, Ipld};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fmt, sync::Arc, time::Duration};
+use std::{collections::BTreeMap, fmt, str::FromStr, sync::Arc, time::Duration};
 use tokio::{
     runtime::Handle,
     time::{timeout_at, Instant},
@@ -40,7 +41,10 @@ const RESOURCES_KEY: &str = "resources";
 /// Status of a [Workflow].
 ///
 /// [Workflow]: homestar_workflow::Workflow
-#[derive(Debug, Clone, PartialEq, diesel_derive_enum::DbEnum)]
+#[derive(
+    Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema, diesel_derive_enum::DbEnum,
+)]
+#[serde(rename_all = "snake_case")]
 pub enum Status {
     /// Workflow is pending - default case.
     Pending,
@@ -50,6 +54,39 @@ pub enum Status {
     Completed,
     /// Workflow is stuck, awaiting CIDs we can't find on the network.
     Stuck,
+    /// Workflow was cancelled before completion.
+    Cancelled,
+    /// Workflow failed, i.e. one of its tasks could not be executed.
+    Failed,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Pending => write!(f, "pending"),
+            Status::Running => write!(f, "running"),
+            Status::Completed => write!(f, "completed"),
+            Status::Stuck => write!(f, "stuck"),
+            Status::Cancelled => write!(f, "cancelled"),
+            Status::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl FromStr for Status {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pending" => Ok(Status::Pending),
+            "running" => Ok(Status::Running),
+            "completed" => Ok(Status::Completed),
+            "stuck" => Ok(Status::Stuck),
+            "cancelled" => Ok(Status::Cancelled),
+            "failed" => Ok(Status::Failed),
+            _ => Err(anyhow!("unknown workflow status: {s}")),
+        }
+    }
 }
 
 /// [Workflow] information stored in the database.
@@ -2,21 +2,54 @@
 //!
 //! [Workflow]: homestar_workflow::Workflow
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DurationMilliSeconds, DurationSeconds};
 use std::time::Duration;
 
 /// Workflow settings.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Can be given within a workflow file or run request under a `settings`
+/// key, with any missing field falling back to its default.
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+#[schemars(rename = "workflow_settings")]
 pub struct Settings {
     /// Number of retries for a given workflow.
+    ///
+    /// Workflows that fail or get stuck are re-run, resuming from their
+    /// last receipted step, until retries are exhausted.
+    #[schemars(description = "Number of retries for a workflow")]
     pub(crate) retries: u32,
     /// Maximum delay between retries.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[schemars(with = "u64", description = "Maximum delay between retries in seconds")]
     pub(crate) retry_max_delay: Duration,
     /// Initial delay between retries.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    #[schemars(
+        with = "u64",
+        description = "Initial delay between retries in milliseconds"
+    )]
     pub(crate) retry_initial_delay: Duration,
     /// Timeout for a given workflow.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[schemars(with = "u64", description = "Timeout for a workflow run in seconds")]
     pub(crate) timeout: Duration,
 }
 
+impl Settings {
+    /// Backoff delay before a given retry attempt (starting at 1), doubling
+    /// the initial delay per attempt, up to the maximum delay.
+    pub(crate) fn retry_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.retry_initial_delay
+            .saturating_mul(factor)
+            .min(self.retry_max_delay)
+    }
+}
+
 #[cfg(all(not(test), not(feature = "test-utils")))]
 impl Default for Settings {
     fn default() -> Self {
@@ -41,3 +74,23 @@ impl Default for Settings {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn partial_settings_and_backoff() {
+        let settings: Settings =
+            serde_json::from_str(r#"{"retries": 5, "retry_initial_delay": 100}"#).unwrap();
+
+        assert_eq!(settings.retries, 5);
+        assert_eq!(settings.retry_initial_delay, Duration::from_millis(100));
+        assert_eq!(settings.timeout, Settings::default().timeout);
+
+        assert_eq!(settings.retry_delay(1), Duration::from_millis(100));
+        assert_eq!(settings.retry_delay(2), Duration::from_millis(200));
+        assert_eq!(settings.retry_delay(4), Duration::from_millis(800));
+        assert_eq!(settings.retry_delay(10), Duration::from_secs(1));
+    }
+}
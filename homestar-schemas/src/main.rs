@@ -2,7 +2,8 @@
 
 use homestar_invocation::Receipt;
 use homestar_runtime::{
-    Health, NetworkNotification, NodeInfo, PrometheusData, ReceiptNotification,
+    runner::response, workflow, Health, NetworkNotification, NodeInfo, PrometheusData,
+    ReceiptEncoding, ReceiptNotification, TaskLogNotification,
 };
 use homestar_workflow::Workflow;
 use schemars::{
@@ -61,6 +62,11 @@ fn main() {
         .unwrap()
         .write_all(&serde_json::to_vec_pretty(&receipt_notification_schema).unwrap());
 
+    let task_log_schema = schema_for!(TaskLogNotification);
+    let _ = fs::File::create(schema_path("task_log.json"))
+        .unwrap()
+        .write_all(&serde_json::to_vec_pretty(&task_log_schema).unwrap());
+
     let api_doc = generate_api_doc(
         health_schema,
         metrics_schema,
@@ -68,6 +74,7 @@ fn main() {
         network_schema,
         workflow_schema,
         receipt_notification_schema,
+        task_log_schema,
     );
     let _ = fs::File::create(schema_path("api.json"))
         .unwrap()
@@ -82,6 +89,7 @@ fn generate_api_doc(
     network_schema: RootSchema,
     workflow_schema: RootSchema,
     receipt_notification_schema: RootSchema,
+    task_log_schema: RootSchema,
 ) -> OpenrpcDocument {
     let discover: MethodObject = MethodObject {
         name: "rpc.discover".to_string(),
@@ -247,16 +255,37 @@ fn generate_api_doc(
         servers: None,
         tags: None,
         param_structure: Some(MethodObjectParamStructure::ByName),
-        params: vec![ContentDescriptorOrReference::ContentDescriptorObject(
-            ContentDescriptorObject {
+        params: vec![
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
                 name: "tasks".to_string(),
                 summary: None,
                 description: None,
                 required: Some(true),
-                schema: JSONSchema::JsonSchemaObject(workflow_schema),
+                schema: JSONSchema::JsonSchemaObject(workflow_schema.clone()),
                 deprecated: Some(false),
-            },
-        )],
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "settings".to_string(),
+                summary: Some("retry and timeout settings for the workflow run".to_string()),
+                description: None,
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(workflow::Settings)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "params".to_string(),
+                summary: Some(
+                    "values for {{name}} placeholders in task arguments, substituted before the workflow CID is computed"
+                        .to_string(),
+                ),
+                description: None,
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(
+                    std::collections::BTreeMap<String, serde_json::Value>
+                )),
+                deprecated: Some(false),
+            }),
+        ],
         result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
             name: "subscription_id".to_string(),
             summary: None,
@@ -304,6 +333,481 @@ fn generate_api_doc(
         x_messages: None,
     };
 
+    let task_logs: MethodObject = MethodObject {
+        name: "subscribe_task_logs".to_string(),
+        description: None,
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::ByName),
+        params: vec![ContentDescriptorOrReference::ContentDescriptorObject(
+            ContentDescriptorObject {
+                name: "workflow".to_string(),
+                summary: Some("CID of the workflow whose task logs to stream".to_string()),
+                description: None,
+                required: Some(true),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(String)),
+                deprecated: Some(false),
+            },
+        )],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "subscription_id".to_string(),
+            summary: None,
+            description: None,
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(String)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: Some(ContentDescriptorObject {
+            name: "task log subscription messages".to_string(),
+            summary: Some("log records of the tasks of a running workflow".to_string()),
+            description: None,
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(task_log_schema),
+            deprecated: Some(false),
+        }),
+    };
+
+    let task_logs_unsubscribe: MethodObject = MethodObject {
+        name: "unsubscribe_task_logs".to_string(),
+        description: None,
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::Either),
+        params: vec![],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "unsubscribe result".to_string(),
+            summary: None,
+            description: None,
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(bool)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
+    let cancel_workflow: MethodObject = MethodObject {
+        name: "cancel_workflow".to_string(),
+        description: Some("Cancel a running workflow, given its Cid".to_string()),
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::ByName),
+        params: vec![ContentDescriptorOrReference::ContentDescriptorObject(
+            ContentDescriptorObject {
+                name: "cid".to_string(),
+                summary: None,
+                description: Some("Cid of the running workflow".to_string()),
+                required: Some(true),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(String)),
+                deprecated: Some(false),
+            },
+        )],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "cancelled workflow".to_string(),
+            summary: None,
+            description: Some("Cid, name, and status of the cancelled workflow".to_string()),
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(response::CancelledWorkflow)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
+    let auth_challenge: MethodObject = MethodObject {
+        name: "auth_challenge".to_string(),
+        description: Some(
+            "Issue a single-use challenge for a DID to sign and send as `Authorization: DID <did> <challenge> <signature>`"
+                .to_string(),
+        ),
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::Either),
+        params: vec![],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "challenge".to_string(),
+            summary: None,
+            description: Some(
+                "Base64url-encoded challenge and the seconds until it expires".to_string(),
+            ),
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(std::collections::BTreeMap<
+                String,
+                serde_json::Value,
+            >)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
+    let list_workflows: MethodObject = MethodObject {
+        name: "list_workflows".to_string(),
+        description: Some(
+            "List stored workflows, most recently created first, with optional filters and paging"
+                .to_string(),
+        ),
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::ByName),
+        params: vec![
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "status".to_string(),
+                summary: None,
+                description: Some("Only include workflows with the given status".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<workflow::Status>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "name".to_string(),
+                summary: None,
+                description: Some(
+                    "Only include workflows whose local name contains this value".to_string(),
+                ),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<String>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "since".to_string(),
+                summary: None,
+                description: Some(
+                    "Only include workflows created at or after this Unix timestamp (seconds)"
+                        .to_string(),
+                ),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<i64>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "until".to_string(),
+                summary: None,
+                description: Some(
+                    "Only include workflows created at or before this Unix timestamp (seconds)"
+                        .to_string(),
+                ),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<i64>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "limit".to_string(),
+                summary: None,
+                description: Some(
+                    "Maximum number of workflows to return, defaults to 25".to_string(),
+                ),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(u32)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "offset".to_string(),
+                summary: None,
+                description: Some("Number of workflows to skip, for paging".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(u32)),
+                deprecated: Some(false),
+            }),
+        ],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "workflows".to_string(),
+            summary: None,
+            description: Some("A page of stored workflows with their progress".to_string()),
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(response::AckWorkflows)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
+    let show_workflow: MethodObject = MethodObject {
+        name: "show_workflow".to_string(),
+        description: Some(
+            "Show a stored workflow, given its Cid or local name, with per-task progress"
+                .to_string(),
+        ),
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::ByName),
+        params: vec![ContentDescriptorOrReference::ContentDescriptorObject(
+            ContentDescriptorObject {
+                name: "workflow".to_string(),
+                summary: None,
+                description: Some("Cid or local name of the workflow".to_string()),
+                required: Some(true),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(String)),
+                deprecated: Some(false),
+            },
+        )],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "workflow details".to_string(),
+            summary: None,
+            description: Some(
+                "Workflow summary and per-task progress and receipt Cids".to_string(),
+            ),
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(response::AckWorkflowDetails)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
+    let plan_workflow: MethodObject = MethodObject {
+        name: "plan_workflow".to_string(),
+        description: Some(
+            "Plan a workflow run without executing anything, returning its batched schedule, steps skipped due to stored receipts, and promises to be resolved over the network"
+                .to_string(),
+        ),
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::ByName),
+        params: vec![
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "workflow".to_string(),
+                summary: None,
+                description: None,
+                required: Some(true),
+                schema: JSONSchema::JsonSchemaObject(workflow_schema),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "name".to_string(),
+                summary: None,
+                description: Some("Local name of the workflow".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(String)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "params".to_string(),
+                summary: None,
+                description: Some(
+                    "Values for {{name}} placeholders in task arguments".to_string(),
+                ),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(
+                    std::collections::BTreeMap<String, serde_json::Value>
+                )),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "dot".to_string(),
+                summary: None,
+                description: Some("Render the plan as a Graphviz DOT graph".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(bool)),
+                deprecated: Some(false),
+            }),
+        ],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "workflow plan".to_string(),
+            summary: None,
+            description: Some(
+                "Batched steps with instruction Cids and resources, and promises to resolve"
+                    .to_string(),
+            ),
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(response::AckPlan)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
+    let get_receipt: MethodObject = MethodObject {
+        name: "get_receipt".to_string(),
+        description: Some("Get a stored receipt, given its Cid".to_string()),
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::ByName),
+        params: vec![
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "cid".to_string(),
+                summary: None,
+                description: Some("Cid of the receipt".to_string()),
+                required: Some(true),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(String)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "encoding".to_string(),
+                summary: None,
+                description: Some("Encoding of the returned receipt(s): dag-json (default) or dag-cbor, wrapped as DAG-JSON bytes".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<ReceiptEncoding>)),
+                deprecated: Some(false),
+            }),
+        ],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "receipt".to_string(),
+            summary: None,
+            description: Some("Receipt encoded as DAG-JSON or DAG-CBOR".to_string()),
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(Receipt<()>)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
+    let list_receipts_for_workflow: MethodObject = MethodObject {
+        name: "list_receipts_for_workflow".to_string(),
+        description: Some("List all stored receipts for a workflow, given its Cid".to_string()),
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::ByName),
+        params: vec![
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "workflow".to_string(),
+                summary: None,
+                description: Some("Cid of the workflow".to_string()),
+                required: Some(true),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(String)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "encoding".to_string(),
+                summary: None,
+                description: Some("Encoding of the returned receipt(s): dag-json (default) or dag-cbor, wrapped as DAG-JSON bytes".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<ReceiptEncoding>)),
+                deprecated: Some(false),
+            }),
+        ],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "receipts".to_string(),
+            summary: None,
+            description: Some("Receipts encoded as a DAG-JSON or DAG-CBOR list".to_string()),
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(Vec<Receipt<()>>)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
+    let find_receipts: MethodObject = MethodObject {
+        name: "find_receipts".to_string(),
+        description: Some("Find stored receipts by invocation, instruction, issuer or operation".to_string()),
+        summary: None,
+        servers: None,
+        tags: None,
+        param_structure: Some(MethodObjectParamStructure::ByName),
+        params: vec![
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "ran".to_string(),
+                summary: None,
+                description: Some("Cid of the invocation the receipt ran".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<String>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "instruction".to_string(),
+                summary: None,
+                description: Some("Cid of the instruction the receipt is for".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<String>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "issuer".to_string(),
+                summary: None,
+                description: Some("Issuer (DID) of the receipt".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<String>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "op".to_string(),
+                summary: None,
+                description: Some("Operation recorded in the receipt metadata, e.g. wasm/run".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<String>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "limit".to_string(),
+                summary: None,
+                description: Some("Maximum number of receipts to return, defaults to 100".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<u32>)),
+                deprecated: Some(false),
+            }),
+            ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+                name: "encoding".to_string(),
+                summary: None,
+                description: Some("Encoding of the returned receipt(s): dag-json (default) or dag-cbor, wrapped as DAG-JSON bytes".to_string()),
+                required: Some(false),
+                schema: JSONSchema::JsonSchemaObject(schema_for!(Option<ReceiptEncoding>)),
+                deprecated: Some(false),
+            }),
+        ],
+        result: ContentDescriptorOrReference::ContentDescriptorObject(ContentDescriptorObject {
+            name: "receipts".to_string(),
+            summary: None,
+            description: Some("Receipts encoded as a DAG-JSON or DAG-CBOR list".to_string()),
+            required: Some(true),
+            schema: JSONSchema::JsonSchemaObject(schema_for!(Vec<Receipt<()>>)),
+            deprecated: Some(false),
+        }),
+        external_docs: None,
+        errors: None,
+        links: None,
+        examples: None,
+        deprecated: Some(false),
+        x_messages: None,
+    };
+
     OpenrpcDocument {
         openrpc: Openrpc::V26,
         info: InfoObject {
@@ -337,6 +841,16 @@ fn generate_api_doc(
             network_unsubscribe,
             workflow,
             workflow_unsubscribe,
+            task_logs,
+            task_logs_unsubscribe,
+            cancel_workflow,
+            list_workflows,
+            show_workflow,
+            plan_workflow,
+            get_receipt,
+            list_receipts_for_workflow,
+            find_receipts,
+            auth_challenge,
         ],
         components: None,
     }
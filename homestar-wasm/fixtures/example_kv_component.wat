@@ -0,0 +1,71 @@
+(component
+  (import "homestar:host/kv@0.1.1" (instance $kv
+    (type (variant
+      (case "key-too-large" u64)
+      (case "value-too-large" u64)
+      (case "quota-exceeded" u64)
+      (case "unavailable" string)
+      (case "denied" string)))
+    (export "error" (type (eq 0)))
+    (type (list u8))
+    (type (option 2))
+    (export "get" (func (param "key" string) (result (result 3 (error 1)))))
+  ))
+  (core module $mem
+    (memory (export "memory") 1)
+    (global $next (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      global.get $next
+      local.set $ptr
+      global.get $next
+      local.get 3
+      i32.add
+      i32.const 7
+      i32.add
+      i32.const -8
+      i32.and
+      global.set $next
+      local.get $ptr
+    )
+  )
+  (core instance $mem (instantiate $mem))
+  (core func $get (canon lower (func $kv "get")
+    (memory $mem "memory") (realloc (func $mem "realloc"))))
+  (core module $m
+    (import "mem" "memory" (memory 1))
+    (import "kv" "get" (func $get (param i32 i32 i32)))
+    (data (i32.const 0) "k")
+    (data (i32.const 64) "not denied")
+    (data (i32.const 80) "\40\00\00\00\0a\00\00\00")
+    ;; Get the `k` key, returning the reason if denied.
+    (func (export "get") (result i32)
+      i32.const 0
+      i32.const 1
+      i32.const 32
+      call $get
+      i32.const 32
+      i32.load8_u
+      if (result i32)
+        i32.const 40
+        i32.load8_u
+        i32.const 4
+        i32.eq
+        if (result i32)
+          i32.const 48
+        else
+          i32.const 80
+        end
+      else
+        i32.const 80
+      end
+    )
+  )
+  (core instance $imports (export "get" (func $get)))
+  (core instance $i (instantiate $m
+    (with "mem" (instance $mem))
+    (with "kv" (instance $imports))
+  ))
+  (func (export "get") (result string)
+    (canon lift (core func $i "get") (memory $mem "memory")))
+)
@@ -0,0 +1,54 @@
+(component
+  (import "homestar:host/secrets@0.1.1" (instance $secrets
+    (type (variant (case "denied" string) (case "not-found" string)))
+    (export "error" (type (eq 0)))
+    (export "get" (func (param "name" string) (result (result string (error 1)))))
+  ))
+  (core module $mem
+    (memory (export "memory") 1)
+    (global $next (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      global.get $next
+      local.set $ptr
+      global.get $next
+      local.get 3
+      i32.add
+      i32.const 7
+      i32.add
+      i32.const -8
+      i32.and
+      global.set $next
+      local.get $ptr
+    )
+  )
+  (core instance $mem (instantiate $mem))
+  (core func $get (canon lower (func $secrets "get")
+    (memory $mem "memory") (realloc (func $mem "realloc"))))
+  (core module $m
+    (import "mem" "memory" (memory 1))
+    (import "secrets" "get" (func $get (param i32 i32 i32)))
+    (data (i32.const 0) "token")
+    ;; Read the `token` secret and return it, or the error, as is.
+    (func (export "leak") (result i32)
+      i32.const 0
+      i32.const 5
+      i32.const 16
+      call $get
+      i32.const 16
+      i32.load8_u
+      if (result i32)
+        i32.const 24
+      else
+        i32.const 20
+      end
+    )
+  )
+  (core instance $imports (export "get" (func $get)))
+  (core instance $i (instantiate $m
+    (with "mem" (instance $mem))
+    (with "secrets" (instance $imports))
+  ))
+  (func (export "leak") (result string)
+    (canon lift (core func $i "leak") (memory $mem "memory")))
+)
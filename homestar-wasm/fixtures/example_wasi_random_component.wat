@@ -0,0 +1,15 @@
+(component
+  (import "wasi:random/random@0.2.0" (instance $random
+    (export "get-random-u64" (func (result u64)))
+  ))
+  (core func $get-random-u64 (canon lower (func $random "get-random-u64")))
+  (core module $m
+    (import "random" "get-random-u64" (func $get-random-u64 (result i64)))
+    (func (export "random") (result i64)
+      call $get-random-u64
+    )
+  )
+  (core instance $imports (export "get-random-u64" (func $get-random-u64)))
+  (core instance $i (instantiate $m (with "random" (instance $imports))))
+  (func (export "random") (result u64) (canon lift (core func $i "random")))
+)
@@ -0,0 +1,112 @@
+//! IPLD blocks read and written by Wasm components through the
+//! `homestar:host/blocks` interface.
+
+use async_trait::async_trait;
+use libipld::{
+    cbor::DagCborCodec,
+    json::DagJsonCodec,
+    multihash::{Code, MultihashDigest},
+    prelude::Codec,
+    Cid, Ipld,
+};
+use std::fmt;
+
+/// Multicodec of raw blocks.
+pub const RAW: u64 = 0x55;
+/// Multicodec of DAG-CBOR blocks.
+pub const DAG_CBOR: u64 = 0x71;
+/// Multicodec of DAG-JSON blocks.
+pub const DAG_JSON: u64 = 0x0129;
+
+/// Maximum size of a block written by a Wasm component, in bytes, matching
+/// the limit of blocks exchanged over IPFS.
+pub const MAX_BLOCK_SIZE: usize = 1024 * 1024;
+
+/// Store of IPLD blocks, read by Wasm components by [Cid].
+///
+/// Blocks written by a component are kept in its [State] instead, to be
+/// stored with the receipt of its task.
+///
+/// [State]: crate::wasmtime::State
+#[async_trait]
+pub trait BlockStore: fmt::Debug + Send + Sync {
+    /// Get a block by [Cid], if available.
+    ///
+    /// Blocks returned must match the hash of their [Cid].
+    async fn get(&self, cid: &Cid) -> anyhow::Result<Option<Vec<u8>>>;
+}
+
+/// Error validating a block written by a Wasm component.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum BlockError {
+    /// Block larger than [MAX_BLOCK_SIZE].
+    #[error("block of {0} bytes exceeds the limit of {MAX_BLOCK_SIZE} bytes")]
+    TooLarge(usize),
+    /// Block with an unsupported codec.
+    #[error("unsupported block codec {0:#x}")]
+    UnsupportedCodec(u64),
+    /// Block with content not matching its codec.
+    #[error("block does not match codec {codec:#x}: {reason}")]
+    InvalidBlock {
+        /// Codec of the block.
+        codec: u64,
+        /// Why the block doesn't decode.
+        reason: String,
+    },
+}
+
+/// Validate a block encoded with the given codec, computing its (CIDv1,
+/// SHA2-256) [Cid].
+pub fn block_cid(codec: u64, data: &[u8]) -> Result<Cid, BlockError> {
+    if data.len() > MAX_BLOCK_SIZE {
+        return Err(BlockError::TooLarge(data.len()));
+    }
+
+    let decoded = match codec {
+        RAW => Ok(()),
+        DAG_CBOR => DagCborCodec.decode::<Ipld>(data).map(|_| ()),
+        DAG_JSON => DagJsonCodec.decode::<Ipld>(data).map(|_| ()),
+        codec => return Err(BlockError::UnsupportedCodec(codec)),
+    };
+    decoded.map_err(|err| BlockError::InvalidBlock {
+        codec,
+        reason: err.to_string(),
+    })?;
+
+    Ok(Cid::new_v1(codec, Code::Sha2_256.digest(data)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn validate_blocks() {
+        let cbor = DagCborCodec.encode(&Ipld::String("hello".into())).unwrap();
+        let cid = block_cid(DAG_CBOR, &cbor).unwrap();
+        assert_eq!(cid.codec(), DAG_CBOR);
+        assert_eq!(*cid.hash(), Code::Sha2_256.digest(&cbor));
+
+        assert_eq!(
+            block_cid(RAW, &cbor).unwrap(),
+            Cid::new_v1(RAW, Code::Sha2_256.digest(&cbor))
+        );
+        assert!(block_cid(DAG_JSON, br#"{"hello": "world"}"#).is_ok());
+
+        assert!(matches!(
+            block_cid(DAG_CBOR, b"not cbor"),
+            Err(BlockError::InvalidBlock {
+                codec: DAG_CBOR,
+                ..
+            })
+        ));
+        assert_eq!(
+            block_cid(0x70, &cbor),
+            Err(BlockError::UnsupportedCodec(0x70))
+        );
+        assert_eq!(
+            block_cid(RAW, &vec![0; MAX_BLOCK_SIZE + 1]),
+            Err(BlockError::TooLarge(MAX_BLOCK_SIZE + 1))
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! Cache of compiled Wasm components, serialized on disk by module [Cid] and
+//! engine configuration, with instantiation pre-processing ([InstancePre])
+//! reused in memory.
+
+use crate::wasmtime::{
+    interrupt::{self, Interruption},
+    sandbox::SandboxPolicy,
+    world::World,
+    Error,
+};
+use indexmap::IndexMap;
+use libipld::{
+    multihash::{Code, MultihashDigest},
+    Cid,
+};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt, fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::SystemTime,
+};
+use tracing::{debug, warn};
+use wasmtime::{
+    component::{Component, InstancePre},
+    Engine,
+};
+
+/// Extension of serialized, compiled components on disk.
+const ARTIFACT_EXTENSION: &str = "cwasm";
+
+/// Maximum number of [InstancePre]s kept in memory.
+const MAX_INSTANCES: usize = 64;
+
+/// [InstancePre]s by module [Cid], the [SandboxPolicy] their imports were
+/// resolved under, and whether they were checked for deterministic
+/// execution.
+type Instances<T> = IndexMap<(Cid, SandboxPolicy, bool), InstancePre<T>>;
+
+/// Counter distinguishing artifacts being written concurrently.
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+/// Cache of compiled Wasm components, shared by tasks in the same process.
+///
+/// Components compile against the cache's [Engine], serialized into its
+/// directory, if any, under the [Cid] of their module and a hash of the
+/// engine's configuration, so that artifacts of a different configuration
+/// or version are never loaded. The directory is kept under its maximum
+/// size by evicting the least recently used artifacts.
+///
+/// [InstancePre]s, resolving a component's imports under a
+/// [SandboxPolicy], are kept in memory.
+pub struct ComponentCache<T> {
+    engine: Engine,
+    interruption: Interruption,
+    dir: Option<PathBuf>,
+    max_size: u64,
+    engine_hash: String,
+    instances: Arc<Mutex<Instances<T>>>,
+}
+
+impl<T> Clone for ComponentCache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            engine: self.engine.clone(),
+            interruption: self.interruption,
+            dir: self.dir.clone(),
+            max_size: self.max_size,
+            engine_hash: self.engine_hash.clone(),
+            instances: Arc::clone(&self.instances),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ComponentCache<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentCache")
+            .field("interruption", &self.interruption)
+            .field("dir", &self.dir)
+            .field("max_size", &self.max_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> ComponentCache<T> {
+    /// Create a new [ComponentCache], serializing compiled components into
+    /// `dir`, if given, up to `max_size` bytes, for an [Engine]
+    /// interrupting execution as given.
+    pub fn new(
+        dir: Option<PathBuf>,
+        max_size: u64,
+        interruption: Interruption,
+    ) -> Result<Self, Error> {
+        let engine = Engine::new(&World::configure(interruption))?;
+        if interruption.epoch() {
+            interrupt::tick_epochs(&engine);
+        }
+        if let Some(dir) = &dir {
+            fs::create_dir_all(dir).map_err(anyhow::Error::from)?;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        engine.precompile_compatibility_hash().hash(&mut hasher);
+        let engine_hash = format!("{:016x}", hasher.finish());
+
+        Ok(Self {
+            engine,
+            interruption,
+            dir,
+            max_size,
+            engine_hash,
+            instances: Arc::new(Mutex::new(IndexMap::new())),
+        })
+    }
+
+    /// [Engine] components are compiled against.
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// How execution is interrupted by the [Engine].
+    pub fn interruption(&self) -> Interruption {
+        self.interruption
+    }
+
+    /// Directory compiled components are serialized into, if any.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// Maximum size of the directory, in bytes.
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+
+    /// [InstancePre] of a module under a [SandboxPolicy], if cached.
+    pub(crate) fn instance_pre(
+        &self,
+        cid: Cid,
+        sandbox: &SandboxPolicy,
+        deterministic: bool,
+    ) -> Option<InstancePre<T>> {
+        self.instances
+            .lock()
+            .ok()?
+            .get(&(cid, sandbox.to_owned(), deterministic))
+            .cloned()
+    }
+
+    /// Cache the [InstancePre] of a module under a [SandboxPolicy], evicting
+    /// the oldest one if full.
+    pub(crate) fn insert_instance_pre(
+        &self,
+        cid: Cid,
+        sandbox: &SandboxPolicy,
+        deterministic: bool,
+        instance_pre: InstancePre<T>,
+    ) {
+        if let Ok(mut instances) = self.instances.lock() {
+            if instances.len() >= MAX_INSTANCES {
+                instances.shift_remove_index(0);
+            }
+            instances.insert((cid, sandbox.to_owned(), deterministic), instance_pre);
+        }
+    }
+
+    /// Compiled [Component] of a module, deserialized from the directory if
+    /// available, or otherwise compiled from its component `bytes` and
+    /// serialized into it.
+    ///
+    /// Failures to read or write the directory aren't fatal, falling back
+    /// to compiling the component.
+    pub(crate) fn component(&self, cid: Cid, bytes: &[u8]) -> Result<Component, Error> {
+        let Some(path) = self.artifact_path(cid) else {
+            return Component::from_binary(&self.engine, bytes).map_err(Error::IntoWasmComponent);
+        };
+
+        if path.is_file() {
+            // SAFETY: artifacts are only written by this cache, serialized
+            // by an engine of the same configuration and version, as
+            // ensured by the engine hash in their name, which wasmtime
+            // checks again on deserialization.
+            #[allow(unsafe_code)]
+            match unsafe { Component::deserialize_file(&self.engine, &path) } {
+                Ok(component) => {
+                    debug!(
+                        subject = "wasm.cache.hit",
+                        category = "wasm_cache",
+                        cid = cid.to_string(),
+                        "loaded compiled component from cache"
+                    );
+                    let _ = fs::File::options()
+                        .write(true)
+                        .open(&path)
+                        .and_then(|file| file.set_modified(SystemTime::now()));
+                    return Ok(component);
+                }
+                Err(err) => warn!(
+                    subject = "wasm.cache.err",
+                    category = "wasm_cache",
+                    cid = cid.to_string(),
+                    err = format!("{err:#}"),
+                    "failed to load compiled component from cache, recompiling"
+                ),
+            }
+        }
+
+        let component =
+            Component::from_binary(&self.engine, bytes).map_err(Error::IntoWasmComponent)?;
+        if let Err(err) = self.store(&path, &component) {
+            warn!(
+                subject = "wasm.cache.err",
+                category = "wasm_cache",
+                cid = cid.to_string(),
+                err = format!("{err:#}"),
+                "failed to store compiled component in cache"
+            );
+        }
+
+        Ok(component)
+    }
+
+    fn artifact_path(&self, cid: Cid) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}-{cid}.{ARTIFACT_EXTENSION}", self.engine_hash)))
+    }
+
+    fn store(&self, path: &Path, component: &Component) -> anyhow::Result<()> {
+        let artifact = component.serialize()?;
+        if artifact.len() as u64 > self.max_size {
+            return Ok(());
+        }
+
+        // Write then rename, so that partially written artifacts are never
+        // loaded by concurrent tasks.
+        let tmp = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&tmp, &artifact)?;
+        fs::rename(&tmp, path)?;
+        self.evict()
+    }
+
+    /// Remove the least recently used artifacts until the directory is
+    /// within its maximum size.
+    fn evict(&self) -> anyhow::Result<()> {
+        let Some(dir) = &self.dir else {
+            return Ok(());
+        };
+
+        let mut artifacts = fs::read_dir(dir)?
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let path = entry.path();
+                let metadata = entry.metadata().ok()?;
+                (path.extension()? == ARTIFACT_EXTENSION && metadata.is_file()).then(|| {
+                    (
+                        path,
+                        metadata.len(),
+                        metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        artifacts.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut size: u64 = artifacts.iter().map(|(_, len, _)| len).sum();
+        for (path, len, _) in artifacts {
+            if size <= self.max_size {
+                break;
+            }
+            fs::remove_file(&path)?;
+            size -= len;
+        }
+
+        Ok(())
+    }
+}
+
+/// [Cid] of a Wasm module, as a raw block hashed with SHA3-256.
+pub fn module_cid(bytes: &[u8]) -> Cid {
+    Cid::new_v1(0x55, Code::Sha3_256.digest(bytes))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wasmtime::State;
+
+    const COMPONENT: &str = r#"(component
+        (core module $m (func (export "f") (result i32) i32.const 42))
+        (core instance $i (instantiate $m))
+        (func (export "f") (result s32) (canon lift (core func $i "f")))
+    )"#;
+
+    fn artifacts(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect()
+    }
+
+    #[test]
+    fn serialize_and_evict_components() {
+        let dir = std::env::temp_dir().join(format!("homestar-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let bytes = wat::parse_str(COMPONENT).unwrap();
+        let cid = module_cid(&bytes);
+
+        let cache =
+            ComponentCache::<State>::new(Some(dir.clone()), u64::MAX, Interruption::Fuel).unwrap();
+        cache.component(cid, &bytes).unwrap();
+        let stored = artifacts(&dir);
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0]
+            .to_string_lossy()
+            .ends_with(&format!("{cid}.{ARTIFACT_EXTENSION}")));
+
+        // Loaded from disk by a new cache, e.g. after a restart.
+        let cache =
+            ComponentCache::<State>::new(Some(dir.clone()), u64::MAX, Interruption::Fuel).unwrap();
+        cache.component(cid, &bytes).unwrap();
+        assert_eq!(artifacts(&dir), stored);
+
+        // Evicted once over the maximum size.
+        let size = fs::metadata(&stored[0]).unwrap().len();
+        let cache =
+            ComponentCache::<State>::new(Some(dir.clone()), size + size / 2, Interruption::Fuel)
+                .unwrap();
+        let other = wat::parse_str(COMPONENT.replace("42", "43")).unwrap();
+        let other_cid = module_cid(&other);
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        cache.component(other_cid, &other).unwrap();
+        let stored = artifacts(&dir);
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].to_string_lossy().contains(&other_cid.to_string()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
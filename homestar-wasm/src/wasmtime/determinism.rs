@@ -0,0 +1,213 @@
+//! Deterministic execution of Wasm components, making their outputs depend
+//! only on their inputs, so that receipts are reproducible across nodes.
+
+use crate::wasmtime::SandboxError;
+use libipld::{Cid, Ipld};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use wasmtime_wasi::preview2::{HostMonotonicClock, HostWallClock};
+use wit_parser::decoding::DecodedWasm;
+
+/// Metadata key of a task requesting deterministic execution.
+const DETERMINISTIC_KEY: &str = "deterministic";
+
+/// Interfaces whose results depend on the node or the outside world, which
+/// components executed deterministically may not import.
+const NON_DETERMINISTIC_INTERFACES: &[&str] = &[
+    "homestar:host/http",
+    "homestar:host/kv",
+    "homestar:host/secrets",
+    "wasi:sockets",
+];
+
+/// Time a [VirtualClock] advances by on every read.
+const TICK: Duration = Duration::from_millis(1);
+
+/// Deterministic execution of a Wasm component.
+///
+/// NaNs are always canonicalized by the engine. When deterministic, clocks
+/// are virtual, starting at zero and advancing a fixed tick per read,
+/// `wasi:random` is seeded, environment variables and preopened
+/// directories aren't exposed, and components importing
+/// non-deterministic interfaces are rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Determinism {
+    seed: u64,
+}
+
+impl Determinism {
+    /// Create a [Determinism] seeding `wasi:random` with `seed`, unless the
+    /// sandbox policy sets a seed.
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Create a [Determinism] seeded from the [Cid] of an invocation, e.g.
+    /// of its instruction.
+    pub fn from_cid(cid: &Cid) -> Self {
+        let digest = cid.hash().digest();
+        let mut seed = [0; 8];
+        let len = digest.len().min(8);
+        seed[..len].copy_from_slice(&digest[..len]);
+        Self::new(u64::from_le_bytes(seed))
+    }
+
+    /// Read whether a task requests deterministic execution from its
+    /// metadata, if set.
+    pub fn from_meta(meta: &Ipld) -> Result<Option<bool>, SandboxError> {
+        match meta {
+            Ipld::Map(map) => match map.get(DETERMINISTIC_KEY) {
+                None | Some(Ipld::Null) => Ok(None),
+                Some(Ipld::Bool(deterministic)) => Ok(Some(*deterministic)),
+                Some(ipld) => Err(SandboxError::InvalidPolicy(format!(
+                    "`{DETERMINISTIC_KEY}` must be a boolean, got {ipld:?}"
+                ))),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Seed of `wasi:random`, unless the sandbox policy sets a seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// Reject components importing any non-deterministic interface, as well as
+/// anything whose imports can't be decoded.
+pub(crate) fn check_imports(bytes: &[u8]) -> Result<(), SandboxError> {
+    let (resolve, world) = match wit_parser::decoding::decode(bytes) {
+        Ok(DecodedWasm::Component(resolve, world)) => (resolve, world),
+        Ok(DecodedWasm::WitPackage(..)) => {
+            return Err(SandboxError::UncheckedImports(
+                "expected a component, got a WIT package".to_string(),
+            ))
+        }
+        Err(err) => return Err(SandboxError::UncheckedImports(err.to_string())),
+    };
+
+    match resolve.worlds[world]
+        .imports
+        .keys()
+        .map(|key| resolve.name_world_key(key))
+        .find(|name| {
+            NON_DETERMINISTIC_INTERFACES
+                .iter()
+                .any(|interface| name.starts_with(interface))
+        }) {
+        Some(interface) => Err(SandboxError::NonDeterministic(interface)),
+        None => Ok(()),
+    }
+}
+
+/// Virtual clock, starting at zero and advancing a fixed tick per read.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct VirtualClock(Arc<AtomicU64>);
+
+impl VirtualClock {
+    /// Read the clock, advancing it.
+    pub(crate) fn tick(&self) -> Duration {
+        let ticks = self.0.fetch_add(1, Ordering::Relaxed) + 1;
+        TICK * ticks as u32
+    }
+}
+
+impl HostWallClock for VirtualClock {
+    fn resolution(&self) -> Duration {
+        TICK
+    }
+
+    fn now(&self) -> Duration {
+        self.tick()
+    }
+}
+
+impl HostMonotonicClock for VirtualClock {
+    fn resolution(&self) -> u64 {
+        TICK.as_nanos() as u64
+    }
+
+    fn now(&self) -> u64 {
+        self.tick().as_nanos() as u64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use libipld::multihash::{Code, MultihashDigest};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn determinism_from_invocation() {
+        let cid = Cid::new_v1(0x55, Code::Sha3_256.digest(b"instruction"));
+        let other = Cid::new_v1(0x55, Code::Sha3_256.digest(b"other"));
+        assert_eq!(Determinism::from_cid(&cid), Determinism::from_cid(&cid));
+        assert_ne!(Determinism::from_cid(&cid), Determinism::from_cid(&other));
+
+        let meta = |value| Ipld::Map(BTreeMap::from([(DETERMINISTIC_KEY.into(), value)]));
+        assert_eq!(
+            Determinism::from_meta(&meta(Ipld::Bool(true))).unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            Determinism::from_meta(&Ipld::Map(BTreeMap::new())).unwrap(),
+            None
+        );
+        assert!(Determinism::from_meta(&meta(Ipld::Integer(1))).is_err());
+    }
+
+    #[test]
+    fn reject_non_deterministic_imports() {
+        let component = |import: &str| {
+            wat::parse_str(format!(
+                r#"(component
+                    {import}
+                    (core module $m (func (export "f") (result i32) i32.const 42))
+                    (core instance $i (instantiate $m))
+                    (func (export "f") (result s32) (canon lift (core func $i "f")))
+                )"#
+            ))
+            .unwrap()
+        };
+
+        assert!(check_imports(&component("")).is_ok());
+        assert!(matches!(
+            check_imports(b"not wasm"),
+            Err(SandboxError::UncheckedImports(_))
+        ));
+        assert!(check_imports(&component(
+            r#"(import "wasi:random/random@0.2.0" (instance))"#
+        ))
+        .is_ok());
+        assert!(matches!(
+            check_imports(&component(r#"(import "homestar:host/http@0.1.1" (instance))"#)),
+            Err(SandboxError::NonDeterministic(interface)) if interface == "homestar:host/http@0.1.1"
+        ));
+        assert!(matches!(
+            check_imports(&component(
+                r#"(import "homestar:host/kv@0.1.1" (instance))"#
+            )),
+            Err(SandboxError::NonDeterministic(_))
+        ));
+        assert!(matches!(
+            check_imports(&component(
+                r#"(import "homestar:host/secrets@0.1.1" (instance))"#
+            )),
+            Err(SandboxError::NonDeterministic(_))
+        ));
+    }
+
+    #[test]
+    fn virtual_clock_ticks() {
+        let clock = VirtualClock::default();
+        assert_eq!(HostWallClock::now(&clock), TICK);
+        assert_eq!(HostMonotonicClock::now(&clock), 2 * TICK.as_nanos() as u64);
+        assert_eq!(clock.clone().tick(), 3 * TICK);
+    }
+}
@@ -3,6 +3,8 @@
 //!
 //! [Wasmtime]: <https://docs.rs/wasmtime/latest/wasmtime>
 
+use std::{path::PathBuf, time::Duration};
+
 /// Generic error type for Wasm execution, conversions, instantiations, etc.
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -11,6 +13,9 @@ pub enum Error {
     /// [InterpreterError]: crate::error::InterpreterError
     #[error(transparent)]
     InterpreterError(#[from] crate::error::InterpreterError),
+    /// Breach of a resource limit set for Wasm execution.
+    #[error(transparent)]
+    LimitExceeded(#[from] LimitError),
     /// Failure to convert from Wasm binary into Wasm component.
     #[error("cannot convert from binary structure to Wasm component")]
     IntoWasmComponent(#[source] anyhow::Error),
@@ -40,6 +45,9 @@ pub enum Error {
     /// Failure to find Wasm function for execution.
     #[error("Wasm function {0} not found in given Wasm component/resource")]
     WasmFunctionNotFound(String),
+    /// Breach, or misconfiguration, of the WASI sandbox policy.
+    #[error(transparent)]
+    Sandbox(#[from] SandboxError),
     /// [Wat] as Wasm component error.
     ///
     /// [Wat]: wat
@@ -52,3 +60,69 @@ pub enum Error {
     #[error(transparent)]
     Wat(#[from] wat::Error),
 }
+
+/// Error for breaching one of the resource limits (fuel, memory, table
+/// elements, or time) set for Wasm execution.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum LimitError {
+    /// Execution ran out of fuel.
+    #[error("wasm execution exhausted its fuel limit of {0} units")]
+    OutOfFuel(u64),
+    /// Memory growth beyond the memory limit.
+    #[error("wasm memory of {desired} bytes exceeds memory limit of {limit} bytes")]
+    Memory {
+        /// Desired memory size in bytes.
+        desired: usize,
+        /// Memory limit in bytes.
+        limit: usize,
+    },
+    /// Table growth beyond the table elements limit.
+    #[error("wasm table of {desired} elements exceeds table limit of {limit} elements")]
+    Table {
+        /// Desired number of table elements.
+        desired: u32,
+        /// Table elements limit.
+        limit: u32,
+    },
+    /// Execution ran beyond its wall-clock deadline.
+    #[error("wasm execution exceeded its time limit of {} ms", .0.as_millis())]
+    Timeout(Duration),
+}
+
+/// Error setting up, or enforcing, the [SandboxPolicy] of Wasm execution.
+///
+/// [SandboxPolicy]: crate::wasmtime::SandboxPolicy
+#[derive(thiserror::Error, Debug)]
+pub enum SandboxError {
+    /// Call to a WASI function whose interface isn't granted.
+    #[error("WASI function {function} of {interface} is not granted by the sandbox policy")]
+    Denied {
+        /// Interface of the function, e.g. `wasi:random/random@0.2.0`.
+        interface: String,
+        /// Name of the function.
+        function: String,
+    },
+    /// Component importing a non-deterministic interface, executed
+    /// deterministically.
+    #[error("{0} is not allowed for deterministic execution")]
+    NonDeterministic(String),
+    /// Output of a task containing the value of a secret it read.
+    #[error("task output contains the value of secret {0}")]
+    LeakedSecret(String),
+    /// Component whose imports can't be checked for deterministic
+    /// execution.
+    #[error("cannot check imports for deterministic execution: {0}")]
+    UncheckedImports(String),
+    /// Malformed policy within task metadata.
+    #[error("invalid sandbox policy: {0}")]
+    InvalidPolicy(String),
+    /// Failure to open a host directory to preopen.
+    #[error("cannot preopen directory {path}: {source}")]
+    Preopen {
+        /// Host path of the directory.
+        path: PathBuf,
+        /// Underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+}
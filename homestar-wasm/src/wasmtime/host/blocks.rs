@@ -0,0 +1,125 @@
+//! IPLD blocks that can be read and written by guest Wasm components.
+
+use crate::wasmtime::{
+    blocks::{self, BlockError},
+    world::homestar::host::blocks as host_blocks,
+    State,
+};
+use async_trait::async_trait;
+use libipld::Cid;
+use tracing::{debug, instrument};
+
+#[async_trait]
+impl host_blocks::Host for State {
+    /// Get a block written by the component, or otherwise from the
+    /// [BlockStore] of the [State].
+    ///
+    /// [BlockStore]: crate::wasmtime::blocks::BlockStore
+    #[instrument(name = "blocks_get", skip_all)]
+    async fn get(&mut self, cid: String) -> wasmtime::Result<Result<Vec<u8>, host_blocks::Error>> {
+        let cid = match Cid::try_from(cid.as_str()) {
+            Ok(cid) => cid,
+            Err(err) => return Ok(Err(host_blocks::Error::InvalidCid(err.to_string()))),
+        };
+
+        if let Some(block) = self.written_blocks().get(&cid) {
+            return Ok(Ok(block.to_owned()));
+        }
+
+        let Some(store) = self.block_store() else {
+            return Ok(Err(host_blocks::Error::NotFound(cid.to_string())));
+        };
+        match store.get(&cid).await {
+            Ok(Some(block)) => Ok(Ok(block)),
+            Ok(None) => Ok(Err(host_blocks::Error::NotFound(cid.to_string()))),
+            Err(err) => {
+                debug!(
+                    subject = "wasm_execution.blocks",
+                    category = "blocks",
+                    cid = cid.to_string(),
+                    err = format!("{err:#}"),
+                    "failed to get block"
+                );
+                Ok(Err(host_blocks::Error::NotFound(cid.to_string())))
+            }
+        }
+    }
+
+    /// Put a block, kept with the [State] until stored with the receipt of
+    /// the task.
+    #[instrument(name = "blocks_put", skip_all)]
+    async fn put(
+        &mut self,
+        codec: u64,
+        data: Vec<u8>,
+    ) -> wasmtime::Result<Result<String, host_blocks::Error>> {
+        match blocks::block_cid(codec, &data) {
+            Ok(cid) => {
+                self.write_block(cid, data);
+                Ok(Ok(cid.to_string()))
+            }
+            Err(BlockError::TooLarge(_)) => Ok(Err(host_blocks::Error::TooLarge(
+                blocks::MAX_BLOCK_SIZE as u64,
+            ))),
+            Err(err) => Ok(Err(host_blocks::Error::InvalidBlock(err.to_string()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wasmtime::blocks::{BlockStore, DAG_CBOR, RAW};
+    use host_blocks::Host;
+    use libipld::multihash::{Code, MultihashDigest};
+    use std::{collections::HashMap, sync::Arc};
+
+    #[derive(Debug)]
+    struct MemoryStore(HashMap<Cid, Vec<u8>>);
+
+    #[async_trait]
+    impl BlockStore for MemoryStore {
+        async fn get(&self, cid: &Cid) -> anyhow::Result<Option<Vec<u8>>> {
+            Ok(self.0.get(cid).cloned())
+        }
+    }
+
+    #[tokio::test]
+    async fn get_and_put_blocks() {
+        let stored = b"stored".to_vec();
+        let stored_cid = Cid::new_v1(RAW, Code::Sha2_256.digest(&stored));
+        let mut state = State::default();
+        state.set_blocks(Arc::new(MemoryStore(HashMap::from([(
+            stored_cid,
+            stored.clone(),
+        )]))));
+
+        assert_eq!(
+            state.get(stored_cid.to_string()).await.unwrap().unwrap(),
+            stored
+        );
+
+        let written = b"written".to_vec();
+        let written_cid = state.put(RAW, written.clone()).await.unwrap().unwrap();
+        assert_eq!(
+            written_cid,
+            Cid::new_v1(RAW, Code::Sha2_256.digest(&written)).to_string()
+        );
+        assert_eq!(state.get(written_cid).await.unwrap().unwrap(), written);
+        assert_eq!(state.written_blocks().len(), 1);
+
+        assert!(matches!(
+            state.put(DAG_CBOR, written).await.unwrap(),
+            Err(host_blocks::Error::InvalidBlock(_))
+        ));
+        assert!(matches!(
+            state.get("not a cid".to_string()).await.unwrap(),
+            Err(host_blocks::Error::InvalidCid(_))
+        ));
+        let missing = Cid::new_v1(RAW, Code::Sha2_256.digest(b"missing"));
+        assert!(matches!(
+            state.get(missing.to_string()).await.unwrap(),
+            Err(host_blocks::Error::NotFound(cid)) if cid == missing.to_string()
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 //! Helper functions that can be used in guest Wasm components.
 
 use crate::wasmtime::{
+    logs::{Level, LogRecord, PRINT_CONTEXT},
     world::{homestar::host::helpers, wasi},
     State,
 };
@@ -10,10 +11,13 @@ use tracing::instrument;
 
 #[async_trait]
 impl helpers::Host for State {
-    /// Get the current time.
+    /// Get the current time, since instantiation, or of the virtual clock
+    /// when executing deterministically.
     async fn get_current_time(&mut self) -> wasmtime::Result<helpers::Time> {
-        let now = Instant::now();
-        let duration = now.duration_since(self.start_time());
+        let duration = match self.virtual_clock() {
+            Some(clock) => clock.tick(),
+            None => Instant::now().duration_since(self.start_time()),
+        };
         Ok(helpers::Time {
             seconds: duration.as_secs(),
             milliseconds: duration.subsec_millis(),
@@ -21,16 +25,23 @@ impl helpers::Host for State {
         })
     }
 
-    /// Print a message.
+    /// Print a message, captured as an info-level log record.
+    ///
+    /// Secrets read by the guest are redacted from the message.
     async fn print(&mut self, from_wasm: String) -> wasmtime::Result<()> {
+        let from_wasm = self.redact_secrets(from_wasm);
         println!("{from_wasm}");
+        self.capture_log(LogRecord::new(Level::Info, PRINT_CONTEXT, from_wasm));
         Ok(())
     }
 }
 
 #[async_trait]
 impl wasi::logging::logging::Host for State {
-    /// Log a message, formatted by the runtime subscriber.
+    /// Log a message, formatted by the runtime subscriber, and captured as
+    /// a log record.
+    ///
+    /// Secrets read by the guest are redacted from the context and message.
     #[instrument(name = "wasi_log", skip_all)]
     async fn log(
         &mut self,
@@ -38,6 +49,9 @@ impl wasi::logging::logging::Host for State {
         context: String,
         message: String,
     ) -> wasmtime::Result<()> {
+        let context = self.redact_secrets(context);
+        let message = self.redact_secrets(message);
+        let record_level = Level::from(level);
         match level {
             wasi::logging::logging::Level::Trace => {
                 tracing::trace!(
@@ -82,6 +96,49 @@ impl wasi::logging::logging::Host for State {
                 )
             }
         }
+        self.capture_log(LogRecord::new(record_level, context, message));
         Ok(())
     }
 }
+
+impl From<wasi::logging::logging::Level> for Level {
+    fn from(level: wasi::logging::logging::Level) -> Self {
+        match level {
+            wasi::logging::logging::Level::Trace => Level::Trace,
+            wasi::logging::logging::Level::Debug => Level::Debug,
+            wasi::logging::logging::Level::Info => Level::Info,
+            wasi::logging::logging::Level::Warn => Level::Warn,
+            wasi::logging::logging::Level::Error => Level::Error,
+            wasi::logging::logging::Level::Critical => Level::Critical,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use helpers::Host as _;
+    use wasi::logging::logging::Host as _;
+
+    #[tokio::test]
+    async fn capture_guest_logs() {
+        let mut state = State::default();
+        state
+            .log(
+                wasi::logging::logging::Level::Warn,
+                "guest".to_string(),
+                "careful".to_string(),
+            )
+            .await
+            .unwrap();
+        state.print("hello".to_string()).await.unwrap();
+
+        assert_eq!(
+            state.logs(),
+            [
+                LogRecord::new(Level::Warn, "guest", "careful"),
+                LogRecord::new(Level::Info, PRINT_CONTEXT, "hello"),
+            ]
+        );
+    }
+}
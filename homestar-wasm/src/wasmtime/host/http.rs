@@ -0,0 +1,278 @@
+//! Outbound HTTP requests that can be made by guest Wasm components,
+//! limited by the [HttpPolicy] of their [State].
+//!
+//! [HttpPolicy]: crate::wasmtime::HttpPolicy
+
+use crate::wasmtime::{world::homestar::host::http, State};
+use async_trait::async_trait;
+use reqwest::{Method, Url};
+use tracing::{debug, instrument};
+
+#[async_trait]
+impl http::Host for State {
+    /// Send an HTTP request allowed by the [HttpPolicy], recording its
+    /// method and URL, without anything that may carry a secret.
+    ///
+    /// [HttpPolicy]: crate::wasmtime::HttpPolicy
+    #[instrument(name = "http_fetch", skip_all)]
+    async fn fetch(
+        &mut self,
+        request: http::Request,
+    ) -> wasmtime::Result<Result<http::Response, http::Error>> {
+        let method = method(request.method);
+        let url = match Url::parse(&request.url) {
+            Ok(url) => url,
+            Err(err) => return Ok(Err(http::Error::InvalidRequest(err.to_string()))),
+        };
+        if let Err(reason) = self.http().check(method.as_str(), &url) {
+            return Ok(Err(http::Error::Denied(reason)));
+        }
+
+        let recorded_url = self.recorded_url(&url);
+        debug!(
+            subject = "wasm_execution.http",
+            category = "http",
+            method = method.as_str(),
+            url = recorded_url,
+            "sending outbound HTTP request"
+        );
+        self.record_http_request(format!("{method} {recorded_url}"));
+
+        let max_response_size = self.http().max_response_size();
+        let mut builder = self.http_client()?.request(method, url);
+        for (name, value) in request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+
+        let mut response = match builder.send().await {
+            Ok(response) => response,
+            Err(err) => return Ok(Err(error(err))),
+        };
+        if response
+            .content_length()
+            .is_some_and(|len| len > max_response_size)
+        {
+            return Ok(Err(http::Error::ResponseTooLarge(max_response_size)));
+        }
+
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+
+        let mut body = vec![];
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    if (body.len() + chunk.len()) as u64 > max_response_size {
+                        return Ok(Err(http::Error::ResponseTooLarge(max_response_size)));
+                    }
+                    body.extend_from_slice(&chunk);
+                }
+                Ok(None) => break,
+                Err(err) => return Ok(Err(error(err))),
+            }
+        }
+
+        Ok(Ok(http::Response {
+            status,
+            headers,
+            body,
+        }))
+    }
+}
+
+impl State {
+    /// URL of a request as logged and recorded in receipts: only its scheme,
+    /// host, and path, with any secret read by the guest redacted.
+    fn recorded_url(&self, url: &Url) -> String {
+        let mut recorded = url.clone();
+        let _ = recorded.set_username("");
+        let _ = recorded.set_password(None);
+        recorded.set_query(None);
+        recorded.set_fragment(None);
+        self.redact_secrets(recorded.into())
+    }
+}
+
+fn method(method: http::Method) -> Method {
+    match method {
+        http::Method::Get => Method::GET,
+        http::Method::Head => Method::HEAD,
+        http::Method::Post => Method::POST,
+        http::Method::Put => Method::PUT,
+        http::Method::Patch => Method::PATCH,
+        http::Method::Delete => Method::DELETE,
+        http::Method::Options => Method::OPTIONS,
+    }
+}
+
+fn error(err: reqwest::Error) -> http::Error {
+    if err.is_timeout() {
+        http::Error::Timeout
+    } else if err.is_builder() {
+        http::Error::InvalidRequest(err.to_string())
+    } else {
+        http::Error::Transport(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wasmtime::{
+        secrets::{Secret, SecretError, SecretStore, REDACTED},
+        world::homestar::host::secrets::Host as _,
+        HttpPolicy,
+    };
+    use http::Host;
+    use std::{sync::Arc, time::Duration};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serve a single fixed HTTP response, after an optional delay.
+    async fn serve(response: &'static str, delay: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = stream.read(&mut buf).await.unwrap();
+            tokio::time::sleep(delay).await;
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+        addr.to_string()
+    }
+
+    fn request(method: http::Method, url: String) -> http::Request {
+        http::Request {
+            method,
+            url,
+            headers: vec![("accept".to_string(), "text/plain".to_string())],
+            body: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_allowed_requests() {
+        let addr = serve(
+            "HTTP/1.1 200 OK\r\ncontent-length: 5\r\nx-test: yes\r\n\r\nhello",
+            Duration::ZERO,
+        )
+        .await;
+        let mut state = State::default();
+        state.set_http(HttpPolicy::default().with_allowed_hosts(vec![addr.clone()]));
+
+        let response = state
+            .fetch(request(http::Method::Get, format!("http://{addr}/hello")))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"hello");
+        assert!(response
+            .headers
+            .contains(&("x-test".to_string(), "yes".to_string())));
+
+        let denied = state
+            .fetch(request(http::Method::Post, format!("http://{addr}/hello")))
+            .await
+            .unwrap();
+        assert!(matches!(denied, Err(http::Error::Denied(_))));
+        let denied = state
+            .fetch(request(http::Method::Get, "http://example.com".to_string()))
+            .await
+            .unwrap();
+        assert!(matches!(denied, Err(http::Error::Denied(_))));
+
+        assert_eq!(state.http_requests(), &[format!("GET http://{addr}/hello")]);
+    }
+
+    #[tokio::test]
+    async fn record_requests_without_secrets() {
+        #[derive(Debug)]
+        struct Token;
+
+        impl SecretStore for Token {
+            fn get(&self, name: &str) -> Result<Secret, SecretError> {
+                match name {
+                    "token" => Ok(Secret::new("hunter2")),
+                    _ => Err(SecretError::NotFound(name.to_string())),
+                }
+            }
+        }
+
+        let addr = serve(
+            "HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n",
+            Duration::ZERO,
+        )
+        .await;
+        let mut state = State::default();
+        state.set_http(HttpPolicy::default().with_allowed_hosts(vec![addr.clone()]));
+        state.set_secrets(vec!["token".to_string()], Arc::new(Token));
+        let token = state.get("token".to_string()).await.unwrap().unwrap();
+
+        let response = state
+            .fetch(request(
+                http::Method::Get,
+                format!("http://user:{token}@{addr}/keys/{token}?api_key={token}#{token}"),
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            state.http_requests(),
+            &[format!("GET http://{addr}/keys/{REDACTED}")]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_within_limits() {
+        let addr = serve(
+            "HTTP/1.1 200 OK\r\ncontent-length: 11\r\n\r\nhello world",
+            Duration::ZERO,
+        )
+        .await;
+        let mut state = State::default();
+        state.set_http(
+            HttpPolicy::default()
+                .with_allowed_hosts(vec![addr.clone()])
+                .with_max_response_size(5),
+        );
+        let too_large = state
+            .fetch(request(http::Method::Get, format!("http://{addr}/")))
+            .await
+            .unwrap();
+        assert!(matches!(too_large, Err(http::Error::ResponseTooLarge(5))));
+
+        let addr = serve(
+            "HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n",
+            Duration::from_secs(5),
+        )
+        .await;
+        let mut state = State::default();
+        state.set_http(
+            HttpPolicy::default()
+                .with_allowed_hosts(vec![addr.clone()])
+                .with_timeout(Duration::from_millis(100)),
+        );
+        let timeout = state
+            .fetch(request(http::Method::Get, format!("http://{addr}/")))
+            .await
+            .unwrap();
+        assert!(matches!(timeout, Err(http::Error::Timeout)));
+    }
+}
@@ -0,0 +1,214 @@
+//! Key-value store that can be read and written by guest Wasm components.
+
+use crate::wasmtime::{
+    kv::{self, KvError, KvOp, KvStore},
+    world::homestar::host::kv as host_kv,
+    State,
+};
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+impl From<KvError> for host_kv::Error {
+    fn from(err: KvError) -> Self {
+        match err {
+            KvError::KeyTooLarge(_) => host_kv::Error::KeyTooLarge(kv::MAX_KEY_SIZE as u64),
+            KvError::ValueTooLarge(_) => host_kv::Error::ValueTooLarge(kv::MAX_VALUE_SIZE as u64),
+            KvError::QuotaExceeded(quota) => host_kv::Error::QuotaExceeded(quota),
+            KvError::Unavailable(reason) => host_kv::Error::Unavailable(reason),
+        }
+    }
+}
+
+impl State {
+    /// Record an operation on the [KvStore] of the [State], returning it,
+    /// if any.
+    fn kv_op(&mut self, op: KvOp) -> Result<Arc<dyn KvStore>, host_kv::Error> {
+        let Some(store) = self.kv_store() else {
+            return Err(host_kv::Error::Denied(
+                "no key-value store for this task".to_string(),
+            ));
+        };
+
+        debug!(
+            subject = "wasm_execution.kv",
+            category = "kv",
+            op = op.to_string(),
+            "key-value operation"
+        );
+        self.record_kv_op(op);
+        Ok(store)
+    }
+}
+
+#[async_trait]
+impl host_kv::Host for State {
+    /// Get the value of a key from the [KvStore] of the [State].
+    ///
+    /// [KvStore]: crate::wasmtime::kv::KvStore
+    #[instrument(name = "kv_get", skip_all)]
+    async fn get(
+        &mut self,
+        key: String,
+    ) -> wasmtime::Result<Result<Option<Vec<u8>>, host_kv::Error>> {
+        if let Err(err) = kv::check_size(&key, None) {
+            return Ok(Err(err.into()));
+        }
+        let store = match self.kv_op(KvOp::Get(key.clone())) {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err)),
+        };
+        Ok(store.get(&key).await.map_err(Into::into))
+    }
+
+    /// Set the value of a key in the [KvStore] of the [State].
+    ///
+    /// [KvStore]: crate::wasmtime::kv::KvStore
+    #[instrument(name = "kv_set", skip_all)]
+    async fn set(
+        &mut self,
+        key: String,
+        value: Vec<u8>,
+    ) -> wasmtime::Result<Result<(), host_kv::Error>> {
+        if let Err(err) = kv::check_size(&key, Some(&value)) {
+            return Ok(Err(err.into()));
+        }
+        let store = match self.kv_op(KvOp::set(&key, &value)) {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err)),
+        };
+        Ok(store.set(&key, &value).await.map_err(Into::into))
+    }
+
+    /// Delete a key from the [KvStore] of the [State].
+    ///
+    /// [KvStore]: crate::wasmtime::kv::KvStore
+    #[instrument(name = "kv_delete", skip_all)]
+    async fn delete(&mut self, key: String) -> wasmtime::Result<Result<bool, host_kv::Error>> {
+        if let Err(err) = kv::check_size(&key, None) {
+            return Ok(Err(err.into()));
+        }
+        let store = match self.kv_op(KvOp::Delete(key.clone())) {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err)),
+        };
+        Ok(store.delete(&key).await.map_err(Into::into))
+    }
+
+    /// List the keys starting with a prefix in the [KvStore] of the
+    /// [State].
+    ///
+    /// [KvStore]: crate::wasmtime::kv::KvStore
+    #[instrument(name = "kv_keys", skip_all)]
+    async fn keys(
+        &mut self,
+        prefix: String,
+    ) -> wasmtime::Result<Result<Vec<String>, host_kv::Error>> {
+        if let Err(err) = kv::check_size(&prefix, None) {
+            return Ok(Err(err.into()));
+        }
+        let store = match self.kv_op(KvOp::Keys(prefix.clone())) {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err)),
+        };
+        Ok(store.keys(&prefix).await.map_err(Into::into))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use host_kv::Host;
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    #[derive(Debug, Default)]
+    struct MemoryStore(Mutex<BTreeMap<String, Vec<u8>>>);
+
+    #[async_trait]
+    impl KvStore for MemoryStore {
+        async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, KvError> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        async fn set(&self, key: &str, value: &[u8]) -> Result<(), KvError> {
+            let mut map = self.0.lock().unwrap();
+            if map.values().map(Vec::len).sum::<usize>() + value.len() > 8 {
+                return Err(KvError::QuotaExceeded(8));
+            }
+            map.insert(key.to_string(), value.to_vec());
+            Ok(())
+        }
+
+        async fn delete(&self, key: &str) -> Result<bool, KvError> {
+            Ok(self.0.lock().unwrap().remove(key).is_some())
+        }
+
+        async fn keys(&self, prefix: &str) -> Result<Vec<String>, KvError> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|key| key.starts_with(prefix))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn get_and_set_keys() {
+        let mut state = State::default();
+        assert!(matches!(
+            state.get("count".to_string()).await.unwrap(),
+            Err(host_kv::Error::Denied(_))
+        ));
+
+        state.set_kv(Arc::new(MemoryStore::default()));
+        state
+            .set("count".to_string(), vec![1])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            state.get("count".to_string()).await.unwrap().unwrap(),
+            Some(vec![1])
+        );
+        assert_eq!(
+            state.keys("co".to_string()).await.unwrap().unwrap(),
+            vec!["count".to_string()]
+        );
+        assert!(state.delete("count".to_string()).await.unwrap().unwrap());
+        assert_eq!(state.get("count".to_string()).await.unwrap().unwrap(), None);
+
+        assert!(matches!(
+            state.set("big".to_string(), vec![0; 9]).await.unwrap(),
+            Err(host_kv::Error::QuotaExceeded(8))
+        ));
+        assert!(matches!(
+            state
+                .set("k".repeat(kv::MAX_KEY_SIZE + 1), vec![])
+                .await
+                .unwrap(),
+            Err(host_kv::Error::KeyTooLarge(_))
+        ));
+
+        assert_eq!(
+            state.kv_ops(),
+            vec![
+                KvOp::set("count", &[1]),
+                KvOp::Get("count".to_string()),
+                KvOp::Keys("co".to_string()),
+                KvOp::Delete("count".to_string()),
+                KvOp::Get("count".to_string()),
+                KvOp::set("big", &[0; 9]),
+            ]
+        );
+        assert_eq!(
+            KvOp::Keys("co".to_string()).to_string(),
+            "keys co".to_string()
+        );
+    }
+}
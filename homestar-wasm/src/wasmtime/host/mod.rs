@@ -1,3 +1,7 @@
 //! Host-based modules for the Wasmtime runtime.
 
+mod blocks;
 mod helpers;
+mod http;
+mod kv;
+mod secrets;
@@ -0,0 +1,100 @@
+//! Secrets that can be read by guest Wasm components.
+
+use crate::wasmtime::{
+    secrets::SecretError, world::homestar::host::secrets as host_secrets, State,
+};
+use async_trait::async_trait;
+use tracing::{debug, instrument};
+
+#[async_trait]
+impl host_secrets::Host for State {
+    /// Get a secret requested by the task from the [SecretStore] of the
+    /// [State], recording its name.
+    ///
+    /// [SecretStore]: crate::wasmtime::secrets::SecretStore
+    #[instrument(name = "secrets_get", skip_all)]
+    async fn get(&mut self, name: String) -> wasmtime::Result<Result<String, host_secrets::Error>> {
+        let store = match self.secret_store() {
+            Some(store) if self.secret_names().contains(&name) => store,
+            _ => return Ok(Err(host_secrets::Error::Denied(name))),
+        };
+
+        debug!(
+            subject = "wasm_execution.secrets",
+            category = "secrets",
+            name = name,
+            "secret accessed"
+        );
+        match store.get(&name) {
+            Ok(secret) => {
+                let value = secret.expose().to_string();
+                self.record_secret_access(name, secret);
+                Ok(Ok(value))
+            }
+            Err(SecretError::Denied(name)) => Ok(Err(host_secrets::Error::Denied(name))),
+            Err(SecretError::NotFound(name)) => Ok(Err(host_secrets::Error::NotFound(name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wasmtime::{
+        secrets::{Secret, SecretError, SecretStore, REDACTED},
+        world::homestar::host::helpers::Host as _,
+    };
+    use host_secrets::Host;
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct Token;
+
+    impl SecretStore for Token {
+        fn get(&self, name: &str) -> Result<Secret, SecretError> {
+            match name {
+                "token" => Ok(Secret::new("hunter2")),
+                "ungranted" => Err(SecretError::Denied(name.to_string())),
+                _ => Err(SecretError::NotFound(name.to_string())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn get_requested_secrets() {
+        let mut state = State::default();
+        assert!(matches!(
+            state.get("token".to_string()).await.unwrap(),
+            Err(host_secrets::Error::Denied(_))
+        ));
+
+        state.set_secrets(
+            vec![
+                "token".to_string(),
+                "missing".to_string(),
+                "ungranted".to_string(),
+            ],
+            Arc::new(Token),
+        );
+        assert_eq!(
+            state.get("token".to_string()).await.unwrap().unwrap(),
+            "hunter2"
+        );
+        assert!(matches!(
+            state.get("missing".to_string()).await.unwrap(),
+            Err(host_secrets::Error::NotFound(_))
+        ));
+        assert!(matches!(
+            state.get("ungranted".to_string()).await.unwrap(),
+            Err(host_secrets::Error::Denied(_))
+        ));
+        assert!(matches!(
+            state.get("other".to_string()).await.unwrap(),
+            Err(host_secrets::Error::Denied(_))
+        ));
+        assert_eq!(state.secrets_accessed(), vec!["token".to_string()]);
+
+        state.print("using hunter2".to_string()).await.unwrap();
+        assert_eq!(state.logs()[0].message(), format!("using {REDACTED}"));
+    }
+}
@@ -0,0 +1,178 @@
+//! Policy for outbound HTTP requests made by Wasm components through the
+//! `homestar:host/http` interface.
+
+use reqwest::Url;
+use std::time::Duration;
+
+/// Default maximum size of a response body, in bytes.
+const DEFAULT_MAX_RESPONSE_SIZE: u64 = 1024 * 1024;
+/// Default timeout of a request, including reading its response.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Policy of which outbound HTTP requests a Wasm component may make.
+///
+/// The default policy allows no hosts, so denies every request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpPolicy {
+    /// Hosts requests may be sent to, either as `host`, `host:port`, or
+    /// `*.domain` to allow any subdomain of `domain`.
+    allowed_hosts: Vec<String>,
+    /// Methods requests may use, e.g. `GET`.
+    allowed_methods: Vec<String>,
+    /// Maximum size of a response body, in bytes.
+    max_response_size: u64,
+    /// Timeout of a request, including reading its response.
+    timeout: Duration,
+}
+
+impl Default for HttpPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: vec![],
+            allowed_methods: vec!["GET".to_string(), "HEAD".to_string()],
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl HttpPolicy {
+    /// Set the hosts requests may be sent to, either as `host`,
+    /// `host:port`, or `*.domain`.
+    pub fn with_allowed_hosts(mut self, hosts: Vec<String>) -> Self {
+        self.allowed_hosts = hosts;
+        self
+    }
+
+    /// Set the methods requests may use.
+    pub fn with_allowed_methods(mut self, methods: Vec<String>) -> Self {
+        self.allowed_methods = methods;
+        self
+    }
+
+    /// Set the maximum size of a response body, in bytes.
+    pub fn with_max_response_size(mut self, max_response_size: u64) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+
+    /// Set the timeout of a request, including reading its response.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Hosts requests may be sent to.
+    pub fn allowed_hosts(&self) -> &[String] {
+        &self.allowed_hosts
+    }
+
+    /// Methods requests may use.
+    pub fn allowed_methods(&self) -> &[String] {
+        &self.allowed_methods
+    }
+
+    /// Maximum size of a response body, in bytes.
+    pub fn max_response_size(&self) -> u64 {
+        self.max_response_size
+    }
+
+    /// Timeout of a request, including reading its response.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Check that a request with the given method and [Url] is allowed,
+    /// returning why it isn't otherwise.
+    pub(crate) fn check(&self, method: &str, url: &Url) -> Result<(), String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("scheme {} is not allowed", url.scheme()));
+        }
+
+        if !self
+            .allowed_methods
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(method))
+        {
+            return Err(format!("method {method} is not allowed"));
+        }
+
+        // Hosts are case-insensitive.
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let port = url.port_or_known_default();
+        let allowed = self.allowed_hosts.iter().any(|allowed| {
+            let (allowed_host, allowed_port) = match allowed
+                .rsplit_once(':')
+                .and_then(|(host, port)| Some((host, port.parse::<u16>().ok()?)))
+            {
+                Some((host, port)) => (host, Some(port)),
+                None => (allowed.as_str(), None),
+            };
+            let allowed_host = allowed_host.to_ascii_lowercase();
+            let host_matches = match allowed_host.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.ends_with('.')),
+                None => host == allowed_host,
+            };
+            host_matches && allowed_port.map_or(true, |allowed| Some(allowed) == port)
+        });
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(format!("host {host} is not allowed"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_allowed_requests() {
+        let policy = HttpPolicy::default().with_allowed_hosts(vec![
+            "api.internal".to_string(),
+            "127.0.0.1:8080".to_string(),
+            "*.svc.internal".to_string(),
+            "[::1]".to_string(),
+        ]);
+        let url = |url| Url::parse(url).unwrap();
+
+        assert!(policy.check("GET", &url("http://api.internal/v1")).is_ok());
+        assert!(policy.check("head", &url("https://API.internal")).is_ok());
+        assert!(policy.check("GET", &url("http://127.0.0.1:8080/")).is_ok());
+        assert!(policy.check("GET", &url("http://[::1]:3000/")).is_ok());
+        assert!(policy
+            .check("GET", &url("http://a.b.svc.internal/"))
+            .is_ok());
+
+        assert!(policy
+            .check("POST", &url("http://api.internal/v1"))
+            .is_err());
+        assert!(policy.check("GET", &url("http://127.0.0.1:8081/")).is_err());
+        assert!(policy.check("GET", &url("http://svc.internal/")).is_err());
+        assert!(policy
+            .check("GET", &url("http://evilsvc.internal/"))
+            .is_err());
+        assert!(policy.check("GET", &url("ftp://api.internal/")).is_err());
+        assert!(HttpPolicy::default()
+            .check("GET", &url("http://api.internal/v1"))
+            .is_err());
+    }
+
+    #[test]
+    fn match_wildcard_hosts_case_insensitively() {
+        let policy = HttpPolicy::default().with_allowed_hosts(vec!["*.Example.com".to_string()]);
+        let url = |url| Url::parse(url).unwrap();
+
+        assert!(policy
+            .check("GET", &url("https://API.Example.com/v1"))
+            .is_ok());
+        assert!(policy
+            .check("GET", &url("https://api.example.com/"))
+            .is_ok());
+        assert!(policy.check("GET", &url("https://example.com/")).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Interruption of long-running Wasm execution, via fuel metering, epochs,
+//! or both.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{Mutex, Once, PoisonError},
+    thread,
+    time::Duration,
+};
+use wasmtime::{Engine, EngineWeak};
+
+/// Interval at which the epoch of an [Engine] interrupting via epochs is
+/// incremented, bounding how late execution is preempted or killed past
+/// its deadline.
+pub const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Engines whose epochs are incremented by the process-wide ticker.
+static TICKED_ENGINES: Mutex<Vec<EngineWeak>> = Mutex::new(Vec::new());
+/// Start of the process-wide ticker, on the first engine ticked.
+static TICKER: Once = Once::new();
+
+/// How Wasm execution is interrupted, set per [Engine].
+///
+/// Fuel metering counts every instruction, bounding the computation a task
+/// performs deterministically, at a cost to its speed. Epoch interruption
+/// is checked at function entries and loop headers as a runtime ticker
+/// advances, yielding to the async executor on every tick and killing
+/// execution precisely at its time limit, at a fraction of the cost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interruption {
+    /// Meter fuel, yielding every so many instructions.
+    #[default]
+    Fuel,
+    /// Check epochs, yielding on every tick.
+    Epoch,
+    /// Meter fuel and check epochs.
+    Both,
+}
+
+impl Interruption {
+    /// Whether fuel is metered.
+    pub fn fuel(&self) -> bool {
+        matches!(self, Self::Fuel | Self::Both)
+    }
+
+    /// Whether epochs are checked.
+    pub fn epoch(&self) -> bool {
+        matches!(self, Self::Epoch | Self::Both)
+    }
+}
+
+/// Increment the epoch of an [Engine] every [EPOCH_TICK] until it's
+/// dropped.
+///
+/// Every engine is ticked by a single process-wide thread, started with
+/// the first one, rather than a thread per engine.
+pub(crate) fn tick_epochs(engine: &Engine) {
+    TICKED_ENGINES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(engine.weak());
+
+    TICKER.call_once(|| {
+        thread::Builder::new()
+            .name("wasm-epoch-ticker".to_string())
+            .spawn(|| loop {
+                thread::sleep(EPOCH_TICK);
+                TICKED_ENGINES
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .retain(|engine| match engine.upgrade() {
+                        Some(engine) => {
+                            engine.increment_epoch();
+                            true
+                        }
+                        None => false,
+                    });
+            })
+            .expect("failed to spawn epoch ticker thread");
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interruption_modes() {
+        assert!(Interruption::default().fuel());
+        assert!(!Interruption::default().epoch());
+        assert!(!Interruption::Epoch.fuel());
+        assert!(Interruption::Both.fuel() && Interruption::Both.epoch());
+
+        let modes: Vec<Interruption> = serde_json::from_str(r#"["fuel","epoch","both"]"#).unwrap();
+        assert_eq!(
+            modes,
+            vec![Interruption::Fuel, Interruption::Epoch, Interruption::Both]
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! Key-value store of small shared state, read and written by Wasm
+//! components through the `homestar:host/kv` interface.
+
+use crate::wasmtime::blocks::RAW;
+use async_trait::async_trait;
+use libipld::{
+    multihash::{Code, MultihashDigest},
+    Cid,
+};
+use std::fmt;
+
+/// Maximum size of a key, in bytes.
+pub const MAX_KEY_SIZE: usize = 1024;
+
+/// Maximum size of a value, in bytes.
+pub const MAX_VALUE_SIZE: usize = 64 * 1024;
+
+/// Key-value store of a Wasm component, scoped by the node, e.g. to the
+/// workflow of its task.
+#[async_trait]
+pub trait KvStore: fmt::Debug + Send + Sync {
+    /// Get the value of a key, if set.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, KvError>;
+
+    /// Set the value of a key, within the store's quota.
+    async fn set(&self, key: &str, value: &[u8]) -> Result<(), KvError>;
+
+    /// Delete a key, returning whether it was set.
+    async fn delete(&self, key: &str) -> Result<bool, KvError>;
+
+    /// Keys starting with a prefix, in order.
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>, KvError>;
+}
+
+/// Error reading or writing a [KvStore].
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum KvError {
+    /// Key larger than [MAX_KEY_SIZE].
+    #[error("key of {0} bytes exceeds the limit of {MAX_KEY_SIZE} bytes")]
+    KeyTooLarge(usize),
+    /// Value larger than [MAX_VALUE_SIZE].
+    #[error("value of {0} bytes exceeds the limit of {MAX_VALUE_SIZE} bytes")]
+    ValueTooLarge(usize),
+    /// Write exceeding the quota of the store, in bytes.
+    #[error("write exceeds the key-value quota of {0} bytes")]
+    QuotaExceeded(u64),
+    /// Store failing, or not available.
+    #[error("key-value store unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// Operation on a [KvStore], recorded for the receipt of its task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KvOp {
+    /// Read of a key.
+    Get(String),
+    /// Write of a key, with the (raw, SHA2-256) [Cid] of its value.
+    Set(String, Cid),
+    /// Deletion of a key.
+    Delete(String),
+    /// Listing of keys by prefix.
+    Keys(String),
+}
+
+impl KvOp {
+    /// Record a write of a key.
+    pub(crate) fn set(key: &str, value: &[u8]) -> Self {
+        Self::Set(
+            key.to_string(),
+            Cid::new_v1(RAW, Code::Sha2_256.digest(value)),
+        )
+    }
+}
+
+impl fmt::Display for KvOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KvOp::Get(key) => write!(f, "get {key}"),
+            KvOp::Set(key, value) => write!(f, "set {key} {value}"),
+            KvOp::Delete(key) => write!(f, "delete {key}"),
+            KvOp::Keys(prefix) => write!(f, "keys {prefix}"),
+        }
+    }
+}
+
+/// Validate the size of a key and, if written, its value.
+pub(crate) fn check_size(key: &str, value: Option<&[u8]>) -> Result<(), KvError> {
+    if key.len() > MAX_KEY_SIZE {
+        return Err(KvError::KeyTooLarge(key.len()));
+    }
+    match value {
+        Some(value) if value.len() > MAX_VALUE_SIZE => Err(KvError::ValueTooLarge(value.len())),
+        _ => Ok(()),
+    }
+}
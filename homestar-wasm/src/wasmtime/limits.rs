@@ -4,6 +4,7 @@
 //! This is a convenience type included to avoid needing to implement the
 //! [wasmtime::ResourceLimiterAsync] trait.
 
+use crate::wasmtime::LimitError;
 use async_trait::async_trait;
 use homestar_invocation::consts;
 use wasmtime::ResourceLimiterAsync;
@@ -16,6 +17,7 @@ pub struct StoreLimitsAsync {
     max_memory_size: Option<usize>,
     max_table_elements: Option<u32>,
     memory_consumed: u64,
+    exceeded: Option<LimitError>,
 }
 
 impl Default for StoreLimitsAsync {
@@ -24,6 +26,7 @@ impl Default for StoreLimitsAsync {
             max_memory_size: Some(consts::WASM_MAX_MEMORY as usize),
             max_table_elements: None,
             memory_consumed: 0,
+            exceeded: None,
         }
     }
 }
@@ -44,6 +47,8 @@ impl ResourceLimiterAsync for StoreLimitsAsync {
         if can_grow {
             self.memory_consumed =
                 (self.memory_consumed as i64 + (desired as i64 - current as i64)) as u64;
+        } else if let Some(limit) = self.max_memory_size {
+            self.exceeded = Some(LimitError::Memory { desired, limit });
         }
         Ok(can_grow)
     }
@@ -59,6 +64,11 @@ impl ResourceLimiterAsync for StoreLimitsAsync {
         } else {
             true
         };
+        if !can_grow {
+            if let Some(limit) = self.max_table_elements {
+                self.exceeded = Some(LimitError::Table { desired, limit });
+            }
+        }
         Ok(can_grow)
     }
 }
@@ -70,6 +80,7 @@ impl StoreLimitsAsync {
             max_memory_size,
             max_table_elements,
             memory_consumed: 0,
+            exceeded: None,
         }
     }
 
@@ -77,4 +88,9 @@ impl StoreLimitsAsync {
     pub fn memory_consumed(&self) -> u64 {
         self.memory_consumed
     }
+
+    /// Last memory or table limit breached, i.e. growth that was denied.
+    pub fn exceeded(&self) -> Option<&LimitError> {
+        self.exceeded.as_ref()
+    }
 }
@@ -0,0 +1,299 @@
+//! Log records of guest Wasm components, written through
+//! `wasi:logging/logging`, `homestar:host/helpers.print`, or to `stdout` and
+//! `stderr`, captured per task.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, mem,
+    sync::{Arc, Mutex},
+};
+use wasmtime_wasi::preview2::{HostOutputStream, StdoutStream, StreamResult, Subscribe};
+
+/// Default maximum size of the log records captured for a task, in bytes.
+pub const MAX_LOG_SIZE: usize = 64 * 1024;
+
+/// Size counted for every [LogRecord] on top of its context and message, in
+/// bytes, so that empty records still count against the size limit.
+pub const RECORD_OVERHEAD: usize = 16;
+
+/// Context of records logged via `helpers.print`.
+pub(crate) const PRINT_CONTEXT: &str = "print";
+
+/// Context of records written to `stdout`.
+pub(crate) const STDOUT_CONTEXT: &str = "stdout";
+
+/// Context of records written to `stderr`.
+pub(crate) const STDERR_CONTEXT: &str = "stderr";
+
+/// Bytes a guest may write to an [OutputPipe] at once.
+const OUTPUT_PERMIT: usize = 1024 * 1024;
+
+/// Level of a [LogRecord], as defined by `wasi:logging`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// Trace level.
+    Trace,
+    /// Debug level.
+    Debug,
+    /// Info level.
+    Info,
+    /// Warn level.
+    Warn,
+    /// Error level.
+    Error,
+    /// Critical level.
+    Critical,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+            Level::Critical => "critical",
+        };
+        f.write_str(level)
+    }
+}
+
+/// Record logged by a guest Wasm component.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogRecord {
+    level: Level,
+    context: String,
+    message: String,
+}
+
+impl LogRecord {
+    /// Create a new [LogRecord].
+    pub fn new(level: Level, context: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            context: context.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Level of the record.
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Context of the record, e.g. the module or subsystem it's from.
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+
+    /// Message of the record.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Size of the record, in bytes, counted against the size limit,
+    /// including a fixed [RECORD_OVERHEAD].
+    pub fn size(&self) -> usize {
+        RECORD_OVERHEAD + self.context.len() + self.message.len()
+    }
+}
+
+/// Sink log records are streamed to, live, as guests log them.
+pub trait LogSink: fmt::Debug + Send + Sync {
+    /// Receive a record logged by a guest.
+    fn log(&self, record: &LogRecord);
+}
+
+/// Log records captured for a task, up to a maximum size.
+#[derive(Clone, Debug)]
+pub(crate) struct LogCapture {
+    records: Vec<LogRecord>,
+    size: usize,
+    max_size: usize,
+    dropped: usize,
+    sink: Option<Arc<dyn LogSink>>,
+}
+
+impl Default for LogCapture {
+    fn default() -> Self {
+        Self {
+            records: vec![],
+            size: 0,
+            max_size: MAX_LOG_SIZE,
+            dropped: 0,
+            sink: None,
+        }
+    }
+}
+
+impl LogCapture {
+    pub(crate) fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+    }
+
+    pub(crate) fn set_sink(&mut self, sink: Arc<dyn LogSink>) {
+        self.sink = Some(sink);
+    }
+
+    /// Capture a record, streaming it to the sink, unless it would exceed
+    /// the maximum size, in which case it's dropped.
+    pub(crate) fn capture(&mut self, record: LogRecord) {
+        if self.size + record.size() > self.max_size {
+            self.dropped += 1;
+            return;
+        }
+
+        if let Some(sink) = &self.sink {
+            sink.log(&record);
+        }
+        self.size += record.size();
+        self.records.push(record);
+    }
+
+    pub(crate) fn records(&self) -> &[LogRecord] {
+        &self.records
+    }
+
+    pub(crate) fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+/// `stdout` or `stderr` of a guest, buffered until captured as log records
+/// rather than inherited from the host, so that what's written goes through
+/// the same redaction and size limit as other logs.
+///
+/// Output past the maximum size is discarded.
+#[derive(Clone, Debug)]
+pub(crate) struct OutputPipe(Arc<Mutex<OutputBuffer>>);
+
+#[derive(Debug)]
+struct OutputBuffer {
+    bytes: Vec<u8>,
+    max_size: usize,
+}
+
+impl Default for OutputPipe {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(OutputBuffer {
+            bytes: vec![],
+            max_size: MAX_LOG_SIZE,
+        })))
+    }
+}
+
+impl OutputPipe {
+    pub(crate) fn set_max_size(&self, max_size: usize) {
+        self.0.lock().unwrap().max_size = max_size;
+    }
+
+    /// Take the lines written so far, including any last unterminated one.
+    pub(crate) fn take_lines(&self) -> Vec<String> {
+        let bytes = mem::take(&mut self.0.lock().unwrap().bytes);
+        String::from_utf8_lossy(&bytes)
+            .lines()
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+
+impl StdoutStream for OutputPipe {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+impl HostOutputStream for OutputPipe {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        let mut buffer = self.0.lock().unwrap();
+        let len = bytes
+            .len()
+            .min(buffer.max_size.saturating_sub(buffer.bytes.len()));
+        buffer.bytes.extend_from_slice(&bytes[..len]);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(OUTPUT_PERMIT)
+    }
+}
+
+#[async_trait]
+impl Subscribe for OutputPipe {
+    async fn ready(&mut self) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct MemorySink(Mutex<Vec<LogRecord>>);
+
+    impl LogSink for MemorySink {
+        fn log(&self, record: &LogRecord) {
+            self.0.lock().unwrap().push(record.to_owned());
+        }
+    }
+
+    #[test]
+    fn capture_up_to_max_size() {
+        let sink = Arc::new(MemorySink::default());
+        let mut logs = LogCapture::default();
+        logs.set_max_size(2 * RECORD_OVERHEAD + 16);
+        logs.set_sink(sink.clone());
+
+        logs.capture(LogRecord::new(Level::Info, "ctx", "hello"));
+        logs.capture(LogRecord::new(Level::Error, "ctx", "too long to fit"));
+        logs.capture(LogRecord::new(Level::Warn, "", "world"));
+
+        let records = vec![
+            LogRecord::new(Level::Info, "ctx", "hello"),
+            LogRecord::new(Level::Warn, "", "world"),
+        ];
+        assert_eq!(logs.records(), records);
+        assert_eq!(logs.dropped(), 1);
+        assert_eq!(*sink.0.lock().unwrap(), records);
+        assert_eq!(Level::Critical.to_string(), "critical");
+    }
+
+    #[test]
+    fn count_empty_records_against_max_size() {
+        let sink = Arc::new(MemorySink::default());
+        let mut logs = LogCapture::default();
+        logs.set_sink(sink.clone());
+
+        for _ in 0..10_000 {
+            logs.capture(LogRecord::new(Level::Info, "", ""));
+        }
+
+        let captured = MAX_LOG_SIZE / RECORD_OVERHEAD;
+        assert_eq!(logs.records().len(), captured);
+        assert_eq!(logs.dropped(), 10_000 - captured);
+        assert_eq!(sink.0.lock().unwrap().len(), captured);
+    }
+
+    #[test]
+    fn buffer_output_up_to_max_size() {
+        let mut pipe = OutputPipe::default();
+        pipe.set_max_size(15);
+        pipe.write(Bytes::from_static(b"hello\nworld")).unwrap();
+        pipe.write(Bytes::from_static(b"\ntoo long to fit"))
+            .unwrap();
+
+        assert_eq!(pipe.take_lines(), ["hello", "world", "too"]);
+        assert!(pipe.take_lines().is_empty());
+    }
+}
@@ -3,12 +3,26 @@
 //!
 //! [Wasmtime]: <https://wasmtime.dev/>
 
+pub mod blocks;
+mod cache;
 pub mod config;
+pub mod determinism;
 mod error;
 mod host;
+pub mod http;
+pub mod interrupt;
 pub mod ipld;
+pub mod kv;
 pub mod limits;
+pub mod logs;
+pub mod sandbox;
+pub mod secrets;
 pub mod world;
 
+pub use cache::{module_cid, ComponentCache};
+pub use determinism::Determinism;
 pub use error::*;
+pub use http::HttpPolicy;
+pub use interrupt::Interruption;
+pub use sandbox::{Preopen, SandboxPolicy};
 pub use world::{State, World};
@@ -0,0 +1,532 @@
+//! Sandbox policy deciding which [WASI preview 2] interfaces a Wasm
+//! component is granted.
+//!
+//! WASI is opt-in: unless a policy enables it, and for any interface it
+//! doesn't grant, a component's WASI imports are linked to stubs that trap
+//! when called.
+//!
+//! [WASI preview 2]: <https://github.com/WebAssembly/WASI/tree/main/preview2>
+
+use crate::wasmtime::{determinism::VirtualClock, logs::OutputPipe, Error, SandboxError};
+use libipld::{serde::from_ipld, Ipld};
+use rand_chacha::{rand_core::SeedableRng, ChaCha20Rng};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+use wasmtime::component::{Component, Linker, ResourceType};
+use wasmtime_wasi::preview2::{
+    bindings as wasi, DirPerms, FilePerms, WasiCtx, WasiCtxBuilder, WasiView,
+};
+use wit_parser::{decoding::DecodedWasm, TypeDefKind, WorldItem};
+
+/// Task metadata key for a requested [SandboxPolicy].
+const SANDBOX_KEY: &str = "sandbox";
+
+/// WASI packages whose imports are stubbed out unless granted.
+const WASI_PACKAGES: &[&str] = &[
+    "wasi:cli",
+    "wasi:clocks",
+    "wasi:filesystem",
+    "wasi:io",
+    "wasi:random",
+    "wasi:sockets",
+];
+
+/// Host directory preopened for a Wasm component.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preopen {
+    /// Path of the directory on the host.
+    ///
+    /// Ignored when requested by a task, which can only select among the
+    /// directories preopened by the node.
+    host: PathBuf,
+    /// Path the directory is exposed at to the guest.
+    guest: String,
+    /// Whether the guest can create, modify, and remove files.
+    writable: bool,
+}
+
+impl Preopen {
+    /// Create a new [Preopen].
+    pub fn new(host: impl Into<PathBuf>, guest: impl Into<String>, writable: bool) -> Self {
+        Self {
+            host: host.into(),
+            guest: guest.into(),
+            writable,
+        }
+    }
+
+    /// Path of the directory on the host.
+    pub fn host(&self) -> &PathBuf {
+        &self.host
+    }
+
+    /// Path the directory is exposed at to the guest.
+    pub fn guest(&self) -> &str {
+        &self.guest
+    }
+
+    /// Whether the guest can create, modify, and remove files.
+    pub fn writable(&self) -> bool {
+        self.writable
+    }
+}
+
+/// Policy of which WASI preview 2 interfaces are granted to a Wasm
+/// component.
+///
+/// Once enabled, `wasi:io`, `wasi:cli` (with an empty stdin and the host's
+/// stdout and stderr) and `wasi:filesystem` (limited to the preopened
+/// directories) are always granted, while `wasi:clocks` and `wasi:random`
+/// must be granted explicitly. `wasi:sockets` is never granted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxPolicy {
+    /// Whether WASI is linked at all.
+    enabled: bool,
+    /// Grant `wasi:clocks`.
+    clocks: bool,
+    /// Grant `wasi:random`.
+    random: bool,
+    /// Seed for `wasi:random`, making it deterministic.
+    random_seed: Option<u64>,
+    /// Environment variables exposed through `wasi:cli/environment`.
+    ///
+    /// (De)serialized as a list of `KEY=VALUE` strings, as configuration
+    /// formats may not preserve the case of map keys.
+    #[serde(with = "env_vars")]
+    env: BTreeMap<String, String>,
+    /// Directories preopened through `wasi:filesystem`.
+    preopens: Vec<Preopen>,
+}
+
+impl SandboxPolicy {
+    /// Create a [SandboxPolicy] with WASI enabled, but none of the optional
+    /// interfaces granted.
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// Read a requested [SandboxPolicy] from a task's metadata, if set.
+    pub fn from_meta(meta: &Ipld) -> Result<Option<Self>, SandboxError> {
+        match meta {
+            Ipld::Map(map) => match map.get(SANDBOX_KEY) {
+                None | Some(Ipld::Null) => Ok(None),
+                Some(ipld) => from_ipld(ipld.to_owned())
+                    .map(Some)
+                    .map_err(|err| SandboxError::InvalidPolicy(err.to_string())),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Grant `wasi:clocks`.
+    pub fn with_clocks(mut self, clocks: bool) -> Self {
+        self.clocks = clocks;
+        self
+    }
+
+    /// Grant `wasi:random`, seeded deterministically if a seed is given.
+    pub fn with_random(mut self, random: bool, seed: Option<u64>) -> Self {
+        self.random = random;
+        self.random_seed = seed;
+        self
+    }
+
+    /// Expose an environment variable.
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Preopen a host directory.
+    pub fn with_preopen(mut self, preopen: Preopen) -> Self {
+        self.preopens.push(preopen);
+        self
+    }
+
+    /// Whether WASI is linked at all.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Whether `wasi:clocks` is granted.
+    pub fn clocks(&self) -> bool {
+        self.enabled && self.clocks
+    }
+
+    /// Whether `wasi:random` is granted.
+    pub fn random(&self) -> bool {
+        self.enabled && self.random
+    }
+
+    /// Seed for `wasi:random`, if deterministic.
+    pub fn random_seed(&self) -> Option<u64> {
+        self.random_seed
+    }
+
+    /// Environment variables exposed to the guest.
+    pub fn env(&self) -> &BTreeMap<String, String> {
+        &self.env
+    }
+
+    /// Directories preopened for the guest.
+    pub fn preopens(&self) -> &[Preopen] {
+        &self.preopens
+    }
+
+    /// Restrict a task's `requested` policy to what this (node) policy
+    /// grants.
+    ///
+    /// Interfaces are granted only if both policies grant them, and
+    /// preopens only if the node preopens the same guest path, writable
+    /// only if both allow it. Tasks may set their own random seed and
+    /// environment variables, the latter overriding the node's.
+    pub fn restrict(&self, requested: &SandboxPolicy) -> SandboxPolicy {
+        let mut env = self.env.clone();
+        env.extend(requested.env.clone());
+
+        let preopens = requested
+            .preopens
+            .iter()
+            .filter_map(|req| {
+                self.preopens
+                    .iter()
+                    .find(|preopen| preopen.guest == req.guest)
+                    .map(|preopen| {
+                        Preopen::new(
+                            preopen.host.clone(),
+                            preopen.guest.clone(),
+                            preopen.writable && req.writable,
+                        )
+                    })
+            })
+            .collect();
+
+        SandboxPolicy {
+            enabled: self.enabled && requested.enabled,
+            clocks: self.clocks && requested.clocks,
+            random: self.random && requested.random,
+            random_seed: requested.random_seed.or(self.random_seed),
+            env,
+            preopens,
+        }
+    }
+
+    /// Restrict this policy for deterministic execution, exposing neither
+    /// environment variables nor preopened directories, which differ
+    /// across nodes, and seeding `wasi:random` with `seed` unless already
+    /// seeded.
+    pub(crate) fn deterministic(&self, seed: u64) -> SandboxPolicy {
+        SandboxPolicy {
+            enabled: self.enabled,
+            clocks: self.clocks,
+            random: self.random,
+            random_seed: self.random_seed.or(Some(seed)),
+            env: BTreeMap::new(),
+            preopens: vec![],
+        }
+    }
+
+    /// Whether a WASI interface, e.g. `wasi:clocks/wall-clock@0.2.0`, is
+    /// granted.
+    fn grants(&self, interface: &str) -> bool {
+        match package(interface) {
+            "wasi:cli" | "wasi:filesystem" | "wasi:io" => self.enabled,
+            "wasi:clocks" => self.clocks(),
+            "wasi:random" => self.random(),
+            _ => false,
+        }
+    }
+
+    /// Build the [WasiCtx] of a Wasm component's store under this policy,
+    /// reading `wasi:clocks` from a [VirtualClock] if given, and writing
+    /// `stdout` and `stderr` to [OutputPipe]s captured as log records.
+    pub(crate) fn wasi_ctx(
+        &self,
+        clock: Option<&VirtualClock>,
+        stdout: &OutputPipe,
+        stderr: &OutputPipe,
+    ) -> Result<WasiCtx, SandboxError> {
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
+            .allow_tcp(false)
+            .allow_udp(false)
+            .allow_ip_name_lookup(false);
+
+        if !self.enabled {
+            return Ok(builder.build());
+        }
+
+        builder.envs(&self.env.iter().collect::<Vec<_>>());
+
+        if let Some(clock) = clock {
+            builder
+                .wall_clock(clock.clone())
+                .monotonic_clock(clock.clone());
+        }
+
+        if let Some(seed) = self.random_seed {
+            builder
+                .secure_random(ChaCha20Rng::seed_from_u64(seed))
+                .insecure_random(ChaCha20Rng::seed_from_u64(seed))
+                .insecure_random_seed(seed.into());
+        }
+
+        for preopen in &self.preopens {
+            let dir =
+                cap_std::fs::Dir::open_ambient_dir(&preopen.host, cap_std::ambient_authority())
+                    .map_err(|source| SandboxError::Preopen {
+                        path: preopen.host.clone(),
+                        source,
+                    })?;
+            let (dir_perms, file_perms) = if preopen.writable {
+                (DirPerms::all(), FilePerms::all())
+            } else {
+                (DirPerms::READ, FilePerms::READ)
+            };
+            builder.preopened_dir(dir, dir_perms, file_perms, &preopen.guest);
+        }
+
+        Ok(builder.build())
+    }
+}
+
+/// Link the WASI interfaces granted by a [SandboxPolicy].
+pub(crate) fn add_to_linker<T: WasiView>(
+    linker: &mut Linker<T>,
+    policy: &SandboxPolicy,
+) -> anyhow::Result<()> {
+    if !policy.is_enabled() {
+        return Ok(());
+    }
+
+    wasi::io::error::add_to_linker(linker, |t| t)?;
+    wasi::io::poll::add_to_linker(linker, |t| t)?;
+    wasi::io::streams::add_to_linker(linker, |t| t)?;
+    wasi::cli::exit::add_to_linker(linker, |t| t)?;
+    wasi::cli::environment::add_to_linker(linker, |t| t)?;
+    wasi::cli::stdin::add_to_linker(linker, |t| t)?;
+    wasi::cli::stdout::add_to_linker(linker, |t| t)?;
+    wasi::cli::stderr::add_to_linker(linker, |t| t)?;
+    wasi::cli::terminal_input::add_to_linker(linker, |t| t)?;
+    wasi::cli::terminal_output::add_to_linker(linker, |t| t)?;
+    wasi::cli::terminal_stdin::add_to_linker(linker, |t| t)?;
+    wasi::cli::terminal_stdout::add_to_linker(linker, |t| t)?;
+    wasi::cli::terminal_stderr::add_to_linker(linker, |t| t)?;
+    wasi::filesystem::types::add_to_linker(linker, |t| t)?;
+    wasi::filesystem::preopens::add_to_linker(linker, |t| t)?;
+
+    if policy.clocks() {
+        wasi::clocks::wall_clock::add_to_linker(linker, |t| t)?;
+        wasi::clocks::monotonic_clock::add_to_linker(linker, |t| t)?;
+    }
+
+    if policy.random() {
+        wasi::random::random::add_to_linker(linker, |t| t)?;
+        wasi::random::insecure::add_to_linker(linker, |t| t)?;
+        wasi::random::insecure_seed::add_to_linker(linker, |t| t)?;
+    }
+
+    Ok(())
+}
+
+/// Resource type standing in for resources of WASI interfaces that aren't
+/// granted, which can never be constructed.
+struct Denied;
+
+/// Link the WASI imports of a component that aren't granted by a
+/// [SandboxPolicy] to stubs that trap when called.
+///
+/// The linker must allow shadowing, as components instantiated with the
+/// same linker may import the same interfaces.
+pub(crate) fn stub_denied_imports<T>(
+    linker: &mut Linker<T>,
+    component: &Component,
+    bytes: &[u8],
+    policy: &SandboxPolicy,
+) -> Result<(), Error> {
+    let DecodedWasm::Component(resolve, world) =
+        wit_parser::decoding::decode(bytes).map_err(Error::IntoWasmComponent)?
+    else {
+        return Ok(());
+    };
+
+    for (key, item) in resolve.worlds[world].imports.iter() {
+        let WorldItem::Interface(id) = item else {
+            continue;
+        };
+        let name = resolve.name_world_key(key);
+        if !WASI_PACKAGES.contains(&package(&name)) || policy.grants(&name) {
+            continue;
+        }
+
+        let interface = &resolve.interfaces[*id];
+        let mut instance = linker.instance(&name)?;
+        for (type_name, type_id) in interface.types.iter() {
+            if matches!(resolve.types[*type_id].kind, TypeDefKind::Resource) {
+                instance.resource(type_name, ResourceType::host::<Denied>(), |_, _| Ok(()))?;
+            }
+        }
+
+        for function in interface.functions.keys() {
+            let (interface, function) = (name.clone(), function.clone());
+            instance.func_new(component, &function.clone(), move |_, _, _| {
+                Err(SandboxError::Denied {
+                    interface: interface.clone(),
+                    function: function.clone(),
+                }
+                .into())
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// (De)serialization of environment variables as `KEY=VALUE` strings.
+mod env_vars {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    pub(super) fn serialize<S>(
+        env: &BTreeMap<String, String>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        env.iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|var| match var.split_once('=') {
+                Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+                _ => Err(de::Error::custom(format!(
+                    "environment variable {var:?} is not of the form KEY=VALUE"
+                ))),
+            })
+            .collect()
+    }
+}
+
+/// Package of an interface name, e.g. `wasi:io` of `wasi:io/streams@0.2.0`.
+fn package(interface: &str) -> &str {
+    interface.split('/').next().unwrap_or(interface)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn restrict_requested_policy() {
+        let node = SandboxPolicy::enabled()
+            .with_clocks(true)
+            .with_env("NODE", "node")
+            .with_preopen(Preopen::new("/srv/data", "/data", true))
+            .with_preopen(Preopen::new("/srv/cache", "/cache", false));
+
+        let requested = SandboxPolicy::enabled()
+            .with_clocks(true)
+            .with_random(true, Some(7))
+            .with_env("TASK", "task")
+            .with_preopen(Preopen::new("/etc", "/data", true))
+            .with_preopen(Preopen::new("", "/cache", true))
+            .with_preopen(Preopen::new("", "/home", false));
+
+        let policy = node.restrict(&requested);
+        assert!(policy.is_enabled());
+        assert!(policy.clocks());
+        assert!(!policy.random());
+        assert_eq!(policy.random_seed(), Some(7));
+        assert_eq!(
+            policy.env(),
+            &BTreeMap::from([
+                ("NODE".to_string(), "node".to_string()),
+                ("TASK".to_string(), "task".to_string())
+            ])
+        );
+        assert_eq!(
+            policy.preopens(),
+            &[
+                Preopen::new("/srv/data", "/data", true),
+                Preopen::new("/srv/cache", "/cache", false)
+            ]
+        );
+
+        assert!(!SandboxPolicy::default().restrict(&requested).is_enabled());
+        assert!(!node.restrict(&SandboxPolicy::default()).is_enabled());
+    }
+
+    #[test]
+    fn grant_interfaces() {
+        let policy = SandboxPolicy::enabled().with_random(true, None);
+        assert!(policy.grants("wasi:io/streams@0.2.0"));
+        assert!(policy.grants("wasi:filesystem/preopens@0.2.0"));
+        assert!(policy.grants("wasi:random/random@0.2.0"));
+        assert!(!policy.grants("wasi:clocks/wall-clock@0.2.0"));
+        assert!(!policy.grants("wasi:sockets/tcp@0.2.0"));
+        assert!(!SandboxPolicy::default().grants("wasi:io/streams@0.2.0"));
+    }
+
+    #[test]
+    fn policy_from_meta() {
+        let meta = Ipld::Map(BTreeMap::from([
+            ("fuel".into(), Ipld::Integer(100)),
+            (
+                SANDBOX_KEY.into(),
+                Ipld::Map(BTreeMap::from([
+                    ("enabled".into(), Ipld::Bool(true)),
+                    ("random_seed".into(), Ipld::Integer(42)),
+                    (
+                        "env".into(),
+                        Ipld::List(vec![Ipld::String("GREETING=hello=world".into())]),
+                    ),
+                    (
+                        "preopens".into(),
+                        Ipld::List(vec![Ipld::Map(BTreeMap::from([(
+                            "guest".into(),
+                            Ipld::String("/data".into()),
+                        )]))]),
+                    ),
+                ])),
+            ),
+        ]));
+
+        let policy = SandboxPolicy::from_meta(&meta).unwrap().unwrap();
+        assert!(policy.is_enabled());
+        assert!(!policy.clocks());
+        assert_eq!(policy.random_seed(), Some(42));
+        assert_eq!(
+            policy.env(),
+            &BTreeMap::from([("GREETING".to_string(), "hello=world".to_string())])
+        );
+        assert_eq!(policy.preopens(), &[Preopen::new("", "/data", false)]);
+
+        assert_eq!(SandboxPolicy::from_meta(&Ipld::Null).unwrap(), None);
+        let invalid = Ipld::Map(BTreeMap::from([(SANDBOX_KEY.into(), Ipld::Integer(1))]));
+        assert!(SandboxPolicy::from_meta(&invalid).is_err());
+        let invalid = Ipld::Map(BTreeMap::from([(
+            SANDBOX_KEY.into(),
+            Ipld::Map(BTreeMap::from([(
+                "env".into(),
+                Ipld::List(vec![Ipld::String("GREETING".into())]),
+            )])),
+        )]));
+        assert!(SandboxPolicy::from_meta(&invalid).is_err());
+    }
+}
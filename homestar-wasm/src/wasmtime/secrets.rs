@@ -0,0 +1,172 @@
+//! Secrets provided by the node to Wasm components through the
+//! `homestar:host/secrets` interface, requested by name in task metadata.
+
+use crate::wasmtime::SandboxError;
+use libipld::{serde::from_ipld, Ipld};
+use std::fmt;
+
+/// Metadata key of the names of the secrets a task requests.
+const SECRETS_KEY: &str = "secrets";
+
+/// Replacement of secrets in log records.
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+/// Value of a secret, never printed.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Create a new [Secret].
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    /// Value of the secret.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+/// Error getting a secret from a [SecretStore].
+#[derive(thiserror::Error, Clone, Debug, PartialEq, Eq)]
+pub enum SecretError {
+    /// Secret not granted to the component, e.g. to the issuer of its
+    /// workflow.
+    #[error("secret {0} is not granted")]
+    Denied(String),
+    /// Secret granted, but not provided by the node.
+    #[error("secret {0} is not found")]
+    NotFound(String),
+}
+
+/// Store of secrets of a Wasm component, scoped by the node, e.g. to the
+/// issuer of its workflow.
+pub trait SecretStore: fmt::Debug + Send + Sync {
+    /// Get a secret by name, if granted to and provided for the component.
+    fn get(&self, name: &str) -> Result<Secret, SecretError>;
+}
+
+/// Read the names of the secrets a task requests from its metadata, if
+/// any.
+pub fn from_meta(meta: &Ipld) -> Result<Vec<String>, SandboxError> {
+    match meta {
+        Ipld::Map(map) => match map.get(SECRETS_KEY) {
+            None | Some(Ipld::Null) => Ok(vec![]),
+            Some(ipld) => from_ipld(ipld.to_owned()).map_err(|err| {
+                SandboxError::InvalidPolicy(format!(
+                    "`{SECRETS_KEY}` must be a list of names: {err}"
+                ))
+            }),
+        },
+        _ => Ok(vec![]),
+    }
+}
+
+/// Redact the values of secrets from a string, e.g. a log record.
+pub(crate) fn redact(text: &str, secrets: &[Secret]) -> String {
+    secrets
+        .iter()
+        .filter(|secret| !secret.expose().is_empty())
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret.expose(), REDACTED)
+        })
+}
+
+/// Find a secret whose value is contained in an [Ipld] value, e.g. the
+/// output of a task, within any string or bytes.
+pub(crate) fn find_leaked<'a>(
+    ipld: &Ipld,
+    secrets: impl IntoIterator<Item = (&'a String, &'a Secret)> + Clone,
+) -> Option<&'a String> {
+    let contains = |haystack: &[u8]| {
+        secrets.clone().into_iter().find(|(_, secret)| {
+            let needle = secret.expose().as_bytes();
+            !needle.is_empty()
+                && haystack
+                    .windows(needle.len())
+                    .any(|window| window == needle)
+        })
+    };
+
+    match ipld {
+        Ipld::String(string) => contains(string.as_bytes()).map(|(name, _)| name),
+        Ipld::Bytes(bytes) => contains(bytes).map(|(name, _)| name),
+        Ipld::List(list) => list
+            .iter()
+            .find_map(|ipld| find_leaked(ipld, secrets.clone())),
+        Ipld::Map(map) => map.iter().find_map(|(key, ipld)| {
+            contains(key.as_bytes())
+                .map(|(name, _)| name)
+                .or_else(|| find_leaked(ipld, secrets.clone()))
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn secrets_from_meta_and_redacted() {
+        let meta = |value| Ipld::Map(BTreeMap::from([(SECRETS_KEY.into(), value)]));
+        assert_eq!(
+            from_meta(&meta(Ipld::List(vec![Ipld::String("token".into())]))).unwrap(),
+            vec!["token".to_string()]
+        );
+        assert!(from_meta(&Ipld::Map(BTreeMap::new())).unwrap().is_empty());
+        assert!(from_meta(&meta(Ipld::String("token".into()))).is_err());
+
+        let secret = Secret::new("hunter2");
+        assert_eq!(format!("{secret:?}"), "Secret(..)");
+        assert_eq!(
+            redact("token=hunter2", &[secret, Secret::new("")]),
+            format!("token={REDACTED}")
+        );
+    }
+
+    #[test]
+    fn find_secrets_leaked_in_ipld() {
+        let secrets = [
+            ("empty".to_string(), Secret::new("")),
+            ("token".to_string(), Secret::new("hunter2")),
+        ];
+        let secrets = secrets.iter().map(|(name, secret)| (name, secret));
+
+        assert_eq!(
+            find_leaked(&Ipld::String("safe".into()), secrets.clone()),
+            None
+        );
+        assert_eq!(
+            find_leaked(
+                &Ipld::List(vec![Ipld::Integer(1), Ipld::String("x-hunter2-x".into())]),
+                secrets.clone()
+            ),
+            Some(&"token".to_string())
+        );
+        assert_eq!(
+            find_leaked(
+                &Ipld::Map(BTreeMap::from([(
+                    "out".into(),
+                    Ipld::Bytes(b"hunter2".to_vec())
+                )])),
+                secrets.clone()
+            ),
+            Some(&"token".to_string())
+        );
+        assert_eq!(
+            find_leaked(
+                &Ipld::Map(BTreeMap::from([("hunter2".into(), Ipld::Null)])),
+                secrets
+            ),
+            Some(&"token".to_string())
+        );
+    }
+}
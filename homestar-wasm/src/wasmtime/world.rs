@@ -8,9 +8,18 @@
 use crate::{
     io::{Arg, Output},
     wasmtime::{
+        blocks::BlockStore,
+        cache::{self, ComponentCache},
+        determinism::{self, Determinism, VirtualClock},
+        http::HttpPolicy,
+        interrupt::{self, Interruption},
         ipld::{InterfaceType, RuntimeVal},
+        kv::{KvOp, KvStore},
         limits::StoreLimitsAsync,
-        Error,
+        logs::{Level, LogCapture, LogRecord, LogSink, OutputPipe, STDERR_CONTEXT, STDOUT_CONTEXT},
+        sandbox::{self, SandboxPolicy},
+        secrets::{self, Secret, SecretStore},
+        Error, LimitError, SandboxError,
     },
 };
 use heck::{ToKebabCase, ToLowerCamelCase, ToPascalCase, ToSnakeCase};
@@ -19,11 +28,18 @@ use homestar_invocation::{
     error::ResolveError,
     task::instruction::{Args, Input},
 };
-use std::{iter, time::Instant};
+use indexmap::IndexMap;
+use libipld::{Cid, Ipld};
+use std::{
+    borrow::Cow,
+    iter,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tracing::{instrument, Instrument};
 use wasmtime::{
     component::{self, Component, Func, Instance, Linker},
-    Config, Engine, Store,
+    Config, Engine, Store, Trap, UpdateDeadline,
 };
 use wit_component::ComponentEncoder;
 
@@ -44,6 +60,10 @@ pub struct State {
     /// Fuel is a measure of how much computation a Wasm module is allowed to
     /// perform.
     fuel: u64,
+    /// How execution is interrupted.
+    interruption: Interruption,
+    /// Wall-clock time execution is limited to, enforced via epochs.
+    time_limit: Option<Duration>,
     /// Limits are a set of limits that can be applied to a store, i.e. memory,
     /// table elements.
     limits: StoreLimitsAsync,
@@ -51,21 +71,72 @@ pub struct State {
     wasi_ctx: wasmtime_wasi::preview2::WasiCtx,
     /// WASI table.
     table: wasmtime::component::ResourceTable,
+    /// Policy of which WASI interfaces are granted.
+    sandbox: SandboxPolicy,
+    /// Policy of which outbound HTTP requests are allowed.
+    http: HttpPolicy,
+    /// Client for outbound HTTP requests, built on first use.
+    http_client: Option<reqwest::Client>,
+    /// Outbound HTTP requests made, as `METHOD URL`.
+    http_requests: Vec<String>,
+    /// Store blocks are read from.
+    blocks: Option<Arc<dyn BlockStore>>,
+    /// Blocks written, by [Cid].
+    written_blocks: IndexMap<Cid, Vec<u8>>,
+    /// Key-value store, if any.
+    kv: Option<Arc<dyn KvStore>>,
+    /// Operations on the key-value store.
+    kv_ops: Vec<KvOp>,
+    /// Store secrets are read from, if any.
+    secrets: Option<Arc<dyn SecretStore>>,
+    /// Names of the secrets requested.
+    secret_names: Vec<String>,
+    /// Secrets read, by name, redacted from log records.
+    secrets_accessed: IndexMap<String, Secret>,
+    /// Deterministic execution, if enabled.
+    determinism: Option<Determinism>,
+    /// Clock read when executing deterministically.
+    clock: Option<VirtualClock>,
+    /// Log records captured from the guest.
+    logs: LogCapture,
+    /// `stdout` of the guest, captured as log records.
+    stdout: OutputPipe,
+    /// `stderr` of the guest, captured as log records.
+    stderr: OutputPipe,
 }
 
 impl Default for State {
     fn default() -> Self {
         let table = wasmtime::component::ResourceTable::new();
+        let (stdout, stderr) = (OutputPipe::default(), OutputPipe::default());
         let wasi_ctx = wasmtime_wasi::preview2::WasiCtxBuilder::new()
-            .inherit_stdout()
-            .inherit_stderr()
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
             .build();
         Self {
             start_time: Instant::now(),
             fuel: u64::MAX,
+            interruption: Interruption::default(),
+            time_limit: None,
             limits: StoreLimitsAsync::default(),
             wasi_ctx,
             table,
+            sandbox: SandboxPolicy::default(),
+            http: HttpPolicy::default(),
+            http_client: None,
+            http_requests: vec![],
+            blocks: None,
+            written_blocks: IndexMap::new(),
+            kv: None,
+            kv_ops: vec![],
+            secrets: None,
+            secret_names: vec![],
+            secrets_accessed: IndexMap::new(),
+            determinism: None,
+            clock: None,
+            logs: LogCapture::default(),
+            stdout,
+            stderr,
         }
     }
 }
@@ -88,15 +159,35 @@ impl State {
     /// Create a new [State] object.
     pub fn new(fuel: u64, limits: StoreLimitsAsync) -> Self {
         let table = wasmtime::component::ResourceTable::new();
+        let (stdout, stderr) = (OutputPipe::default(), OutputPipe::default());
         let wasi_ctx = wasmtime_wasi::preview2::WasiCtxBuilder::new()
-            .inherit_stdio()
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
             .build();
         Self {
             start_time: Instant::now(),
             fuel,
+            interruption: Interruption::default(),
+            time_limit: None,
             limits,
             wasi_ctx,
             table,
+            sandbox: SandboxPolicy::default(),
+            http: HttpPolicy::default(),
+            http_client: None,
+            http_requests: vec![],
+            blocks: None,
+            written_blocks: IndexMap::new(),
+            kv: None,
+            kv_ops: vec![],
+            secrets: None,
+            secret_names: vec![],
+            secrets_accessed: IndexMap::new(),
+            determinism: None,
+            clock: None,
+            logs: LogCapture::default(),
+            stdout,
+            stderr,
         }
     }
 
@@ -105,10 +196,232 @@ impl State {
         self.fuel = fuel
     }
 
+    /// Set how execution is interrupted, unless set by the [Engine] of a
+    /// [ComponentCache].
+    pub fn set_interruption(&mut self, interruption: Interruption) {
+        self.interruption = interruption
+    }
+
+    /// Set the wall-clock time execution is limited to, enforced precisely
+    /// when interrupting via epochs.
+    pub fn set_time_limit(&mut self, time_limit: Duration) {
+        self.time_limit = Some(time_limit)
+    }
+
+    /// Set the [SandboxPolicy] deciding which WASI interfaces are granted,
+    /// opening any directories it preopens.
+    ///
+    /// When executing deterministically, the policy is restricted
+    /// accordingly.
+    pub fn set_sandbox(&mut self, sandbox: SandboxPolicy) -> Result<(), Error> {
+        let sandbox = match self.determinism {
+            Some(determinism) => sandbox.deterministic(determinism.seed()),
+            None => sandbox,
+        };
+        self.wasi_ctx = sandbox.wasi_ctx(self.clock.as_ref(), &self.stdout, &self.stderr)?;
+        self.sandbox = sandbox;
+        Ok(())
+    }
+
+    /// Execute deterministically, restricting the [SandboxPolicy] and
+    /// reading clocks from a virtual clock.
+    pub fn set_determinism(&mut self, determinism: Determinism) -> Result<(), Error> {
+        self.determinism = Some(determinism);
+        self.clock = Some(VirtualClock::default());
+        self.set_sandbox(self.sandbox.clone())
+    }
+
+    /// Set the [HttpPolicy] deciding which outbound HTTP requests are
+    /// allowed.
+    pub fn set_http(&mut self, http: HttpPolicy) {
+        self.http = http;
+        self.http_client = None;
+    }
+
+    /// Set the maximum size, in bytes, of the log records captured from
+    /// the guest, past which records are dropped.
+    pub fn set_max_log_size(&mut self, max_size: usize) {
+        self.logs.set_max_size(max_size);
+        self.stdout.set_max_size(max_size);
+        self.stderr.set_max_size(max_size);
+    }
+
+    /// Set the [LogSink] captured log records are streamed to.
+    pub fn set_log_sink(&mut self, sink: Arc<dyn LogSink>) {
+        self.logs.set_sink(sink);
+    }
+
+    /// Set the [BlockStore] blocks are read from.
+    pub fn set_blocks(&mut self, blocks: Arc<dyn BlockStore>) {
+        self.blocks = Some(blocks);
+    }
+
+    /// Set the [KvStore] keys are read from and written to.
+    pub fn set_kv(&mut self, kv: Arc<dyn KvStore>) {
+        self.kv = Some(kv);
+    }
+
+    /// Set the [SecretStore] secrets are read from, and the names of the
+    /// secrets requested, the only ones that may be read.
+    pub fn set_secrets(&mut self, names: Vec<String>, secrets: Arc<dyn SecretStore>) {
+        self.secret_names = names;
+        self.secrets = Some(secrets);
+    }
+
     /// Initial time from instantiation.
     pub fn start_time(&self) -> Instant {
         self.start_time
     }
+
+    /// Fuel limit.
+    pub fn fuel(&self) -> u64 {
+        self.fuel
+    }
+
+    /// How execution is interrupted.
+    pub fn interruption(&self) -> Interruption {
+        self.interruption
+    }
+
+    /// Wall-clock time limit, if any.
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+
+    /// Memory and table limits.
+    pub fn limits(&self) -> &StoreLimitsAsync {
+        &self.limits
+    }
+
+    /// Policy of which WASI interfaces are granted.
+    pub fn sandbox(&self) -> &SandboxPolicy {
+        &self.sandbox
+    }
+
+    /// Deterministic execution, if enabled.
+    pub fn determinism(&self) -> Option<Determinism> {
+        self.determinism
+    }
+
+    /// Policy of which outbound HTTP requests are allowed.
+    pub fn http(&self) -> &HttpPolicy {
+        &self.http
+    }
+
+    /// Outbound HTTP requests made so far, as `METHOD URL`.
+    pub fn http_requests(&self) -> &[String] {
+        &self.http_requests
+    }
+
+    /// Blocks written so far, by [Cid].
+    pub fn written_blocks(&self) -> &IndexMap<Cid, Vec<u8>> {
+        &self.written_blocks
+    }
+
+    /// Operations on the key-value store so far.
+    pub fn kv_ops(&self) -> &[KvOp] {
+        &self.kv_ops
+    }
+
+    /// Names of the secrets read so far.
+    pub fn secrets_accessed(&self) -> Vec<String> {
+        self.secrets_accessed.keys().cloned().collect()
+    }
+
+    /// Log records captured from the guest so far.
+    pub fn logs(&self) -> &[LogRecord] {
+        self.logs.records()
+    }
+
+    /// Number of log records dropped past the maximum size so far.
+    pub fn dropped_logs(&self) -> usize {
+        self.logs.dropped()
+    }
+
+    pub(crate) fn capture_log(&mut self, record: LogRecord) {
+        self.logs.capture(record);
+    }
+
+    /// Capture what the guest wrote to `stdout` and `stderr` so far as log
+    /// records, line by line, redacting the secrets it read.
+    pub fn capture_output(&mut self) {
+        for (pipe, level, context) in [
+            (self.stdout.clone(), Level::Info, STDOUT_CONTEXT),
+            (self.stderr.clone(), Level::Warn, STDERR_CONTEXT),
+        ] {
+            for line in pipe.take_lines() {
+                let line = self.redact_secrets(line);
+                self.capture_log(LogRecord::new(level, context, line));
+            }
+        }
+    }
+
+    /// Name of a secret read so far whose value is contained in an output.
+    pub fn leaked_secret(&self, output: &Ipld) -> Option<String> {
+        secrets::find_leaked(output, &self.secrets_accessed).cloned()
+    }
+
+    /// Redact the secrets read so far from a string logged by the guest.
+    pub(crate) fn redact_secrets(&self, text: String) -> String {
+        if self.secrets_accessed.is_empty() {
+            return text;
+        }
+
+        let accessed = self.secrets_accessed.values().cloned().collect::<Vec<_>>();
+        secrets::redact(&text, &accessed)
+    }
+
+    pub(crate) fn write_block(&mut self, cid: Cid, block: Vec<u8>) {
+        self.written_blocks.insert(cid, block);
+    }
+
+    pub(crate) fn virtual_clock(&self) -> Option<&VirtualClock> {
+        self.clock.as_ref()
+    }
+
+    pub(crate) fn block_store(&self) -> Option<Arc<dyn BlockStore>> {
+        self.blocks.clone()
+    }
+
+    pub(crate) fn kv_store(&self) -> Option<Arc<dyn KvStore>> {
+        self.kv.clone()
+    }
+
+    pub(crate) fn secret_store(&self) -> Option<Arc<dyn SecretStore>> {
+        self.secrets.clone()
+    }
+
+    pub(crate) fn secret_names(&self) -> &[String] {
+        &self.secret_names
+    }
+
+    pub(crate) fn record_secret_access(&mut self, name: String, secret: Secret) {
+        self.secrets_accessed.insert(name, secret);
+    }
+
+    pub(crate) fn record_kv_op(&mut self, op: KvOp) {
+        self.kv_ops.push(op);
+    }
+
+    pub(crate) fn record_http_request(&mut self, request: String) {
+        self.http_requests.push(request);
+    }
+
+    /// Client for outbound HTTP requests under the [HttpPolicy], following
+    /// no redirects, as those could lead to hosts that aren't allowed.
+    pub(crate) fn http_client(&mut self) -> Result<reqwest::Client, Error> {
+        if let Some(client) = &self.http_client {
+            return Ok(client.clone());
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(self.http.timeout())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .map_err(anyhow::Error::from)?;
+        self.http_client = Some(client.clone());
+        Ok(client)
+    }
 }
 
 /// Runtime struct wrapping wasm/host bindings, the
@@ -120,19 +433,38 @@ pub struct Env<T> {
     instance: Option<Instance>,
     linker: Linker<T>,
     store: Store<T>,
+    sandbox: SandboxPolicy,
+    determinism: Option<Determinism>,
+    interruption: Interruption,
+    cache: Option<ComponentCache<T>>,
 }
 
 impl<T> Env<T> {
-    fn new(engine: Engine, linker: Linker<T>, store: Store<T>) -> Env<T> {
+    fn new(
+        engine: Engine,
+        linker: Linker<T>,
+        store: Store<T>,
+        sandbox: SandboxPolicy,
+        determinism: Option<Determinism>,
+        interruption: Interruption,
+    ) -> Env<T> {
         Self {
             bindings: None,
             engine,
             instance: None,
             linker,
             store,
+            sandbox,
+            determinism,
+            interruption,
+            cache: None,
         }
     }
 
+    fn set_cache(&mut self, cache: ComponentCache<T>) {
+        self.cache = Some(cache);
+    }
+
     fn set_bindings(&mut self, bindings: World) {
         self.bindings = Some(bindings);
     }
@@ -199,7 +531,13 @@ impl<T> Env<T> {
             .func()
             .call_async(&mut self.store, &params, &mut results_alloc)
             .in_current_span()
-            .await?;
+            .await
+            // Surface calls to WASI functions not granted by the sandbox
+            // policy as such, rather than as generic traps.
+            .map_err(|err| match err.downcast::<SandboxError>() {
+                Ok(err) => Error::Sandbox(err),
+                Err(err) => Error::WasmRuntime(err),
+            })?;
 
         self.bindings
             .as_mut()
@@ -237,6 +575,105 @@ impl<T> Env<T> {
     pub fn store(&self) -> &Store<T> {
         &self.store
     }
+
+    /// Return the [SandboxPolicy] WASI imports are linked under.
+    pub fn sandbox(&self) -> &SandboxPolicy {
+        &self.sandbox
+    }
+
+    /// Return the [Determinism] components are executed with, if
+    /// deterministic.
+    pub fn determinism(&self) -> Option<Determinism> {
+        self.determinism
+    }
+
+    /// Return how execution is interrupted.
+    pub fn interruption(&self) -> Interruption {
+        self.interruption
+    }
+}
+
+impl Env<State> {
+    /// Map an instantiation or execution [Error] onto a [LimitError], if
+    /// it was caused by running out of fuel, by breaching a memory or
+    /// table limit, or by running past its deadline.
+    pub fn limit_error(&self, err: Error) -> Error {
+        match err {
+            Error::WasmRuntime(ref runtime_err)
+                if runtime_err.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) =>
+            {
+                LimitError::OutOfFuel(self.store.data().fuel()).into()
+            }
+            Error::WasmRuntime(runtime_err) => match runtime_err.downcast::<LimitError>() {
+                Ok(exceeded) => exceeded.into(),
+                Err(runtime_err) => match self.store.data().limits().exceeded() {
+                    Some(exceeded) => exceeded.to_owned().into(),
+                    None => Error::WasmRuntime(runtime_err),
+                },
+            },
+            err => err,
+        }
+    }
+
+    /// Fuel consumed so far, i.e. the fuel given via [State] minus the fuel
+    /// remaining in the [wasmtime::Store], or zero if fuel isn't metered.
+    pub fn fuel_consumed(&self) -> u64 {
+        if !self.interruption.fuel() {
+            return 0;
+        }
+
+        self.store
+            .data()
+            .fuel()
+            .saturating_sub(self.store.get_fuel().unwrap_or_default())
+    }
+
+    /// Memory (in bytes) grown to so far, which is the peak, as linear
+    /// memories never shrink.
+    pub fn memory_consumed(&self) -> u64 {
+        self.store.data().limits().memory_consumed()
+    }
+
+    /// Outbound HTTP requests made so far, as `METHOD URL`.
+    pub fn http_requests(&self) -> &[String] {
+        self.store.data().http_requests()
+    }
+
+    /// Blocks written so far, by [Cid].
+    pub fn written_blocks(&self) -> &IndexMap<Cid, Vec<u8>> {
+        self.store.data().written_blocks()
+    }
+
+    /// Operations on the key-value store so far.
+    pub fn kv_ops(&self) -> &[KvOp] {
+        self.store.data().kv_ops()
+    }
+
+    /// Names of the secrets read so far.
+    pub fn secrets_accessed(&self) -> Vec<String> {
+        self.store.data().secrets_accessed()
+    }
+
+    /// Name of a secret read so far whose value is contained in an output.
+    pub fn leaked_secret(&self, output: &Ipld) -> Option<String> {
+        self.store.data().leaked_secret(output)
+    }
+
+    /// Capture what the guest wrote to `stdout` and `stderr` so far as log
+    /// records.
+    pub fn capture_output(&mut self) {
+        self.store.data_mut().capture_output()
+    }
+
+    /// Log records captured from the guest so far.
+    pub fn logs(&self) -> &[LogRecord] {
+        self.store.data().logs()
+    }
+
+    /// Number of log records dropped past the maximum size so far.
+    pub fn dropped_logs(&self) -> usize {
+        self.store.data().dropped_logs()
+    }
 }
 
 /// Shim for Wasmtime [Function] execution.
@@ -251,27 +688,83 @@ impl World {
     ///
     /// [environment]: Env
     pub fn default(data: State) -> Result<Env<State>, Error> {
-        let config = Self::configure();
-        let engine = Engine::new(&config)?;
+        let interruption = data.interruption();
+        let engine = Self::engine(interruption)?;
+        Self::env(engine, interruption, data)
+    }
+
+    /// Instantiate a default [environment] for a [World], given [State],
+    /// compiling components via a [ComponentCache] shared with other
+    /// environments, and interrupted as configured by its [Engine].
+    ///
+    /// [environment]: Env
+    pub fn with_cache(data: State, cache: ComponentCache<State>) -> Result<Env<State>, Error> {
+        let mut env = Self::env(cache.engine().clone(), cache.interruption(), data)?;
+        env.set_cache(cache);
+        Ok(env)
+    }
+
+    fn env(engine: Engine, interruption: Interruption, data: State) -> Result<Env<State>, Error> {
         let mut linker = Self::define_linker(&engine);
 
-        // Add WASI to the linker in order to support WASI modules.
-        // This is a temporary measure until WASI is supported by default and is
-        // unused otherwise.
-        wasmtime_wasi::preview2::command::add_to_linker(&mut linker)?;
+        // Add the WASI interfaces granted by the sandbox policy, if any, in
+        // order to support WASI modules.
+        let sandbox = data.sandbox().to_owned();
+        let determinism = data.determinism();
+        sandbox::add_to_linker(&mut linker, &sandbox)?;
         Imports::add_to_linker(&mut linker, |state: &mut State| state)?;
 
-        let mut store = Store::new(&engine, data);
-        store.set_fuel(store.data().fuel)?;
-
-        // Configures a `Store` to yield execution of async WebAssembly code
-        // periodically and not cause extended polling.
-        store.fuel_async_yield_interval(Some(UNIT_OF_COMPUTE_INSTRUCTIONS))?;
+        let store = Self::store(&engine, interruption, data)?;
 
-        let env = Env::new(engine, linker, store);
+        let env = Env::new(engine, linker, store, sandbox, determinism, interruption);
         Ok(env)
     }
 
+    /// Create the [Engine] of an environment, ticking its epoch if
+    /// interrupting via epochs.
+    fn engine(interruption: Interruption) -> Result<Engine, Error> {
+        let engine = Engine::new(&Self::configure(interruption))?;
+        if interruption.epoch() {
+            interrupt::tick_epochs(&engine);
+        }
+        Ok(engine)
+    }
+
+    /// Create the [Store] of an environment, interrupting execution as
+    /// configured by its [Engine].
+    fn store(
+        engine: &Engine,
+        interruption: Interruption,
+        data: State,
+    ) -> Result<Store<State>, Error> {
+        let time_limit = data.time_limit();
+        let mut store = Store::new(engine, data);
+        store.limiter_async(|s| &mut s.limits);
+
+        if interruption.fuel() {
+            store.set_fuel(store.data().fuel)?;
+
+            // Configures a `Store` to yield execution of async WebAssembly
+            // code periodically and not cause extended polling.
+            store.fuel_async_yield_interval(Some(UNIT_OF_COMPUTE_INSTRUCTIONS))?;
+        }
+
+        if interruption.epoch() {
+            // Yield on every tick, preempting long-running tasks, until
+            // past the deadline, if any.
+            let deadline = time_limit.map(|limit| (Instant::now() + limit, limit));
+            store.set_epoch_deadline(1);
+            store.epoch_deadline_callback(move |_| match deadline {
+                Some((deadline, limit)) if Instant::now() >= deadline => {
+                    Err(LimitError::Timeout(limit).into())
+                }
+                _ => Ok(UpdateDeadline::Yield(1)),
+            });
+        }
+
+        Ok(store)
+    }
+
     /// Instantiates the provided `module` using the specified
     /// parameters, wrapping up the result in a [Env] structure
     /// that translates between wasm and the host, and gives access
@@ -283,34 +776,33 @@ impl World {
         fun_name: &str,
         data: State,
     ) -> Result<Env<State>, Error> {
-        let config = Self::configure();
-        let engine = Engine::new(&config)?;
+        let interruption = data.interruption();
+        let engine = Self::engine(interruption)?;
         let mut linker = Self::define_linker(&engine);
 
-        // Add WASI to the linker in order to support WASI modules.
-        // This is a temporary measure until WASI is supported by default and is
-        // unused otherwise.
-        wasmtime_wasi::preview2::command::add_to_linker(&mut linker)?;
+        // Add the WASI interfaces granted by the sandbox policy, if any, in
+        // order to support WASI modules.
+        let sandbox = data.sandbox().to_owned();
+        let determinism = data.determinism();
+        sandbox::add_to_linker(&mut linker, &sandbox)?;
         Imports::add_to_linker(&mut linker, |state: &mut State| state)?;
 
-        let mut store = Store::new(&engine, data);
-        store.limiter_async(|s| &mut s.limits);
-        store.set_fuel(store.data().fuel)?;
-
-        // Configures a `Store` to yield execution of async WebAssembly code
-        // periodically and not cause extended polling.
-        store.fuel_async_yield_interval(Some(UNIT_OF_COMPUTE_INSTRUCTIONS))?;
+        let mut store = Self::store(&engine, interruption, data)?;
 
-        // engine clones are shallow (not deep).
-        let component = component_from_bytes(&bytes, engine.clone())?;
+        let bytes = component_bytes(&bytes)?;
+        if determinism.is_some() {
+            determinism::check_imports(&bytes)?;
+        }
+        let component =
+            Component::from_binary(&engine, &bytes).map_err(Error::IntoWasmComponent)?;
+        sandbox::stub_denied_imports(&mut linker, &component, &bytes, &sandbox)?;
 
         let (_bindings, instance) =
             Imports::instantiate_async(&mut store, &component, &linker).await?;
 
         let bindings = Self::new(&mut store, &instance, fun_name)?;
 
-        //let bindings = Self::new(&mut store, &instance, fun_name)?;
-        let mut env = Env::new(engine, linker, store);
+        let mut env = Env::new(engine, linker, store, sandbox, determinism, interruption);
         env.set_instance(instance);
         env.set_bindings(bindings);
         Ok(env)
@@ -321,6 +813,10 @@ impl World {
     /// a new set of bindings for execution, and overriding
     /// the instance for the Wasm component.
     ///
+    /// With a [ComponentCache], the component is compiled, and its imports
+    /// resolved (and checked, if deterministic), once per module, sandbox
+    /// policy, and determinism.
+    ///
     /// [environment]: Env
     pub async fn instantiate_with_current_env<'a, T>(
         bytes: Vec<u8>,
@@ -330,11 +826,47 @@ impl World {
     where
         T: Send,
     {
-        // engine clones are shallow (not deep).
-        let component = component_from_bytes(&bytes, env.engine.clone())?;
+        let instance = if let Some(cache) = env.cache.clone() {
+            let cid = cache::module_cid(&bytes);
+            let deterministic = env.determinism.is_some();
+            let instance_pre = match cache.instance_pre(cid, &env.sandbox, deterministic) {
+                Some(instance_pre) => instance_pre,
+                None => {
+                    let bytes = component_bytes(&bytes)?;
+                    if deterministic {
+                        determinism::check_imports(&bytes)?;
+                    }
+                    let component = cache.component(cid, &bytes)?;
+                    sandbox::stub_denied_imports(
+                        &mut env.linker,
+                        &component,
+                        &bytes,
+                        &env.sandbox,
+                    )?;
+                    let instance_pre = env.linker.instantiate_pre(&component)?;
+                    cache.insert_instance_pre(
+                        cid,
+                        &env.sandbox,
+                        deterministic,
+                        instance_pre.clone(),
+                    );
+                    instance_pre
+                }
+            };
+            instance_pre.instantiate_async(&mut env.store).await?
+        } else {
+            let bytes = component_bytes(&bytes)?;
+            if env.determinism.is_some() {
+                determinism::check_imports(&bytes)?;
+            }
+            let component =
+                Component::from_binary(&env.engine, &bytes).map_err(Error::IntoWasmComponent)?;
+            sandbox::stub_denied_imports(&mut env.linker, &component, &bytes, &env.sandbox)?;
 
-        let (_bindings, instance) =
-            Imports::instantiate_async(&mut env.store, &component, &env.linker).await?;
+            let (_bindings, instance) =
+                Imports::instantiate_async(&mut env.store, &component, &env.linker).await?;
+            instance
+        };
 
         let bindings = Self::new(&mut env.store, &instance, fun_name)?;
         env.set_instance(instance);
@@ -346,12 +878,13 @@ impl World {
         self.0
     }
 
-    fn configure() -> Config {
+    pub(crate) fn configure(interruption: Interruption) -> Config {
         let mut config = Config::new();
         config.strategy(wasmtime::Strategy::Cranelift);
         config.wasm_component_model(true);
         config.async_support(true);
         config.cranelift_nan_canonicalization(true);
+        config.relaxed_simd_deterministic(true);
         config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
 
         // Most Wasm instructions consume 1 unit of fuel.
@@ -359,13 +892,18 @@ impl World {
         // units, as any execution cost associated with them involves other
         // instructions which do consume fuel. We use *these* defaults for now
         // for Ops, instead of parsing each Op.
-        config.consume_fuel(true);
+        config.consume_fuel(interruption.fuel());
+        config.epoch_interruption(interruption.epoch());
 
         config
     }
 
     fn define_linker<U>(engine: &Engine) -> Linker<U> {
-        Linker::<U>::new(engine)
+        let mut linker = Linker::<U>::new(engine);
+        // Imports not granted by the sandbox policy are stubbed per
+        // component, possibly redefining stubs of previous components.
+        linker.allow_shadowing(true);
+        linker
     }
 
     /// Low-level creation wrapper for wrapping up the exports
@@ -400,8 +938,9 @@ impl World {
     }
 }
 
-/// Turn bytes into a Wasm [Component] module.
-fn component_from_bytes(bytes: &[u8], engine: Engine) -> Result<Component, Error> {
+/// Turn bytes into the binary encoding of a Wasm [Component], converting
+/// core Wasm modules into components.
+fn component_bytes(bytes: &[u8]) -> Result<Cow<'_, [u8]>, Error> {
     fn is_component(chunk: wasmparser::Chunk<'_>) -> bool {
         matches!(
             chunk,
@@ -417,7 +956,7 @@ fn component_from_bytes(bytes: &[u8], engine: Engine) -> Result<Component, Error
     match wasmparser::Parser::new(0).parse(bytes, true) {
         Ok(chunk) => {
             if is_component(chunk) {
-                Component::from_binary(&engine, bytes).map_err(Error::IntoWasmComponent)
+                Ok(Cow::Borrowed(bytes))
             } else {
                 tracing::info!("converting Wasm binary into a Wasm component");
 
@@ -425,13 +964,13 @@ fn component_from_bytes(bytes: &[u8], engine: Engine) -> Result<Component, Error
                     .module(bytes)?
                     .validate(true)
                     .encode()?;
-                Component::from_binary(&engine, &component).map_err(Error::IntoWasmComponent)
+                Ok(Cow::Owned(component))
             }
         }
         Err(_) => {
             let wasm_bytes = wat::parse_bytes(bytes)?;
             if is_component(wasmparser::Parser::new(0).parse(&wasm_bytes, true)?) {
-                Component::from_binary(&engine, &wasm_bytes).map_err(Error::IntoWasmComponent)
+                Ok(Cow::Owned(wasm_bytes.into_owned()))
             } else {
                 Err(Error::WatComponent(
                     "WAT must reference a Wasm component.".to_string(),
@@ -440,3 +979,53 @@ fn component_from_bytes(bytes: &[u8], engine: Engine) -> Result<Component, Error
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wasmtime::secrets::{SecretError, REDACTED};
+    use bytes::Bytes;
+    use wasmtime_wasi::preview2::StdoutStream;
+
+    #[derive(Debug)]
+    struct Token;
+
+    impl SecretStore for Token {
+        fn get(&self, name: &str) -> Result<Secret, SecretError> {
+            match name {
+                "token" => Ok(Secret::new("hunter2")),
+                _ => Err(SecretError::NotFound(name.to_string())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn capture_guest_output() {
+        use homestar::host::secrets::Host as _;
+
+        let mut state = State::default();
+        state.set_secrets(vec!["token".to_string()], Arc::new(Token));
+        state.get("token".to_string()).await.unwrap().unwrap();
+
+        state
+            .stdout
+            .stream()
+            .write(Bytes::from_static(b"hello\nusing hunter2\n"))
+            .unwrap();
+        state
+            .stderr
+            .stream()
+            .write(Bytes::from_static(b"careful"))
+            .unwrap();
+        state.capture_output();
+
+        assert_eq!(
+            state.logs(),
+            [
+                LogRecord::new(Level::Info, STDOUT_CONTEXT, "hello"),
+                LogRecord::new(Level::Info, STDOUT_CONTEXT, format!("using {REDACTED}")),
+                LogRecord::new(Level::Warn, STDERR_CONTEXT, "careful"),
+            ]
+        );
+    }
+}
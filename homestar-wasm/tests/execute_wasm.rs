@@ -8,7 +8,10 @@ use homestar_invocation::{
 };
 use homestar_wasm::{
     io::{Arg, Output},
-    wasmtime::{limits::StoreLimitsAsync, Error, State, World},
+    wasmtime::{
+        limits::StoreLimitsAsync, ComponentCache, Determinism, Error, Interruption, LimitError,
+        SandboxError, SandboxPolicy, State, World,
+    },
 };
 use libipld::{
     cid::{
@@ -40,6 +43,24 @@ async fn test_wasm_exceeds_max_memory() {
     }
 }
 
+#[tokio::test]
+async fn test_wasm_exceeds_fuel() {
+    let ipld = Input::Ipld(Ipld::Map(BTreeMap::from([
+        ("func".into(), Ipld::String("add_two".to_string())),
+        ("args".into(), Ipld::List(vec![Ipld::Integer(1)])),
+    ])));
+    let wat = fs::read(fixtures("example_add_component.wat")).unwrap();
+    let mut env = World::instantiate(wat, "add_two", State::new(1, StoreLimitsAsync::default()))
+        .await
+        .unwrap();
+
+    let err = env.execute(ipld.parse().unwrap().into()).await.unwrap_err();
+    assert!(matches!(
+        env.limit_error(err),
+        Error::LimitExceeded(LimitError::OutOfFuel(1))
+    ));
+}
+
 #[tokio::test]
 async fn test_execute_wat() {
     let ipld = Input::Ipld(Ipld::Map(BTreeMap::from([
@@ -642,3 +663,157 @@ async fn test_subtract_float_to_int() {
     let res = env.execute(ipld.parse().unwrap().into()).await.unwrap();
     assert_eq!(res, Output::Value(wasmtime::component::Val::S8(-1)));
 }
+
+#[tokio::test]
+async fn test_wasi_sandbox_denies_ungranted_imports() {
+    let ipld = Input::Ipld(Ipld::Map(BTreeMap::from([
+        ("func".into(), Ipld::String("random".to_string())),
+        ("args".into(), Ipld::List(vec![])),
+    ])));
+    let wat = fs::read(fixtures("example_wasi_random_component.wat")).unwrap();
+
+    for sandbox in [SandboxPolicy::default(), SandboxPolicy::enabled()] {
+        let mut state = State::default();
+        state.set_sandbox(sandbox).unwrap();
+        let mut env = World::instantiate(wat.clone(), "random", state)
+            .await
+            .unwrap();
+
+        let err = env.execute(ipld.parse().unwrap().into()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Sandbox(SandboxError::Denied { interface, function })
+                if interface == "wasi:random/random@0.2.0" && function == "get-random-u64"
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_wasi_sandbox_seeded_random() {
+    let ipld = Input::Ipld(Ipld::Map(BTreeMap::from([
+        ("func".into(), Ipld::String("random".to_string())),
+        ("args".into(), Ipld::List(vec![])),
+    ])));
+    let wat = fs::read(fixtures("example_wasi_random_component.wat")).unwrap();
+
+    let mut outputs = vec![];
+    for seed in [Some(42), Some(42), Some(7)] {
+        let mut state = State::default();
+        state
+            .set_sandbox(SandboxPolicy::enabled().with_random(true, seed))
+            .unwrap();
+        let mut env = World::instantiate(wat.clone(), "random", state)
+            .await
+            .unwrap();
+        outputs.push(env.execute(ipld.parse().unwrap().into()).await.unwrap());
+    }
+
+    assert_eq!(outputs[0], outputs[1]);
+    assert_ne!(outputs[0], outputs[2]);
+}
+
+#[tokio::test]
+async fn test_component_cache_reuses_instances() {
+    let ipld = Input::Ipld(Ipld::Map(BTreeMap::from([
+        ("func".into(), Ipld::String("random".to_string())),
+        ("args".into(), Ipld::List(vec![])),
+    ])));
+    let wat = fs::read(fixtures("example_wasi_random_component.wat")).unwrap();
+    let cache = ComponentCache::new(None, u64::MAX, Interruption::Fuel).unwrap();
+
+    let mut outputs = vec![];
+    for _ in 0..2 {
+        let mut state = State::default();
+        state
+            .set_sandbox(SandboxPolicy::enabled().with_random(true, Some(42)))
+            .unwrap();
+        let mut env = World::with_cache(state, cache.clone()).unwrap();
+        let env = World::instantiate_with_current_env(wat.clone(), "random", &mut env)
+            .await
+            .unwrap();
+        outputs.push(env.execute(ipld.parse().unwrap().into()).await.unwrap());
+    }
+    assert_eq!(outputs[0], outputs[1]);
+
+    // Imports are resolved again under a different sandbox policy.
+    let mut state = State::default();
+    state.set_sandbox(SandboxPolicy::enabled()).unwrap();
+    let mut env = World::with_cache(state, cache).unwrap();
+    let env = World::instantiate_with_current_env(wat, "random", &mut env)
+        .await
+        .unwrap();
+    assert!(matches!(
+        env.execute(ipld.parse().unwrap().into()).await,
+        Err(Error::Sandbox(SandboxError::Denied { .. }))
+    ));
+}
+
+#[tokio::test]
+async fn test_deterministic_execution() {
+    let ipld = Input::Ipld(Ipld::Map(BTreeMap::from([
+        ("func".into(), Ipld::String("random".to_string())),
+        ("args".into(), Ipld::List(vec![])),
+    ])));
+    let wat = fs::read(fixtures("example_wasi_random_component.wat")).unwrap();
+    let cache = ComponentCache::new(None, u64::MAX, Interruption::Fuel).unwrap();
+
+    let mut outputs = vec![];
+    for seed in [1, 1, 2] {
+        let mut state = State::default();
+        state
+            .set_sandbox(
+                SandboxPolicy::enabled()
+                    .with_random(true, None)
+                    .with_env("HOST", "node"),
+            )
+            .unwrap();
+        state.set_determinism(Determinism::new(seed)).unwrap();
+        assert_eq!(state.sandbox().random_seed(), Some(seed));
+        assert!(state.sandbox().env().is_empty());
+
+        let mut env = World::with_cache(state, cache.clone()).unwrap();
+        let env = World::instantiate_with_current_env(wat.clone(), "random", &mut env)
+            .await
+            .unwrap();
+        assert_eq!(env.determinism(), Some(Determinism::new(seed)));
+        outputs.push(env.execute(ipld.parse().unwrap().into()).await.unwrap());
+    }
+
+    assert_eq!(outputs[0], outputs[1]);
+    assert_ne!(outputs[0], outputs[2]);
+}
+
+#[tokio::test]
+async fn test_epoch_interruption_deadline() {
+    let ipld = Input::Ipld(Ipld::Map(BTreeMap::from([
+        ("func".into(), Ipld::String("spin".to_string())),
+        ("args".into(), Ipld::List(vec![])),
+    ])));
+    let wat = r#"(component
+        (core module $m (func (export "spin") (loop br 0)))
+        (core instance $i (instantiate $m))
+        (func (export "spin") (canon lift (core func $i "spin")))
+    )"#;
+
+    for interruption in [Interruption::Epoch, Interruption::Both] {
+        let time_limit = std::time::Duration::from_millis(100);
+        let mut state = State::default();
+        state.set_interruption(interruption);
+        state.set_time_limit(time_limit);
+        let mut env = World::instantiate(wat.as_bytes().to_vec(), "spin", state)
+            .await
+            .unwrap();
+        assert_eq!(env.interruption(), interruption);
+
+        // Other tasks keep running while the guest spins.
+        let other = tokio::spawn(async {});
+        let started = std::time::Instant::now();
+        let err = env.execute(ipld.parse().unwrap().into()).await.unwrap_err();
+        assert!(other.is_finished());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(matches!(
+            env.limit_error(err),
+            Error::LimitExceeded(LimitError::Timeout(limit)) if limit == time_limit
+        ));
+    }
+}
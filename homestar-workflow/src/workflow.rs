@@ -13,6 +13,9 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+mod params;
+pub use params::{ParamError, Params};
+
 const TASKS_KEY: &str = "tasks";
 
 /// Workflow composed of [tasks].
@@ -59,6 +62,42 @@ impl<'a, T> Workflow<'a, T> {
     }
 }
 
+impl<'a, T> Workflow<'a, T>
+where
+    T: Clone + From<Ipld>,
+    Ipld: From<T>,
+{
+    /// Substitute `{{name}}` placeholders in task `args` with [Params],
+    /// before any instruction Cids are computed.
+    ///
+    /// A string consisting of just a placeholder is replaced by the
+    /// parameter value itself; otherwise, values are interpolated into the
+    /// string. Awaited promises on instructions that changed due to
+    /// substitution are relinked to their new Cids.
+    pub fn with_params(self, params: &Params) -> Result<Self, ParamError> {
+        let mut relinked = BTreeMap::new();
+        let tasks = self
+            .tasks
+            .into_iter()
+            .enumerate()
+            .map(|(index, task)| {
+                let template = <Ipld as From<Task<'a, T>>>::from(task.clone());
+                let mut ipld = template.clone();
+                params::substitute_task(&mut ipld, params, &relinked, index)?;
+                if ipld == template {
+                    return Ok(task);
+                }
+
+                let substituted = Task::try_from(ipld)?;
+                relinked.insert(task.instruction_cid()?, substituted.instruction_cid()?);
+                Ok(substituted)
+            })
+            .collect::<Result<Vec<_>, ParamError>>()?;
+
+        Ok(Self { tasks })
+    }
+}
+
 impl<'a, T> From<Workflow<'a, T>> for Ipld
 where
     Ipld: From<Task<'a, T>>,
@@ -121,8 +160,13 @@ mod test {
     use super::*;
     use homestar_invocation::{
         authority::UcanPrf,
-        task::{instruction::RunInstruction, Resources},
-        test_utils,
+        ipld::DagCbor,
+        pointer::{Await, AwaitResult},
+        task::{
+            instruction::{Ability, Input, RunInstruction},
+            Instruction, Resources,
+        },
+        test_utils, Pointer,
     };
     use std::assert_eq;
 
@@ -206,6 +250,61 @@ mod test {
         assert_eq!(workflow, ipld_to_workflow);
     }
 
+    #[test]
+    fn substitute_params_and_relink_awaits() {
+        let resource = test_utils::instruction::<Unit>().resource().to_owned();
+        let build = |arg: Ipld| {
+            let instruction1 = Instruction::<Unit>::new(
+                resource.clone(),
+                Ability::from("wasm/run"),
+                Input::Ipld(Ipld::Map(BTreeMap::from([
+                    ("func".into(), Ipld::String("add_one".into())),
+                    ("args".into(), Ipld::List(vec![arg])),
+                ]))),
+            );
+            let promise = Await::new(
+                Pointer::new(instruction1.clone().to_cid().unwrap()),
+                AwaitResult::Ok,
+            );
+            let instruction2 = Instruction::<Unit>::new(
+                resource.clone(),
+                Ability::from("wasm/run"),
+                Input::Ipld(Ipld::Map(BTreeMap::from([
+                    ("func".into(), Ipld::String("add_one".into())),
+                    ("args".into(), Ipld::List(vec![promise.into()])),
+                ]))),
+            );
+            let config = Resources::default();
+            Workflow::new(vec![
+                Task::new(
+                    RunInstruction::Expanded(instruction1),
+                    config.clone().into(),
+                    UcanPrf::default(),
+                ),
+                Task::new(
+                    RunInstruction::Expanded(instruction2),
+                    config.into(),
+                    UcanPrf::default(),
+                ),
+            ])
+        };
+
+        let template = build(Ipld::String("{{n}}".into()));
+        let params = Params::from([("n".to_string(), Ipld::Integer(41))]);
+        let substituted = template.clone().with_params(&params).unwrap();
+        assert_eq!(substituted, build(Ipld::Integer(41)));
+
+        let err = template.with_params(&Params::new()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing value for workflow parameter `n`, used by task at index 0"
+        );
+
+        // Workflows without placeholders are left as-is.
+        let workflow = build(Ipld::Integer(1));
+        assert_eq!(workflow.clone().with_params(&params).unwrap(), workflow);
+    }
+
     #[test]
     fn ser_de() {
         let config = Resources::default();
@@ -0,0 +1,182 @@
+//! Parameters for templated [Workflow]s, substituted into task `args`
+//! before any instruction Cids are computed.
+//!
+//! [Workflow]: crate::Workflow
+
+use homestar_invocation::{error::Error, Unit};
+use libipld::{Cid, Ipld};
+use std::collections::BTreeMap;
+
+const RUN_KEY: &str = "run";
+const INPUT_KEY: &str = "input";
+const ARGS_KEY: &str = "args";
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+
+/// Named parameter values for `{{name}}` placeholders in task `args`.
+pub type Params = BTreeMap<String, Ipld>;
+
+/// Error substituting [Params] into a [Workflow].
+///
+/// [Workflow]: crate::Workflow
+#[derive(thiserror::Error, Debug)]
+pub enum ParamError {
+    /// Placeholder without a given parameter value.
+    #[error("missing value for workflow parameter `{name}`, used by task at index {index}")]
+    Missing {
+        /// Name of the parameter.
+        name: String,
+        /// Index of the task using the parameter.
+        index: usize,
+    },
+    /// Parameter value which cannot be interpolated within a string.
+    #[error(
+        "workflow parameter `{0}` must be a string, number, or boolean to be used within a string"
+    )]
+    NotInterpolable(String),
+    /// Propagated error from converting tasks or computing their Cids.
+    #[error(transparent)]
+    Invocation(#[from] Error<Unit>),
+}
+
+/// Substitute [Params] into the `args` of a task in Ipld form, at a given
+/// index, relinking awaited instruction Cids changed by substitution in
+/// earlier tasks.
+pub(crate) fn substitute_task(
+    task: &mut Ipld,
+    params: &Params,
+    relinked: &BTreeMap<Cid, Cid>,
+    index: usize,
+) -> Result<(), ParamError> {
+    let args = match task {
+        Ipld::Map(task) => task
+            .get_mut(RUN_KEY)
+            .and_then(|run| match run {
+                Ipld::Map(run) => run.get_mut(INPUT_KEY),
+                _ => None,
+            })
+            .and_then(|input| match input {
+                Ipld::Map(input) => input.get_mut(ARGS_KEY),
+                _ => None,
+            }),
+        _ => None,
+    };
+
+    if let Some(args) = args {
+        *args = substitute(args.to_owned(), params, relinked, index)?;
+    }
+
+    Ok(())
+}
+
+fn substitute(
+    ipld: Ipld,
+    params: &Params,
+    relinked: &BTreeMap<Cid, Cid>,
+    index: usize,
+) -> Result<Ipld, ParamError> {
+    match ipld {
+        Ipld::String(s) => substitute_str(s, params, index),
+        Ipld::Link(cid) => Ok(Ipld::Link(*relinked.get(&cid).unwrap_or(&cid))),
+        Ipld::List(list) => list
+            .into_iter()
+            .map(|ipld| substitute(ipld, params, relinked, index))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Ipld::List),
+        Ipld::Map(map) => map
+            .into_iter()
+            .map(|(k, v)| Ok((k, substitute(v, params, relinked, index)?)))
+            .collect::<Result<BTreeMap<_, _>, _>>()
+            .map(Ipld::Map),
+        ipld => Ok(ipld),
+    }
+}
+
+/// Substitute placeholders within a string, where a string consisting of
+/// just a placeholder is replaced by the parameter value itself.
+fn substitute_str(s: String, params: &Params, index: usize) -> Result<Ipld, ParamError> {
+    let lookup = |name: &str| {
+        params.get(name).ok_or_else(|| ParamError::Missing {
+            name: name.to_string(),
+            index,
+        })
+    };
+
+    if let Some(name) = s
+        .strip_prefix(OPEN)
+        .and_then(|rest| rest.strip_suffix(CLOSE))
+        .filter(|name| !name.contains(OPEN) && !name.contains(CLOSE))
+    {
+        return lookup(name.trim()).cloned();
+    }
+
+    let mut interpolated = String::with_capacity(s.len());
+    let mut rest = s.as_str();
+    while let Some((before, after)) = rest.split_once(OPEN) {
+        let Some((name, after)) = after.split_once(CLOSE) else {
+            break;
+        };
+
+        let name = name.trim();
+        interpolated.push_str(before);
+        match lookup(name)? {
+            Ipld::String(value) => interpolated.push_str(value),
+            Ipld::Integer(value) => interpolated.push_str(&value.to_string()),
+            Ipld::Float(value) => interpolated.push_str(&value.to_string()),
+            Ipld::Bool(value) => interpolated.push_str(&value.to_string()),
+            _ => return Err(ParamError::NotInterpolable(name.to_string())),
+        }
+        rest = after;
+    }
+    interpolated.push_str(rest);
+
+    Ok(Ipld::String(interpolated))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn substitute_placeholders() {
+        let params = Params::from([
+            ("n".to_string(), Ipld::Integer(42)),
+            ("name".to_string(), Ipld::String("world".into())),
+            ("list".to_string(), Ipld::List(vec![Ipld::Bool(true)])),
+        ]);
+        let relinked = BTreeMap::new();
+
+        assert_eq!(
+            substitute_str("{{n}}".into(), &params, 0).unwrap(),
+            Ipld::Integer(42)
+        );
+        assert_eq!(
+            substitute_str("{{ list }}".into(), &params, 0).unwrap(),
+            Ipld::List(vec![Ipld::Bool(true)])
+        );
+        assert_eq!(
+            substitute_str("hello {{name}}, {{n}}!".into(), &params, 0).unwrap(),
+            Ipld::String("hello world, 42!".into())
+        );
+        assert_eq!(
+            substitute_str("no {{placeholder".into(), &params, 0).unwrap(),
+            Ipld::String("no {{placeholder".into())
+        );
+        assert!(matches!(
+            substitute_str("{{list}}s".into(), &params, 0),
+            Err(ParamError::NotInterpolable(name)) if name == "list"
+        ));
+
+        let err = substitute(
+            Ipld::List(vec![Ipld::String("{{missing}}".into())]),
+            &params,
+            &relinked,
+            1,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "missing value for workflow parameter `missing`, used by task at index 1"
+        );
+    }
+}